@@ -0,0 +1,343 @@
+/*!
+Platform admin commands, for operators: managing users, stopping function
+instances and checking node capacity.
+*/
+use crate::host_manager;
+use crate::serverless_function::{api_error, authenticated_client, FunctionError};
+use serde_json::{json, Value};
+
+fn field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or("-")
+}
+
+fn print_functions(functions: &[Value]) {
+    println!(
+        "{:<36} {:<36} {:<24} LAST DEPLOYED",
+        "ID", "NAMESPACE", "NAME"
+    );
+    for function in functions {
+        println!(
+            "{:<36} {:<36} {:<24} {}",
+            field(function, "uuid"),
+            field(function, "namespace"),
+            field(function, "name"),
+            field(function, "last_deployed_at")
+        );
+    }
+}
+
+fn print_user(user: &Value) {
+    println!("User ID: {}", field(user, "uuid"));
+    println!("Email: {}", field(user, "email"));
+    println!("Role: {}", field(user, "role"));
+    println!("Verified: {}", field(user, "email_verified_at"));
+    println!("Suspended: {}", field(user, "suspended_at"));
+}
+
+/// Lists users, optionally searching their emails.
+///
+/// # Arguments
+///
+/// * `search` - Part of the email addresses to match
+/// * `suspended` - Only suspended users, or only active ones
+/// * `limit` - The most users to show; the server default when `None`
+pub fn list_users(
+    search: Option<&str>,
+    suspended: Option<bool>,
+    limit: Option<u64>,
+) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let mut params = Vec::new();
+    if let Some(search) = search {
+        params.push(("q", search.to_string()));
+    }
+    if let Some(suspended) = suspended {
+        params.push(("suspended", suspended.to_string()));
+    }
+    if let Some(limit) = limit {
+        params.push(("limit", limit.to_string()));
+    }
+    let response = client
+        .get(host_manager::admin_users_url())
+        .query(&params)
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let page: Value = serde_json::from_str(&response.text()?)?;
+    let users = page["users"].as_array().cloned().unwrap_or_default();
+    println!("{:<36} {:<32} {:<6} SUSPENDED", "USER ID", "EMAIL", "ROLE");
+    for user in &users {
+        println!(
+            "{:<36} {:<32} {:<6} {}",
+            field(user, "uuid"),
+            field(user, "email"),
+            field(user, "role"),
+            field(user, "suspended_at")
+        );
+    }
+    Ok(())
+}
+
+/// Shows a user and the functions of their personal organization.
+pub fn user_info(user_uuid: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::admin_user_url(user_uuid)).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let user: Value = serde_json::from_str(&response.text()?)?;
+    print_user(&user);
+    let functions = user["functions"].as_array().cloned().unwrap_or_default();
+    if functions.is_empty() {
+        println!("Functions: none");
+    } else {
+        println!("Functions:");
+        print_functions(&functions);
+    }
+    Ok(())
+}
+
+/// Suspends a user, or lifts their suspension.
+pub fn suspend_user(user_uuid: &str, suspend: bool) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let url = if suspend {
+        host_manager::admin_suspend_url(user_uuid)
+    } else {
+        host_manager::admin_unsuspend_url(user_uuid)
+    };
+    let response = client.post(url).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let user: Value = serde_json::from_str(&response.text()?)?;
+    if suspend {
+        println!("Suspended {}", field(&user, "email"));
+    } else {
+        println!("Lifted the suspension of {}", field(&user, "email"));
+    }
+    Ok(())
+}
+
+/// Makes a user a platform admin, or a regular user again.
+pub fn set_user_role(user_uuid: &str, role: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .put(host_manager::admin_user_role_url(user_uuid))
+        .json(&json!({ "role": role }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let user: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "{} now has the platform role {}",
+        field(&user, "email"),
+        field(&user, "role")
+    );
+    Ok(())
+}
+
+/// Lifts the login lockout of an account or IP address.
+pub fn unlock(target: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::admin_unlock_url())
+        .json(&json!({ "target": target }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let result: Value = serde_json::from_str(&response.text()?)?;
+    if result["was_locked"].as_bool().unwrap_or(false) {
+        println!("Unlocked {}", target);
+    } else {
+        println!("{} was not locked out", target);
+    }
+    Ok(())
+}
+
+/// Searches the functions of every namespace.
+///
+/// # Arguments
+///
+/// * `search` - Part of the function names to match
+/// * `namespace` - Only functions of this organization
+/// * `limit` - The most functions to show; the server default when `None`
+pub fn list_functions(
+    search: Option<&str>,
+    namespace: Option<&str>,
+    limit: Option<u64>,
+) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let mut params = Vec::new();
+    if let Some(search) = search {
+        params.push(("q", search.to_string()));
+    }
+    if let Some(namespace) = namespace {
+        params.push(("namespace", namespace.to_string()));
+    }
+    if let Some(limit) = limit {
+        params.push(("limit", limit.to_string()));
+    }
+    let response = client
+        .get(host_manager::admin_functions_url())
+        .query(&params)
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let page: Value = serde_json::from_str(&response.text()?)?;
+    print_functions(&page["functions"].as_array().cloned().unwrap_or_default());
+    Ok(())
+}
+
+/// Stops every running instance of a function immediately.
+pub fn stop_instances(function_uuid: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::admin_stop_function_url(function_uuid))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let report: Value = serde_json::from_str(&response.text()?)?;
+    let stopped = report["instances_stopped"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    println!(
+        "Stopped {} instances of '{}'",
+        stopped.len(),
+        field(&report, "function")
+    );
+    for failure in report["failures"].as_array().into_iter().flatten() {
+        eprintln!("Failed: {}", failure.as_str().unwrap_or("-"));
+    }
+    Ok(())
+}
+
+/// Shows the capacity of the node serving the request and its load.
+pub fn node_capacity() -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::admin_capacity_url()).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let capacity: Value = serde_json::from_str(&response.text()?)?;
+    let number = |value: &Value| {
+        value
+            .as_i64()
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    println!("Host: {}", field(&capacity, "host"));
+    println!("CPUs: {}", number(&capacity["cpus"]));
+    println!("Memory: {} MB", number(&capacity["memory_mb"]));
+    println!(
+        "Running containers: {}",
+        number(&capacity["containers_running"])
+    );
+    let load = &capacity["load"];
+    println!("Requests in flight: {}", number(&load["in_flight"]));
+    println!("Requests queued: {}", number(&load["queued"]));
+    Ok(())
+}
+
+/// The quota limits, as named in the API and shown to operators.
+pub const QUOTA_LIMITS: [(&str, &str); 5] = [
+    ("max_functions", "Functions"),
+    ("max_instances", "Running instances"),
+    ("max_memory_mb", "Memory (MB)"),
+    ("builds_per_hour", "Builds per hour"),
+    ("invocations_per_day", "Invocations per day"),
+];
+
+fn print_quota(quota: &Value) {
+    let limit = |value: &Value| match value.as_u64() {
+        Some(0) => "unlimited".to_string(),
+        Some(limit) => limit.to_string(),
+        None => "default".to_string(),
+    };
+    println!("Namespace: {}", field(quota, "namespace"));
+    println!("{:<22} {:>10} {:>10}", "QUOTA", "OVERRIDE", "ENFORCED");
+    for (key, label) in QUOTA_LIMITS {
+        println!(
+            "{:<22} {:>10} {:>10}",
+            label,
+            limit(&quota["overrides"][key]),
+            limit(&quota["effective"][key])
+        );
+    }
+}
+
+/// Shows the quota overrides of an organization and the limits enforced on it.
+pub fn show_quota(namespace: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .get(host_manager::admin_quota_url(namespace))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    print_quota(&serde_json::from_str(&response.text()?)?);
+    Ok(())
+}
+
+/// Sets the quota overrides of an organization, replacing the previous ones.
+///
+/// # Arguments
+///
+/// * `namespace` - The organization's ID
+/// * `limits` - The limits to override, by API name; 0 lifts a limit
+pub fn set_quota(namespace: &str, limits: &[(&str, u64)]) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let body: serde_json::Map<String, Value> = limits
+        .iter()
+        .map(|(key, limit)| (key.to_string(), json!(limit)))
+        .collect();
+    let response = client
+        .put(host_manager::admin_quota_url(namespace))
+        .json(&body)
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    print_quota(&serde_json::from_str(&response.text()?)?);
+    Ok(())
+}
+
+/// Removes the quota overrides of an organization, so the server defaults apply.
+pub fn reset_quota(namespace: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .delete(host_manager::admin_quota_url(namespace))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    println!("Quota of {} reset to the server defaults", namespace);
+    Ok(())
+}
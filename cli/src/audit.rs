@@ -0,0 +1,129 @@
+/*!
+Audit log commands, for reviewing who did what in the active organization,
+or on the whole platform for platform admins.
+*/
+use crate::host_manager;
+use crate::serverless_function::{api_error, authenticated_client, FunctionError};
+use serde_json::Value;
+use std::fs::File;
+use std::io;
+
+/// Filters for audit events; every filter that is set must match. `all` reads
+/// the events of the whole platform rather than the active organization.
+#[derive(Debug, Default)]
+pub struct AuditQuery {
+    pub all: bool,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub outcome: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl AuditQuery {
+    /// The filters as query string parameters.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        [
+            ("actor", &self.actor),
+            ("action", &self.action),
+            ("target", &self.target),
+            ("outcome", &self.outcome),
+            ("since", &self.since),
+            ("until", &self.until),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.clone().map(|value| (name, value)))
+        .collect()
+    }
+
+    fn list_url(&self) -> String {
+        if self.all {
+            host_manager::admin_audit_url()
+        } else {
+            host_manager::audit_url()
+        }
+    }
+
+    fn export_url(&self) -> String {
+        if self.all {
+            host_manager::admin_audit_export_url()
+        } else {
+            host_manager::audit_export_url()
+        }
+    }
+}
+
+/// Lists the newest audit events of the active organization, or of the platform.
+///
+/// # Arguments
+///
+/// * `query` - The filters the events must match
+/// * `limit` - The most events to show; the server default when `None`
+pub fn list_events(query: &AuditQuery, limit: Option<u64>) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let mut params = query.params();
+    if let Some(limit) = limit {
+        params.push(("limit", limit.to_string()));
+    }
+    let response = client.get(query.list_url()).query(&params).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let page: Value = serde_json::from_str(&response.text()?)?;
+    let events = page["events"].as_array().cloned().unwrap_or_default();
+    if events.is_empty() {
+        println!("No audit events");
+        return Ok(());
+    }
+    let field = |event: &Value, key: &str| event[key].as_str().unwrap_or("-").to_string();
+    println!(
+        "{:<32} {:<26} {:<8} {:<36} {:<24} IP",
+        "TIME", "ACTION", "OUTCOME", "ACTOR", "TARGET"
+    );
+    for event in &events {
+        println!(
+            "{:<32} {:<26} {:<8} {:<36} {:<24} {}",
+            field(event, "occurred_at"),
+            field(event, "action"),
+            field(event, "outcome"),
+            field(event, "actor"),
+            field(event, "target"),
+            field(event, "ip")
+        );
+    }
+    Ok(())
+}
+
+/// Exports every audit event of the active organization, or of the platform,
+/// matching `query` as newline-delimited JSON.
+///
+/// # Arguments
+///
+/// * `query` - The filters the events must match
+/// * `output` - The file to write; standard output when `None`
+pub fn export_events(query: &AuditQuery, output: Option<&str>) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let mut response = client
+        .get(query.export_url())
+        .query(&query.params())
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    match output {
+        Some(path) => {
+            let mut file = File::create(path)?;
+            let bytes = response.copy_to(&mut file)?;
+            eprintln!("Wrote {} bytes of audit events to {}", bytes, path);
+        }
+        None => {
+            response.copy_to(&mut io::stdout().lock())?;
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,31 @@
 use crate::host_manager;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use rand::RngCore;
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::File;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 // File to store auth token
 const AUTH_FILE: &str = ".serverless-cli-auth";
 
+// Environment variable holding a personal access token, used instead of the saved session
+const TOKEN_ENV: &str = "INVOK_TOKEN";
+
+// Environment variable selecting the organization when authenticating with `INVOK_TOKEN`
+const ORGANIZATION_ENV: &str = "INVOK_ORG";
+
+// Refresh the access token when it expires within this many seconds
+const REFRESH_MARGIN_SECS: u64 = 60;
+
 /// Authentication errors
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum AuthError {
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
@@ -36,6 +51,11 @@ pub struct Credentials {
 #[derive(Deserialize)]
 pub struct AuthResponse {
     pub token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Seconds until `token` expires
+    #[serde(default)]
+    pub expires_in: Option<u64>,
     pub user: UserResponse,
 }
 
@@ -52,6 +72,47 @@ pub struct AuthSession {
     pub token: String,
     pub user_uuid: String,
     pub email: String,
+    /// The organization commands act on; the personal one when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Token exchanged for a new access token once `token` expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// When `token` expires, as a Unix timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl AuthSession {
+    /// Builds a session from the server's response, keeping the active organization
+    fn from_response(auth_response: AuthResponse, organization: Option<String>) -> Self {
+        AuthSession {
+            token: auth_response.token,
+            user_uuid: auth_response.user.uuid,
+            email: auth_response.user.email,
+            organization,
+            refresh_token: auth_response.refresh_token,
+            expires_at: auth_response
+                .expires_in
+                .map(|expires_in| now_secs() + expires_in),
+        }
+    }
+
+    /// Whether the access token expires within the refresh margin
+    fn needs_refresh(&self) -> bool {
+        self.refresh_token.is_some()
+            && self
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now_secs() + REFRESH_MARGIN_SECS)
+    }
+}
+
+/// The current time as a Unix timestamp
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
 }
 
 /// Registers a new user
@@ -63,8 +124,9 @@ pub struct AuthSession {
 ///
 /// # Returns
 ///
-/// An AuthSession on success or AuthError on failure
-pub fn register(email: &str, password: &str) -> Result<AuthSession, AuthError> {
+/// The new AuthSession, None if the email address must be verified before
+/// logging in, or AuthError on failure
+pub fn register(email: &str, password: &str) -> Result<Option<AuthSession>, AuthError> {
     let client = Client::new();
     let credentials = Credentials {
         email: email.to_string(),
@@ -81,18 +143,68 @@ pub fn register(email: &str, password: &str) -> Result<AuthSession, AuthError> {
         return Err(AuthError::AuthenticationError(error_text));
     }
 
-    let auth_response: AuthResponse = response.json()?;
+    let body: serde_json::Value = response.json()?;
+    if body.get("token").is_none() {
+        // The server sent a verification link instead of starting a session
+        return Ok(None);
+    }
+    let auth_response: AuthResponse = serde_json::from_value(body)?;
 
     // Save the session locally
-    let session = AuthSession {
-        token: auth_response.token,
-        user_uuid: auth_response.user.uuid,
-        email: auth_response.user.email,
-    };
+    let session = AuthSession::from_response(auth_response, None);
 
     save_session(&session)?;
 
-    Ok(session)
+    Ok(Some(session))
+}
+
+/// Verify an email address with the token emailed to it
+///
+/// # Arguments
+///
+/// * `token` - The token from the verification email
+pub fn verify_email(token: &str) -> Result<(), AuthError> {
+    post_auth_request(
+        host_manager::auth_verify_url(),
+        &serde_json::json!({ "token": token }),
+    )
+}
+
+/// Ask for a password reset token to be emailed
+///
+/// # Arguments
+///
+/// * `email` - Email address of the account
+pub fn forgot_password(email: &str) -> Result<(), AuthError> {
+    post_auth_request(
+        host_manager::auth_forgot_url(),
+        &serde_json::json!({ "email": email }),
+    )
+}
+
+/// Set a new password with an emailed reset token
+///
+/// # Arguments
+///
+/// * `token` - The token from the reset email
+/// * `password` - The new password
+pub fn reset_password(token: &str, password: &str) -> Result<(), AuthError> {
+    post_auth_request(
+        host_manager::auth_reset_url(),
+        &serde_json::json!({ "token": token, "password": password }),
+    )
+}
+
+/// Send an unauthenticated request to an auth endpoint, failing on an error status
+fn post_auth_request(url: String, body: &serde_json::Value) -> Result<(), AuthError> {
+    let response = Client::new().post(url).json(body).send()?;
+
+    if !response.status().is_success() {
+        let error_text = response.text()?;
+        return Err(AuthError::AuthenticationError(error_text));
+    }
+
+    Ok(())
 }
 
 /// Login a user
@@ -125,19 +237,138 @@ pub fn login(email: &str, password: &str) -> Result<AuthSession, AuthError> {
     let auth_response: AuthResponse = response.json()?;
 
     // Save the session locally
-    let session = AuthSession {
-        token: auth_response.token,
-        user_uuid: auth_response.user.uuid,
-        email: auth_response.user.email,
-    };
+    let session = AuthSession::from_response(auth_response, None);
 
     save_session(&session)?;
 
     Ok(session)
 }
 
+/// Login through the platform's single sign-on identity provider
+///
+/// Opens the provider's sign-in page in a browser and waits on a loopback
+/// port for the platform to hand back a one-time login code, which is
+/// redeemed with the PKCE verifier only this process knows.
+///
+/// # Returns
+///
+/// An AuthSession on success or AuthError on failure
+pub fn login_sso() -> Result<AuthSession, AuthError> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let redirect_uri = format!("http://{}/callback", listener.local_addr()?);
+    let state = random_token();
+    let code_verifier = random_token();
+    let code_challenge = BASE64_URL.encode(Sha256::digest(code_verifier.as_bytes()));
+
+    let client = Client::new();
+    let response = client
+        .post(host_manager::auth_sso_start_url())
+        .json(&serde_json::json!({
+            "redirect_uri": redirect_uri,
+            "code_challenge": code_challenge,
+            "state": state,
+        }))
+        .send()?;
+    if !response.status().is_success() {
+        let error_text = response.text()?;
+        return Err(AuthError::AuthenticationError(error_text));
+    }
+    let body: serde_json::Value = response.json()?;
+    let authorization_url = body["authorization_url"].as_str().ok_or_else(|| {
+        AuthError::AuthenticationError("The server didn't return a sign-in URL".to_string())
+    })?;
+
+    println!(
+        "Opening your browser to sign in. If it doesn't open, visit:\n\n  {authorization_url}\n"
+    );
+    open_browser(authorization_url);
+
+    let code = wait_for_login_code(&listener, &state)?;
+    let response = client
+        .post(host_manager::auth_sso_token_url())
+        .json(&serde_json::json!({ "code": code, "code_verifier": code_verifier }))
+        .send()?;
+    if !response.status().is_success() {
+        let error_text = response.text()?;
+        return Err(AuthError::AuthenticationError(error_text));
+    }
+
+    let auth_response: AuthResponse = response.json()?;
+    let session = AuthSession::from_response(auth_response, None);
+    save_session(&session)?;
+
+    Ok(session)
+}
+
+/// Wait for the browser to be redirected to the loopback listener and return
+/// the login code it carries
+fn wait_for_login_code(listener: &TcpListener, state: &str) -> Result<String, AuthError> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let url = match reqwest::Url::parse(&format!("http://127.0.0.1{target}")) {
+            Ok(url) if url.path() == "/callback" => url,
+            // Browsers also ask for things like /favicon.ico
+            _ => {
+                stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")?;
+                continue;
+            }
+        };
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        let result = if param("state").as_deref() != Some(state) {
+            Err("The sign-in response doesn't belong to this login".to_string())
+        } else if let Some(code) = param("code") {
+            Ok(code)
+        } else {
+            Err(param("error").unwrap_or_else(|| "No login code received".to_string()))
+        };
+        let message = match &result {
+            Ok(_) => "Signed in. You can close this tab and return to the terminal.",
+            Err(_) => "Sign-in failed. Check the terminal for details.",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            message.len(),
+            message
+        )?;
+        return result.map_err(AuthError::AuthenticationError);
+    }
+    Err(AuthError::AuthenticationError(
+        "Stopped waiting for the sign-in".to_string(),
+    ))
+}
+
+/// Try to open `url` in the user's browser; the URL is printed either way
+fn open_browser(url: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    let _ = command.arg(url).spawn();
+}
+
+/// A random URL-safe value for the sign-in state and PKCE verifier
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64_URL.encode(bytes)
+}
+
 /// Save authentication session to a local file
-fn save_session(session: &AuthSession) -> Result<(), AuthError> {
+pub fn save_session(session: &AuthSession) -> Result<(), AuthError> {
     let auth_file_path = get_auth_file_path();
     let serialized = serde_json::to_string_pretty(session)?;
 
@@ -148,7 +379,23 @@ fn save_session(session: &AuthSession) -> Result<(), AuthError> {
 }
 
 /// Load authentication session from the local file
+///
+/// A personal access token in `INVOK_TOKEN` takes precedence, so CI jobs can
+/// authenticate without logging in.
 pub fn load_session() -> Result<AuthSession, AuthError> {
+    if let Some(token) = env_token() {
+        return Ok(AuthSession {
+            token,
+            user_uuid: String::new(),
+            email: String::new(),
+            organization: std::env::var(ORGANIZATION_ENV)
+                .ok()
+                .filter(|organization| !organization.is_empty()),
+            refresh_token: None,
+            expires_at: None,
+        });
+    }
+
     let auth_file_path = get_auth_file_path();
 
     if !auth_file_path.exists() {
@@ -166,18 +413,90 @@ pub fn load_session() -> Result<AuthSession, AuthError> {
     Ok(session)
 }
 
+/// Load the authentication session, first refreshing its access token if it
+/// is about to expire
+pub fn active_session() -> Result<AuthSession, AuthError> {
+    let session = load_session()?;
+    if session.needs_refresh() {
+        return refresh_session(session);
+    }
+    Ok(session)
+}
+
+/// Exchange the session's refresh token for a new access token and refresh token
+fn refresh_session(session: AuthSession) -> Result<AuthSession, AuthError> {
+    let refresh_token = session.refresh_token.unwrap_or_default();
+    let response = Client::new()
+        .post(host_manager::auth_refresh_url())
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(AuthError::AuthenticationError(
+            "Session expired. Please run 'invok login' again.".to_string(),
+        ));
+    }
+
+    let auth_response: AuthResponse = response.json()?;
+    let session = AuthSession::from_response(auth_response, session.organization);
+    save_session(&session)?;
+
+    Ok(session)
+}
+
+/// The personal access token set in `INVOK_TOKEN`, if any
+pub fn env_token() -> Option<String> {
+    std::env::var(TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
 /// Get the path to the auth file
 fn get_auth_file_path() -> std::path::PathBuf {
     let home_dir = dirs::home_dir().unwrap_or_else(|| Path::new(".").to_path_buf());
     home_dir.join(AUTH_FILE)
 }
 
-/// Logout (remove saved session)
-pub fn logout() -> Result<(), AuthError> {
+/// Logout: revoke the session on the server and remove the saved session
+///
+/// # Arguments
+///
+/// * `everywhere` - Revoke every session of the user, not just this one
+pub fn logout(everywhere: bool) -> Result<(), AuthError> {
     let auth_file_path = get_auth_file_path();
 
-    if auth_file_path.exists() {
-        std::fs::remove_file(auth_file_path)?;
+    if !auth_file_path.exists() {
+        return Ok(());
+    }
+
+    let session = load_session()?;
+    let revoked = match session.refresh_token {
+        Some(refresh_token) => revoke_session(&refresh_token, everywhere),
+        None => Ok(()),
+    };
+
+    // The local session is removed even if the server couldn't be reached
+    std::fs::remove_file(auth_file_path)?;
+
+    revoked
+}
+
+/// Revoke the session of a refresh token on the server
+fn revoke_session(refresh_token: &str, everywhere: bool) -> Result<(), AuthError> {
+    let response = Client::new()
+        .post(host_manager::auth_logout_url())
+        .json(&serde_json::json!({
+            "refresh_token": refresh_token,
+            "everywhere": everywhere,
+        }))
+        .send()?;
+
+    if !response.status().is_success() {
+        let error_text = response.text()?;
+        return Err(AuthError::AuthenticationError(format!(
+            "Removed the local session, but the server couldn't revoke it: {}",
+            error_text
+        )));
     }
 
     Ok(())
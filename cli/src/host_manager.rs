@@ -18,11 +18,151 @@ pub fn auth_login_url() -> String {
 pub fn auth_register_url() -> String {
     format!("{}/auth/register", HOST_BASE)
 }
+/// Generates the URL for the token refresh endpoint
+pub fn auth_refresh_url() -> String {
+    format!("{}/auth/refresh", HOST_BASE)
+}
+/// Generates the URL for the logout endpoint
+pub fn auth_logout_url() -> String {
+    format!("{}/auth/logout", HOST_BASE)
+}
+/// Generates the URL for the email verification endpoint
+pub fn auth_verify_url() -> String {
+    format!("{}/auth/verify", HOST_BASE)
+}
+/// Generates the URL for requesting a password reset
+pub fn auth_forgot_url() -> String {
+    format!("{}/auth/forgot", HOST_BASE)
+}
+/// Generates the URL for resetting a password
+pub fn auth_reset_url() -> String {
+    format!("{}/auth/reset", HOST_BASE)
+}
+/// Generates the URL for starting a single sign-on
+pub fn auth_sso_start_url() -> String {
+    format!("{}/auth/sso/start", HOST_BASE)
+}
+/// Generates the URL for redeeming a single sign-on login code
+pub fn auth_sso_token_url() -> String {
+    format!("{}/auth/sso/token", HOST_BASE)
+}
+/// Generates the URL for listing and creating personal access tokens
+pub fn auth_tokens_url() -> String {
+    format!("{}/auth/tokens", HOST_BASE)
+}
+/// Generates the URL for a single personal access token
+pub fn auth_token_url(token_uuid: &str) -> String {
+    format!("{}/auth/tokens/{}", HOST_BASE, token_uuid)
+}
 /// Generates the URL for the function upload endpoint
 pub fn function_upload_url() -> String {
     format!("{}/invok/deploy", HOST_BASE)
 }
+/// Generates the URL for the multi-function project deploy endpoint
+pub fn project_upload_url() -> String {
+    format!("{}/invok/deploy/project", HOST_BASE)
+}
 /// Generates the URL for the function list endpoint
 pub fn function_list_url() -> String {
     format!("{}/invok/list", HOST_BASE)
 }
+/// Generates the URL for the namespace usage endpoint
+pub fn usage_url() -> String {
+    format!("{}/invok/usage", HOST_BASE)
+}
+/// Generates the URL for a single function's management endpoint
+pub fn function_url(name: &str) -> String {
+    format!("{}/invok/functions/{}", HOST_BASE, name)
+}
+/// Generates the URL for listing and creating a function's API keys
+pub fn function_keys_url(name: &str) -> String {
+    format!("{}/invok/functions/{}/keys", HOST_BASE, name)
+}
+/// Generates the URL for a single API key of a function
+pub fn function_key_url(name: &str, key_uuid: &str) -> String {
+    format!("{}/invok/functions/{}/keys/{}", HOST_BASE, name, key_uuid)
+}
+/// Generates the URL for a function's secrets endpoint
+pub fn function_secrets_url(name: &str) -> String {
+    format!("{}/invok/functions/{}/secrets", HOST_BASE, name)
+}
+/// Generates the URL for a single secret of a function
+pub fn function_secret_url(name: &str, key: &str) -> String {
+    format!("{}/invok/functions/{}/secrets/{}", HOST_BASE, name, key)
+}
+/// Generates the URL for listing and creating organizations
+pub fn organizations_url() -> String {
+    format!("{}/orgs", HOST_BASE)
+}
+/// Generates the URL for the active organization's members
+pub fn organization_members_url() -> String {
+    format!("{}/orgs/members", HOST_BASE)
+}
+/// Generates the URL for a single member of the active organization
+pub fn organization_member_url(user_uuid: &str) -> String {
+    format!("{}/orgs/members/{}", HOST_BASE, user_uuid)
+}
+/// Generates the URL for inviting members to the active organization
+pub fn organization_invitations_url() -> String {
+    format!("{}/orgs/invitations", HOST_BASE)
+}
+/// Generates the URL for accepting an organization invitation
+pub fn accept_invitation_url() -> String {
+    format!("{}/orgs/invitations/accept", HOST_BASE)
+}
+/// Generates the URL for listing the active organization's audit events
+pub fn audit_url() -> String {
+    format!("{}/audit", HOST_BASE)
+}
+/// Generates the URL for exporting the active organization's audit events
+pub fn audit_export_url() -> String {
+    format!("{}/audit/export", HOST_BASE)
+}
+/// Generates the URL for listing every organization's audit events
+pub fn admin_audit_url() -> String {
+    format!("{}/admin/audit", HOST_BASE)
+}
+/// Generates the URL for exporting every organization's audit events
+pub fn admin_audit_export_url() -> String {
+    format!("{}/admin/audit/export", HOST_BASE)
+}
+/// Generates the URL for listing and searching users
+pub fn admin_users_url() -> String {
+    format!("{}/admin/users", HOST_BASE)
+}
+/// Generates the URL for a user, as seen by platform admins
+pub fn admin_user_url(user_uuid: &str) -> String {
+    format!("{}/admin/users/{}", HOST_BASE, user_uuid)
+}
+/// Generates the URL for changing a user's platform role
+pub fn admin_user_role_url(user_uuid: &str) -> String {
+    format!("{}/admin/users/{}/role", HOST_BASE, user_uuid)
+}
+/// Generates the URL for suspending a user
+pub fn admin_suspend_url(user_uuid: &str) -> String {
+    format!("{}/admin/users/{}/suspend", HOST_BASE, user_uuid)
+}
+/// Generates the URL for lifting a user's suspension
+pub fn admin_unsuspend_url(user_uuid: &str) -> String {
+    format!("{}/admin/users/{}/unsuspend", HOST_BASE, user_uuid)
+}
+/// Generates the URL for lifting a login lockout
+pub fn admin_unlock_url() -> String {
+    format!("{}/admin/unlock", HOST_BASE)
+}
+/// Generates the URL for searching every namespace's functions
+pub fn admin_functions_url() -> String {
+    format!("{}/admin/functions", HOST_BASE)
+}
+/// Generates the URL for force-stopping a function's instances
+pub fn admin_stop_function_url(function_uuid: &str) -> String {
+    format!("{}/admin/functions/{}/stop", HOST_BASE, function_uuid)
+}
+/// Generates the URL for the node's capacity
+pub fn admin_capacity_url() -> String {
+    format!("{}/admin/capacity", HOST_BASE)
+}
+/// Generates the URL for a namespace's quota overrides
+pub fn admin_quota_url(namespace: &str) -> String {
+    format!("{}/admin/quotas/{}", HOST_BASE, namespace)
+}
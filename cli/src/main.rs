@@ -1,13 +1,115 @@
+mod admin;
+mod audit;
 mod auth;
 mod host_manager;
+mod organization;
 mod serverless_function;
+mod tokens;
 mod utils;
 
-use crate::auth::{login, logout, register};
-use crate::serverless_function::{create_new_project, deploy_function, list_functions};
-use clap::{Arg, Command};
+use crate::audit::{export_events, list_events, AuditQuery};
+use crate::auth::{
+    forgot_password, login, login_sso, logout, register, reset_password, verify_email,
+};
+use crate::organization::{
+    accept_invitation, create_organization, invite_member, list_members, list_organizations,
+    remove_member, set_member_role, switch_organization,
+};
+use crate::serverless_function::{
+    create_api_key, create_new_project, delete_function, deploy_function, deploy_project,
+    function_info, list_api_keys, list_functions, list_secrets, revoke_api_key, set_secrets,
+    show_usage, unset_secret,
+};
+use crate::tokens::{create_token, list_tokens, revoke_token};
+use clap::{value_parser, Arg, ArgAction, ArgGroup, Command};
+use shared_utils::ArchiveFormat;
 use std::process;
 
+/// The organization argument of the quota commands.
+fn namespace_arg() -> Arg {
+    Arg::new("namespace")
+        .value_name("NAMESPACE")
+        .required(true)
+        .help("The organization's ID")
+}
+
+/// The limits of `invok admin quota set`, one flag per limit.
+fn quota_limit_args() -> Vec<Arg> {
+    [
+        ("max_functions", "max-functions", "Most functions"),
+        ("max_instances", "max-instances", "Most running instances"),
+        (
+            "max_memory_mb",
+            "max-memory-mb",
+            "Most memory of running instances, in MB",
+        ),
+        ("builds_per_hour", "builds-per-hour", "Most builds per hour"),
+        (
+            "invocations_per_day",
+            "invocations-per-day",
+            "Most invocations per day",
+        ),
+    ]
+    .into_iter()
+    .map(|(id, long, help)| {
+        Arg::new(id)
+            .long(long)
+            .value_name("COUNT")
+            .value_parser(value_parser!(u64))
+            .help(help)
+    })
+    .collect()
+}
+
+/// Filters shared by the audit log commands.
+fn audit_filter_args() -> Vec<Arg> {
+    vec![
+        Arg::new("actor")
+            .long("actor")
+            .value_name("USER_ID")
+            .help("Only events of this user"),
+        Arg::new("action")
+            .long("action")
+            .value_name("ACTION")
+            .help("Only this action, or actions under it, e.g. 'function' or 'function.deploy'"),
+        Arg::new("target")
+            .long("target")
+            .value_name("TARGET")
+            .help("Only events acting on this, e.g. a function name"),
+        Arg::new("outcome")
+            .long("outcome")
+            .value_name("OUTCOME")
+            .value_parser(["success", "denied", "failure"])
+            .help("Only events with this outcome"),
+        Arg::new("since")
+            .long("since")
+            .value_name("TIME")
+            .help("Only events at or after this RFC 3339 time"),
+        Arg::new("until")
+            .long("until")
+            .value_name("TIME")
+            .help("Only events before this RFC 3339 time"),
+        Arg::new("all")
+            .long("all")
+            .action(ArgAction::SetTrue)
+            .help("Every organization's events and operator actions; platform admins only"),
+    ]
+}
+
+/// The audit log filters given on the command line.
+fn audit_query(args: &clap::ArgMatches) -> AuditQuery {
+    let value = |name: &str| args.get_one::<String>(name).cloned();
+    AuditQuery {
+        all: args.get_flag("all"),
+        actor: value("actor"),
+        action: value("action"),
+        target: value("target"),
+        outcome: value("outcome"),
+        since: value("since"),
+        until: value("until"),
+    }
+}
+
 fn main() {
     let matches = Command::new("CLI")
         .version("1.0")
@@ -34,17 +136,440 @@ fn main() {
         )
         .subcommand(
             Command::new("deploy")
-                .about("Deploys an existing function")
+                .about("Deploys an existing function, or every function of the project")
+                .args([
+                    Arg::new("name")
+                        .short('n')
+                        .long("name")
+                        .value_name("NAME")
+                        .help("The name of the function to deploy"),
+                    Arg::new("all")
+                        .short('a')
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .help("Deploy every function listed in config.json in one request"),
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .default_value("zip")
+                        .value_parser(["zip", "tar.gz", "tar.zst"])
+                        .help("The archive format used to upload the function"),
+                ])
+                .group(ArgGroup::new("target").args(["name", "all"]).required(true)),
+        )
+        .subcommand(
+            Command::new("delete")
+                .about("Deletes a deployed function and its resources")
                 .arg(
                     Arg::new("name")
                         .short('n')
                         .long("name")
                         .value_name("NAME")
                         .required(true)
-                        .help("The name of the function to deploy"),
+                        .help("The name or ID of the function to delete"),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Shows a function's metadata and live status")
+                .arg(
+                    Arg::new("name")
+                        .short('n')
+                        .long("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .help("The name or ID of the function to describe"),
                 ),
         )
         .subcommand(Command::new("list").about("Lists all functions"))
+        .subcommand(
+            Command::new("usage").about("Shows what your organization uses against its quota"),
+        )
+        .subcommand(
+            Command::new("secrets")
+                .about("Manages a function's encrypted secrets")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("set").about("Sets one or more secrets").args([
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                        Arg::new("secrets")
+                            .value_name("KEY=VALUE")
+                            .num_args(1..)
+                            .required(true)
+                            .help("The secrets to set"),
+                    ]),
+                )
+                .subcommand(
+                    Command::new("unset").about("Removes a secret").args([
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                        Arg::new("key")
+                            .value_name("KEY")
+                            .required(true)
+                            .help("The secret to remove"),
+                    ]),
+                )
+                .subcommand(
+                    Command::new("list").about("Lists secret names").arg(
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("Manages the API keys of functions using the api_key auth mode")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create").about("Creates an API key").args([
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                        Arg::new("key_name")
+                            .value_name("KEY_NAME")
+                            .required(true)
+                            .help("A name to recognise the key by"),
+                    ]),
+                )
+                .subcommand(
+                    Command::new("list").about("Lists API keys").arg(
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                    ),
+                )
+                .subcommand(
+                    Command::new("revoke").about("Revokes an API key").args([
+                        Arg::new("name")
+                            .short('n')
+                            .long("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name or ID of the function"),
+                        Arg::new("key_id")
+                            .value_name("KEY_ID")
+                            .required(true)
+                            .help("The ID of the key to revoke"),
+                    ]),
+                ),
+        )
+        .subcommand(
+            Command::new("org")
+                .about("Manages organizations and their members")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create").about("Creates an organization").arg(
+                        Arg::new("name")
+                            .value_name("NAME")
+                            .required(true)
+                            .help("The name of the organization"),
+                    ),
+                )
+                .subcommand(Command::new("list").about("Lists your organizations"))
+                .subcommand(
+                    Command::new("switch")
+                        .about("Sets the organization other commands act on")
+                        .arg(
+                            Arg::new("name")
+                                .value_name("NAME")
+                                .required(true)
+                                .help("The organization's name or ID, or 'personal'"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("members").about("Lists the active organization's members"),
+                )
+                .subcommand(
+                    Command::new("invite")
+                        .about("Invites someone to the active organization")
+                        .args([
+                            Arg::new("email")
+                                .value_name("EMAIL")
+                                .required(true)
+                                .help("The email to invite"),
+                            Arg::new("role")
+                                .short('r')
+                                .long("role")
+                                .value_name("ROLE")
+                                .default_value("developer")
+                                .value_parser(["owner", "admin", "developer", "viewer"])
+                                .help("The role to grant"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("accept").about("Accepts an invitation").arg(
+                        Arg::new("token")
+                            .value_name("TOKEN")
+                            .required(true)
+                            .help("The invitation token"),
+                    ),
+                )
+                .subcommand(
+                    Command::new("set-role")
+                        .about("Changes a member's role")
+                        .args([
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The member's user ID"),
+                            Arg::new("role")
+                                .value_name("ROLE")
+                                .required(true)
+                                .value_parser(["owner", "admin", "developer", "viewer"])
+                                .help("The new role"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Removes a member, or leaves with your own user ID")
+                        .arg(
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The member's user ID"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("tokens")
+                .about("Manages personal access tokens for CI and scripts")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Creates a token; its secret is shown only once")
+                        .args([
+                            Arg::new("name")
+                                .value_name("NAME")
+                                .required(true)
+                                .help("A name to recognise the token by"),
+                            Arg::new("scope")
+                                .short('s')
+                                .long("scope")
+                                .value_name("SCOPE")
+                                .action(ArgAction::Append)
+                                .required(true)
+                                .value_parser(["read", "deploy", "invoke", "admin"])
+                                .help("A scope to grant; repeat for several"),
+                            Arg::new("expires-in")
+                                .short('e')
+                                .long("expires-in")
+                                .value_name("DAYS")
+                                .value_parser(value_parser!(u32).range(1..=365))
+                                .help("Days until the token expires, at most 365 [default: 90]"),
+                        ]),
+                )
+                .subcommand(Command::new("list").about("Lists your tokens"))
+                .subcommand(
+                    Command::new("revoke").about("Revokes a token").arg(
+                        Arg::new("id")
+                            .value_name("TOKEN_ID")
+                            .required(true)
+                            .help("The ID of the token to revoke"),
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("Reviews the audit log of the active organization")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("Lists the newest audit events")
+                        .args(audit_filter_args())
+                        .arg(
+                            Arg::new("limit")
+                                .short('l')
+                                .long("limit")
+                                .value_name("COUNT")
+                                .value_parser(value_parser!(u64))
+                                .help("The most events to show [default: 100]"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Exports audit events as newline-delimited JSON")
+                        .args(audit_filter_args())
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("The file to write; standard output by default"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("admin")
+                .about("Operates the platform; requires the admin platform role")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("users")
+                        .about("Lists and searches users")
+                        .args([
+                            Arg::new("search")
+                                .short('s')
+                                .long("search")
+                                .value_name("TEXT")
+                                .help("Only users whose email contains this"),
+                            Arg::new("suspended")
+                                .long("suspended")
+                                .action(ArgAction::SetTrue)
+                                .conflicts_with("active")
+                                .help("Only suspended users"),
+                            Arg::new("active")
+                                .long("active")
+                                .action(ArgAction::SetTrue)
+                                .help("Only users who aren't suspended"),
+                            Arg::new("limit")
+                                .short('l')
+                                .long("limit")
+                                .value_name("COUNT")
+                                .value_parser(value_parser!(u64))
+                                .help("The most users to show [default: 100]"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("user")
+                        .about("Shows a user and their functions")
+                        .arg(
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The user's ID"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("suspend")
+                        .about("Suspends a user: no deploys, and their functions return 403")
+                        .arg(
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The user's ID"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("unsuspend")
+                        .about("Lifts a user's suspension")
+                        .arg(
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The user's ID"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("set-role")
+                        .about("Changes a user's platform role")
+                        .args([
+                            Arg::new("user")
+                                .value_name("USER_ID")
+                                .required(true)
+                                .help("The user's ID"),
+                            Arg::new("role")
+                                .value_name("ROLE")
+                                .required(true)
+                                .value_parser(["admin", "user"])
+                                .help("The new platform role"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("unlock")
+                        .about("Lifts the login lockout of an account or IP address")
+                        .arg(
+                            Arg::new("target")
+                                .value_name("EMAIL_OR_IP")
+                                .required(true)
+                                .help("The account's email or the IP address"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("functions")
+                        .about("Searches the functions of every organization")
+                        .args([
+                            Arg::new("search")
+                                .short('s')
+                                .long("search")
+                                .value_name("TEXT")
+                                .help("Only functions whose name contains this"),
+                            Arg::new("namespace")
+                                .short('n')
+                                .long("namespace")
+                                .value_name("NAMESPACE")
+                                .help("Only functions of this organization ID"),
+                            Arg::new("limit")
+                                .short('l')
+                                .long("limit")
+                                .value_name("COUNT")
+                                .value_parser(value_parser!(u64))
+                                .help("The most functions to show [default: 100]"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("stop")
+                        .about("Stops every running instance of a function immediately")
+                        .arg(
+                            Arg::new("function")
+                                .value_name("FUNCTION_ID")
+                                .required(true)
+                                .help("The function's ID"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("capacity")
+                        .about("Shows the node's CPUs, memory, containers and load"),
+                )
+                .subcommand(
+                    Command::new("quota")
+                        .about("Manages the quota of an organization")
+                        .subcommand_required(true)
+                        .subcommand(
+                            Command::new("show")
+                                .about("Shows an organization's quota overrides and limits")
+                                .arg(namespace_arg()),
+                        )
+                        .subcommand(
+                            Command::new("set")
+                                .about(
+                                    "Overrides an organization's limits; the ones left out \
+                                     use the server defaults, 0 is unlimited",
+                                )
+                                .arg(namespace_arg())
+                                .args(quota_limit_args())
+                                .group(
+                                    ArgGroup::new("limits")
+                                        .args(admin::QUOTA_LIMITS.map(|(key, _)| key))
+                                        .multiple(true)
+                                        .required(true),
+                                ),
+                        )
+                        .subcommand(
+                            Command::new("reset")
+                                .about("Removes an organization's overrides")
+                                .arg(namespace_arg()),
+                        ),
+                ),
+        )
         .subcommand(
             Command::new("login")
                 .about("Login to the serverless platform")
@@ -53,14 +578,19 @@ fn main() {
                         .short('e')
                         .long("email")
                         .value_name("EMAIL")
-                        .required(true)
+                        .required_unless_present("sso")
                         .help("The email to login with"),
                     Arg::new("password")
                         .short('p')
                         .long("password")
                         .value_name("PASSWORD")
-                        .required(true)
+                        .required_unless_present("sso")
                         .help("The password to login with"),
+                    Arg::new("sso")
+                        .long("sso")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["email", "password"])
+                        .help("Login through your organization's identity provider in a browser"),
                 ]),
         )
         .subcommand(
@@ -79,7 +609,57 @@ fn main() {
                     .help("The password to register with"),
             ]),
         )
-        .subcommand(Command::new("logout").about("Logout from the serverless platform"))
+        .subcommand(
+            Command::new("verify")
+                .about("Verifies your email address with the token emailed to it")
+                .arg(
+                    Arg::new("token")
+                        .value_name("TOKEN")
+                        .required(true)
+                        .help("The token from the verification email"),
+                ),
+        )
+        .subcommand(
+            Command::new("password")
+                .about("Recovers a forgotten password")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("forgot")
+                        .about("Emails a password reset token")
+                        .arg(
+                            Arg::new("email")
+                                .value_name("EMAIL")
+                                .required(true)
+                                .help("The email of your account"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("reset")
+                        .about("Sets a new password with an emailed reset token")
+                        .args([
+                            Arg::new("token")
+                                .value_name("TOKEN")
+                                .required(true)
+                                .help("The token from the reset email"),
+                            Arg::new("password")
+                                .short('p')
+                                .long("password")
+                                .value_name("PASSWORD")
+                                .required(true)
+                                .help("The new password"),
+                        ]),
+                ),
+        )
+        .subcommand(
+            Command::new("logout")
+                .about("Logout from the serverless platform")
+                .arg(
+                    Arg::new("everywhere")
+                        .long("everywhere")
+                        .action(ArgAction::SetTrue)
+                        .help("Sign out every session of your account, on all machines"),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
@@ -100,8 +680,17 @@ fn main() {
             }
         }
         Some(("deploy", sub_matches)) => {
-            if let Some(name) = sub_matches.get_one::<String>("name") {
-                if let Err(err) = deploy_function(name) {
+            let format = sub_matches
+                .get_one::<String>("format")
+                .and_then(|format| format.parse().ok())
+                .unwrap_or(ArchiveFormat::Zip);
+            if sub_matches.get_flag("all") {
+                if let Err(err) = deploy_project(format) {
+                    eprintln!("Error deploying project: {}", err);
+                    process::exit(1);
+                }
+            } else if let Some(name) = sub_matches.get_one::<String>("name") {
+                if let Err(err) = deploy_function(name, format) {
                     eprintln!("Error deploying function: {}", err);
                     process::exit(1);
                 }
@@ -110,14 +699,213 @@ fn main() {
                 process::exit(1);
             }
         }
+        Some(("delete", sub_matches)) => {
+            if let Some(name) = sub_matches.get_one::<String>("name") {
+                if let Err(err) = delete_function(name) {
+                    eprintln!("Error deleting function: {}", err);
+                    process::exit(1);
+                }
+            } else {
+                eprintln!("Name parameter is required");
+                process::exit(1);
+            }
+        }
+        Some(("info", sub_matches)) => {
+            let name = sub_matches.get_one::<String>("name").expect("required");
+            if let Err(err) = function_info(name) {
+                eprintln!("Error getting function info: {}", err);
+                process::exit(1);
+            }
+        }
         Some(("list", _)) => {
             if let Err(err) = list_functions() {
                 eprintln!("Error getting function: {}", err);
                 process::exit(1);
             }
         }
+        Some(("usage", _)) => {
+            if let Err(err) = show_usage() {
+                eprintln!("Error getting usage: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("secrets", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("set", args)) => {
+                    let name = args.get_one::<String>("name").expect("required");
+                    let pairs = args
+                        .get_many::<String>("secrets")
+                        .expect("required")
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    set_secrets(name, &pairs)
+                }
+                Some(("unset", args)) => unset_secret(
+                    args.get_one::<String>("name").expect("required"),
+                    args.get_one::<String>("key").expect("required"),
+                ),
+                Some(("list", args)) => {
+                    list_secrets(args.get_one::<String>("name").expect("required"))
+                }
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error managing secrets: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("keys", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("create", args)) => create_api_key(
+                    args.get_one::<String>("name").expect("required"),
+                    args.get_one::<String>("key_name").expect("required"),
+                ),
+                Some(("list", args)) => {
+                    list_api_keys(args.get_one::<String>("name").expect("required"))
+                }
+                Some(("revoke", args)) => revoke_api_key(
+                    args.get_one::<String>("name").expect("required"),
+                    args.get_one::<String>("key_id").expect("required"),
+                ),
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error managing API keys: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("org", sub_matches)) => {
+            let arg = |args: &clap::ArgMatches, name: &str| {
+                args.get_one::<String>(name).expect("required").clone()
+            };
+            let result = match sub_matches.subcommand() {
+                Some(("create", args)) => create_organization(&arg(args, "name")),
+                Some(("list", _)) => list_organizations(),
+                Some(("switch", args)) => switch_organization(&arg(args, "name")),
+                Some(("members", _)) => list_members(),
+                Some(("invite", args)) => invite_member(&arg(args, "email"), &arg(args, "role")),
+                Some(("accept", args)) => accept_invitation(&arg(args, "token")),
+                Some(("set-role", args)) => set_member_role(&arg(args, "user"), &arg(args, "role")),
+                Some(("remove", args)) => remove_member(&arg(args, "user")),
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error managing organization: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("tokens", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("create", args)) => {
+                    let scopes = args
+                        .get_many::<String>("scope")
+                        .expect("required")
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    create_token(
+                        args.get_one::<String>("name").expect("required"),
+                        &scopes,
+                        args.get_one::<u32>("expires-in").copied(),
+                    )
+                }
+                Some(("list", _)) => list_tokens(),
+                Some(("revoke", args)) => {
+                    revoke_token(args.get_one::<String>("id").expect("required"))
+                }
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error managing tokens: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("audit", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("list", args)) => {
+                    list_events(&audit_query(args), args.get_one::<u64>("limit").copied())
+                }
+                Some(("export", args)) => export_events(
+                    &audit_query(args),
+                    args.get_one::<String>("output").map(String::as_str),
+                ),
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error reading the audit log: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("admin", sub_matches)) => {
+            let arg = |args: &clap::ArgMatches, name: &str| {
+                args.get_one::<String>(name).expect("required").clone()
+            };
+            let optional =
+                |args: &clap::ArgMatches, name: &str| args.get_one::<String>(name).cloned();
+            let result = match sub_matches.subcommand() {
+                Some(("users", args)) => {
+                    let suspended = if args.get_flag("suspended") {
+                        Some(true)
+                    } else if args.get_flag("active") {
+                        Some(false)
+                    } else {
+                        None
+                    };
+                    admin::list_users(
+                        optional(args, "search").as_deref(),
+                        suspended,
+                        args.get_one::<u64>("limit").copied(),
+                    )
+                }
+                Some(("user", args)) => admin::user_info(&arg(args, "user")),
+                Some(("suspend", args)) => admin::suspend_user(&arg(args, "user"), true),
+                Some(("unsuspend", args)) => admin::suspend_user(&arg(args, "user"), false),
+                Some(("set-role", args)) => {
+                    admin::set_user_role(&arg(args, "user"), &arg(args, "role"))
+                }
+                Some(("unlock", args)) => admin::unlock(&arg(args, "target")),
+                Some(("functions", args)) => admin::list_functions(
+                    optional(args, "search").as_deref(),
+                    optional(args, "namespace").as_deref(),
+                    args.get_one::<u64>("limit").copied(),
+                ),
+                Some(("stop", args)) => admin::stop_instances(&arg(args, "function")),
+                Some(("capacity", _)) => admin::node_capacity(),
+                Some(("quota", quota)) => match quota.subcommand() {
+                    Some(("show", args)) => admin::show_quota(&arg(args, "namespace")),
+                    Some(("set", args)) => {
+                        let limits = admin::QUOTA_LIMITS
+                            .iter()
+                            .filter_map(|(key, _)| {
+                                args.get_one::<u64>(key).map(|limit| (*key, *limit))
+                            })
+                            .collect::<Vec<_>>();
+                        admin::set_quota(&arg(args, "namespace"), &limits)
+                    }
+                    Some(("reset", args)) => admin::reset_quota(&arg(args, "namespace")),
+                    _ => unreachable!("subcommand is required"),
+                },
+                _ => unreachable!("subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("Error running admin command: {}", err);
+                process::exit(1);
+            }
+        }
         Some(("login", sub_matches)) => {
-            if let (Some(email), Some(password)) = (
+            if sub_matches.get_flag("sso") {
+                match login_sso() {
+                    Ok(session) => {
+                        println!(
+                            "Logged in successfully as {} (User ID: {})",
+                            session.email, session.user_uuid
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("Login failed: {}", err);
+                        process::exit(1);
+                    }
+                }
+            } else if let (Some(email), Some(password)) = (
                 sub_matches.get_one::<String>("email"),
                 sub_matches.get_one::<String>("password"),
             ) {
@@ -144,12 +932,18 @@ fn main() {
                 sub_matches.get_one::<String>("password"),
             ) {
                 match register(email, password) {
-                    Ok(session) => {
+                    Ok(Some(session)) => {
                         println!(
                             "Registered and logged in successfully as {} (User ID: {})",
                             session.email, session.user_uuid
                         );
                     }
+                    Ok(None) => {
+                        println!(
+                            "Registered {}. Open the link emailed to you, or run 'invok verify <TOKEN>', then log in",
+                            email
+                        );
+                    }
                     Err(err) => {
                         eprintln!("Registration failed: {}", err);
                         process::exit(1);
@@ -160,7 +954,38 @@ fn main() {
                 process::exit(1);
             }
         }
-        Some(("logout", _)) => match logout() {
+        Some(("verify", sub_matches)) => {
+            match verify_email(sub_matches.get_one::<String>("token").expect("required")) {
+                Ok(()) => println!("Email address verified; you can now log in"),
+                Err(err) => {
+                    eprintln!("Verification failed: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(("password", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("forgot", args)) => {
+                    forgot_password(args.get_one::<String>("email").expect("required")).map(|_| {
+                        "If an account uses this address, a reset token has been emailed to it"
+                    })
+                }
+                Some(("reset", args)) => reset_password(
+                    args.get_one::<String>("token").expect("required"),
+                    args.get_one::<String>("password").expect("required"),
+                )
+                .map(|_| "Password reset; every session was signed out, log in again"),
+                _ => unreachable!("subcommand is required"),
+            };
+            match result {
+                Ok(message) => println!("{}", message),
+                Err(err) => {
+                    eprintln!("Password recovery failed: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(("logout", sub_matches)) => match logout(sub_matches.get_flag("everywhere")) {
             Ok(_) => {
                 println!("Logged out successfully");
             }
@@ -0,0 +1,200 @@
+/*!
+Organization commands: creating organizations, managing members and switching
+the organization other commands act on.
+*/
+use crate::auth::{env_token, load_session, save_session, AuthError};
+use crate::host_manager;
+use crate::serverless_function::{api_error, authenticated_client, FunctionError};
+use serde_json::{json, Value};
+
+/// Creates an organization owned by the logged-in user.
+pub fn create_organization(name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::organizations_url())
+        .json(&json!({ "name": name }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let organization: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Organization '{}' created (ID: {})",
+        organization["name"].as_str().unwrap_or(name),
+        organization["uuid"].as_str().unwrap_or("N/A")
+    );
+    println!("Run 'invok org switch {}' to use it", name);
+    Ok(())
+}
+
+/// Lists the organizations the logged-in user belongs to, marking the active one.
+pub fn list_organizations() -> Result<(), FunctionError> {
+    let session = load_session()?;
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::organizations_url()).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let organizations: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    println!("  {:<36} {:<32} ROLE", "ID", "NAME");
+    for organization in organizations {
+        let uuid = organization["uuid"].as_str().unwrap_or("N/A");
+        let name = organization["name"].as_str().unwrap_or("N/A");
+        let active = match &session.organization {
+            Some(active) => active == name || active == uuid,
+            None => organization["personal"].as_bool().unwrap_or(false),
+        };
+        println!(
+            "{} {:<36} {:<32} {}",
+            if active { "*" } else { " " },
+            uuid,
+            name,
+            organization["role"].as_str().unwrap_or("N/A")
+        );
+    }
+    Ok(())
+}
+
+/// Makes `name` the organization other commands act on. `personal` switches back
+/// to the user's personal organization.
+pub fn switch_organization(name: &str) -> Result<(), FunctionError> {
+    // Switching saves the session, which must never persist a token from the environment.
+    if env_token().is_some() {
+        return Err(AuthError::AuthenticationError(
+            "Can't switch organization while INVOK_TOKEN is set; set INVOK_ORG instead".to_string(),
+        )
+        .into());
+    }
+    let mut session = load_session()?;
+    if name == "personal" {
+        session.organization = None;
+        save_session(&session)?;
+        println!("Switched to your personal organization");
+        return Ok(());
+    }
+
+    // Check membership before switching, so later commands don't fail obscurely.
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::organizations_url()).send()?;
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+    let organizations: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    let organization = organizations
+        .iter()
+        .find(|organization| organization["name"] == name || organization["uuid"] == name)
+        .ok_or_else(|| {
+            FunctionError::CompressionError(format!("You are not a member of '{}'", name))
+        })?;
+
+    session.organization = organization["name"].as_str().map(str::to_string);
+    save_session(&session)?;
+    println!(
+        "Switched to organization '{}' as {}",
+        organization["name"].as_str().unwrap_or(name),
+        organization["role"].as_str().unwrap_or("N/A")
+    );
+    Ok(())
+}
+
+/// Lists the members of the active organization.
+pub fn list_members() -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .get(host_manager::organization_members_url())
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let members: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    println!("{:<36} {:<32} ROLE", "USER ID", "EMAIL");
+    for member in members {
+        println!(
+            "{:<36} {:<32} {}",
+            member["user_uuid"].as_str().unwrap_or("N/A"),
+            member["email"].as_str().unwrap_or("N/A"),
+            member["role"].as_str().unwrap_or("N/A")
+        );
+    }
+    Ok(())
+}
+
+/// Invites someone to the active organization and prints the invitation token.
+pub fn invite_member(email: &str, role: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::organization_invitations_url())
+        .json(&json!({ "email": email, "role": role }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let invitation: Value = serde_json::from_str(&response.text()?)?;
+    println!("Invited {} as {}", email, role);
+    println!(
+        "Share this token; they can join with 'invok org accept <TOKEN>' until {}:",
+        invitation["expires_at"].as_str().unwrap_or("N/A")
+    );
+    println!("{}", invitation["token"].as_str().unwrap_or("N/A"));
+    Ok(())
+}
+
+/// Accepts an invitation to join an organization.
+pub fn accept_invitation(token: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::accept_invitation_url())
+        .json(&json!({ "token": token }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let organization: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Joined organization '{}' as {}",
+        organization["name"].as_str().unwrap_or("N/A"),
+        organization["role"].as_str().unwrap_or("N/A")
+    );
+    Ok(())
+}
+
+/// Changes the role of a member of the active organization.
+pub fn set_member_role(user_uuid: &str, role: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .put(host_manager::organization_member_url(user_uuid))
+        .json(&json!({ "role": role }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    println!("Role of {} set to {}", user_uuid, role);
+    Ok(())
+}
+
+/// Removes a member from the active organization.
+pub fn remove_member(user_uuid: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .delete(host_manager::organization_member_url(user_uuid))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    println!("Removed {} from the organization", user_uuid);
+    Ok(())
+}
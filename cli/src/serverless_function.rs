@@ -1,11 +1,14 @@
 // use crate::template::ROUTES_TEMPLATE;
-use crate::auth::{load_session, AuthError};
+use crate::auth::{active_session, AuthError};
 use crate::host_manager;
 use crate::utils::{create_fn_project_file, init_go_mod, GlobalConfig};
 use reqwest::blocking::{multipart, Client};
 use reqwest::header::{self, HeaderMap, HeaderValue};
 use serde_json::Value;
-use shared_utils::{compress_dir_with_excludes, template::ROUTES_TEMPLATE, to_camel_case_handler};
+use shared_utils::{
+    compress_dir, template::ROUTES_TEMPLATE, to_camel_case_handler, validate_function_name,
+    ArchiveFormat,
+};
 use std::fs::File;
 use std::io::{self, Cursor, Read, Write};
 use std::path::Path;
@@ -14,6 +17,8 @@ use thiserror::Error;
 
 // Constants
 const REQUEST_TIMEOUT_SECS: u64 = 120;
+/// Header selecting the organization a request acts on.
+const ORGANIZATION_HEADER: &str = "x-invok-org";
 const CONFIG_FILE_PATH: &str = "./config.json";
 
 /// Errors that can occur during serverless function operations
@@ -36,6 +41,9 @@ pub enum FunctionError {
 
     #[error("Authentication error: {0}")]
     AuthError(#[from] AuthError),
+
+    #[error("Invalid function name '{0}': {1}")]
+    InvalidName(String, &'static str),
 }
 
 /// Creates a new serverless function project with the specified name and runtime.
@@ -49,6 +57,7 @@ pub enum FunctionError {
 ///
 /// A Result indicating success or containing an error
 pub fn create_new_project(name: &str, runtime: &str) -> Result<(), FunctionError> {
+    validate_function_name(name).map_err(|e| FunctionError::InvalidName(name.to_string(), e))?;
     println!("Creating service... '{name}' [RUNTIME:'{runtime}']");
     let handler_name = to_camel_case_handler(name);
 
@@ -73,22 +82,7 @@ pub fn create_new_project(name: &str, runtime: &str) -> Result<(), FunctionError
 
 /// List all functions
 pub fn list_functions() -> Result<(), FunctionError> {
-    // Load authentication session
-    let session = load_session()?;
-
-    // Set up authorization headers
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        header::AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", session.token))
-            .map_err(|_| FunctionError::CompressionError("Invalid token format".to_string()))?,
-    );
-
-    // Build client with timeout
-    let client = Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .default_headers(headers)
-        .build()?;
+    let client = authenticated_client()?;
 
     // Send request to API
     let response = client.get(host_manager::function_list_url()).send()?;
@@ -105,7 +99,7 @@ pub fn list_functions() -> Result<(), FunctionError> {
 
         // Print table header
         println!("+--------------------------------------+----------------------+---------+");
-        println!("| UUID                                 | Name                 | Runtime |");
+        println!("| ID                                   | Name                 | Runtime |");
         println!("+--------------------------------------+----------------------+---------+");
 
         // Print each function as a table row
@@ -135,6 +129,344 @@ pub fn list_functions() -> Result<(), FunctionError> {
     }
 }
 
+/// Shows what the current organization uses against its quota.
+pub fn show_usage() -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::usage_url()).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let usage: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Namespace: {}",
+        usage["namespace"].as_str().unwrap_or("N/A")
+    );
+    println!("{:<22} {:>10} {:>10}", "QUOTA", "USED", "LIMIT");
+    for (label, key) in [
+        ("Functions", "functions"),
+        ("Running instances", "instances"),
+        ("Memory (MB)", "memory_mb"),
+        ("Builds this hour", "builds_this_hour"),
+        ("Invocations today", "invocations_today"),
+    ] {
+        let item = &usage[key];
+        let limit = item["limit"]
+            .as_u64()
+            .map_or_else(|| "unlimited".to_string(), |limit| limit.to_string());
+        println!(
+            "{:<22} {:>10} {:>10}",
+            label,
+            item["used"].as_u64().unwrap_or(0),
+            limit
+        );
+    }
+    Ok(())
+}
+
+/// Deletes a deployed function and all of its resources.
+///
+/// # Arguments
+///
+/// * `name` - The name of the function to delete
+///
+/// # Returns
+///
+/// A Result indicating success or containing an error
+pub fn delete_function(name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+
+    // Send request to API
+    let response = client.delete(host_manager::function_url(name)).send()?;
+
+    // Check the response
+    if response.status().is_success() {
+        let report: Value = serde_json::from_str(&response.text()?)?;
+        println!("Function '{}' deleted", name);
+
+        let print_list = |label: &str, key: &str| {
+            let items = report[key].as_array().cloned().unwrap_or_default();
+            println!("  {:<19} {}", format!("{label}:"), items.len());
+            for item in items {
+                println!("    - {}", item.as_str().unwrap_or("N/A"));
+            }
+        };
+        print_list("Cache entries", "cache_entries");
+        print_list("Instances stopped", "instances_stopped");
+        print_list("Images removed", "images_removed");
+        print_list("Failures", "failures");
+
+        Ok(())
+    } else {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        Err(FunctionError::CompressionError(format!(
+            "API error: Status code {}. {}",
+            status, error_text
+        )))
+    }
+}
+
+/// Shows the metadata and live status of a deployed function.
+///
+/// # Arguments
+///
+/// * `name` - The name of the function
+pub fn function_info(name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::function_url(name)).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let info: Value = serde_json::from_str(&response.text()?)?;
+    let field = |key: &str| match &info[key] {
+        Value::Null => "-".to_string(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+
+    println!("{:<18} {}", "Name:", field("name"));
+    println!("{:<18} {}", "ID:", field("uuid"));
+    println!("{:<18} {}", "Namespace:", field("namespace"));
+    println!("{:<18} {}", "Runtime:", field("runtime"));
+    println!("{:<18} {}", "Description:", field("description"));
+    let labels = info["labels"].as_object().cloned().unwrap_or_default();
+    if labels.is_empty() {
+        println!("{:<18} -", "Labels:");
+    } else {
+        println!("Labels:");
+        for (key, value) in labels {
+            println!("  {}={}", key, value.as_str().unwrap_or("N/A"));
+        }
+    }
+    println!("{:<18} {}", "Latest version:", field("latest_version"));
+    println!("{:<18} {}", "Max concurrency:", field("max_concurrency"));
+    println!(
+        "{:<18} {}",
+        "Auth:",
+        info["auth"]["mode"].as_str().unwrap_or("public")
+    );
+    println!("{:<18} {}", "Image digest:", field("image_digest"));
+    println!("{:<18} {}", "Created at:", field("created_at"));
+    println!("{:<18} {}", "Updated at:", field("updated_at"));
+    println!("{:<18} {}", "Last deployed at:", field("last_deployed_at"));
+    println!("{:<18} {}", "Status:", field("status"));
+    for instance in info["running_instances"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+    {
+        println!(
+            "  - v{} at {} ({} in flight)",
+            instance["version"],
+            instance["address"].as_str().unwrap_or("N/A"),
+            instance["in_flight"]
+        );
+    }
+    println!("{:<18} {}", "Last invocation:", field("last_invocation_at"));
+    match info["last_error"].as_object() {
+        Some(error) => println!(
+            "{:<18} {} ({})",
+            "Last error:",
+            error["message"].as_str().unwrap_or("N/A"),
+            error["at"].as_str().unwrap_or("N/A")
+        ),
+        None => println!("{:<18} -", "Last error:"),
+    }
+
+    Ok(())
+}
+
+/// Sets secrets on a deployed function from `KEY=VALUE` pairs.
+///
+/// Secrets are encrypted by the server and injected as environment variables
+/// when the function's containers start.
+///
+/// # Arguments
+///
+/// * `name` - The name of the function
+/// * `pairs` - The secrets to set, each formatted as `KEY=VALUE`
+pub fn set_secrets(name: &str, pairs: &[String]) -> Result<(), FunctionError> {
+    let mut secrets = serde_json::Map::new();
+    for pair in pairs {
+        let (key, value) = pair.split_once('=').ok_or_else(|| {
+            FunctionError::CompressionError(format!("Expected KEY=VALUE, got '{}'", pair))
+        })?;
+        secrets.insert(key.to_string(), Value::String(value.to_string()));
+    }
+
+    let client = authenticated_client()?;
+    let response = client
+        .put(host_manager::function_secrets_url(name))
+        .json(&serde_json::json!({ "secrets": secrets }))
+        .send()?;
+
+    if response.status().is_success() {
+        let body: Value = serde_json::from_str(&response.text()?)?;
+        for key in body["set"].as_array().cloned().unwrap_or_default() {
+            println!("Secret '{}' set", key.as_str().unwrap_or("N/A"));
+        }
+        Ok(())
+    } else {
+        Err(api_error(response))
+    }
+}
+
+/// Removes a secret from a deployed function.
+pub fn unset_secret(name: &str, key: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .delete(host_manager::function_secret_url(name, key))
+        .send()?;
+
+    if response.status().is_success() {
+        println!("Secret '{}' unset", key);
+        Ok(())
+    } else {
+        Err(api_error(response))
+    }
+}
+
+/// Lists the secret names of a deployed function. Values are never shown.
+pub fn list_secrets(name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .get(host_manager::function_secrets_url(name))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let secrets: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    if secrets.is_empty() {
+        println!("No secrets set for function '{}'", name);
+        return Ok(());
+    }
+
+    println!("{:<32} UPDATED AT", "KEY");
+    for secret in secrets {
+        println!(
+            "{:<32} {}",
+            secret["key"].as_str().unwrap_or("N/A"),
+            secret["updated_at"].as_str().unwrap_or("N/A")
+        );
+    }
+    Ok(())
+}
+
+/// Creates an API key for invoking a function whose auth mode is `api_key`.
+/// The key is only shown once.
+pub fn create_api_key(name: &str, key_name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::function_keys_url(name))
+        .json(&serde_json::json!({ "name": key_name }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let key: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Created API key '{}' (ID: {})",
+        key["name"].as_str().unwrap_or("N/A"),
+        key["uuid"].as_str().unwrap_or("N/A")
+    );
+    println!("Copy it now, it won't be shown again:\n");
+    println!("{}", key["key"].as_str().unwrap_or("N/A"));
+    Ok(())
+}
+
+/// Lists the API keys of a function. Secrets are never shown.
+pub fn list_api_keys(name: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::function_keys_url(name)).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let keys: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    if keys.is_empty() {
+        println!("No API keys for function '{}'", name);
+        return Ok(());
+    }
+
+    println!("{:<36} {:<20} {:<20} LAST USED", "ID", "NAME", "PREFIX");
+    for key in keys {
+        println!(
+            "{:<36} {:<20} {:<20} {}",
+            key["uuid"].as_str().unwrap_or("N/A"),
+            key["name"].as_str().unwrap_or("N/A"),
+            key["prefix"].as_str().unwrap_or("N/A"),
+            key["last_used_at"].as_str().unwrap_or("never")
+        );
+    }
+    Ok(())
+}
+
+/// Revokes an API key of a function.
+pub fn revoke_api_key(name: &str, key_uuid: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .delete(host_manager::function_key_url(name, key_uuid))
+        .send()?;
+
+    if response.status().is_success() {
+        println!("API key '{}' revoked", key_uuid);
+        Ok(())
+    } else {
+        Err(api_error(response))
+    }
+}
+
+/// Converts an unsuccessful API response into an error.
+pub(crate) fn api_error(response: reqwest::blocking::Response) -> FunctionError {
+    let status = response.status();
+    let error_text = response
+        .text()
+        .unwrap_or_else(|_| "Unknown error".to_string());
+
+    FunctionError::CompressionError(format!("API error: Status code {}. {}", status, error_text))
+}
+
+/// Builds an HTTP client that sends the saved session's bearer token, and the
+/// active organization when one was selected with `invok org switch`.
+pub(crate) fn authenticated_client() -> Result<Client, FunctionError> {
+    // Load authentication session
+    let session = active_session()?;
+
+    // Set up authorization headers
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", session.token))
+            .map_err(|_| FunctionError::CompressionError("Invalid token format".to_string()))?,
+    );
+    if let Some(organization) = &session.organization {
+        headers.insert(
+            ORGANIZATION_HEADER,
+            HeaderValue::from_str(organization).map_err(|_| {
+                FunctionError::CompressionError("Invalid organization name".to_string())
+            })?,
+        );
+    }
+
+    // Build client with timeout
+    Ok(Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .default_headers(headers)
+        .build()?)
+}
+
 /// Deploys an existing function to the serverless platform using authentication.
 ///
 /// # Arguments
@@ -144,7 +476,7 @@ pub fn list_functions() -> Result<(), FunctionError> {
 /// # Returns
 ///
 /// A Result indicating success or containing an error
-pub fn deploy_function(name: &str) -> Result<(), FunctionError> {
+pub fn deploy_function(name: &str, format: ArchiveFormat) -> Result<(), FunctionError> {
     // Read configuration file
     let mut config_file = File::open(CONFIG_FILE_PATH)?;
     let mut contents = String::new();
@@ -160,48 +492,113 @@ pub fn deploy_function(name: &str) -> Result<(), FunctionError> {
     let _runtime = config.runtime;
     println!("Deploying service... '{}'", name);
 
-    // Create ZIP archive
-    let mut dest_zip = Cursor::new(Vec::new());
-    compress_dir_with_excludes(Path::new(name), &mut dest_zip, &["go.mod", "go.sum"])
+    // Create the archive
+    let archive = compress_dir(Path::new(name), format, &["go.mod", "go.sum"])
         .map_err(|e| FunctionError::CompressionError(e.to_string()))?;
 
-    // Reset the cursor to the beginning of the buffer
-    dest_zip.set_position(0);
-
-    println!("Zipped up the folder service... '{}'", name);
+    println!("Packed up the folder service as {}... '{}'", format, name);
 
     // Try authenticated deployment first
-    deploy_with_auth(name, dest_zip)?;
+    deploy_with_auth(name, format, Cursor::new(archive))?;
 
     Ok(())
 }
 
-/// Deploy a function using authentication
-fn deploy_with_auth(name: &str, dest_zip: Cursor<Vec<u8>>) -> Result<String, FunctionError> {
-    // Load authentication session
-    let session = load_session()?;
+/// Deploys every function listed in the project's `config.json` in a single request.
+///
+/// The server builds the functions in parallel and reports a result for each of
+/// them, so one broken function doesn't hide the outcome of the others.
+///
+/// # Arguments
+///
+/// * `format` - The archive format used to upload the functions
+///
+/// # Returns
+///
+/// An error if the request failed or any function failed to deploy
+pub fn deploy_project(format: ArchiveFormat) -> Result<(), FunctionError> {
+    // Read the project manifest
+    let contents = std::fs::read_to_string(CONFIG_FILE_PATH)?;
+    let config: GlobalConfig = serde_json::from_str(&contents)?;
+    if config.function_name.is_empty() {
+        return Err(FunctionError::FunctionNotFound(
+            "no functions listed in config.json".to_string(),
+        ));
+    }
+
+    let mut form = multipart::Form::new();
+    for name in &config.function_name {
+        let archive = compress_dir(Path::new(name), format, &["go.mod", "go.sum"])
+            .map_err(|e| FunctionError::CompressionError(format!("{name}: {e}")))?;
+        println!("Packed up the folder service as {}... '{}'", format, name);
+
+        form = form.part(
+            "file",
+            multipart::Part::bytes(archive)
+                .file_name(format!("{name}{}", format.extension()))
+                .mime_str(format.mime_type())?,
+        );
+    }
+
+    println!("Deploying {} services...", config.function_name.len());
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::project_upload_url())
+        .multipart(form)
+        .send()?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(api_error(response));
+    }
+
+    let body: Value = serde_json::from_str(&response.text()?)?;
+    let results = body["results"].as_array().cloned().unwrap_or_default();
+    let mut failed = 0;
+    for result in &results {
+        let name = result["function"].as_str().unwrap_or("N/A");
+        let message = result["message"].as_str().unwrap_or("");
+        if result["success"].as_bool().unwrap_or(false) {
+            println!("  [ok]     {}: {}", name, message);
+        } else {
+            failed += 1;
+            println!("  [failed] {}: {}", name, message);
+            for error in result["errors"].as_array().cloned().unwrap_or_default() {
+                println!(
+                    "             - {}: {}",
+                    error["field"].as_str().unwrap_or("N/A"),
+                    error["message"].as_str().unwrap_or("")
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(FunctionError::CompressionError(format!(
+            "{} of {} functions failed to deploy",
+            failed,
+            results.len()
+        )));
+    }
+    println!("All {} functions deployed", results.len());
+    Ok(())
+}
 
+/// Deploy a function using authentication
+fn deploy_with_auth(
+    name: &str,
+    format: ArchiveFormat,
+    archive: Cursor<Vec<u8>>,
+) -> Result<String, FunctionError> {
     // Create multipart form
     let form = multipart::Form::new().part(
         "file",
-        multipart::Part::reader(dest_zip)
-            .file_name(format!("{name}.zip"))
-            .mime_str("application/zip")?,
+        multipart::Part::reader(archive)
+            .file_name(format!("{name}{}", format.extension()))
+            .mime_str(format.mime_type())?,
     );
 
-    // Set up authorization headers
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        header::AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", session.token))
-            .map_err(|_| FunctionError::CompressionError("Invalid token format".to_string()))?,
-    );
-
-    // Build client with timeout
-    let client = Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .default_headers(headers)
-        .build()?;
+    let client = authenticated_client()?;
 
     // Send request to API
     let response = client
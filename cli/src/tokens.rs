@@ -0,0 +1,111 @@
+/*!
+Personal access token commands, for authenticating CI jobs and scripts through
+the `INVOK_TOKEN` environment variable.
+*/
+use crate::host_manager;
+use crate::serverless_function::{api_error, authenticated_client, FunctionError};
+use serde_json::{json, Value};
+
+/// Creates a personal access token and prints its secret, which is shown only once.
+///
+/// # Arguments
+///
+/// * `name` - A name to recognise the token by
+/// * `scopes` - The scopes to grant: `read`, `deploy`, `invoke` or `admin`
+/// * `expires_in_days` - Days until the token expires, from 1 to 365; the server default when `None`
+pub fn create_token(
+    name: &str,
+    scopes: &[String],
+    expires_in_days: Option<u32>,
+) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .post(host_manager::auth_tokens_url())
+        .json(&json!({
+            "name": name,
+            "scopes": scopes,
+            "expires_in_days": expires_in_days,
+        }))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let token: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Token '{}' created (ID: {})",
+        token["name"].as_str().unwrap_or(name),
+        token["uuid"].as_str().unwrap_or("N/A")
+    );
+    println!(
+        "Expires: {}",
+        token["expires_at"].as_str().unwrap_or("never")
+    );
+    println!();
+    println!("{}", token["token"].as_str().unwrap_or("N/A"));
+    println!();
+    println!("Copy the token now, it won't be shown again. Use it by setting INVOK_TOKEN.");
+    Ok(())
+}
+
+/// Lists the logged-in user's personal access tokens.
+pub fn list_tokens() -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client.get(host_manager::auth_tokens_url()).send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let tokens: Vec<Value> = serde_json::from_str(&response.text()?)?;
+    if tokens.is_empty() {
+        println!("No tokens");
+        return Ok(());
+    }
+    println!(
+        "{:<36} {:<20} {:<18} {:<24} {:<25} LAST USED",
+        "ID", "NAME", "PREFIX", "SCOPES", "EXPIRES"
+    );
+    for token in tokens {
+        let scopes = token["scopes"]
+            .as_array()
+            .map(|scopes| {
+                scopes
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
+        println!(
+            "{:<36} {:<20} {:<18} {:<24} {:<25} {}",
+            token["uuid"].as_str().unwrap_or("N/A"),
+            token["name"].as_str().unwrap_or("N/A"),
+            token["prefix"].as_str().unwrap_or("N/A"),
+            scopes,
+            token["expires_at"].as_str().unwrap_or("never"),
+            token["last_used_at"].as_str().unwrap_or("never")
+        );
+    }
+    Ok(())
+}
+
+/// Revokes a personal access token, after which it can no longer be used.
+pub fn revoke_token(token_uuid: &str) -> Result<(), FunctionError> {
+    let client = authenticated_client()?;
+    let response = client
+        .delete(host_manager::auth_token_url(token_uuid))
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(api_error(response));
+    }
+
+    let token: Value = serde_json::from_str(&response.text()?)?;
+    println!(
+        "Token '{}' revoked",
+        token["name"].as_str().unwrap_or(token_uuid)
+    );
+    Ok(())
+}
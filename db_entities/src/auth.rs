@@ -11,12 +11,41 @@ pub struct Model {
     pub email: String,
     pub password: String,
     pub uuid: Uuid,
+    pub email_verified_at: Option<DateTimeWithTimeZone>,
+    pub role: String,
+    pub suspended_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::access_token::Entity")]
+    AccessToken,
+    #[sea_orm(has_many = "super::auth_identity::Entity")]
+    AuthIdentity,
+    #[sea_orm(has_many = "super::auth_session::Entity")]
+    AuthSession,
     #[sea_orm(has_many = "super::function::Entity")]
     Function,
+    #[sea_orm(has_many = "super::organization_member::Entity")]
+    OrganizationMember,
+}
+
+impl Related<super::access_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AccessToken.def()
+    }
+}
+
+impl Related<super::auth_identity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuthIdentity.def()
+    }
+}
+
+impl Related<super::auth_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuthSession.def()
+    }
 }
 
 impl Related<super::function::Entity> for Entity {
@@ -25,4 +54,10 @@ impl Related<super::function::Entity> for Entity {
     }
 }
 
+impl Related<super::organization_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrganizationMember.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -9,8 +9,23 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub runtime: String,
+    #[sea_orm(unique)]
     pub uuid: Uuid,
+    pub namespace: Uuid,
     pub auth_id: i32,
+    pub max_concurrency: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub env: Option<Json>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+    pub last_deployed_at: Option<DateTimeWithTimeZone>,
+    pub image_digest: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub labels: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub auth: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,6 +38,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Auth,
+    #[sea_orm(has_many = "super::function_alias::Entity")]
+    FunctionAlias,
+    #[sea_orm(has_many = "super::function_api_key::Entity")]
+    FunctionApiKey,
+    #[sea_orm(has_many = "super::function_secret::Entity")]
+    FunctionSecret,
+    #[sea_orm(has_many = "super::function_version::Entity")]
+    FunctionVersion,
 }
 
 impl Related<super::auth::Entity> for Entity {
@@ -31,4 +54,28 @@ impl Related<super::auth::Entity> for Entity {
     }
 }
 
+impl Related<super::function_alias::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FunctionAlias.def()
+    }
+}
+
+impl Related<super::function_api_key::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FunctionApiKey.def()
+    }
+}
+
+impl Related<super::function_secret::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FunctionSecret.def()
+    }
+}
+
+impl Related<super::function_version::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FunctionVersion.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,5 +2,18 @@
 
 pub mod prelude;
 
+pub mod access_token;
+pub mod audit_event;
 pub mod auth;
+pub mod auth_identity;
+pub mod auth_session;
 pub mod function;
+pub mod function_alias;
+pub mod function_api_key;
+pub mod function_secret;
+pub mod function_version;
+pub mod namespace_quota;
+pub mod organization;
+pub mod organization_invitation;
+pub mod organization_member;
+pub mod refresh_token;
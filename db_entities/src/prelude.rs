@@ -1,4 +1,17 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.4
 
+pub use super::access_token::Entity as AccessToken;
+pub use super::audit_event::Entity as AuditEvent;
 pub use super::auth::Entity as Auth;
+pub use super::auth_identity::Entity as AuthIdentity;
+pub use super::auth_session::Entity as AuthSession;
 pub use super::function::Entity as Function;
+pub use super::function_alias::Entity as FunctionAlias;
+pub use super::function_api_key::Entity as FunctionApiKey;
+pub use super::function_secret::Entity as FunctionSecret;
+pub use super::function_version::Entity as FunctionVersion;
+pub use super::namespace_quota::Entity as NamespaceQuota;
+pub use super::organization::Entity as Organization;
+pub use super::organization_invitation::Entity as OrganizationInvitation;
+pub use super::organization_member::Entity as OrganizationMember;
+pub use super::refresh_token::Entity as RefreshToken;
@@ -8,8 +8,46 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20250111_230947_create_auth_table::Migration),
             Box::new(m20250111_231042_create_function_table::Migration),
+            Box::new(m20261018_090000_add_function_max_concurrency::Migration),
+            Box::new(m20261018_100000_create_function_version_table::Migration),
+            Box::new(m20261018_100100_create_function_alias_table::Migration),
+            Box::new(m20261018_110000_create_function_secret_table::Migration),
+            Box::new(m20261018_120000_add_function_env::Migration),
+            Box::new(m20261018_130000_add_function_version_digests::Migration),
+            Box::new(m20261018_140000_add_function_version_resources::Migration),
+            Box::new(m20261018_150000_add_function_metadata::Migration),
+            Box::new(m20261018_160000_add_function_namespace::Migration),
+            Box::new(m20261018_170000_create_organization_tables::Migration),
+            Box::new(m20261018_180000_create_access_token_table::Migration),
+            Box::new(m20261018_190000_create_auth_session_tables::Migration),
+            Box::new(m20261018_200000_add_auth_email_verified_at::Migration),
+            Box::new(m20261018_210000_create_auth_identity_table::Migration),
+            Box::new(m20261018_220000_add_function_auth::Migration),
+            Box::new(m20261018_220100_create_function_api_key_table::Migration),
+            Box::new(m20261018_230000_create_audit_event_table::Migration),
+            Box::new(m20261018_233000_add_auth_role_and_suspension::Migration),
+            Box::new(m20261019_000000_create_namespace_quota_table::Migration),
         ]
     }
 }
 mod m20250111_230947_create_auth_table;
 mod m20250111_231042_create_function_table;
+mod m20261018_090000_add_function_max_concurrency;
+mod m20261018_100000_create_function_version_table;
+mod m20261018_100100_create_function_alias_table;
+mod m20261018_110000_create_function_secret_table;
+mod m20261018_120000_add_function_env;
+mod m20261018_130000_add_function_version_digests;
+mod m20261018_140000_add_function_version_resources;
+mod m20261018_150000_add_function_metadata;
+mod m20261018_160000_add_function_namespace;
+mod m20261018_170000_create_organization_tables;
+mod m20261018_180000_create_access_token_table;
+mod m20261018_190000_create_auth_session_tables;
+mod m20261018_200000_add_auth_email_verified_at;
+mod m20261018_210000_create_auth_identity_table;
+mod m20261018_220000_add_function_auth;
+mod m20261018_220100_create_function_api_key_table;
+mod m20261018_230000_create_audit_event_table;
+mod m20261018_233000_add_auth_role_and_suspension;
+mod m20261019_000000_create_namespace_quota_table;
@@ -0,0 +1,94 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionVersion::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FunctionVersion::Id))
+                    .col(integer(FunctionVersion::FunctionId))
+                    .col(integer(FunctionVersion::Version))
+                    .col(string(FunctionVersion::ImageTag))
+                    .col(
+                        timestamp_with_time_zone(FunctionVersion::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-function_version-function_id")
+                            .from(FunctionVersion::Table, FunctionVersion::FunctionId)
+                            .to(Function::Table, Function::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Version numbers are unique per function
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-function_version-function-version-unique")
+                    .table(FunctionVersion::Table)
+                    .col(FunctionVersion::FunctionId)
+                    .col(FunctionVersion::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing functions were built under the unversioned `{name}-{hash}` tag,
+        // record that image as their first version.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                INSERT INTO function_version (function_id, version, image_tag)
+                SELECT id, 1, name || '-' || substr(md5(uuid::text), 1, 20)
+                FROM function
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop index first
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function_version-function-version-unique")
+                    .to_owned(),
+            )
+            .await?;
+
+        // Then drop table
+        manager
+            .drop_table(Table::drop().table(FunctionVersion::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum FunctionVersion {
+    Table,
+    Id,
+    FunctionId,
+    Version,
+    ImageTag,
+    CreatedAt,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionAlias::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FunctionAlias::Id))
+                    .col(integer(FunctionAlias::FunctionId))
+                    .col(string(FunctionAlias::Name))
+                    .col(integer(FunctionAlias::Version))
+                    .col(integer_null(FunctionAlias::CanaryVersion))
+                    .col(integer(FunctionAlias::CanaryWeight).default(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-function_alias-function_id")
+                            .from(FunctionAlias::Table, FunctionAlias::FunctionId)
+                            .to(Function::Table, Function::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Alias names are unique per function
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-function_alias-function-name-unique")
+                    .table(FunctionAlias::Table)
+                    .col(FunctionAlias::FunctionId)
+                    .col(FunctionAlias::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop index first
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function_alias-function-name-unique")
+                    .to_owned(),
+            )
+            .await?;
+
+        // Then drop table
+        manager
+            .drop_table(Table::drop().table(FunctionAlias::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum FunctionAlias {
+    Table,
+    Id,
+    FunctionId,
+    Name,
+    Version,
+    CanaryVersion,
+    CanaryWeight,
+}
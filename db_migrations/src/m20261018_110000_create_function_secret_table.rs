@@ -0,0 +1,81 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionSecret::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FunctionSecret::Id))
+                    .col(integer(FunctionSecret::FunctionId))
+                    .col(string(FunctionSecret::Key))
+                    .col(string(FunctionSecret::Nonce))
+                    .col(text(FunctionSecret::Ciphertext))
+                    .col(
+                        timestamp_with_time_zone(FunctionSecret::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-function_secret-function_id")
+                            .from(FunctionSecret::Table, FunctionSecret::FunctionId)
+                            .to(Function::Table, Function::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Secret keys are unique per function
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-function_secret-function-key-unique")
+                    .table(FunctionSecret::Table)
+                    .col(FunctionSecret::FunctionId)
+                    .col(FunctionSecret::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop index first
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function_secret-function-key-unique")
+                    .to_owned(),
+            )
+            .await?;
+
+        // Then drop table
+        manager
+            .drop_table(Table::drop().table(FunctionSecret::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum FunctionSecret {
+    Table,
+    Id,
+    FunctionId,
+    Key,
+    Nonce,
+    Ciphertext,
+    UpdatedAt,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SHA-256 of the uploaded archive, and of everything that went into the image
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FunctionVersion::Table)
+                    .add_column_if_not_exists(string_null(FunctionVersion::ArtifactDigest))
+                    .add_column_if_not_exists(string_null(FunctionVersion::BuildDigest))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-function_version-artifact_digest")
+                    .table(FunctionVersion::Table)
+                    .col(FunctionVersion::ArtifactDigest)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function_version-artifact_digest")
+                    .table(FunctionVersion::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FunctionVersion::Table)
+                    .drop_column(FunctionVersion::ArtifactDigest)
+                    .drop_column(FunctionVersion::BuildDigest)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionVersion {
+    Table,
+    ArtifactDigest,
+    BuildDigest,
+}
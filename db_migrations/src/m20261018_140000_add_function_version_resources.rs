@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Container limits from the function's config; NULL uses the server defaults
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FunctionVersion::Table)
+                    .add_column_if_not_exists(integer_null(FunctionVersion::MemoryMb))
+                    .add_column_if_not_exists(integer_null(FunctionVersion::CpuMillis))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FunctionVersion::Table)
+                    .drop_column(FunctionVersion::MemoryMb)
+                    .drop_column(FunctionVersion::CpuMillis)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionVersion {
+    Table,
+    MemoryMb,
+    CpuMillis,
+}
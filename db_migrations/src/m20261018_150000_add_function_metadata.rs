@@ -0,0 +1,59 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing functions get the migration time as their creation and update time
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Function::Table)
+                    .add_column_if_not_exists(
+                        timestamp_with_time_zone(Function::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column_if_not_exists(
+                        timestamp_with_time_zone(Function::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column_if_not_exists(timestamp_with_time_zone_null(
+                        Function::LastDeployedAt,
+                    ))
+                    .add_column_if_not_exists(string_null(Function::ImageDigest))
+                    .add_column_if_not_exists(text_null(Function::Description))
+                    .add_column_if_not_exists(json_binary_null(Function::Labels))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Function::Table)
+                    .drop_column(Function::CreatedAt)
+                    .drop_column(Function::UpdatedAt)
+                    .drop_column(Function::LastDeployedAt)
+                    .drop_column(Function::ImageDigest)
+                    .drop_column(Function::Description)
+                    .drop_column(Function::Labels)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    CreatedAt,
+    UpdatedAt,
+    LastDeployedAt,
+    ImageDigest,
+    Description,
+    Labels,
+}
@@ -0,0 +1,171 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Function::Table)
+                    .add_column_if_not_exists(uuid_null(Function::Namespace))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Functions used to carry their owner's UUID, which is the namespace they
+        // are invoked under. Each one now gets its own ID.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE "function" SET "namespace" = "auth"."uuid"
+               FROM "auth" WHERE "auth"."id" = "function"."auth_id""#,
+        )
+        .await?;
+        db.execute_unprepared(r#"UPDATE "function" SET "uuid" = gen_random_uuid()"#)
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Function::Table)
+                    .modify_column(ColumnDef::new(Function::Namespace).uuid().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-function-uuid-unique")
+                    .table(Function::Table)
+                    .col(Function::Uuid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-function-namespace-name-unique")
+                    .table(Function::Table)
+                    .col(Function::Namespace)
+                    .col(Function::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function-namespace-name-unique")
+                    .table(Function::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-function-uuid-unique")
+                    .table(Function::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(r#"UPDATE "function" SET "uuid" = "namespace""#)
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Function::Table)
+                    .drop_column(Function::Namespace)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Name,
+    Uuid,
+    Namespace,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{
+        Database, DatabaseBackend, DatabaseConnection, ProxyDatabaseTrait, ProxyExecResult,
+        ProxyRow, Statement,
+    };
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    /// A database that records the statements it is sent instead of running them.
+    #[derive(Debug)]
+    struct RecordingDatabase(Log);
+
+    #[async_trait::async_trait]
+    impl ProxyDatabaseTrait for RecordingDatabase {
+        async fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+            self.0.lock().unwrap().push(statement.sql);
+            Ok(Vec::new())
+        }
+
+        async fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+            self.0.lock().unwrap().push(statement.sql);
+            Ok(ProxyExecResult::default())
+        }
+    }
+
+    async fn connect() -> (DatabaseConnection, Log) {
+        let log = Log::default();
+        let proxy: Box<dyn ProxyDatabaseTrait> = Box::new(RecordingDatabase(log.clone()));
+        let db = Database::connect_proxy(DatabaseBackend::Postgres, Arc::new(proxy))
+            .await
+            .unwrap();
+        (db, log)
+    }
+
+    #[async_std::test]
+    async fn test_up_backfills_namespaces_before_requiring_them() {
+        let (db, log) = connect().await;
+        Migration.up(&SchemaManager::new(&db)).await.unwrap();
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 6);
+        assert!(log[0].contains(r#"ADD COLUMN IF NOT EXISTS "namespace" uuid NULL"#));
+        assert!(log[1].contains(r#"SET "namespace" = "auth"."uuid""#));
+        assert!(log[2].contains("gen_random_uuid()"));
+        assert!(log[3].contains(r#"ALTER COLUMN "namespace" SET NOT NULL"#));
+        assert!(log[4].contains("idx-function-uuid-unique"));
+        assert!(log[5].contains("idx-function-namespace-name-unique"));
+    }
+
+    #[async_std::test]
+    async fn test_down_restores_owner_uuids_before_dropping_namespaces() {
+        let (db, log) = connect().await;
+        Migration.down(&SchemaManager::new(&db)).await.unwrap();
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 4);
+        assert!(log[0].contains("idx-function-namespace-name-unique"));
+        assert!(log[1].contains("idx-function-uuid-unique"));
+        assert!(log[2].contains(r#"SET "uuid" = "namespace""#));
+        assert!(log[3].contains(r#"DROP COLUMN "namespace""#));
+    }
+}
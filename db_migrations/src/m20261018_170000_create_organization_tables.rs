@@ -0,0 +1,231 @@
+use crate::m20250111_230947_create_auth_table::Auth;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Organization::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Organization::Id))
+                    .col(uuid_uniq(Organization::Uuid))
+                    .col(string_uniq(Organization::Name))
+                    .col(boolean(Organization::Personal).default(false))
+                    .col(
+                        timestamp_with_time_zone(Organization::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrganizationMember::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OrganizationMember::Id))
+                    .col(integer(OrganizationMember::OrganizationId))
+                    .col(integer(OrganizationMember::AuthId))
+                    .col(string(OrganizationMember::Role))
+                    .col(
+                        timestamp_with_time_zone(OrganizationMember::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-organization_member-organization_id")
+                            .from(
+                                OrganizationMember::Table,
+                                OrganizationMember::OrganizationId,
+                            )
+                            .to(Organization::Table, Organization::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-organization_member-auth_id")
+                            .from(OrganizationMember::Table, OrganizationMember::AuthId)
+                            .to(Auth::Table, Auth::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user is a member of an organization at most once
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-organization_member-organization-auth-unique")
+                    .table(OrganizationMember::Table)
+                    .col(OrganizationMember::OrganizationId)
+                    .col(OrganizationMember::AuthId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrganizationInvitation::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OrganizationInvitation::Id))
+                    .col(integer(OrganizationInvitation::OrganizationId))
+                    .col(string(OrganizationInvitation::Email))
+                    .col(string(OrganizationInvitation::Role))
+                    .col(string_uniq(OrganizationInvitation::TokenHash))
+                    .col(integer(OrganizationInvitation::InvitedBy))
+                    .col(
+                        timestamp_with_time_zone(OrganizationInvitation::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(OrganizationInvitation::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(
+                        OrganizationInvitation::AcceptedAt,
+                    ))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-organization_invitation-organization_id")
+                            .from(
+                                OrganizationInvitation::Table,
+                                OrganizationInvitation::OrganizationId,
+                            )
+                            .to(Organization::Table, Organization::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-organization_invitation-invited_by")
+                            .from(
+                                OrganizationInvitation::Table,
+                                OrganizationInvitation::InvitedBy,
+                            )
+                            .to(Auth::Table, Auth::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every existing user gets a personal organization that keeps their UUID as
+        // its namespace, so deployed functions stay where they are.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"INSERT INTO "organization" ("uuid", "name", "personal")
+               SELECT "uuid", "email", TRUE FROM "auth"
+               ON CONFLICT DO NOTHING"#,
+        )
+        .await?;
+        db.execute_unprepared(
+            r#"INSERT INTO "organization_member" ("organization_id", "auth_id", "role")
+               SELECT "organization"."id", "auth"."id", 'owner'
+               FROM "auth" JOIN "organization" ON "organization"."uuid" = "auth"."uuid"
+               ON CONFLICT DO NOTHING"#,
+        )
+        .await?;
+
+        // Names are now unique per namespace rather than per user, so members of
+        // several organizations can reuse a name in each of them.
+        manager
+            .drop_index(
+                Index::drop()
+                    .if_exists()
+                    .name("idx-function-name-auth-unique")
+                    .table(Function::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-function-name-auth-unique")
+                    .table(Function::Table)
+                    .col(Function::Name)
+                    .col(Function::AuthId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(OrganizationInvitation::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-organization_member-organization-auth-unique")
+                    .table(OrganizationMember::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(OrganizationMember::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Organization::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Name,
+    AuthId,
+}
+
+#[derive(DeriveIden)]
+enum Organization {
+    Table,
+    Id,
+    Uuid,
+    Name,
+    Personal,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum OrganizationMember {
+    Table,
+    Id,
+    OrganizationId,
+    AuthId,
+    Role,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum OrganizationInvitation {
+    Table,
+    Id,
+    OrganizationId,
+    Email,
+    Role,
+    TokenHash,
+    InvitedBy,
+    CreatedAt,
+    ExpiresAt,
+    AcceptedAt,
+}
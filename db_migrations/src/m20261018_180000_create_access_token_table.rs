@@ -0,0 +1,63 @@
+use crate::m20250111_230947_create_auth_table::Auth;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessToken::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AccessToken::Id))
+                    .col(uuid_uniq(AccessToken::Uuid))
+                    .col(integer(AccessToken::AuthId))
+                    .col(string(AccessToken::Name))
+                    .col(string(AccessToken::Prefix))
+                    .col(string_uniq(AccessToken::TokenHash))
+                    .col(string(AccessToken::Scopes))
+                    .col(
+                        timestamp_with_time_zone(AccessToken::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(AccessToken::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(AccessToken::LastUsedAt))
+                    .col(timestamp_with_time_zone_null(AccessToken::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-access_token-auth_id")
+                            .from(AccessToken::Table, AccessToken::AuthId)
+                            .to(Auth::Table, Auth::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AccessToken::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccessToken {
+    Table,
+    Id,
+    Uuid,
+    AuthId,
+    Name,
+    Prefix,
+    TokenHash,
+    Scopes,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+    RevokedAt,
+}
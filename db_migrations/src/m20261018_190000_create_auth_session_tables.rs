@@ -0,0 +1,96 @@
+use crate::m20250111_230947_create_auth_table::Auth;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthSession::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AuthSession::Id))
+                    .col(uuid_uniq(AuthSession::Uuid))
+                    .col(integer(AuthSession::AuthId))
+                    .col(
+                        timestamp_with_time_zone(AuthSession::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        timestamp_with_time_zone(AuthSession::LastRefreshedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(AuthSession::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-auth_session-auth_id")
+                            .from(AuthSession::Table, AuthSession::AuthId)
+                            .to(Auth::Table, Auth::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RefreshToken::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RefreshToken::Id))
+                    .col(integer(RefreshToken::SessionId))
+                    .col(string_uniq(RefreshToken::TokenHash))
+                    .col(
+                        timestamp_with_time_zone(RefreshToken::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(RefreshToken::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(RefreshToken::UsedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-refresh_token-session_id")
+                            .from(RefreshToken::Table, RefreshToken::SessionId)
+                            .to(AuthSession::Table, AuthSession::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RefreshToken::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AuthSession::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthSession {
+    Table,
+    Id,
+    Uuid,
+    AuthId,
+    CreatedAt,
+    LastRefreshedAt,
+    RevokedAt,
+}
+
+#[derive(DeriveIden)]
+enum RefreshToken {
+    Table,
+    Id,
+    SessionId,
+    TokenHash,
+    CreatedAt,
+    ExpiresAt,
+    UsedAt,
+}
@@ -0,0 +1,44 @@
+use crate::m20250111_230947_create_auth_table::Auth;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .add_column_if_not_exists(timestamp_with_time_zone_null(
+                        AuthVerification::EmailVerifiedAt,
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Accounts registered before verification existed keep working.
+        manager
+            .get_connection()
+            .execute_unprepared(r#"UPDATE "auth" SET "email_verified_at" = CURRENT_TIMESTAMP"#)
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .drop_column(AuthVerification::EmailVerifiedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthVerification {
+    EmailVerifiedAt,
+}
@@ -0,0 +1,64 @@
+use crate::m20250111_230947_create_auth_table::Auth;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthIdentity::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AuthIdentity::Id))
+                    .col(integer(AuthIdentity::AuthId))
+                    .col(string(AuthIdentity::Issuer))
+                    .col(string(AuthIdentity::Subject))
+                    .col(
+                        timestamp_with_time_zone(AuthIdentity::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-auth_identity-auth_id")
+                            .from(AuthIdentity::Table, AuthIdentity::AuthId)
+                            .to(Auth::Table, Auth::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-auth_identity-issuer-subject-unique")
+                    .table(AuthIdentity::Table)
+                    .col(AuthIdentity::Issuer)
+                    .col(AuthIdentity::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthIdentity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthIdentity {
+    Table,
+    Id,
+    AuthId,
+    Issuer,
+    Subject,
+    CreatedAt,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionApiKey::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FunctionApiKey::Id))
+                    .col(uuid_uniq(FunctionApiKey::Uuid))
+                    .col(integer(FunctionApiKey::FunctionId))
+                    .col(string(FunctionApiKey::Name))
+                    .col(string(FunctionApiKey::Prefix))
+                    .col(string_uniq(FunctionApiKey::KeyHash))
+                    .col(
+                        timestamp_with_time_zone(FunctionApiKey::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(FunctionApiKey::LastUsedAt))
+                    .col(timestamp_with_time_zone_null(FunctionApiKey::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-function_api_key-function_id")
+                            .from(FunctionApiKey::Table, FunctionApiKey::FunctionId)
+                            .to(Function::Table, Function::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FunctionApiKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionApiKey {
+    Table,
+    Id,
+    Uuid,
+    FunctionId,
+    Name,
+    Prefix,
+    KeyHash,
+    CreatedAt,
+    LastUsedAt,
+    RevokedAt,
+}
+
+#[derive(DeriveIden)]
+enum Function {
+    Table,
+    Id,
+}
@@ -0,0 +1,95 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvent::Table)
+                    .if_not_exists()
+                    .col(big_integer(AuditEvent::Id).auto_increment().primary_key())
+                    .col(
+                        timestamp_with_time_zone(AuditEvent::OccurredAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(uuid_null(AuditEvent::ActorUuid))
+                    .col(uuid_null(AuditEvent::Namespace))
+                    .col(string(AuditEvent::Action))
+                    .col(string_null(AuditEvent::Target))
+                    .col(string_null(AuditEvent::Ip))
+                    .col(string_null(AuditEvent::RequestId))
+                    .col(string(AuditEvent::Outcome))
+                    .col(json_binary_null(AuditEvent::Detail))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-audit_event-namespace-id")
+                    .table(AuditEvent::Table)
+                    .col(AuditEvent::Namespace)
+                    .col(AuditEvent::Id)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-audit_event-occurred_at")
+                    .table(AuditEvent::Table)
+                    .col(AuditEvent::OccurredAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Events are only ever added, or removed once past their retention.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"CREATE OR REPLACE FUNCTION "audit_event_reject_update"() RETURNS trigger AS $$
+               BEGIN
+                   RAISE EXCEPTION 'audit events cannot be modified';
+               END;
+               $$ LANGUAGE plpgsql"#,
+        )
+        .await?;
+        db.execute_unprepared(
+            r#"CREATE TRIGGER "audit_event_append_only"
+               BEFORE UPDATE ON "audit_event"
+               FOR EACH ROW EXECUTE FUNCTION "audit_event_reject_update"()"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvent::Table).to_owned())
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(r#"DROP FUNCTION IF EXISTS "audit_event_reject_update"()"#)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditEvent {
+    Table,
+    Id,
+    OccurredAt,
+    ActorUuid,
+    Namespace,
+    Action,
+    Target,
+    Ip,
+    RequestId,
+    Outcome,
+    Detail,
+}
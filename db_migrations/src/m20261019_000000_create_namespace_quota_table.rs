@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Unset limits fall back to the server's defaults.
+        manager
+            .create_table(
+                Table::create()
+                    .table(NamespaceQuota::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NamespaceQuota::Id))
+                    .col(uuid_uniq(NamespaceQuota::Namespace))
+                    .col(big_integer_null(NamespaceQuota::MaxFunctions))
+                    .col(big_integer_null(NamespaceQuota::MaxInstances))
+                    .col(big_integer_null(NamespaceQuota::MaxMemoryMb))
+                    .col(big_integer_null(NamespaceQuota::BuildsPerHour))
+                    .col(big_integer_null(NamespaceQuota::InvocationsPerDay))
+                    .col(
+                        timestamp_with_time_zone(NamespaceQuota::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-namespace_quota-namespace")
+                            .from(NamespaceQuota::Table, NamespaceQuota::Namespace)
+                            .to(Organization::Table, Organization::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(NamespaceQuota::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NamespaceQuota {
+    Table,
+    Id,
+    Namespace,
+    MaxFunctions,
+    MaxInstances,
+    MaxMemoryMb,
+    BuildsPerHour,
+    InvocationsPerDay,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Organization {
+    Table,
+    Uuid,
+}
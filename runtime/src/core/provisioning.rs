@@ -1,6 +1,6 @@
 use crate::shared::error::{AppResult, RuntimeError};
 use bollard::errors::Error as BollardError;
-use bollard::image::BuildImageOptions;
+use bollard::image::{BuildImageOptions, RemoveImageOptions, TagImageOptions};
 use bollard::Docker;
 use futures_util::StreamExt;
 use shared_utils;
@@ -94,6 +94,76 @@ pub async fn provisioning(
     Ok(())
 }
 
+/// Removes a Docker image built by [`provisioning`].
+///
+/// # Arguments
+/// * `image_name` - The Docker image name/tag to remove.
+///
+/// # Returns
+/// * `Ok(())` if the image was removed.
+/// * `AppError` if Docker is unreachable or the image could not be removed.
+pub async fn remove_image(image_name: &str) -> AppResult<()> {
+    let docker = Docker::connect_with_http_defaults()
+        .map_err(|e| RuntimeError::System(format!("Unable to connect to Docker: {e}")))?;
+
+    docker
+        .remove_image(
+            image_name,
+            Some(RemoveImageOptions {
+                force: true,
+                ..Default::default()
+            }),
+            None,
+        )
+        .await
+        .map_err(|e| RuntimeError::System(format!("Failed to remove image: {e}")))?;
+    Ok(())
+}
+
+/// Tags an image that already exists on the Docker host under a new name.
+///
+/// Used for prebuilt function images, so every version still has its own tag
+/// that can be run and later removed without touching the source image.
+///
+/// # Arguments
+/// * `source` - The existing image reference.
+/// * `target` - The new `repository:tag` reference.
+///
+/// # Returns
+/// * `Ok(())` if the image was tagged.
+/// * `AppError` if Docker is unreachable or the source image does not exist locally.
+pub async fn tag_image(source: &str, target: &str) -> AppResult<()> {
+    let docker = Docker::connect_with_http_defaults()
+        .map_err(|e| RuntimeError::System(format!("Unable to connect to Docker: {e}")))?;
+
+    let (repo, tag) = target.rsplit_once(':').unwrap_or((target, "latest"));
+    docker
+        .tag_image(source, Some(TagImageOptions { repo, tag }))
+        .await
+        .map_err(|e| RuntimeError::System(format!("Failed to tag image: {e}")))?;
+    Ok(())
+}
+
+/// Returns the content-addressed ID (`sha256:...`) of an image on the Docker host.
+///
+/// # Arguments
+/// * `image_name` - The image reference to inspect.
+///
+/// # Returns
+/// * The image ID on success.
+/// * `AppError` if Docker is unreachable or the image does not exist.
+pub async fn image_digest(image_name: &str) -> AppResult<String> {
+    let docker = Docker::connect_with_http_defaults()
+        .map_err(|e| RuntimeError::System(format!("Unable to connect to Docker: {e}")))?;
+
+    docker
+        .inspect_image(image_name)
+        .await
+        .map_err(|e| RuntimeError::System(format!("Failed to inspect image: {e}")))?
+        .id
+        .ok_or_else(|| RuntimeError::System(format!("Image '{image_name}' has no ID")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,7 +1,7 @@
 use crate::shared::error::{AppResult, RuntimeError};
 use bollard::container::{
     AttachContainerOptions, AttachContainerResults, Config, CreateContainerOptions,
-    NetworkingConfig, RemoveContainerOptions,
+    RemoveContainerOptions, StopContainerOptions,
 };
 use bollard::models::{HostConfig, PortBinding, PortMap};
 use bollard::network::ConnectNetworkOptions;
@@ -22,6 +22,12 @@ pub struct ContainerDetails {
     pub container_name: String,
     pub timeout: u64,
     pub docker_compose_network_host: String,
+    /// Environment variables (`KEY=value`) set on the container at creation.
+    pub env: Vec<String>,
+    /// Memory limit in MB; defaults to 256 MB.
+    pub memory_mb: Option<u32>,
+    /// CPU limit in thousandths of a CPU; defaults to one CPU.
+    pub cpu_millis: Option<u32>,
 }
 
 /// Spawns a Docker container with given image and ports, attaches to it,
@@ -60,16 +66,28 @@ pub async fn runner(image_name: &str, container_details: ContainerDetails) -> Ap
     let mut exposed_ports = HashMap::new();
     exposed_ports.insert("8080/tcp", HashMap::new());
 
-    let (cpu_period, cpu_quota) = cpu_limits(NUM_CPUS);
+    let memory = container_details
+        .memory_mb
+        .map_or(SIZE_256_MB, |mb| i64::from(mb) * BYTES_IN_MB);
+    let cpus = container_details
+        .cpu_millis
+        .map_or(NUM_CPUS, |millis| f64::from(millis) / 1000.0);
+    let (cpu_period, cpu_quota) = cpu_limits(cpus);
+    let env = container_details
+        .env
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
     // Configure the container.
     let container_config = Config {
         image: Some(image_name),
+        env: Some(env),
         tty: Some(true),
         attach_stdout: Some(true),
         attach_stderr: Some(true),
         exposed_ports: Some(exposed_ports),
         host_config: Some(HostConfig {
-            memory: Some(SIZE_256_MB),
+            memory: Some(memory),
             cpu_period: Some(cpu_period),
             cpu_quota: Some(cpu_quota),
             port_bindings: Some(port_map),
@@ -210,12 +228,66 @@ async fn clean_up_v2(docker: &Docker, container_id: &str) -> AppResult<()> {
     Ok(())
 }
 
+/// Gracefully stops a running container, giving it `grace_period_secs` to exit
+/// before Docker kills it. Containers are created with `auto_remove`, so a stopped
+/// container is also removed.
+///
+/// # Arguments
+///
+/// * `container_name` - Name or ID of the container to stop.
+/// * `grace_period_secs` - Seconds to wait after SIGTERM before sending SIGKILL.
+pub async fn stop_container(container_name: &str, grace_period_secs: i64) -> AppResult<()> {
+    let docker = Docker::connect_with_http_defaults()
+        .map_err(|e| RuntimeError::System(format!("Failed to connect to Docker: {e}")))?;
+
+    docker
+        .stop_container(
+            container_name,
+            Some(StopContainerOptions {
+                t: grace_period_secs,
+            }),
+        )
+        .await
+        .map_err(|e| RuntimeError::System(format!("Failed to stop container: {e}")))?;
+    Ok(())
+}
+
+/// Resources of the Docker host function containers run on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostCapacity {
+    /// Name of the Docker host.
+    pub name: Option<String>,
+    /// CPUs available to containers.
+    pub cpus: Option<i64>,
+    /// Memory available to containers, in bytes.
+    pub memory_bytes: Option<i64>,
+    /// Containers currently running, including ones not started by the platform.
+    pub containers_running: Option<i64>,
+}
+
+/// Reads the CPUs, memory and running containers of the Docker host.
+pub async fn host_capacity() -> AppResult<HostCapacity> {
+    let docker = Docker::connect_with_http_defaults()
+        .map_err(|e| RuntimeError::System(format!("Failed to connect to Docker: {e}")))?;
+
+    let info = docker
+        .info()
+        .await
+        .map_err(|e| RuntimeError::System(format!("Failed to read Docker host info: {e}")))?;
+    Ok(HostCapacity {
+        name: info.name,
+        cpus: info.ncpu,
+        memory_bytes: info.mem_total,
+        containers_running: info.containers_running,
+    })
+}
+
 /// Calculates the CPU period and CPU quota for a given `x` (number of CPUs).
 ///
 /// # Arguments
 ///
 /// * `x` - The number of CPUs to allocate. For example, 1.0 = 1 CPU core,
-///         2.0 = 2 CPU cores, 0.5 = half a CPU core, etc.
+///   2.0 = 2 CPU cores, 0.5 = half a CPU core, etc.
 ///
 /// # Returns
 ///
@@ -264,6 +336,9 @@ async fn test_runner() {
             container_name: "c-test".to_string(),
             timeout: 50,
             docker_compose_network_host: "asdf".to_string(),
+            env: vec![],
+            memory_mb: None,
+            cpu_millis: None,
         },
     )
     .await;
@@ -1,10 +1,20 @@
 use std::env;
 
+use artifact::InvokArtifactConfig;
+use audit::InvokAuditConfig;
+use auth::InvokAuthConfig;
 use function::InvokFunctionConfig;
+use mail::InvokMailConfig;
+use oidc::InvokOidcConfig;
 use server::InvokServerConfig;
 use thiserror::Error;
 
+pub(crate) mod artifact;
+mod audit;
+pub(crate) mod auth;
 mod function;
+pub(crate) mod mail;
+pub(crate) mod oidc;
 mod server;
 
 /// Error that can occur during configuration loading
@@ -16,6 +26,15 @@ pub enum InvokConfigError {
     #[error("The port {0} provided is invalid")]
     InvalidPort(String),
 
+    #[error("{0} must be a base64-encoded 32-byte key")]
+    InvalidSecretsKey(String),
+
+    #[error("Unknown artifact store '{0}', expected 'local' or 's3'")]
+    InvalidArtifactStore(String),
+
+    #[error("Unknown mail backend '{0}', expected 'log', 'file' or 'smtp'")]
+    InvalidMailBackend(String),
+
     #[error("Environment error: {0}")]
     EnvError(#[from] env::VarError),
 }
@@ -28,6 +47,21 @@ pub struct InvokConfig {
 
     /// Function configuration
     pub function_config: InvokFunctionConfig,
+
+    /// Artifact store configuration
+    pub artifact_config: InvokArtifactConfig,
+
+    /// Authentication configuration
+    pub auth_config: InvokAuthConfig,
+
+    /// Mail configuration
+    pub mail_config: InvokMailConfig,
+
+    /// Single sign-on configuration, if an identity provider is configured
+    pub oidc_config: Option<InvokOidcConfig>,
+
+    /// Audit log configuration
+    pub audit_config: InvokAuditConfig,
 }
 
 impl InvokConfig {
@@ -35,10 +69,20 @@ impl InvokConfig {
     pub fn load() -> Result<Self, InvokConfigError> {
         let server_config = InvokServerConfig::from_env()?;
         let function_config = InvokFunctionConfig::from_env();
+        let artifact_config = InvokArtifactConfig::from_env()?;
+        let auth_config = InvokAuthConfig::from_env();
+        let mail_config = InvokMailConfig::from_env()?;
+        let oidc_config = InvokOidcConfig::from_env(&mail_config.public_url)?;
+        let audit_config = InvokAuditConfig::from_env();
 
         Ok(Self {
             server_config,
             function_config,
+            artifact_config,
+            auth_config,
+            mail_config,
+            oidc_config,
+            audit_config,
         })
     }
 }
@@ -0,0 +1,64 @@
+use super::InvokConfigError;
+use crate::artifacts::s3::S3Settings;
+use std::env;
+
+const ARTIFACT_STORE_ENV_VARIABLE: &str = "ARTIFACT_STORE";
+const ARTIFACT_STORE_PATH_ENV_VARIABLE: &str = "ARTIFACT_STORE_PATH";
+const ARTIFACT_S3_BUCKET_ENV_VARIABLE: &str = "ARTIFACT_S3_BUCKET";
+const ARTIFACT_S3_ENDPOINT_ENV_VARIABLE: &str = "ARTIFACT_S3_ENDPOINT";
+const ARTIFACT_S3_REGION_ENV_VARIABLE: &str = "ARTIFACT_S3_REGION";
+const ARTIFACT_S3_ACCESS_KEY_ENV_VARIABLE: &str = "ARTIFACT_S3_ACCESS_KEY";
+const ARTIFACT_S3_SECRET_KEY_ENV_VARIABLE: &str = "ARTIFACT_S3_SECRET_KEY";
+
+/// Default directory of the local artifact store
+pub const DEFAULT_ARTIFACT_STORE_PATH_VALUE: &str = "artifacts";
+
+/// Default region of the S3 artifact store
+pub const DEFAULT_ARTIFACT_S3_REGION_VALUE: &str = "us-east-1";
+
+/// Where uploaded function archives are stored
+#[derive(Debug, Clone)]
+pub enum ArtifactStoreConfig {
+    /// A directory on the server's filesystem
+    Local { path: String },
+
+    /// An S3-compatible bucket
+    S3(S3Settings),
+}
+
+/// Artifact store configuration
+#[derive(Debug, Clone)]
+pub struct InvokArtifactConfig {
+    /// The configured store backend
+    pub store: ArtifactStoreConfig,
+}
+
+/// Reads a required environment variable
+fn required(name: &str) -> Result<String, InvokConfigError> {
+    env::var(name).map_err(|_| InvokConfigError::MissingVar(name.to_string()))
+}
+
+impl InvokArtifactConfig {
+    /// Load artifact store configuration from environment
+    pub fn from_env() -> Result<Self, InvokConfigError> {
+        let backend = env::var(ARTIFACT_STORE_ENV_VARIABLE).unwrap_or_else(|_| "local".to_string());
+
+        let store = match backend.to_ascii_lowercase().as_str() {
+            "local" => ArtifactStoreConfig::Local {
+                path: env::var(ARTIFACT_STORE_PATH_ENV_VARIABLE)
+                    .unwrap_or_else(|_| DEFAULT_ARTIFACT_STORE_PATH_VALUE.to_string()),
+            },
+            "s3" => ArtifactStoreConfig::S3(S3Settings {
+                bucket: required(ARTIFACT_S3_BUCKET_ENV_VARIABLE)?,
+                endpoint: required(ARTIFACT_S3_ENDPOINT_ENV_VARIABLE)?,
+                region: env::var(ARTIFACT_S3_REGION_ENV_VARIABLE)
+                    .unwrap_or_else(|_| DEFAULT_ARTIFACT_S3_REGION_VALUE.to_string()),
+                access_key: required(ARTIFACT_S3_ACCESS_KEY_ENV_VARIABLE)?,
+                secret_key: required(ARTIFACT_S3_SECRET_KEY_ENV_VARIABLE)?,
+            }),
+            _ => return Err(InvokConfigError::InvalidArtifactStore(backend)),
+        };
+
+        Ok(Self { store })
+    }
+}
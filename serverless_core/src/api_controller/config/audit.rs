@@ -0,0 +1,30 @@
+use std::env;
+
+const AUDIT_RETENTION_DAYS_ENV_VARIABLE: &str = "AUDIT_RETENTION_DAYS";
+
+/// Default number of days audit events are kept
+pub const DEFAULT_AUDIT_RETENTION_DAYS_VALUE: u64 = 365;
+
+/// Longest retention that can be configured (100 years); longer values fall
+/// back to the default
+pub const MAX_AUDIT_RETENTION_DAYS_VALUE: u64 = 36_500;
+
+/// Audit log configuration
+#[derive(Debug, Clone)]
+pub struct InvokAuditConfig {
+    /// Days audit events are kept before they are deleted; 0 keeps them forever
+    pub retention_days: u64,
+}
+
+impl InvokAuditConfig {
+    /// Load audit log configuration from environment
+    pub fn from_env() -> Self {
+        let retention_days = env::var(AUDIT_RETENTION_DAYS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n <= MAX_AUDIT_RETENTION_DAYS_VALUE)
+            .unwrap_or(DEFAULT_AUDIT_RETENTION_DAYS_VALUE);
+
+        Self { retention_days }
+    }
+}
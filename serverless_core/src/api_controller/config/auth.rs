@@ -0,0 +1,142 @@
+use std::env;
+
+const MAX_FAILED_LOGINS_ENV_VARIABLE: &str = "AUTH_MAX_FAILED_LOGINS";
+const MAX_FAILED_LOGINS_PER_IP_ENV_VARIABLE: &str = "AUTH_MAX_FAILED_LOGINS_PER_IP";
+const FAILED_LOGIN_WINDOW_ENV_VARIABLE: &str = "AUTH_FAILED_LOGIN_WINDOW_SECS";
+const LOCKOUT_DURATION_ENV_VARIABLE: &str = "AUTH_LOCKOUT_SECS";
+const TRUSTED_PROXY_HOPS_ENV_VARIABLE: &str = "AUTH_TRUSTED_PROXY_HOPS";
+const REQUIRE_EMAIL_VERIFICATION_ENV_VARIABLE: &str = "AUTH_REQUIRE_EMAIL_VERIFICATION";
+const PASSWORD_MIN_LENGTH_ENV_VARIABLE: &str = "AUTH_PASSWORD_MIN_LENGTH";
+const BREACHED_PASSWORDS_FILE_ENV_VARIABLE: &str = "AUTH_BREACHED_PASSWORDS_FILE";
+const ARGON2_MEMORY_ENV_VARIABLE: &str = "AUTH_ARGON2_MEMORY_KIB";
+const ARGON2_ITERATIONS_ENV_VARIABLE: &str = "AUTH_ARGON2_ITERATIONS";
+const ARGON2_PARALLELISM_ENV_VARIABLE: &str = "AUTH_ARGON2_PARALLELISM";
+
+/// Default failed logins of an account before it is locked
+pub const DEFAULT_MAX_FAILED_LOGINS_VALUE: u64 = 5;
+
+/// Default failed logins from an IP address before it is locked
+pub const DEFAULT_MAX_FAILED_LOGINS_PER_IP_VALUE: u64 = 50;
+
+/// Default time failed logins are counted over (15 minutes)
+pub const DEFAULT_FAILED_LOGIN_WINDOW_VALUE: u64 = 15 * 60;
+
+/// Default time an account or IP address stays locked (15 minutes)
+pub const DEFAULT_LOCKOUT_DURATION_VALUE: u64 = 15 * 60;
+
+/// Default minimum password length
+pub const DEFAULT_PASSWORD_MIN_LENGTH_VALUE: usize = 8;
+
+/// Authentication configuration
+#[derive(Debug, Clone)]
+pub struct InvokAuthConfig {
+    /// Failed logins of an account within the window before it is locked
+    pub max_failed_logins: u64,
+
+    /// Failed logins from an IP address within the window before it is locked
+    pub max_failed_logins_per_ip: u64,
+
+    /// Seconds failed logins are counted over
+    pub failed_login_window: u64,
+
+    /// Seconds an account or IP address stays locked
+    pub lockout_duration: u64,
+
+    /// Proxies in front of the server that append to `X-Forwarded-For`; 0 ignores the header
+    pub trusted_proxy_hops: usize,
+
+    /// Whether accounts must verify their email address before logging in
+    pub require_email_verification: bool,
+
+    /// Minimum password length
+    pub password_min_length: usize,
+
+    /// File of breached passwords, one per line, rejected at registration
+    pub breached_passwords_file: Option<String>,
+
+    /// Argon2 memory cost in KiB
+    pub argon2_memory_kib: u32,
+
+    /// Argon2 number of iterations
+    pub argon2_iterations: u32,
+
+    /// Argon2 degree of parallelism
+    pub argon2_parallelism: u32,
+}
+
+impl InvokAuthConfig {
+    /// Load authentication configuration from environment
+    pub fn from_env() -> Self {
+        let max_failed_logins = env::var(MAX_FAILED_LOGINS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_FAILED_LOGINS_VALUE);
+
+        let max_failed_logins_per_ip = env::var(MAX_FAILED_LOGINS_PER_IP_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_FAILED_LOGINS_PER_IP_VALUE);
+
+        let failed_login_window = env::var(FAILED_LOGIN_WINDOW_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_FAILED_LOGIN_WINDOW_VALUE);
+
+        let lockout_duration = env::var(LOCKOUT_DURATION_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_LOCKOUT_DURATION_VALUE);
+
+        let trusted_proxy_hops = env::var(TRUSTED_PROXY_HOPS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let require_email_verification = env::var(REQUIRE_EMAIL_VERIFICATION_ENV_VARIABLE)
+            .map(|s| !matches!(s.to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(true);
+
+        let password_min_length = env::var(PASSWORD_MIN_LENGTH_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_PASSWORD_MIN_LENGTH_VALUE);
+
+        let breached_passwords_file = env::var(BREACHED_PASSWORDS_FILE_ENV_VARIABLE)
+            .ok()
+            .filter(|path| !path.is_empty());
+
+        let argon2_memory_kib = env::var(ARGON2_MEMORY_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(argon2::Params::DEFAULT_M_COST);
+
+        let argon2_iterations = env::var(ARGON2_ITERATIONS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(argon2::Params::DEFAULT_T_COST);
+
+        let argon2_parallelism = env::var(ARGON2_PARALLELISM_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(argon2::Params::DEFAULT_P_COST);
+
+        Self {
+            max_failed_logins,
+            max_failed_logins_per_ip,
+            failed_login_window,
+            lockout_duration,
+            trusted_proxy_hops,
+            require_email_verification,
+            password_min_length,
+            breached_passwords_file,
+            argon2_memory_kib,
+            argon2_iterations,
+            argon2_parallelism,
+        }
+    }
+}
@@ -2,6 +2,22 @@ use std::env;
 
 const MAX_FUNCTION_SIZE_ENV_VARIABLE: &str = "MAX_FUNCTION_SIZE";
 const DEFAULT_RUNTIME_ENV_VARIABLE: &str = "DEFAULT_RUNTIME";
+const MAX_CONCURRENCY_ENV_VARIABLE: &str = "FUNCTION_MAX_CONCURRENCY";
+const MAX_QUEUED_REQUESTS_ENV_VARIABLE: &str = "FUNCTION_MAX_QUEUED_REQUESTS";
+const COLD_START_TIMEOUT_ENV_VARIABLE: &str = "FUNCTION_COLD_START_TIMEOUT_SECS";
+const DRAIN_TIMEOUT_ENV_VARIABLE: &str = "FUNCTION_DRAIN_TIMEOUT_SECS";
+const DISALLOWED_INSTRUCTIONS_ENV_VARIABLE: &str = "FUNCTION_DOCKERFILE_DISALLOWED_INSTRUCTIONS";
+const ALLOWED_IMAGE_PREFIXES_ENV_VARIABLE: &str = "FUNCTION_ALLOWED_IMAGE_PREFIXES";
+const MAX_EXTRACTED_SIZE_ENV_VARIABLE: &str = "FUNCTION_MAX_EXTRACTED_SIZE";
+const MAX_ARCHIVE_ENTRIES_ENV_VARIABLE: &str = "FUNCTION_MAX_ARCHIVE_ENTRIES";
+const MAX_COMPRESSION_RATIO_ENV_VARIABLE: &str = "FUNCTION_MAX_COMPRESSION_RATIO";
+const DEPLOY_PARALLELISM_ENV_VARIABLE: &str = "FUNCTION_DEPLOY_PARALLELISM";
+const MAX_PROJECT_FUNCTIONS_ENV_VARIABLE: &str = "FUNCTION_MAX_PROJECT_FUNCTIONS";
+const QUOTA_MAX_FUNCTIONS_ENV_VARIABLE: &str = "QUOTA_MAX_FUNCTIONS";
+const QUOTA_MAX_INSTANCES_ENV_VARIABLE: &str = "QUOTA_MAX_INSTANCES";
+const QUOTA_MAX_MEMORY_MB_ENV_VARIABLE: &str = "QUOTA_MAX_MEMORY_MB";
+const QUOTA_BUILDS_PER_HOUR_ENV_VARIABLE: &str = "QUOTA_BUILDS_PER_HOUR";
+const QUOTA_INVOCATIONS_PER_DAY_ENV_VARIABLE: &str = "QUOTA_INVOCATIONS_PER_DAY";
 
 /// Default runtime if not specified
 pub const DEFAULT_RUNTIME_VALUE: &str = "go";
@@ -9,6 +25,69 @@ pub const DEFAULT_RUNTIME_VALUE: &str = "go";
 /// Default maximum function size (10MB)
 pub const DEFAULT_MAX_FUNCTION_SIZE_VALUE: usize = 10 * 1024 * 1024;
 
+/// Default maximum in-flight requests per function
+pub const DEFAULT_MAX_CONCURRENCY_VALUE: usize = 100;
+
+/// Default number of requests allowed to wait per function
+pub const DEFAULT_MAX_QUEUED_REQUESTS_VALUE: usize = 50;
+
+/// Default time a request may wait for a cold start or a free slot (30s)
+pub const DEFAULT_COLD_START_TIMEOUT_VALUE: u64 = 30;
+
+/// Default time a replaced instance may finish in-flight requests (30s)
+pub const DEFAULT_DRAIN_TIMEOUT_VALUE: u64 = 30;
+
+/// Default Dockerfile instructions rejected in user-supplied Dockerfiles
+pub const DEFAULT_DISALLOWED_INSTRUCTIONS_VALUE: &str = "ADD,ONBUILD,VOLUME";
+
+/// Default maximum size of an extracted function archive (100MB)
+pub const DEFAULT_MAX_EXTRACTED_SIZE_VALUE: u64 = 100 * 1024 * 1024;
+
+/// Default maximum number of entries in a function archive
+pub const DEFAULT_MAX_ARCHIVE_ENTRIES_VALUE: usize = 1000;
+
+/// Default maximum compression ratio of an archive entry
+pub const DEFAULT_MAX_COMPRESSION_RATIO_VALUE: u64 = 100;
+
+/// Default number of functions of a project deploy built at the same time
+pub const DEFAULT_DEPLOY_PARALLELISM_VALUE: usize = 4;
+
+/// Default maximum number of functions in a project deploy
+pub const DEFAULT_MAX_PROJECT_FUNCTIONS_VALUE: usize = 20;
+
+/// Default maximum number of functions per namespace
+pub const DEFAULT_QUOTA_MAX_FUNCTIONS_VALUE: u64 = 50;
+
+/// Default maximum number of running instances per namespace
+pub const DEFAULT_QUOTA_MAX_INSTANCES_VALUE: u64 = 20;
+
+/// Default maximum memory of the running instances of a namespace (4GB)
+pub const DEFAULT_QUOTA_MAX_MEMORY_MB_VALUE: u64 = 4096;
+
+/// Default maximum number of builds per namespace per hour
+pub const DEFAULT_QUOTA_BUILDS_PER_HOUR_VALUE: u64 = 30;
+
+/// Default maximum number of invocations per namespace per day; 0 for unlimited
+pub const DEFAULT_QUOTA_INVOCATIONS_PER_DAY_VALUE: u64 = 0;
+
+/// Reads a quota from the environment; 0 means unlimited
+fn quota_from_env(name: &str, default: u64) -> u64 {
+    env::var(name)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+/// Splits a comma-separated list, dropping empty entries
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Function service configuration
 #[derive(Debug, Clone)]
 pub struct InvokFunctionConfig {
@@ -17,6 +96,54 @@ pub struct InvokFunctionConfig {
 
     /// Maximum function size in bytes
     pub max_function_size: usize,
+
+    /// Maximum in-flight requests per function, unless the function overrides it
+    pub max_concurrency: usize,
+
+    /// Maximum requests queued per function before new ones are rejected
+    pub max_queued_requests: usize,
+
+    /// Seconds a queued request waits before being rejected
+    pub cold_start_timeout: u64,
+
+    /// Seconds a replaced instance may finish in-flight requests before being stopped
+    pub drain_timeout: u64,
+
+    /// Instructions rejected in user-supplied Dockerfiles
+    pub dockerfile_disallowed_instructions: Vec<String>,
+
+    /// Prefixes prebuilt image references must match; empty disables prebuilt images
+    pub allowed_image_prefixes: Vec<String>,
+
+    /// Maximum total bytes extracted from a function archive
+    pub max_extracted_size: u64,
+
+    /// Maximum number of entries in a function archive
+    pub max_archive_entries: usize,
+
+    /// Maximum uncompressed-to-compressed ratio of an archive entry
+    pub max_compression_ratio: u64,
+
+    /// Maximum number of functions of a project deploy built at the same time
+    pub deploy_parallelism: usize,
+
+    /// Maximum number of functions in a single project deploy
+    pub max_project_functions: usize,
+
+    /// Maximum number of functions per namespace, unless overridden; 0 for unlimited
+    pub quota_max_functions: u64,
+
+    /// Maximum number of running instances per namespace, unless overridden; 0 for unlimited
+    pub quota_max_instances: u64,
+
+    /// Maximum memory in MB of a namespace's running instances, unless overridden; 0 for unlimited
+    pub quota_max_memory_mb: u64,
+
+    /// Maximum number of builds per namespace per hour, unless overridden; 0 for unlimited
+    pub quota_builds_per_hour: u64,
+
+    /// Maximum number of invocations per namespace per day, unless overridden; 0 for unlimited
+    pub quota_invocations_per_day: u64,
 }
 
 impl InvokFunctionConfig {
@@ -30,9 +157,106 @@ impl InvokFunctionConfig {
             .and_then(|s| s.parse::<usize>().ok())
             .unwrap_or(DEFAULT_MAX_FUNCTION_SIZE_VALUE);
 
+        let max_concurrency = env::var(MAX_CONCURRENCY_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY_VALUE);
+
+        let max_queued_requests = env::var(MAX_QUEUED_REQUESTS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_QUEUED_REQUESTS_VALUE);
+
+        let cold_start_timeout = env::var(COLD_START_TIMEOUT_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_COLD_START_TIMEOUT_VALUE);
+
+        let drain_timeout = env::var(DRAIN_TIMEOUT_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT_VALUE);
+
+        let dockerfile_disallowed_instructions = parse_list(
+            &env::var(DISALLOWED_INSTRUCTIONS_ENV_VARIABLE)
+                .unwrap_or_else(|_| DEFAULT_DISALLOWED_INSTRUCTIONS_VALUE.to_string()),
+        )
+        .into_iter()
+        .map(|instruction| instruction.to_ascii_uppercase())
+        .collect();
+
+        let allowed_image_prefixes =
+            parse_list(&env::var(ALLOWED_IMAGE_PREFIXES_ENV_VARIABLE).unwrap_or_default());
+
+        let max_extracted_size = env::var(MAX_EXTRACTED_SIZE_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_EXTRACTED_SIZE_VALUE);
+
+        let max_archive_entries = env::var(MAX_ARCHIVE_ENTRIES_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_ARCHIVE_ENTRIES_VALUE);
+
+        let max_compression_ratio = env::var(MAX_COMPRESSION_RATIO_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_COMPRESSION_RATIO_VALUE);
+
+        let deploy_parallelism = env::var(DEPLOY_PARALLELISM_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_DEPLOY_PARALLELISM_VALUE);
+
+        let max_project_functions = env::var(MAX_PROJECT_FUNCTIONS_ENV_VARIABLE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_PROJECT_FUNCTIONS_VALUE);
+
+        let quota_max_functions = quota_from_env(
+            QUOTA_MAX_FUNCTIONS_ENV_VARIABLE,
+            DEFAULT_QUOTA_MAX_FUNCTIONS_VALUE,
+        );
+        let quota_max_instances = quota_from_env(
+            QUOTA_MAX_INSTANCES_ENV_VARIABLE,
+            DEFAULT_QUOTA_MAX_INSTANCES_VALUE,
+        );
+        let quota_max_memory_mb = quota_from_env(
+            QUOTA_MAX_MEMORY_MB_ENV_VARIABLE,
+            DEFAULT_QUOTA_MAX_MEMORY_MB_VALUE,
+        );
+        let quota_builds_per_hour = quota_from_env(
+            QUOTA_BUILDS_PER_HOUR_ENV_VARIABLE,
+            DEFAULT_QUOTA_BUILDS_PER_HOUR_VALUE,
+        );
+        let quota_invocations_per_day = quota_from_env(
+            QUOTA_INVOCATIONS_PER_DAY_ENV_VARIABLE,
+            DEFAULT_QUOTA_INVOCATIONS_PER_DAY_VALUE,
+        );
+
         Self {
             default_runtime,
             max_function_size,
+            max_concurrency,
+            max_queued_requests,
+            cold_start_timeout,
+            drain_timeout,
+            dockerfile_disallowed_instructions,
+            allowed_image_prefixes,
+            max_extracted_size,
+            max_archive_entries,
+            max_compression_ratio,
+            deploy_parallelism,
+            max_project_functions,
+            quota_max_functions,
+            quota_max_instances,
+            quota_max_memory_mb,
+            quota_builds_per_hour,
+            quota_invocations_per_day,
         }
     }
 }
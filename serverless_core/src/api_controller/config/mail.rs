@@ -0,0 +1,88 @@
+use super::InvokConfigError;
+use std::env;
+
+const MAIL_BACKEND_ENV_VARIABLE: &str = "MAIL_BACKEND";
+const MAIL_FROM_ENV_VARIABLE: &str = "MAIL_FROM";
+const MAIL_FILE_DIR_ENV_VARIABLE: &str = "MAIL_FILE_DIR";
+const SMTP_HOST_ENV_VARIABLE: &str = "SMTP_HOST";
+const SMTP_PORT_ENV_VARIABLE: &str = "SMTP_PORT";
+const PUBLIC_URL_ENV_VARIABLE: &str = "PUBLIC_URL";
+
+/// Default sender of platform emails
+pub const DEFAULT_MAIL_FROM_VALUE: &str = "Invok <no-reply@localhost>";
+
+/// Default directory of the file mailer
+pub const DEFAULT_MAIL_FILE_DIR_VALUE: &str = "mail";
+
+/// Default port of the SMTP server
+pub const DEFAULT_SMTP_PORT_VALUE: u16 = 25;
+
+/// Default URL the platform is reached at, used in links sent by email
+pub const DEFAULT_PUBLIC_URL_VALUE: &str = "http://localhost:3000";
+
+/// How platform emails are delivered
+#[derive(Debug, Clone)]
+pub enum MailBackendConfig {
+    /// Written to the server log
+    Log,
+
+    /// Saved as `.eml` files in a directory
+    File { dir: String },
+
+    /// Sent through an SMTP server
+    Smtp { host: String, port: u16 },
+}
+
+/// Mail configuration
+#[derive(Debug, Clone)]
+pub struct InvokMailConfig {
+    /// The configured delivery backend
+    pub backend: MailBackendConfig,
+
+    /// Sender mailbox, e.g. `Invok <no-reply@example.com>`
+    pub from: String,
+
+    /// Base URL of the platform, used in verification and reset links
+    pub public_url: String,
+}
+
+impl InvokMailConfig {
+    /// Load mail configuration from environment
+    pub fn from_env() -> Result<Self, InvokConfigError> {
+        let backend = env::var(MAIL_BACKEND_ENV_VARIABLE).unwrap_or_else(|_| "log".to_string());
+
+        let backend = match backend.to_ascii_lowercase().as_str() {
+            "log" => MailBackendConfig::Log,
+            "file" => MailBackendConfig::File {
+                dir: env::var(MAIL_FILE_DIR_ENV_VARIABLE)
+                    .unwrap_or_else(|_| DEFAULT_MAIL_FILE_DIR_VALUE.to_string()),
+            },
+            "smtp" => MailBackendConfig::Smtp {
+                host: env::var(SMTP_HOST_ENV_VARIABLE).map_err(|_| {
+                    InvokConfigError::MissingVar(SMTP_HOST_ENV_VARIABLE.to_string())
+                })?,
+                port: match env::var(SMTP_PORT_ENV_VARIABLE) {
+                    Ok(port_str) => port_str
+                        .parse::<u16>()
+                        .map_err(|_| InvokConfigError::InvalidPort(port_str))?,
+                    Err(_) => DEFAULT_SMTP_PORT_VALUE,
+                },
+            },
+            _ => return Err(InvokConfigError::InvalidMailBackend(backend)),
+        };
+
+        let from = env::var(MAIL_FROM_ENV_VARIABLE)
+            .unwrap_or_else(|_| DEFAULT_MAIL_FROM_VALUE.to_string());
+
+        let public_url = env::var(PUBLIC_URL_ENV_VARIABLE)
+            .unwrap_or_else(|_| DEFAULT_PUBLIC_URL_VALUE.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Self {
+            backend,
+            from,
+            public_url,
+        })
+    }
+}
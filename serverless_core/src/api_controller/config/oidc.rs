@@ -0,0 +1,70 @@
+use std::env;
+
+const OIDC_ISSUER_ENV_VARIABLE: &str = "OIDC_ISSUER";
+const OIDC_CLIENT_ID_ENV_VARIABLE: &str = "OIDC_CLIENT_ID";
+const OIDC_CLIENT_SECRET_ENV_VARIABLE: &str = "OIDC_CLIENT_SECRET";
+const OIDC_REDIRECT_URL_ENV_VARIABLE: &str = "OIDC_REDIRECT_URL";
+const OIDC_SCOPES_ENV_VARIABLE: &str = "OIDC_SCOPES";
+
+/// Default scopes requested from the identity provider
+pub const DEFAULT_OIDC_SCOPES_VALUE: &str = "openid email profile";
+
+/// Path the identity provider redirects back to, appended to `PUBLIC_URL`
+pub const OIDC_CALLBACK_PATH: &str = "/auth/sso/callback";
+
+/// OpenID Connect single sign-on configuration
+#[derive(Debug, Clone)]
+pub struct InvokOidcConfig {
+    /// Issuer URL; its discovery document is read from `/.well-known/openid-configuration`
+    pub issuer: String,
+
+    /// Client ID registered with the identity provider
+    pub client_id: String,
+
+    /// Client secret, for confidential clients
+    pub client_secret: Option<String>,
+
+    /// Callback URL registered with the identity provider
+    pub redirect_url: String,
+
+    /// Space-separated scopes to request
+    pub scopes: String,
+}
+
+impl InvokOidcConfig {
+    /// Load OIDC configuration from environment; SSO is disabled without `OIDC_ISSUER`
+    ///
+    /// # Arguments
+    ///
+    /// * `public_url` - Base URL of the platform, for the default callback URL
+    pub fn from_env(public_url: &str) -> Result<Option<Self>, super::InvokConfigError> {
+        let Some(issuer) = env::var(OIDC_ISSUER_ENV_VARIABLE)
+            .ok()
+            .filter(|issuer| !issuer.is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let client_id = env::var(OIDC_CLIENT_ID_ENV_VARIABLE).map_err(|_| {
+            super::InvokConfigError::MissingVar(OIDC_CLIENT_ID_ENV_VARIABLE.to_string())
+        })?;
+
+        let client_secret = env::var(OIDC_CLIENT_SECRET_ENV_VARIABLE)
+            .ok()
+            .filter(|secret| !secret.is_empty());
+
+        let redirect_url = env::var(OIDC_REDIRECT_URL_ENV_VARIABLE)
+            .unwrap_or_else(|_| format!("{public_url}{OIDC_CALLBACK_PATH}"));
+
+        let scopes = env::var(OIDC_SCOPES_ENV_VARIABLE)
+            .unwrap_or_else(|_| DEFAULT_OIDC_SCOPES_VALUE.to_string());
+
+        Ok(Some(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret,
+            redirect_url,
+            scopes,
+        }))
+    }
+}
@@ -1,4 +1,5 @@
 use super::InvokConfigError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use std::env;
 
 // Env variables
@@ -6,7 +7,9 @@ const REDIS_URL_ENV_VARIABLE: &str = "REDIS_URL";
 const DATABASE_URL_ENV_VARIABLE: &str = "DATABASE_URL";
 const PORT_ENV_VARIABLE: &str = "SERVER_PORT";
 const SERVER_HOST_ENV_VARIABLE: &str = "SERVER_HOST";
-const AUTH_JWT_SECRET_ENV_VARIABLE: &str = "AUTH_JWT_SECRET";
+const AUTH_JWT_KEYS_DIR_ENV_VARIABLE: &str = "AUTH_JWT_KEYS_DIR";
+const AUTH_JWT_ACTIVE_KID_ENV_VARIABLE: &str = "AUTH_JWT_ACTIVE_KID";
+const SECRETS_ENCRYPTION_KEY_ENV_VARIABLE: &str = "SECRETS_ENCRYPTION_KEY";
 
 const DOCKER_COMPOSE_NETWORK_ENV_VARIABLE: &str = "DOCKER_COMPOSE_NETWORK";
 const DOCKER_HOST_ENV_VARIABLE: &str = "DOCKER_HOST";
@@ -15,7 +18,10 @@ const DOCKER_HOST_ENV_VARIABLE: &str = "DOCKER_HOST";
 const DEFAULT_PORT_VALUE: u16 = 3000;
 
 /// Default host to bind to if not configured
-const DEFAULT_HOST_VALUE: &'static str = "0.0.0.0";
+const DEFAULT_HOST_VALUE: &str = "0.0.0.0";
+
+/// Default directory of JWT signing keys if not configured
+const DEFAULT_JWT_KEYS_DIR_VALUE: &str = "jwt-keys";
 
 /// Server configuration
 #[derive(Debug, Clone)]
@@ -26,8 +32,14 @@ pub struct InvokServerConfig {
     /// Database connection URL
     pub database_url: String,
 
-    /// JWT auth secret
-    pub jwt_auth_secret: String,
+    /// Directory of the Ed25519 keys JWTs are signed with, one `<kid>.pem` each
+    pub jwt_keys_dir: String,
+
+    /// ID of the key new JWTs are signed with; the last one by name when unset
+    pub jwt_active_kid: Option<String>,
+
+    /// 32-byte key used to encrypt function secrets at rest
+    pub secrets_encryption_key: Vec<u8>,
 
     /// Server listen address
     pub host: String,
@@ -57,8 +69,22 @@ impl InvokServerConfig {
                 InvokConfigError::MissingVar(DOCKER_COMPOSE_NETWORK_ENV_VARIABLE.to_string())
             })?;
 
-        let jwt_auth_secret = env::var(AUTH_JWT_SECRET_ENV_VARIABLE)
-            .map_err(|_| InvokConfigError::MissingVar(AUTH_JWT_SECRET_ENV_VARIABLE.to_string()))?;
+        let jwt_keys_dir = env::var(AUTH_JWT_KEYS_DIR_ENV_VARIABLE)
+            .unwrap_or_else(|_| DEFAULT_JWT_KEYS_DIR_VALUE.to_string());
+        let jwt_active_kid = env::var(AUTH_JWT_ACTIVE_KID_ENV_VARIABLE)
+            .ok()
+            .filter(|kid| !kid.is_empty());
+
+        let encoded_key = env::var(SECRETS_ENCRYPTION_KEY_ENV_VARIABLE).map_err(|_| {
+            InvokConfigError::MissingVar(SECRETS_ENCRYPTION_KEY_ENV_VARIABLE.to_string())
+        })?;
+        let secrets_encryption_key = BASE64
+            .decode(encoded_key.trim())
+            .ok()
+            .filter(|key| key.len() == 32)
+            .ok_or_else(|| {
+                InvokConfigError::InvalidSecretsKey(SECRETS_ENCRYPTION_KEY_ENV_VARIABLE.to_string())
+            })?;
 
         let host =
             env::var(SERVER_HOST_ENV_VARIABLE).unwrap_or_else(|_| DEFAULT_HOST_VALUE.to_string());
@@ -73,7 +99,9 @@ impl InvokServerConfig {
         Ok(Self {
             redis_url,
             database_url,
-            jwt_auth_secret,
+            jwt_keys_dir,
+            jwt_active_kid,
+            secrets_encryption_key,
             docker_compose_network_host,
             host,
             port,
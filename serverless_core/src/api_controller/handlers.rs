@@ -1,2 +1,8 @@
+pub mod admin;
+pub mod audit;
 pub mod auth;
 pub mod functions;
+pub mod organizations;
+pub mod quotas;
+pub mod sso;
+pub mod tokens;
@@ -0,0 +1,194 @@
+use axum::extract::{Json, Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::admin::PlatformAdmin;
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::AppState;
+use crate::db::models::{FunctionFilter, UserFilter};
+use crate::lifecycle_manager::admin::{self, PlatformRole};
+use crate::lifecycle_manager::login_guard;
+
+/// Query parameter limiting how many users or functions are listed.
+#[derive(Debug, Deserialize)]
+pub struct AdminPage {
+    /// Defaults to 100, at most 1000.
+    limit: Option<u64>,
+}
+
+/// Request body for changing a user's platform role.
+#[derive(Debug, Deserialize)]
+pub struct SetPlatformRoleRequest {
+    role: String,
+}
+
+/// Request body for lifting a login lockout.
+#[derive(Debug, Deserialize)]
+pub struct UnlockRequest {
+    /// An email address or IP address.
+    target: String,
+}
+
+/// Lists and searches users, oldest first.
+///
+/// Responds with the users and the `next_after` ID to pass as `after` for the
+/// next page.
+pub(crate) async fn list_users(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+    Query(filter): Query<UserFilter>,
+    Query(page): Query<AdminPage>,
+) -> impl IntoResponse {
+    match admin::list_users(&state.db_conn, &filter, page.limit).await {
+        Ok(page) => (StatusCode::OK, Json(page)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Shows a user and the functions of their personal organization.
+pub(crate) async fn get_user(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+    Path(user_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    match admin::user_details(&state.db_conn, user_uuid).await {
+        Ok(details) => (StatusCode::OK, Json(details)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Suspends a user: they can no longer deploy or change functions, and the
+/// functions of their personal organization return `403`.
+pub(crate) async fn suspend_user(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(user_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.user_suspend")
+        .target(user_uuid.to_string());
+    let response =
+        match admin::set_suspended(&state.db_conn, admin.user.uuid, user_uuid, true).await {
+            Ok(user) => (StatusCode::OK, Json(user)).into_response(),
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Lifts a user's suspension.
+pub(crate) async fn unsuspend_user(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(user_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.user_unsuspend")
+        .target(user_uuid.to_string());
+    let response =
+        match admin::set_suspended(&state.db_conn, admin.user.uuid, user_uuid, false).await {
+            Ok(user) => (StatusCode::OK, Json(user)).into_response(),
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Makes a user a platform admin, or a regular user again.
+pub(crate) async fn set_user_role(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(user_uuid): Path<Uuid>,
+    Json(payload): Json<SetPlatformRoleRequest>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.user_role")
+        .target(user_uuid.to_string())
+        .detail(serde_json::json!({ "role": payload.role }));
+    let response = match payload.role.parse::<PlatformRole>() {
+        Ok(role) => {
+            match admin::set_role_of(&state.db_conn, admin.user.uuid, user_uuid, role).await {
+                Ok(user) => (StatusCode::OK, Json(user)).into_response(),
+                Err(e) => e.into_response(),
+            }
+        }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Lifts the login lockout of an account or IP address.
+pub(crate) async fn unlock_lockout(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Json(payload): Json<UnlockRequest>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.login_unlock")
+        .target(payload.target.clone());
+    let mut cache_conn = state.cache_conn.clone();
+    let response = match login_guard::unlock(&mut cache_conn, &payload.target).await {
+        Ok(was_locked) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "target": payload.target,
+                "was_locked": was_locked,
+            })),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Searches the functions of every namespace, oldest first.
+///
+/// Responds with the functions and the `next_after` ID to pass as `after` for
+/// the next page.
+pub(crate) async fn list_all_functions(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+    Query(filter): Query<FunctionFilter>,
+    Query(page): Query<AdminPage>,
+) -> impl IntoResponse {
+    match admin::search_functions(&state.db_conn, &filter, page.limit).await {
+        Ok(page) => (StatusCode::OK, Json(page)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Stops every running instance of a function immediately, in-flight requests
+/// included. The next invocation starts a fresh container.
+pub(crate) async fn stop_function_instances(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(function_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.instances_stop")
+        .target(function_uuid.to_string());
+    let mut cache_conn = state.cache_conn.clone();
+    let response = match admin::stop_instances(&state.db_conn, &mut cache_conn, function_uuid).await
+    {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Shows the CPUs, memory and running containers of this node's Docker host,
+/// and the invocations this node is handling.
+pub(crate) async fn node_capacity(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+) -> impl IntoResponse {
+    match admin::node_capacity(&state.concurrency).await {
+        Ok(capacity) => (StatusCode::OK, Json(capacity)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
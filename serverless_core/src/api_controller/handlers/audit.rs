@@ -0,0 +1,104 @@
+use axum::body::StreamBody;
+use axum::extract::{Json, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::admin::PlatformAdmin;
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::rbac::{Authorized, ViewAudit};
+use crate::api_controller::AppState;
+use crate::db::models::AuditFilter;
+use crate::lifecycle_manager::audit;
+
+/// Query parameter limiting how many audit events are listed.
+#[derive(Debug, Deserialize)]
+pub struct AuditPage {
+    /// Defaults to 100, at most 1000.
+    limit: Option<u64>,
+}
+
+/// Lists the audit events of the active organization, newest first.
+///
+/// Filters are taken from the query string. Responds with the events and the
+/// `next_before` ID to pass as `before` for the next page.
+pub(crate) async fn list_audit_events(
+    State(state): State<AppState>,
+    access: Authorized<ViewAudit>,
+    Query(filter): Query<AuditFilter>,
+    Query(page): Query<AuditPage>,
+) -> impl IntoResponse {
+    list_events(&state, Some(access.namespace), &filter, page.limit).await
+}
+
+/// Lists the audit events of every organization, and those belonging to none
+/// such as failed logins and operator actions, for platform admins.
+pub(crate) async fn list_all_audit_events(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+    Query(filter): Query<AuditFilter>,
+    Query(page): Query<AuditPage>,
+) -> impl IntoResponse {
+    list_events(&state, None, &filter, page.limit).await
+}
+
+async fn list_events(
+    state: &AppState,
+    namespace: Option<Uuid>,
+    filter: &AuditFilter,
+    limit: Option<u64>,
+) -> Response {
+    match audit::list_events(&state.db_conn, namespace, filter, limit).await {
+        Ok(events) => {
+            let next_before = events.last().map(|event| event.id);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "events": events,
+                    "next_before": next_before,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Exports every audit event of the active organization matching the filters
+/// as newline-delimited JSON, newest first. Exports are audited themselves.
+pub(crate) async fn export_audit_events(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<ViewAudit>,
+    Query(filter): Query<AuditFilter>,
+) -> impl IntoResponse {
+    let event = access.audit_event("audit.export");
+    let response = export_events(&state, Some(access.namespace), filter);
+    audit.record(event, response).await
+}
+
+/// Exports the audit events of the whole platform matching the filters, for
+/// platform admins.
+pub(crate) async fn export_all_audit_events(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Query(filter): Query<AuditFilter>,
+) -> impl IntoResponse {
+    let event = admin.audit_event("audit.export");
+    let response = export_events(&state, None, filter);
+    audit.record(event, response).await
+}
+
+fn export_events(state: &AppState, namespace: Option<Uuid>, filter: AuditFilter) -> Response {
+    match audit::export_events(state.db_conn.clone(), namespace, filter) {
+        Ok(lines) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/x-ndjson")],
+            StreamBody::new(lines),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
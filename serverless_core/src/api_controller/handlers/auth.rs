@@ -1,19 +1,24 @@
 use axum::{
-    extract::{Json, State},
+    extract::{Json, Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{error, info};
 use uuid::Uuid;
 
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::client_ip::ClientIp;
 use crate::api_controller::AppState;
 use crate::db::auth::AuthDBRepo;
-
-// JWT token validity period in seconds (24 hours)
-const TOKEN_VALIDITY: u64 = 24 * 60 * 60;
+use crate::lifecycle_manager::account_emails;
+use crate::lifecycle_manager::admin::ensure_active;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::login_guard::{self, LockoutPolicy};
+use crate::lifecycle_manager::sessions::{self, IssuedSession, ACCESS_TOKEN_VALIDITY_SECS};
+use crate::utils::jwt_keys::{JwtKeys, TokenUse};
 
 /// User registration request
 #[derive(Debug, Deserialize)]
@@ -29,10 +34,47 @@ pub struct LoginRequest {
     password: String,
 }
 
-/// Response containing an authentication token
+/// Request carrying an emailed verification or reset token
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    token: String,
+}
+
+/// Password reset request
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    email: String,
+}
+
+/// Request setting a new password with a reset token
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    token: String,
+    password: String,
+}
+
+/// Request carrying a refresh token
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// Logout request
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    refresh_token: String,
+    /// Revoke every session of the user, not just this one
+    #[serde(default)]
+    everywhere: bool,
+}
+
+/// Response containing an access token and the refresh token to renew it
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     token: String,
+    refresh_token: String,
+    /// Seconds until `token` expires
+    expires_in: u64,
     user: UserResponse,
 }
 
@@ -47,15 +89,65 @@ pub struct UserResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     sub: String, // Subject (user UUID)
+    sid: String, // Session the token was issued for
     exp: u64,    // Expiration time (Unix timestamp)
     iat: u64,    // Issued at (Unix timestamp)
 }
 
+/// Builds the response for a newly started or refreshed session
+pub(crate) fn session_response(
+    state: &AppState,
+    session: IssuedSession,
+    status: StatusCode,
+) -> Response {
+    match generate_token(
+        &session.user.uuid.to_string(),
+        &session.session_uuid.to_string(),
+        &state.jwt_keys,
+    ) {
+        Ok(token) => {
+            let auth_response = AuthResponse {
+                token,
+                refresh_token: session.refresh_token,
+                expires_in: ACCESS_TOKEN_VALIDITY_SECS,
+                user: UserResponse {
+                    uuid: session.user.uuid.to_string(),
+                    email: session.user.email,
+                },
+            };
+
+            (status, Json(auth_response)).into_response()
+        }
+        Err(e) => {
+            error!("Failed to generate token: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to generate authentication token"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// Handles user registration
 pub async fn register(
     State(state): State<AppState>,
+    audit: Audit,
     Json(payload): Json<RegisterRequest>,
 ) -> impl IntoResponse {
+    let mut event = AuditEvent::new("auth.register").target(payload.email.clone());
+    let response = register_user(&state, payload, &mut event).await;
+    audit.record(event, response).await
+}
+
+/// Registers a user, who becomes the actor of `event` once created
+async fn register_user(
+    state: &AppState,
+    payload: RegisterRequest,
+    event: &mut AuditEvent,
+) -> Response {
     // Validate email and password
     if payload.email.is_empty() || payload.password.is_empty() {
         return (
@@ -67,50 +159,66 @@ pub async fn register(
             .into_response();
     }
 
-    // Check password length
-    if payload.password.len() < 6 {
+    // Check the password against the configured policy
+    if let Err(reason) = state.passwords.check(&payload.password, &payload.email) {
         return (
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": "Password must be at least 6 characters"
-            })),
+            Json(serde_json::json!({ "error": reason })),
         )
             .into_response();
     }
 
+    let password_hash = match state.passwords.hash(&payload.password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            error!("Failed to hash password: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to register user"
+                })),
+            )
+                .into_response();
+        }
+    };
+
     // Register the user
-    match AuthDBRepo::register(&state.db_conn, payload.email, payload.password).await {
+    match AuthDBRepo::register(&state.db_conn, payload.email, password_hash).await {
         Ok(user) => {
             info!("User registered: {}", user.email);
+            event.actor = Some(user.uuid);
+            event.namespace = Some(user.uuid);
 
-            // Generate a token for the user
-            match generate_token(
-                &user.uuid.to_string(),
-                &state.config.server_config.jwt_auth_secret,
-            ) {
-                Ok(token) => {
-                    let user_response = UserResponse {
-                        uuid: user.uuid.to_string(),
-                        email: user.email,
-                    };
-
-                    let auth_response = AuthResponse {
-                        token,
-                        user: user_response,
-                    };
-
-                    (StatusCode::CREATED, Json(auth_response)).into_response()
-                }
-                Err(e) => {
-                    error!("Failed to generate token: {}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({
-                            "error": "Failed to generate authentication token"
-                        })),
-                    )
-                        .into_response()
-                }
+            if let Err(e) = account_emails::send_verification(
+                state.mailer.as_ref(),
+                &state.jwt_keys,
+                &state.config.mail_config.public_url,
+                &user,
+            )
+            .await
+            {
+                error!("Failed to send verification email to {}: {}", user.email, e);
+            }
+
+            // Unverified accounts can only log in once they confirm their address
+            if state.config.auth_config.require_email_verification {
+                return (
+                    StatusCode::CREATED,
+                    Json(serde_json::json!({
+                        "user": UserResponse {
+                            uuid: user.uuid.to_string(),
+                            email: user.email,
+                        },
+                        "verification_required": true,
+                    })),
+                )
+                    .into_response();
+            }
+
+            // Start a session for the user
+            match sessions::start_session(&state.db_conn, user).await {
+                Ok(session) => session_response(state, session, StatusCode::CREATED),
+                Err(e) => e.into_response(),
             }
         }
         Err(e) => {
@@ -136,91 +244,306 @@ pub async fn register(
     }
 }
 
+/// The failed login limits from configuration
+fn lockout_policy(state: &AppState) -> LockoutPolicy {
+    let config = &state.config.auth_config;
+    LockoutPolicy {
+        max_failed_logins: config.max_failed_logins,
+        max_failed_logins_per_ip: config.max_failed_logins_per_ip,
+        window: config.failed_login_window,
+        lockout_duration: config.lockout_duration,
+    }
+}
+
 /// Handles user login
+///
+/// Failed logins are counted per account and per client IP; past their limits
+/// further attempts are delayed and then locked out with `429 Too Many Requests`.
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    audit: Audit,
     Json(payload): Json<LoginRequest>,
 ) -> impl IntoResponse {
-    match AuthDBRepo::login(&state.db_conn, payload.email, payload.password).await {
-        Ok(user) => {
-            info!("User logged in: {}", user.email);
-
-            // Generate a token for the user
-            match generate_token(
-                &user.uuid.to_string(),
-                &state.config.server_config.jwt_auth_secret,
-            ) {
-                Ok(token) => {
-                    let user_response = UserResponse {
-                        uuid: user.uuid.to_string(),
-                        email: user.email,
-                    };
-
-                    let auth_response = AuthResponse {
-                        token,
-                        user: user_response,
-                    };
-
-                    (StatusCode::OK, Json(auth_response)).into_response()
-                }
+    let mut event = AuditEvent::new("auth.login").target(payload.email.clone());
+    let response = authenticate_login(&state, ip, payload, &mut event).await;
+    audit.record(event, response).await
+}
+
+/// Logs a user in. Attempts on an existing account are noted in its
+/// organization's audit log, and the user becomes the actor once the
+/// password checks out.
+async fn authenticate_login(
+    state: &AppState,
+    ip: IpAddr,
+    payload: LoginRequest,
+    event: &mut AuditEvent,
+) -> Response {
+    let mut cache_conn = state.cache_conn.clone();
+    if let Err(e) = login_guard::check_allowed(&mut cache_conn, &payload.email, ip).await {
+        return e.into_response();
+    }
+
+    let user = match AuthDBRepo::find_by_email(&state.db_conn, &payload.email).await {
+        Ok(user) => user,
+        Err(e) => {
+            error!("Login error: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to authenticate user"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(user) = &user {
+        event.namespace = Some(user.uuid);
+    }
+
+    // Unknown users cost as much as wrong passwords, so emails can't be probed
+    let user = match user {
+        Some(user) if state.passwords.verify(&payload.password, &user.password) => user,
+        user => {
+            if user.is_none() {
+                state.passwords.verify_unknown_user(&payload.password);
+            }
+            let policy = lockout_policy(state);
+            if let Err(e) =
+                login_guard::record_failure(&mut cache_conn, &policy, &payload.email, ip).await
+            {
+                return e.into_response();
+            }
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({
+                    "error": "Invalid credentials"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Err(e) = login_guard::record_success(&mut cache_conn, &payload.email).await {
+        return e.into_response();
+    }
+
+    // Suspended accounts are refused before anything is sent or started for them
+    if let Err(e) = ensure_active(&user) {
+        return e.into_response();
+    }
+
+    if state.config.auth_config.require_email_verification && user.email_verified_at.is_none() {
+        if let Err(e) = account_emails::send_verification(
+            state.mailer.as_ref(),
+            &state.jwt_keys,
+            &state.config.mail_config.public_url,
+            &user,
+        )
+        .await
+        {
+            error!("Failed to send verification email to {}: {}", user.email, e);
+        }
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Email address not verified; a new verification link has been sent"
+            })),
+        )
+            .into_response();
+    }
+    info!("User logged in: {}", user.email);
+    event.actor = Some(user.uuid);
+
+    // Upgrade hashes made with older Argon2 parameters while the password is at hand
+    let user = if state.passwords.needs_rehash(&user.password) {
+        match state.passwords.hash(&payload.password) {
+            Ok(hash) => match AuthDBRepo::update_password(&state.db_conn, user.clone(), hash).await
+            {
+                Ok(user) => user,
                 Err(e) => {
-                    error!("Failed to generate token: {}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({
-                            "error": "Failed to generate authentication token"
-                        })),
-                    )
-                        .into_response()
+                    error!("Failed to rehash password of {}: {}", user.email, e);
+                    user
                 }
+            },
+            Err(e) => {
+                error!("Failed to rehash password of {}: {}", user.email, e);
+                user
             }
         }
-        Err(e) => {
-            if e.to_string().contains("Invalid credentials") {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({
-                        "error": "Invalid credentials"
-                    })),
-                )
-                    .into_response();
-            }
+    } else {
+        user
+    };
 
-            error!("Login error: {}", e);
+    // Start a session for the user
+    match sessions::start_session(&state.db_conn, user).await {
+        Ok(session) => session_response(state, session, StatusCode::OK),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Responds to a verification attempt
+async fn verify(state: &AppState, audit: Audit, token: &str) -> Response {
+    let mut event = AuditEvent::new("auth.verify_email");
+    let response = match account_emails::verify_email(&state.db_conn, &state.jwt_keys, token).await
+    {
+        Ok(user) => {
+            event = event.by(user.uuid, user.uuid).target(user.email.clone());
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::OK,
                 Json(serde_json::json!({
-                    "error": "Failed to authenticate user"
+                    "verified": true,
+                    "email": user.email,
                 })),
             )
                 .into_response()
         }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Verifies an email address with the token sent to it
+pub async fn verify_email(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(payload): Json<TokenRequest>,
+) -> impl IntoResponse {
+    verify(&state, audit, &payload.token).await
+}
+
+/// Verifies an email address from the link sent to it
+pub async fn verify_email_link(
+    State(state): State<AppState>,
+    audit: Audit,
+    Query(query): Query<TokenRequest>,
+) -> impl IntoResponse {
+    verify(&state, audit, &query.token).await
+}
+
+/// Emails a password reset token, answering the same whether or not the account exists
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> impl IntoResponse {
+    let event = AuditEvent::new("auth.forgot_password").target(payload.email.clone());
+    let response = match account_emails::request_password_reset(
+        &state.db_conn,
+        state.mailer.as_ref(),
+        &state.jwt_keys,
+        &payload.email,
+    )
+    .await
+    {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "message": "If an account uses this address, a reset token has been sent to it"
+            })),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Sets a new password with a reset token, signing out every session
+pub async fn reset_password(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> impl IntoResponse {
+    let event = AuditEvent::new("auth.reset_password");
+    let user = match account_emails::reset_password(
+        &state.db_conn,
+        &state.jwt_keys,
+        &state.passwords,
+        &payload.token,
+        &payload.password,
+    )
+    .await
+    {
+        Ok(user) => user,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+    let event = event.by(user.uuid, user.uuid).target(user.email.clone());
+
+    // Proving control of the inbox lifts a lockout of the account
+    let mut cache_conn = state.cache_conn.clone();
+    if let Err(e) = login_guard::record_success(&mut cache_conn, &user.email).await {
+        error!("Failed to clear failed logins of {}: {}", user.email, e);
+    }
+
+    let response = (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "message": "Password reset; log in with the new password"
+        })),
+    )
+        .into_response();
+    audit.record(event, response).await
+}
+
+/// Exchanges a refresh token for a new access token and refresh token
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> impl IntoResponse {
+    match sessions::refresh_session(&state.db_conn, &payload.refresh_token).await {
+        Ok(session) => session_response(&state, session, StatusCode::OK),
+        Err(e) => e.into_response(),
     }
 }
 
-/// Validates a JWT token
+/// Revokes the session of a refresh token, or every session of its user
+pub async fn logout(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(payload): Json<LogoutRequest>,
+) -> impl IntoResponse {
+    let mut event = AuditEvent::new("auth.logout");
+    let response =
+        match sessions::logout(&state.db_conn, &payload.refresh_token, payload.everywhere).await {
+            Ok((user_uuid, revoked)) => {
+                if let Some(user_uuid) = user_uuid {
+                    event = event.by(user_uuid, user_uuid);
+                }
+                event = event.detail(serde_json::json!({ "revoked_sessions": revoked }));
+                (
+                    StatusCode::OK,
+                    Json(serde_json::json!({ "revoked_sessions": revoked })),
+                )
+                    .into_response()
+            }
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Validates a JWT token, returning the user's and the session's UUIDs
 pub fn validate_token(
     token: &str,
-    auth_jwt_secret: &str,
-) -> Result<Uuid, jsonwebtoken::errors::Error> {
-    // Decode and validate the token
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(auth_jwt_secret.as_bytes()),
-        &Validation::default(),
-    )?;
+    jwt_keys: &JwtKeys,
+) -> Result<(Uuid, Uuid), jsonwebtoken::errors::Error> {
+    // Decode and validate the token with the key named by its `kid`
+    let claims = jwt_keys.verify::<Claims>(TokenUse::Access, token)?;
 
     // Extract the user UUID from the subject claim
-    let uuid = Uuid::parse_str(&token_data.claims.sub)
+    let uuid = Uuid::parse_str(&claims.sub)
         .map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidSubject)?;
+    let session_uuid =
+        Uuid::parse_str(&claims.sid).map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
 
-    Ok(uuid)
+    Ok((uuid, session_uuid))
 }
 
-/// Generates a JWT token for a user
+/// Generates a short-lived JWT token for a user's session
 fn generate_token(
     user_uuid: &str,
-    auth_jwt_secret: &str,
+    session_uuid: &str,
+    jwt_keys: &JwtKeys,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -229,13 +552,15 @@ fn generate_token(
 
     let claims = Claims {
         sub: user_uuid.to_string(),
-        exp: now + TOKEN_VALIDITY,
+        sid: session_uuid.to_string(),
+        exp: now + ACCESS_TOKEN_VALIDITY_SECS,
         iat: now,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(auth_jwt_secret.as_bytes()),
-    )
+    jwt_keys.issue(TokenUse::Access, &claims)
+}
+
+/// Publishes the public keys platform JWTs can be verified with
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.jwt_keys.jwks())
 }
@@ -1,99 +1,370 @@
 use axum::body::Body;
 use axum::extract::{Multipart, Path, Query, State};
-use axum::http::{HeaderMap, Request, StatusCode};
-use axum::response::IntoResponse;
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
 
-use crate::api_controller::middlewares::jwt::AuthenticatedUser;
+use crate::api_controller::handlers::quotas::default_quota;
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::jwt::{authenticate, require_scope, AuthenticatedUser};
+use crate::api_controller::middlewares::rbac::{
+    Authorized, DeleteFunctions, DeployFunctions, ReadFunctions,
+};
 use crate::api_controller::AppState;
+use crate::db::alias::FunctionAliasDBRepo;
+use crate::db::cache::FunctionCacheRepo;
 use crate::db::function::FunctionDBRepo;
-use crate::db::models::DeployableFunction;
-use crate::lifecycle_manager::deploy::deploy_function;
-use crate::lifecycle_manager::invoke::{check_function_status, start_function};
+use crate::db::models::{DeployableFunction, FunctionAuth};
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::admin::ensure_function_active;
+use crate::lifecycle_manager::api_keys::{create_key, list_keys, revoke_key};
+use crate::lifecycle_manager::audit::Outcome;
+use crate::lifecycle_manager::concurrency::ConcurrencyLimits;
+use crate::lifecycle_manager::delete::delete_function;
+use crate::lifecycle_manager::deploy::{
+    deploy_function, deploy_functions, BuildPolicy, RolloutContext,
+};
+use crate::lifecycle_manager::environment::update_env;
+use crate::lifecycle_manager::error::ServelessCoreResult;
+use crate::lifecycle_manager::invocation_auth::{
+    authenticate_api_key, authenticate_jwt, authorize_member, forward_identity, function_auth,
+    Caller,
+};
+use crate::lifecycle_manager::invoke::{
+    check_function_status, function_key, start_function, InstanceContext,
+};
+use crate::lifecycle_manager::quotas::effective_quota;
+use crate::lifecycle_manager::rollout::recycle_instances;
+use crate::lifecycle_manager::rollout::IN_FLIGHT_TTL_SECS;
+use crate::lifecycle_manager::secrets::{list_secret_keys, set_secrets, unset_secret};
+use crate::lifecycle_manager::status::{function_details, record_failure, record_invocation};
+use crate::lifecycle_manager::tokens::Scope;
+use crate::lifecycle_manager::versioning::{
+    resolve_version, rollback_alias, set_alias, version_artifact,
+};
 use crate::utils::utils::make_request;
 use futures_util::stream::StreamExt;
+use serde::Deserialize;
+use shared_utils::{ArchiveFormat, ExtractLimits};
 use std::collections::HashMap;
+use std::time::Duration;
 use tracing::{error, info};
+use uuid::Uuid;
 
-/// Handles uploading a function as a ZIP file with authentication.
+/// Request body for pointing an alias at one or two versions.
+#[derive(Debug, Deserialize)]
+pub struct SetAliasRequest {
+    version: i32,
+    canary_version: Option<i32>,
+    #[serde(default)]
+    canary_weight: i32,
+}
+
+/// Request body for setting function secrets, as a map of name to value.
+#[derive(Debug, Deserialize)]
+pub struct SetSecretsRequest {
+    secrets: HashMap<String, String>,
+}
+
+/// Request body for creating an API key of a function.
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    name: String,
+}
+
+/// Request body for updating environment variables; `null` removes a variable.
+#[derive(Debug, Deserialize)]
+pub struct UpdateEnvRequest {
+    env: HashMap<String, Option<String>>,
+}
+
+/// Request body for rolling an alias back.
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    alias: String,
+    version: Option<i32>,
+}
+
+/// Reads every function archive of a multipart upload.
 ///
-/// This endpoint expects a multipart request with one or more files and an Authorization header.
-/// If a file with a name ending in ".zip" is found, it reads its content
-/// and deploys the function for the authenticated user.
+/// Files whose name ends in ".zip", ".tar.gz" or ".tar.zst" are read, and named
+/// after the file without its extension; other fields are skipped. The archive
+/// format itself is detected from its magic bytes during extraction.
+///
+/// # Returns
+///
+/// The function names and archive contents in upload order, or an error response
+/// if a file cannot be read, is too large, or the same function appears twice.
+async fn read_archives(
+    multipart: &mut Multipart,
+    max_size: usize,
+    max_files: usize,
+) -> Result<Vec<(String, Vec<u8>)>, Response> {
+    let mut archives: Vec<(String, Vec<u8>)> = Vec::new();
+
+    // Iterate over the fields in the multipart request.
+    while let Ok(Some(mut field)) = multipart.next_field().await {
+        // Check if the field has a file name.
+        let Some(file_name) = field.file_name() else {
+            error!("Encountered a multipart field without a filename");
+            continue;
+        };
+        // Process only archive files.
+        let Some(function_name) = ArchiveFormat::strip_extension(file_name) else {
+            continue;
+        };
+        let function_name = function_name.to_string();
+
+        if archives.iter().any(|(name, _)| *name == function_name) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Function '{}' was uploaded more than once", function_name),
+            )
+                .into_response());
+        }
+        if archives.len() == max_files {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("At most {} functions can be deployed at once", max_files),
+            )
+                .into_response());
+        }
+
+        // Read file content in chunks.
+        let buffer = read_field_chunks(&mut field, max_size).await.map_err(|e| {
+            error!("Error reading file chunk: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Error reading file: {}", e),
+            )
+                .into_response()
+        })?;
+
+        info!("Received service: {}", function_name);
+        archives.push((function_name, buffer));
+    }
+    Ok(archives)
+}
+
+/// Builds the rollout settings of a deploy from the server configuration.
+fn rollout_context(state: &AppState) -> RolloutContext {
+    RolloutContext {
+        cache_conn: state.cache_conn.clone(),
+        docker_compose_network_host: state
+            .config
+            .server_config
+            .docker_compose_network_host
+            .clone(),
+        drain_timeout: Duration::from_secs(state.config.function_config.drain_timeout),
+        secret_cipher: state.secret_cipher.clone(),
+    }
+}
+
+/// Builds the build policy of a deploy from the server configuration.
+fn build_policy(state: &AppState) -> BuildPolicy {
+    let function_config = &state.config.function_config;
+    BuildPolicy {
+        disallowed_instructions: function_config.dockerfile_disallowed_instructions.clone(),
+        allowed_image_prefixes: function_config.allowed_image_prefixes.clone(),
+        extract_limits: ExtractLimits {
+            max_total_bytes: function_config.max_extracted_size,
+            max_entries: function_config.max_archive_entries,
+            max_compression_ratio: function_config.max_compression_ratio,
+        },
+    }
+}
+
+/// Handles uploading a function archive with authentication.
+///
+/// This endpoint expects a multipart request with exactly one file named after the
+/// function and ending in ".zip", ".tar.gz" or ".tar.zst", and an Authorization
+/// header. The function is deployed for the authenticated user. Several functions
+/// are deployed together through [`upload_project`].
 ///
 /// Returns an HTTP response indicating success or an appropriate error.
 pub(crate) async fn upload_function(
     State(state): State<AppState>,
-    AuthenticatedUser(user_uuid): AuthenticatedUser,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
+    let event = access.audit_event("function.deploy");
     // Get configuration from state
-    let supported_archive_ext = ".zip"; // Currently we only support ZIP
     let default_runtime = &state.config.function_config.default_runtime;
     let max_size = state.config.function_config.max_function_size;
 
-    // Iterate over the fields in the multipart request.
-    while let Ok(Some(mut field)) = multipart.next_field().await {
-        // Check if the field has a file name.
-        if let Some(file_name) = field.file_name() {
-            let file_name = file_name.to_owned();
-            // Process only archive files.
-            if file_name.ends_with(supported_archive_ext) {
-                // Read file content in chunks.
-                let buffer = match read_field_chunks(&mut field, max_size).await {
-                    Ok(buffer) => buffer,
-                    Err(e) => {
-                        error!("Error reading file chunk: {}", e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Error reading file: {}", e),
-                        )
-                            .into_response();
-                    }
-                };
-
-                let function_name = file_name
-                    .strip_suffix(supported_archive_ext)
-                    .unwrap_or(&file_name);
-                info!("Received service: {}", function_name);
-
-                let function = DeployableFunction {
-                    name: function_name.to_string(),
-                    runtime: default_runtime.clone(),
-                    content: buffer,
-                    user_uuid,
-                };
-
-                // Deploy the function
-                return match deploy_function(&state.db_conn, function).await {
-                    Ok(res) => (
-                        StatusCode::OK,
-                        format!(
-                            "{}\nFunction: {}\nUser UUID: {}",
-                            res, function_name, user_uuid
-                        ),
-                    )
-                        .into_response(),
-                    Err(e) => {
-                        error!("Error deploying function {}: {}", function_name, e);
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Failed to deploy function: {}", e),
-                        )
-                            .into_response()
-                    }
-                };
+    let max_files = state.config.function_config.max_project_functions;
+    let mut archives = match read_archives(&mut multipart, max_size, max_files).await {
+        Ok(archives) => archives,
+        Err(response) => return audit.record(event, response).await,
+    };
+    if archives.len() != 1 {
+        let response = (
+            StatusCode::BAD_REQUEST,
+            "Expected exactly one function archive; use /invok/deploy/project to deploy several",
+        )
+            .into_response();
+        return audit.record(event, response).await;
+    }
+    let (function_name, buffer) = archives.remove(0);
+    let event = event.target(function_name.clone());
+    let user_uuid = access.namespace;
+
+    let function = DeployableFunction {
+        name: function_name.clone(),
+        runtime: default_runtime.clone(),
+        content: buffer,
+        user_uuid,
+        deployed_by: access.membership.user.id,
+    };
+
+    let quota = match effective_quota(&state.db_conn, &default_quota(&state), user_uuid).await {
+        Ok(quota) => quota,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    // Deploy the function
+    let result = deploy_function(
+        &state.db_conn,
+        state.artifacts.as_ref(),
+        rollout_context(&state),
+        &build_policy(&state),
+        &quota,
+        function,
+    )
+    .await;
+    let response = deploy_response(&function_name, user_uuid, result);
+    audit.record(event, response).await
+}
+
+/// Answers a single-function deploy. Client errors keep their own status so
+/// the audit log tells rejected deploys apart from failed ones.
+fn deploy_response(
+    function_name: &str,
+    user_uuid: Uuid,
+    result: ServelessCoreResult<String>,
+) -> Response {
+    match result {
+        Ok(res) => (
+            StatusCode::OK,
+            format!(
+                "{}\nFunction: {}\nUser UUID: {}",
+                res, function_name, user_uuid
+            ),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Error deploying function {}: {}", function_name, e);
+            if e.is_client_error() {
+                return e.into_response();
             }
-        } else {
-            error!("Encountered a multipart field without a filename");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to deploy function: {}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Deploys every function of a project from a single multipart request.
+///
+/// Each file is an archive named after its function, as for [`upload_function`].
+/// Functions are built in parallel, up to `FUNCTION_DEPLOY_PARALLELISM` at a time,
+/// and each one succeeds or fails on its own.
+///
+/// Responds with one result per function, in upload order. The status is `200` if
+/// every function was deployed and `207 Multi-Status` if any of them failed. Each
+/// function's deploy is audited on its own.
+pub(crate) async fn upload_project(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let function_config = &state.config.function_config;
+    let archives = match read_archives(
+        &mut multipart,
+        function_config.max_function_size,
+        function_config.max_project_functions,
+    )
+    .await
+    {
+        Ok(archives) => archives,
+        Err(response) => {
+            let event = access.audit_event("function.deploy_project");
+            return audit.record(event, response).await;
         }
+    };
+    if archives.is_empty() {
+        let event = access.audit_event("function.deploy_project");
+        let response = (StatusCode::BAD_REQUEST, "No function archives uploaded").into_response();
+        return audit.record(event, response).await;
     }
-    (StatusCode::BAD_REQUEST, "Unexpected request").into_response()
+
+    let user_uuid = access.namespace;
+    let quota = match effective_quota(&state.db_conn, &default_quota(&state), user_uuid).await {
+        Ok(quota) => quota,
+        Err(e) => {
+            let event = access.audit_event("function.deploy_project");
+            return audit.record(event, e.into_response()).await;
+        }
+    };
+    let functions = archives
+        .into_iter()
+        .map(|(name, content)| DeployableFunction {
+            name,
+            runtime: function_config.default_runtime.clone(),
+            content,
+            user_uuid,
+            deployed_by: access.membership.user.id,
+        })
+        .collect();
+
+    let results = deploy_functions(
+        &state.db_conn,
+        state.artifacts.as_ref(),
+        rollout_context(&state),
+        &build_policy(&state),
+        &quota,
+        functions,
+        function_config.deploy_parallelism,
+    )
+    .await;
+    for result in &results {
+        let event = access
+            .audit_event("function.deploy")
+            .target(result.function.clone())
+            .detail(serde_json::json!({ "project": true }));
+        let outcome = if result.success {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        };
+        audit.record_outcome(event, outcome).await;
+    }
+
+    let status = if results.iter().all(|result| result.success) {
+        StatusCode::OK
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+    (
+        status,
+        axum::Json(serde_json::json!({
+            "user_uuid": user_uuid,
+            "results": results,
+        })),
+    )
+        .into_response()
 }
 
 /// List functions for an authenticated user
 pub(crate) async fn list_functions(
     State(state): State<AppState>,
-    AuthenticatedUser(user_uuid): AuthenticatedUser,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
 ) -> impl IntoResponse {
     // Get functions for this user
     match FunctionDBRepo::find_functions_by_user_uuid(&state.db_conn, user_uuid).await {
@@ -104,8 +375,10 @@ pub(crate) async fn list_functions(
                 .map(|f| {
                     serde_json::json!({
                         "uuid": f.uuid.to_string(),
+                        "namespace": f.namespace.to_string(),
                         "name": f.name,
-                        "runtime": f.runtime
+                        "runtime": f.runtime,
+                        "last_deployed_at": f.last_deployed_at.map(|at| at.to_rfc3339()),
                     })
                 })
                 .collect::<Vec<_>>();
@@ -123,6 +396,64 @@ pub(crate) async fn list_functions(
     }
 }
 
+/// Shows the metadata and live status of a function owned by the authenticated user.
+pub(crate) async fn get_function(
+    mut state: State<AppState>,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
+    Path(function_name): Path<String>,
+) -> impl IntoResponse {
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_conn = state.db_conn.clone();
+    match function_details(&db_conn, &mut state.cache_conn, function, user_uuid).await {
+        Ok(details) => (StatusCode::OK, axum::Json(details)).into_response(),
+        Err(e) => {
+            error!("Error describing function {}: {}", function_name, e);
+            e.into_response()
+        }
+    }
+}
+
+/// Deletes a function owned by the authenticated user and cleans up its resources.
+///
+/// Responds with a report of the database records, cache entries, running
+/// instances, images and stored archives that were removed.
+pub(crate) async fn remove_function(
+    mut state: State<AppState>,
+    audit: Audit,
+    access: Authorized<DeleteFunctions>,
+    Path(function_name): Path<String>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.delete")
+        .target(function_name.clone());
+    let db_conn = state.db_conn.clone();
+    let artifacts = state.artifacts.clone();
+    let response = match delete_function(
+        &db_conn,
+        &mut state.cache_conn,
+        artifacts.as_ref(),
+        &function_name,
+        user_uuid,
+    )
+    .await
+    {
+        Ok(report) => (StatusCode::OK, axum::Json(report)).into_response(),
+        Err(e) => {
+            error!("Error deleting function {}: {}", function_name, e);
+            e.into_response()
+        }
+    };
+    audit.record(event, response).await
+}
+
 /// Reads all chunks from a multipart field into a buffer.
 async fn read_field_chunks(
     field: &mut axum::extract::multipart::Field<'_>,
@@ -153,13 +484,17 @@ async fn read_field_chunks(
 ///
 /// This endpoint:
 /// - Checks if the function exists in the user's namespace.
+/// - Resolves the version to run, honouring an optional `@alias` suffix and its
+///   weighted traffic split.
+/// - Reserves one of the function's concurrency slots, queueing or rejecting
+///   with `503` when the function is at capacity.
 /// - Starts the function if needed (using a cache connection).
 /// - Forwards the incoming request (including headers and query parameters) to the service.
 ///
 /// # Parameters
 ///
 /// * `namespace` - The user's UUID serving as a namespace for their functions
-/// * `function_name` - The name of the function to invoke
+/// * `function_name` - The ID or name of the function to invoke, optionally `name@alias`
 ///
 /// # Returns
 ///
@@ -168,7 +503,7 @@ pub(crate) async fn call_function(
     mut state: State<AppState>,
     Path((namespace, function_name)): Path<(String, String)>,
     Query(query): Query<HashMap<String, String>>,
-    headers: HeaderMap,
+    mut headers: HeaderMap,
     request: Request<Body>,
 ) -> impl IntoResponse {
     // Parse and validate namespace UUID
@@ -188,28 +523,83 @@ pub(crate) async fn call_function(
         }
     };
 
-    if let Err(e) = check_function_status(&state.db_conn, &function_name, user_uuid).await {
-        error!(
-            namespace = %namespace,
-            function = %function_name,
-            error = %e,
-            "Function status check failed"
-        );
+    let (function_name, alias) = match function_name.split_once('@') {
+        Some((name, alias)) => (name.to_string(), Some(alias.to_string())),
+        None => (function_name, None),
+    };
+
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => {
+            error!(
+                namespace = %namespace,
+                function = %function_name,
+                error = %e,
+                "Function status check failed"
+            );
+            return e.into_response();
+        }
+    };
+    // The function may have been referred to by its ID.
+    let function_name = function.name.clone();
+
+    if let Err(e) = ensure_function_active(&state.db_conn, &function).await {
         return e.into_response();
     }
 
-    let docker_compose_network_host = state
-        .config
-        .server_config
-        .docker_compose_network_host
-        .clone();
+    // Enforce the function's auth mode and pass the verified caller on to it.
+    match authorize_invocation(&state, &function, &headers).await {
+        Ok(caller) => forward_identity(&mut headers, &caller, &state.jwt_keys),
+        Err(response) => return response,
+    }
+
+    let version = match resolve_version(&state.db_conn, &function, alias.as_deref()).await {
+        Ok(version) => version,
+        Err(e) => return e.into_response(),
+    };
+
+    let function_config = &state.config.function_config;
+    let limits = ConcurrencyLimits {
+        max_concurrency: function
+            .max_concurrency
+            .and_then(|n| usize::try_from(n).ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(function_config.max_concurrency),
+        queue_size: function_config.max_queued_requests,
+        wait_timeout: Duration::from_secs(function_config.cold_start_timeout),
+    };
+
+    // Hold a concurrency slot until the downstream request completes.
+    let _permit = match state
+        .concurrency
+        .acquire(&function_key(&function_name, user_uuid), &limits)
+        .await
+    {
+        Ok(permit) => permit,
+        Err(e) => return e.into_response(),
+    };
+
+    let quota = match effective_quota(&state.db_conn, &default_quota(&state), user_uuid).await {
+        Ok(quota) => quota,
+        Err(e) => return e.into_response(),
+    };
 
     // Attempt to start the function using the cache connection.
+    let mut cache_conn = state.cache_conn.clone();
+    let instance = InstanceContext {
+        db_conn: &state.db_conn,
+        cipher: &state.secret_cipher,
+        docker_compose_network_host: &state.config.server_config.docker_compose_network_host,
+        quota: &quota,
+    };
     let addr = match start_function(
-        &mut state.cache_conn,
+        &mut cache_conn,
+        &instance,
+        &state.concurrency,
+        &limits,
         &function_name,
         user_uuid,
-        docker_compose_network_host,
+        &version,
     )
     .await
     {
@@ -221,17 +611,489 @@ pub(crate) async fn call_function(
                 error = ?e,
                 "Error starting function"
             );
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to start function: {}", e),
-            )
-                .into_response();
+            record_failure(&mut cache_conn, &function_name, user_uuid, e.to_string()).await;
+            return e.into_response();
         }
     };
 
     info!(namespace = %namespace, function = %function_name, "Making request to service");
+    record_invocation(&mut cache_conn, &function_name, user_uuid).await;
+    // Track the request so a redeploy can drain this instance before stopping it.
+    let _ =
+        FunctionCacheRepo::begin_request(&mut state.cache_conn, &addr, IN_FLIGHT_TTL_SECS).await;
     // Forward the request to the service and return its response.
-    make_request(&addr, &function_name, query, headers, request)
+    let response = make_request(&addr, &function_name, query, headers, request)
         .await
-        .into_response()
+        .into_response();
+    let _ = FunctionCacheRepo::end_request(&mut state.cache_conn, &addr).await;
+    if response.status().is_server_error() {
+        let message = format!("Function responded with {}", response.status());
+        record_failure(&mut cache_conn, &function_name, user_uuid, message).await;
+    }
+    response
+}
+
+/// Checks that the caller may invoke `function` according to its auth mode.
+///
+/// # Returns
+///
+/// The verified caller, or the response rejecting the invocation.
+async fn authorize_invocation(
+    state: &AppState,
+    function: &db_entities::function::Model,
+    headers: &HeaderMap,
+) -> Result<Caller, Response> {
+    let auth = function_auth(function).map_err(IntoResponse::into_response)?;
+    let caller = match auth {
+        FunctionAuth::Public => Ok(Caller::Anonymous),
+        FunctionAuth::ApiKey => authenticate_api_key(&state.db_conn, function, headers).await,
+        FunctionAuth::Platform => {
+            let AuthenticatedUser(user_uuid, scopes) = authenticate(state, headers)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            require_scope(&scopes, Scope::Invoke).map_err(IntoResponse::into_response)?;
+            authorize_member(&state.db_conn, function, user_uuid).await
+        }
+        FunctionAuth::Jwt {
+            issuer,
+            audience,
+            jwks_url,
+        } => {
+            authenticate_jwt(
+                &state.jwks,
+                function,
+                &issuer,
+                audience.as_deref(),
+                jwks_url.as_deref(),
+                headers,
+            )
+            .await
+        }
+    };
+    caller.map_err(IntoResponse::into_response)
+}
+
+/// Lists the versions and aliases of a function owned by the authenticated user.
+pub(crate) async fn list_versions(
+    State(state): State<AppState>,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
+    Path(function_name): Path<String>,
+) -> impl IntoResponse {
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return e.into_response(),
+    };
+
+    let versions = FunctionVersionDBRepo::list_versions(&state.db_conn, function.id).await;
+    let aliases = FunctionAliasDBRepo::list_aliases(&state.db_conn, function.id).await;
+    match (versions, aliases) {
+        (Ok(versions), Ok(aliases)) => {
+            let versions = versions
+                .into_iter()
+                .map(|v| {
+                    serde_json::json!({
+                        "version": v.version,
+                        "image_tag": v.image_tag,
+                        "artifact_digest": v.artifact_digest,
+                        "created_at": v.created_at.to_rfc3339(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let aliases = aliases.iter().map(alias_json).collect::<Vec<_>>();
+
+            (
+                StatusCode::OK,
+                axum::Json(serde_json::json!({
+                    "name": function.name,
+                    "versions": versions,
+                    "aliases": aliases,
+                })),
+            )
+                .into_response()
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Error listing versions of {}: {}", function_name, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Error listing versions: {}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Downloads the archive a version of a function was deployed from.
+pub(crate) async fn download_artifact(
+    State(state): State<AppState>,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
+    Path((function_name, version)): Path<(String, i32)>,
+) -> impl IntoResponse {
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return e.into_response(),
+    };
+
+    match version_artifact(&state.db_conn, state.artifacts.as_ref(), &function, version).await {
+        Ok(content) => {
+            let format = ArchiveFormat::detect(&content).unwrap_or(ArchiveFormat::Zip);
+            let file_name = format!("{}-v{}{}", function.name, version, format.extension());
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, format.mime_type().to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{file_name}\""),
+                    ),
+                ],
+                content,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Creates or repoints an alias of a function, optionally with a canary split.
+pub(crate) async fn put_alias(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path((function_name, alias)): Path<(String, String)>,
+    axum::Json(payload): axum::Json<SetAliasRequest>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.alias_set")
+        .target(function_name.clone())
+        .detail(serde_json::json!({
+            "alias": alias,
+            "version": payload.version,
+            "canary_version": payload.canary_version,
+            "canary_weight": payload.canary_weight,
+        }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match set_alias(
+        &state.db_conn,
+        &function,
+        &alias,
+        payload.version,
+        payload.canary_version,
+        payload.canary_weight,
+    )
+    .await
+    {
+        Ok(alias) => (StatusCode::OK, axum::Json(alias_json(&alias))).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Repoints an alias of a function to a previous version.
+///
+/// Without an explicit `version`, the alias moves to the newest version older
+/// than the one it currently serves.
+pub(crate) async fn rollback_function(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path(function_name): Path<String>,
+    axum::Json(payload): axum::Json<RollbackRequest>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.rollback")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "alias": payload.alias, "version": payload.version }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response =
+        match rollback_alias(&state.db_conn, &function, &payload.alias, payload.version).await {
+            Ok(alias) => {
+                info!(
+                    function = %function_name,
+                    alias = %alias.name,
+                    version = alias.version,
+                    "Alias rolled back"
+                );
+                (StatusCode::OK, axum::Json(alias_json(&alias))).into_response()
+            }
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Lists the secret names of a function; values are never returned.
+pub(crate) async fn list_secrets(
+    State(state): State<AppState>,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
+    Path(function_name): Path<String>,
+) -> impl IntoResponse {
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return e.into_response(),
+    };
+
+    match list_secret_keys(&state.db_conn, &function).await {
+        Ok(secrets) => {
+            let secrets = secrets
+                .into_iter()
+                .map(|(key, updated_at)| {
+                    serde_json::json!({
+                        "key": key,
+                        "updated_at": updated_at.to_rfc3339(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            (StatusCode::OK, axum::Json(secrets)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Sets one or more secrets of a function.
+///
+/// Running instances are recycled so the next invocation starts a container
+/// with the new values.
+pub(crate) async fn put_secrets(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path(function_name): Path<String>,
+    axum::Json(payload): axum::Json<SetSecretsRequest>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.secrets_set")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "keys": payload.secrets.keys().collect::<Vec<_>>() }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match set_secrets(
+        &state.db_conn,
+        &state.secret_cipher,
+        &function,
+        payload.secrets,
+    )
+    .await
+    {
+        Ok(keys) => {
+            recycle(&state, &function, user_uuid).await;
+            (
+                StatusCode::OK,
+                axum::Json(serde_json::json!({ "set": keys })),
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Removes a secret from a function and recycles its running instances.
+pub(crate) async fn delete_secret(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path((function_name, key)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.secret_unset")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "key": key }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match unset_secret(&state.db_conn, &function, &key).await {
+        Ok(()) => {
+            recycle(&state, &function, user_uuid).await;
+            (
+                StatusCode::OK,
+                axum::Json(serde_json::json!({ "unset": key })),
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Lists the API keys of a function; secrets are never returned.
+pub(crate) async fn list_api_keys(
+    State(state): State<AppState>,
+    Authorized {
+        namespace: user_uuid,
+        ..
+    }: Authorized<ReadFunctions>,
+    Path(function_name): Path<String>,
+) -> impl IntoResponse {
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return e.into_response(),
+    };
+
+    match list_keys(&state.db_conn, &function).await {
+        Ok(keys) => (StatusCode::OK, axum::Json(keys)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Creates an API key for invoking a function.
+///
+/// Responds with the key's secret, which can't be retrieved again.
+pub(crate) async fn create_api_key(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path(function_name): Path<String>,
+    axum::Json(payload): axum::Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.api_key_create")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "name": payload.name }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match create_key(&state.db_conn, &function, &payload.name).await {
+        Ok(key) => (StatusCode::CREATED, axum::Json(key)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Revokes an API key of a function.
+pub(crate) async fn revoke_api_key(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path((function_name, key_uuid)): Path<(String, Uuid)>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.api_key_revoke")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "key": key_uuid }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match revoke_key(&state.db_conn, &function, key_uuid).await {
+        Ok(key) => (StatusCode::OK, axum::Json(key)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Updates the environment variables of a function without rebuilding it.
+///
+/// Running instances are recycled so the next invocation starts a container
+/// with the new environment.
+pub(crate) async fn patch_env(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<DeployFunctions>,
+    Path(function_name): Path<String>,
+    axum::Json(payload): axum::Json<UpdateEnvRequest>,
+) -> impl IntoResponse {
+    let user_uuid = access.namespace;
+    let event = access
+        .audit_event("function.env_update")
+        .target(function_name.clone())
+        .detail(serde_json::json!({ "keys": payload.env.keys().collect::<Vec<_>>() }));
+    let function = match check_function_status(&state.db_conn, &function_name, user_uuid).await {
+        Ok(function) => function,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+
+    let response = match update_env(&state.db_conn, function.clone(), payload.env).await {
+        Ok(env) => {
+            recycle(&state, &function, user_uuid).await;
+            (
+                StatusCode::OK,
+                axum::Json(serde_json::json!({ "env": env })),
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Drains the running instances of every version of a function in the background.
+async fn recycle(state: &AppState, function: &db_entities::function::Model, user_uuid: Uuid) {
+    let drain_timeout = Duration::from_secs(state.config.function_config.drain_timeout);
+    if let Err(e) = recycle_instances(
+        &state.db_conn,
+        state.cache_conn.clone(),
+        function,
+        user_uuid,
+        drain_timeout,
+    )
+    .await
+    {
+        error!("Error recycling instances of {}: {}", function.name, e);
+    }
+}
+
+fn alias_json(alias: &db_entities::function_alias::Model) -> serde_json::Value {
+    serde_json::json!({
+        "name": alias.name,
+        "version": alias.version,
+        "canary_version": alias.canary_version,
+        "canary_weight": alias.canary_weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle_manager::error::ServelessCoreError;
+
+    fn outcome(result: ServelessCoreResult<String>) -> Outcome {
+        Outcome::from_status(deploy_response("hello", Uuid::new_v4(), result).status())
+    }
+
+    #[test]
+    fn test_deploys_over_quota_are_audited_as_denied() {
+        let over_quota = ServelessCoreError::Forbidden("quota of 10 functions".to_string());
+        assert_eq!(outcome(Err(over_quota)), Outcome::Denied);
+    }
+
+    #[test]
+    fn test_failed_deploys_are_audited_as_failures() {
+        let invalid = ServelessCoreError::BadFunction("missing entrypoint".to_string());
+        let busy = ServelessCoreError::Conflict("already being deployed".to_string());
+        let broken = ServelessCoreError::SystemError("docker unreachable".to_string());
+        assert_eq!(outcome(Err(invalid)), Outcome::Failure);
+        assert_eq!(outcome(Err(busy)), Outcome::Failure);
+        assert_eq!(outcome(Err(broken)), Outcome::Failure);
+        assert_eq!(outcome(Ok("Deployed".to_string())), Outcome::Success);
+    }
 }
@@ -0,0 +1,183 @@
+use axum::extract::{Json, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::jwt::{AdminAccess, ReadAccess, Scoped};
+use crate::api_controller::middlewares::rbac::{Authorized, ManageMembers, ReadFunctions};
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::organizations::{self, Role};
+
+/// Request body for creating an organization.
+#[derive(Debug, Deserialize)]
+pub struct CreateOrganizationRequest {
+    name: String,
+}
+
+/// Request body for inviting someone to the active organization.
+#[derive(Debug, Deserialize)]
+pub struct InviteRequest {
+    email: String,
+    role: String,
+}
+
+/// Request body for accepting an invitation.
+#[derive(Debug, Deserialize)]
+pub struct AcceptInvitationRequest {
+    token: String,
+}
+
+/// Request body for changing a member's role.
+#[derive(Debug, Deserialize)]
+pub struct SetRoleRequest {
+    role: String,
+}
+
+/// Lists the organizations the authenticated user belongs to.
+pub(crate) async fn list_organizations(
+    State(state): State<AppState>,
+    Scoped { user_uuid, .. }: Scoped<ReadAccess>,
+) -> impl IntoResponse {
+    match organizations::list_organizations(&state.db_conn, user_uuid).await {
+        Ok(organizations) => (StatusCode::OK, Json(organizations)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Creates an organization owned by the authenticated user.
+pub(crate) async fn create_organization(
+    State(state): State<AppState>,
+    audit: Audit,
+    Scoped { user_uuid, .. }: Scoped<AdminAccess>,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> impl IntoResponse {
+    let mut event = AuditEvent::new("org.create").target(payload.name.clone());
+    let response =
+        match organizations::create_organization(&state.db_conn, user_uuid, &payload.name).await {
+            Ok(organization) => {
+                event = event.by(user_uuid, organization.uuid);
+                (StatusCode::CREATED, Json(organization)).into_response()
+            }
+            Err(e) => {
+                event = event.by(user_uuid, user_uuid);
+                e.into_response()
+            }
+        };
+    audit.record(event, response).await
+}
+
+/// Lists the members of the active organization.
+pub(crate) async fn list_members(
+    State(state): State<AppState>,
+    access: Authorized<ReadFunctions>,
+) -> impl IntoResponse {
+    match organizations::list_members(&state.db_conn, &access.membership).await {
+        Ok(members) => (StatusCode::OK, Json(members)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Invites someone to the active organization.
+///
+/// Responds with the invitation token, which the invitee accepts while logged in
+/// with the invited email.
+pub(crate) async fn invite_member(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<ManageMembers>,
+    Json(payload): Json<InviteRequest>,
+) -> impl IntoResponse {
+    let event = access
+        .audit_event("org.invite")
+        .target(payload.email.clone())
+        .detail(serde_json::json!({ "role": payload.role }));
+    let role = match payload.role.parse::<Role>() {
+        Ok(role) => role,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+    let response = match organizations::invite_member(
+        &state.db_conn,
+        &access.membership,
+        &payload.email,
+        role,
+    )
+    .await
+    {
+        Ok(invitation) => (StatusCode::CREATED, Json(invitation)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Accepts an invitation on behalf of the authenticated user.
+pub(crate) async fn accept_invitation(
+    State(state): State<AppState>,
+    audit: Audit,
+    Scoped { user_uuid, .. }: Scoped<AdminAccess>,
+    Json(payload): Json<AcceptInvitationRequest>,
+) -> impl IntoResponse {
+    let mut event = AuditEvent::new("org.accept_invitation").by(user_uuid, user_uuid);
+    let response =
+        match organizations::accept_invitation(&state.db_conn, user_uuid, &payload.token).await {
+            Ok(organization) => {
+                event = event.by(user_uuid, organization.uuid);
+                (StatusCode::OK, Json(organization)).into_response()
+            }
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Changes the role of a member of the active organization.
+pub(crate) async fn set_member_role(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<ManageMembers>,
+    Path(member_uuid): Path<Uuid>,
+    Json(payload): Json<SetRoleRequest>,
+) -> impl IntoResponse {
+    let event = access
+        .audit_event("org.member_role")
+        .target(member_uuid.to_string())
+        .detail(serde_json::json!({ "role": payload.role }));
+    let role = match payload.role.parse::<Role>() {
+        Ok(role) => role,
+        Err(e) => return audit.record(event, e.into_response()).await,
+    };
+    let response =
+        match organizations::change_role(&state.db_conn, &access.membership, member_uuid, role)
+            .await
+        {
+            Ok(member) => (StatusCode::OK, Json(member)).into_response(),
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Removes a member from the active organization.
+///
+/// Any member can remove themselves; removing others requires managing members,
+/// which is checked once the target is known.
+pub(crate) async fn remove_member(
+    State(state): State<AppState>,
+    audit: Audit,
+    access: Authorized<ReadFunctions>,
+    Path(member_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = access
+        .audit_event("org.member_remove")
+        .target(member_uuid.to_string());
+    let response =
+        match organizations::remove_member(&state.db_conn, &access.membership, member_uuid).await {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(serde_json::json!({ "removed": member_uuid })),
+            )
+                .into_response(),
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
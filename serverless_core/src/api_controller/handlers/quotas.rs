@@ -0,0 +1,92 @@
+use axum::extract::{Json, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::admin::PlatformAdmin;
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::rbac::{Authorized, ReadFunctions};
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::quotas::{
+    self, effective_quota, namespace_quota, reset_quota, set_quota, Quota, QuotaOverrides,
+};
+
+/// Builds the default namespace quota from the server configuration.
+pub(crate) fn default_quota(state: &AppState) -> Quota {
+    let function_config = &state.config.function_config;
+    Quota {
+        max_functions: function_config.quota_max_functions,
+        max_instances: function_config.quota_max_instances,
+        max_memory_mb: function_config.quota_max_memory_mb,
+        builds_per_hour: function_config.quota_builds_per_hour,
+        invocations_per_day: function_config.quota_invocations_per_day,
+    }
+}
+
+/// Shows what the caller's namespace uses against its quota.
+///
+/// Each item has the current usage and its limit, `null` when unlimited.
+pub(crate) async fn get_usage(
+    State(state): State<AppState>,
+    Authorized { namespace, .. }: Authorized<ReadFunctions>,
+) -> impl IntoResponse {
+    let quota = match effective_quota(&state.db_conn, &default_quota(&state), namespace).await {
+        Ok(quota) => quota,
+        Err(e) => return e.into_response(),
+    };
+    let mut cache_conn = state.cache_conn.clone();
+    match quotas::usage(&state.db_conn, &mut cache_conn, &quota, namespace).await {
+        Ok(usage) => (StatusCode::OK, Json(usage)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Shows the quota overrides of a namespace and the limits enforced on it.
+pub(crate) async fn get_namespace_quota(
+    State(state): State<AppState>,
+    _admin: PlatformAdmin,
+    Path(namespace): Path<Uuid>,
+) -> impl IntoResponse {
+    match namespace_quota(&state.db_conn, &default_quota(&state), namespace).await {
+        Ok(quota) => (StatusCode::OK, Json(quota)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Sets the quota overrides of a namespace. Limits left out of the body fall
+/// back to the server defaults; 0 lifts a limit.
+pub(crate) async fn put_namespace_quota(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(namespace): Path<Uuid>,
+    Json(overrides): Json<QuotaOverrides>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.quota_set")
+        .target(namespace.to_string())
+        .detail(serde_json::to_value(&overrides).unwrap_or_default());
+    let response =
+        match set_quota(&state.db_conn, &default_quota(&state), namespace, overrides).await {
+            Ok(quota) => (StatusCode::OK, Json(quota)).into_response(),
+            Err(e) => e.into_response(),
+        };
+    audit.record(event, response).await
+}
+
+/// Removes the quota overrides of a namespace, so the server defaults apply again.
+pub(crate) async fn delete_namespace_quota(
+    State(state): State<AppState>,
+    audit: Audit,
+    admin: PlatformAdmin,
+    Path(namespace): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = admin
+        .audit_event("admin.quota_reset")
+        .target(namespace.to_string());
+    let response = match reset_quota(&state.db_conn, &default_quota(&state), namespace).await {
+        Ok(quota) => (StatusCode::OK, Json(quota)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
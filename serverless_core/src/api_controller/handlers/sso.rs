@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::api_controller::handlers::auth::session_response;
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::sso;
+
+/// Request starting a single sign-on from the CLI
+#[derive(Debug, Deserialize)]
+pub struct SsoStartRequest {
+    /// Loopback URL the CLI listens on for the login code
+    redirect_uri: String,
+    /// S256 PKCE challenge of the CLI's verifier
+    code_challenge: String,
+    /// Value echoed back to `redirect_uri`
+    state: String,
+}
+
+/// Identity provider redirect back to the platform
+#[derive(Debug, Deserialize)]
+pub struct SsoCallbackQuery {
+    state: String,
+    code: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Request exchanging a one-time login code for a session
+#[derive(Debug, Deserialize)]
+pub struct SsoTokenRequest {
+    code: String,
+    code_verifier: String,
+}
+
+/// Response to SSO requests when no identity provider is configured
+fn sso_disabled() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": "Single sign-on is not configured" })),
+    )
+        .into_response()
+}
+
+/// Starts a sign-in and returns the identity provider URL to open in a browser
+pub async fn sso_start(
+    State(state): State<AppState>,
+    Json(payload): Json<SsoStartRequest>,
+) -> impl IntoResponse {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return sso_disabled();
+    };
+    let mut cache_conn = state.cache_conn.clone();
+    match sso::start_sign_in(
+        &mut cache_conn,
+        oidc,
+        &payload.redirect_uri,
+        &payload.state,
+        &payload.code_challenge,
+    )
+    .await
+    {
+        Ok(authorization_url) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "authorization_url": authorization_url })),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Finishes a sign-in and sends the browser back to the CLI with a login code
+pub async fn sso_callback(
+    State(state): State<AppState>,
+    Query(query): Query<SsoCallbackQuery>,
+) -> impl IntoResponse {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return sso_disabled();
+    };
+    let provider_error = query
+        .error_description
+        .or(query.error)
+        .unwrap_or_else(|| "no authorization code".to_string());
+    let outcome = query.code.as_deref().ok_or(provider_error.as_str());
+
+    let mut cache_conn = state.cache_conn.clone();
+    match sso::finish_sign_in(&mut cache_conn, &state.db_conn, oidc, &query.state, outcome).await {
+        Ok(redirect) => (StatusCode::FOUND, [(header::LOCATION, redirect)]).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Exchanges a one-time login code for an access and refresh token
+pub async fn sso_token(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(payload): Json<SsoTokenRequest>,
+) -> impl IntoResponse {
+    if state.oidc.is_none() {
+        return sso_disabled();
+    }
+    let mut event = AuditEvent::new("auth.sso_login");
+    let mut cache_conn = state.cache_conn.clone();
+    let response = match sso::redeem_login_code(
+        &mut cache_conn,
+        &state.db_conn,
+        &payload.code,
+        &payload.code_verifier,
+    )
+    .await
+    {
+        Ok(session) => {
+            let user = &session.user;
+            event = event.by(user.uuid, user.uuid).target(user.email.clone());
+            session_response(&state, session, StatusCode::OK)
+        }
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
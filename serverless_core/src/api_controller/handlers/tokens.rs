@@ -0,0 +1,79 @@
+use axum::extract::{Json, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::jwt::{AdminAccess, Scoped};
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::tokens;
+
+/// Request body for creating a personal access token.
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+    /// Days until the token expires, from 1 to 365. Defaults to 90.
+    expires_in_days: Option<u32>,
+}
+
+/// Lists the authenticated user's personal access tokens.
+pub(crate) async fn list_tokens(
+    State(state): State<AppState>,
+    Scoped { user_uuid, .. }: Scoped<AdminAccess>,
+) -> impl IntoResponse {
+    match tokens::list_tokens(&state.db_conn, user_uuid).await {
+        Ok(tokens) => (StatusCode::OK, Json(tokens)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Creates a personal access token for the authenticated user.
+///
+/// Responds with the token's secret, which can't be retrieved again.
+pub(crate) async fn create_token(
+    State(state): State<AppState>,
+    audit: Audit,
+    Scoped {
+        user_uuid, scopes, ..
+    }: Scoped<AdminAccess>,
+    Json(payload): Json<CreateTokenRequest>,
+) -> impl IntoResponse {
+    let event = AuditEvent::new("auth.token_create")
+        .by(user_uuid, user_uuid)
+        .target(payload.name.clone())
+        .detail(serde_json::json!({ "scopes": payload.scopes }));
+    let response = match tokens::create_token(
+        &state.db_conn,
+        user_uuid,
+        &scopes,
+        &payload.name,
+        &payload.scopes,
+        payload.expires_in_days,
+    )
+    .await
+    {
+        Ok(token) => (StatusCode::CREATED, Json(token)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
+
+/// Revokes one of the authenticated user's personal access tokens.
+pub(crate) async fn revoke_token(
+    State(state): State<AppState>,
+    audit: Audit,
+    Scoped { user_uuid, .. }: Scoped<AdminAccess>,
+    Path(token_uuid): Path<Uuid>,
+) -> impl IntoResponse {
+    let event = AuditEvent::new("auth.token_revoke")
+        .by(user_uuid, user_uuid)
+        .target(token_uuid.to_string());
+    let response = match tokens::revoke_token(&state.db_conn, user_uuid, token_uuid).await {
+        Ok(token) => (StatusCode::OK, Json(token)).into_response(),
+        Err(e) => e.into_response(),
+    };
+    audit.record(event, response).await
+}
@@ -1 +1,6 @@
+pub(crate) mod admin;
+pub(crate) mod audit;
+pub(crate) mod client_ip;
 pub(crate) mod jwt;
+pub(crate) mod rbac;
+pub(crate) mod request_id;
@@ -0,0 +1,81 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+};
+use db_entities::auth::Model as AuthUser;
+use tracing::{error, warn};
+
+use crate::api_controller::middlewares::jwt::{authenticate, require_scope, AuthError};
+use crate::api_controller::AppState;
+use crate::db::auth::AuthDBRepo;
+use crate::lifecycle_manager::admin::PlatformRole;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::login_guard::AUDIT_TARGET;
+use crate::lifecycle_manager::tokens::Scope;
+
+/// Extractor for a platform operator: a user with the `admin` platform role,
+/// whose credentials carry the `admin` scope.
+///
+/// Unlike `AuthenticatedUser` and `Authorized`, which act on the caller's own
+/// organizations, it grants access to every user and function.
+///
+/// # Fields
+/// - `user`: The operator.
+#[derive(Debug, Clone)]
+pub struct PlatformAdmin {
+    pub user: AuthUser,
+}
+
+impl PlatformAdmin {
+    /// An audit event of the operator acting on the platform, outside any
+    /// organization.
+    pub fn audit_event(&self, action: &'static str) -> AuditEvent {
+        AuditEvent::new(action).actor(self.user.uuid)
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for PlatformAdmin
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = AppState::from_ref(state);
+        let authenticated = authenticate(&app_state, &parts.headers).await?;
+        require_scope(&authenticated.1, Scope::Admin)?;
+
+        let user = match AuthDBRepo::find_by_uuid(&app_state.db_conn, authenticated.0).await {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                return Err(AuthError(
+                    StatusCode::UNAUTHORIZED,
+                    "User no longer exists".to_string(),
+                ))
+            }
+            Err(e) => {
+                error!("Error loading user: {}", e);
+                return Err(AuthError(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                ));
+            }
+        };
+
+        if PlatformRole::of(&user) != PlatformRole::Admin || user.suspended_at.is_some() {
+            warn!(
+                target: AUDIT_TARGET,
+                "admin.denied user={} path={}",
+                user.uuid,
+                parts.uri.path()
+            );
+            return Err(AuthError(
+                StatusCode::FORBIDDEN,
+                "Only platform admins can do this".to_string(),
+            ));
+        }
+        Ok(PlatformAdmin { user })
+    }
+}
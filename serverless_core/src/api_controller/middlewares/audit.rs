@@ -0,0 +1,58 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+    response::Response,
+};
+use sea_orm::DatabaseConnection;
+use std::convert::Infallible;
+
+use crate::api_controller::middlewares::client_ip::ClientIp;
+use crate::api_controller::middlewares::request_id::REQUEST_ID_HEADER;
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::audit::{self, AuditEvent, Outcome, RequestOrigin};
+
+/// Extractor recording the management actions of a request in the audit log,
+/// along with the client's address and the request's ID.
+#[derive(Clone)]
+pub struct Audit {
+    conn: DatabaseConnection,
+    origin: RequestOrigin,
+}
+
+impl Audit {
+    /// Records `event` with the outcome its response reports, and passes the
+    /// response on.
+    pub async fn record(&self, event: AuditEvent, response: Response) -> Response {
+        self.record_outcome(event, Outcome::from_status(response.status()))
+            .await;
+        response
+    }
+
+    /// Records `event` with an outcome known without a response of its own,
+    /// such as one function of a project deploy.
+    pub async fn record_outcome(&self, event: AuditEvent, outcome: Outcome) {
+        audit::record(&self.conn, &self.origin, event, outcome).await;
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Audit
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ClientIp(ip) = ClientIp::from_request_parts(parts, state).await?;
+        let request_id = parts
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Audit {
+            conn: AppState::from_ref(state).db_conn,
+            origin: RequestOrigin { ip, request_id },
+        })
+    }
+}
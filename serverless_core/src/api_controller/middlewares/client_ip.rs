@@ -0,0 +1,87 @@
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+};
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use crate::api_controller::AppState;
+
+/// Extractor for the address of the client making the request.
+///
+/// Clients can put anything in `X-Forwarded-For`, but each trusted proxy
+/// appends the address it received the request from. Behind `hops` trusted
+/// proxies the client is therefore the entry `hops` places from the right, and
+/// every entry to its left is ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+/// The address the outermost of `hops` trusted proxies received the request
+/// from, if the `X-Forwarded-For` headers have that many entries.
+fn forwarded_for(headers: &HeaderMap, hops: usize) -> Option<IpAddr> {
+    let hops = hops.checked_sub(1)?;
+    let entries = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .map(|value| value.to_str().ok())
+        .collect::<Option<Vec<_>>>()?;
+    entries
+        .iter()
+        .flat_map(|value| value.split(','))
+        .rev()
+        .nth(hops)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = AppState::from_ref(state);
+        let hops = app_state.config.auth_config.trusted_proxy_hops;
+        if let Some(ip) = forwarded_for(&parts.headers, hops) {
+            return Ok(ClientIp(ip));
+        }
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        Ok(ClientIp(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_forwarded_for_skips_client_supplied_entries() {
+        // The client sent `X-Forwarded-For: 1.1.1.1` and one proxy appended
+        // the address it saw.
+        let spoofed = headers(&["1.1.1.1, 203.0.113.7"]);
+        assert_eq!(forwarded_for(&spoofed, 1), "203.0.113.7".parse().ok());
+        assert_eq!(forwarded_for(&spoofed, 0), None);
+
+        let two_proxies = headers(&["1.1.1.1", "203.0.113.7, 10.0.0.2"]);
+        assert_eq!(forwarded_for(&two_proxies, 2), "203.0.113.7".parse().ok());
+        assert_eq!(forwarded_for(&two_proxies, 4), None);
+        assert_eq!(forwarded_for(&headers(&["not-an-ip"]), 1), None);
+    }
+}
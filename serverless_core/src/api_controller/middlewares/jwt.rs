@@ -1,21 +1,30 @@
 use axum::{
     extract::{FromRef, FromRequestParts},
-    http::{header, request::Parts, StatusCode},
+    http::{header, request::Parts, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
+use std::marker::PhantomData;
 use tracing::error;
 use uuid::Uuid;
 
 use crate::{
     api_controller::{handlers::auth::validate_token, AppState},
-    db::auth::AuthDBRepo,
+    lifecycle_manager::{
+        error::ServelessCoreError,
+        sessions,
+        tokens::{self, Scope, Scopes, TOKEN_PREFIX},
+    },
 };
 
-/// Extractor for authenticated user UUID
+/// Extractor for authenticated user UUID and the scopes the request carries.
+///
+/// The bearer token is either a JWT from logging in, which carries every scope,
+/// or a personal access token, which carries the scopes it was created with.
+/// Either is refused with `403` while its user is suspended.
 #[derive(Debug, Clone)]
-pub struct AuthenticatedUser(pub Uuid);
+pub struct AuthenticatedUser(pub Uuid, pub Scopes);
 
 /// Error response for authentication failures
 #[derive(Debug)]
@@ -28,7 +37,8 @@ impl IntoResponse for AuthError {
     }
 }
 
-/// Authentication middleware that extracts the user UUID from the JWT token
+/// Authentication middleware that extracts the user UUID from the JWT or
+/// personal access token
 #[axum::async_trait]
 impl<S> FromRequestParts<S> for AuthenticatedUser
 where
@@ -38,56 +48,146 @@ where
     type Rejection = AuthError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        // Extract the authorization header
-        let auth_header = parts
-            .headers
-            .get(header::AUTHORIZATION)
-            .and_then(|value| value.to_str().ok())
-            .ok_or_else(|| {
-                AuthError(
-                    StatusCode::UNAUTHORIZED,
-                    "Missing authorization header".to_string(),
-                )
-            })?;
-
-        // Check if the authorization header starts with "Bearer "
-        if !auth_header.starts_with("Bearer ") {
-            return Err(AuthError(
+        authenticate(&AppState::from_ref(state), &parts.headers).await
+    }
+}
+
+/// Authenticates the bearer token of a request: a JWT from logging in or a
+/// personal access token.
+pub async fn authenticate(
+    app_state: &AppState,
+    headers: &HeaderMap,
+) -> Result<AuthenticatedUser, AuthError> {
+    // Extract the authorization header
+    let auth_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            AuthError(
                 StatusCode::UNAUTHORIZED,
-                "Invalid authorization header format".to_string(),
-            ));
-        }
-        let app_state = AppState::from_ref(state);
-
-        // Extract the token
-        let token = &auth_header[7..];
-
-        // Validate the token
-        let user_uuid = validate_token(token, &app_state.config.server_config.jwt_auth_secret)
-            .map_err(|e| {
-                error!("Token validation error: {}", e);
-                AuthError(
-                    StatusCode::UNAUTHORIZED,
-                    "Invalid or expired token".to_string(),
-                )
-            })?;
-
-        // Get the app state
-
-        // Verify the user exists in the database
-        match AuthDBRepo::find_by_uuid(&app_state.db_conn, user_uuid).await {
-            Ok(Some(_)) => Ok(AuthenticatedUser(user_uuid)),
+                "Missing authorization header".to_string(),
+            )
+        })?;
+
+    // Check if the authorization header starts with "Bearer "
+    if !auth_header.starts_with("Bearer ") {
+        return Err(AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid authorization header format".to_string(),
+        ));
+    }
+    // Extract the token
+    let token = &auth_header[7..];
+
+    // Personal access tokens are looked up by their hash
+    if token.starts_with(TOKEN_PREFIX) {
+        return match tokens::authenticate(&app_state.db_conn, token).await {
+            Ok(Some((user_uuid, scopes))) => Ok(AuthenticatedUser(user_uuid, scopes)),
             Ok(None) => Err(AuthError(
                 StatusCode::UNAUTHORIZED,
-                "User not found".to_string(),
+                "Invalid, expired or revoked token".to_string(),
             )),
+            Err(ServelessCoreError::Forbidden(message)) => {
+                Err(AuthError(StatusCode::FORBIDDEN, message))
+            }
             Err(e) => {
-                error!("Error finding user by UUID: {}", e);
+                error!("Error authenticating access token: {}", e);
                 Err(AuthError(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Internal server error".to_string(),
                 ))
             }
+        };
+    }
+
+    // Validate the token
+    let (user_uuid, session_uuid) = validate_token(token, &app_state.jwt_keys).map_err(|e| {
+        error!("Token validation error: {}", e);
+        AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired token".to_string(),
+        )
+    })?;
+
+    // Verify the session hasn't been logged out or revoked
+    match sessions::is_active(&app_state.db_conn, session_uuid, user_uuid).await {
+        Ok(true) => Ok(AuthenticatedUser(user_uuid, Scopes::all())),
+        Ok(false) => Err(AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Session has been revoked".to_string(),
+        )),
+        Err(ServelessCoreError::Forbidden(message)) => {
+            Err(AuthError(StatusCode::FORBIDDEN, message))
+        }
+        Err(e) => {
+            error!("Error checking session: {}", e);
+            Err(AuthError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ))
         }
     }
 }
+
+/// A scope a `Scoped` extractor requires.
+pub trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+/// Requires the `read` scope.
+pub struct ReadAccess;
+/// Requires the `admin` scope.
+pub struct AdminAccess;
+
+impl RequiredScope for ReadAccess {
+    const SCOPE: Scope = Scope::Read;
+}
+impl RequiredScope for AdminAccess {
+    const SCOPE: Scope = Scope::Admin;
+}
+
+/// Checks that a request's scopes grant `scope`.
+pub fn require_scope(scopes: &Scopes, scope: Scope) -> Result<(), AuthError> {
+    if scopes.allows(scope) {
+        Ok(())
+    } else {
+        Err(AuthError(
+            StatusCode::FORBIDDEN,
+            format!("Token lacks the '{scope}' scope"),
+        ))
+    }
+}
+
+/// Extractor for an authenticated user whose credentials grant the scope `S`,
+/// for routes that act on the user rather than on an organization.
+///
+/// # Fields
+/// - `user_uuid`: The authenticated user.
+/// - `scopes`: The scopes the request carries.
+#[derive(Debug, Clone)]
+pub struct Scoped<S> {
+    pub user_uuid: Uuid,
+    pub scopes: Scopes,
+    scope: PhantomData<S>,
+}
+
+#[axum::async_trait]
+impl<S, R> FromRequestParts<S> for Scoped<R>
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+    R: RequiredScope,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser(user_uuid, scopes) =
+            AuthenticatedUser::from_request_parts(parts, state).await?;
+        require_scope(&scopes, R::SCOPE)?;
+        Ok(Scoped {
+            user_uuid,
+            scopes,
+            scope: PhantomData,
+        })
+    }
+}
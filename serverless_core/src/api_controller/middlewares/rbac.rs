@@ -0,0 +1,146 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+use crate::api_controller::middlewares::audit::Audit;
+use crate::api_controller::middlewares::jwt::{require_scope, AuthError, AuthenticatedUser};
+use crate::api_controller::AppState;
+use crate::lifecycle_manager::audit::AuditEvent;
+use crate::lifecycle_manager::organizations::{resolve_membership, Membership, Permission};
+
+/// Header selecting the organization a request acts on, by name or UUID.
+/// Requests without it act on the caller's personal organization.
+pub const ORGANIZATION_HEADER: &str = "x-invok-org";
+
+/// Audit action of an authenticated caller refused a permission.
+const DENIED_ACTION: &str = "authorization.denied";
+
+/// A permission an `Authorized` extractor requires.
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+/// Requires permission to list and inspect functions.
+pub struct ReadFunctions;
+/// Requires permission to deploy and configure functions.
+pub struct DeployFunctions;
+/// Requires permission to delete functions.
+pub struct DeleteFunctions;
+/// Requires permission to manage the organization's members.
+pub struct ManageMembers;
+/// Requires permission to read the organization's audit log.
+pub struct ViewAudit;
+
+impl RequiredPermission for ReadFunctions {
+    const PERMISSION: Permission = Permission::ReadFunctions;
+}
+impl RequiredPermission for DeployFunctions {
+    const PERMISSION: Permission = Permission::DeployFunctions;
+}
+impl RequiredPermission for DeleteFunctions {
+    const PERMISSION: Permission = Permission::DeleteFunctions;
+}
+impl RequiredPermission for ManageMembers {
+    const PERMISSION: Permission = Permission::ManageMembers;
+}
+impl RequiredPermission for ViewAudit {
+    const PERMISSION: Permission = Permission::ViewAudit;
+}
+
+/// Extractor for an authenticated member of the active organization whose role
+/// grants the permission `P`, and whose credentials carry the scope it needs.
+///
+/// # Fields
+/// - `namespace`: The organization's UUID, which namespaces its functions.
+/// - `membership`: The caller, the organization and the caller's role in it.
+#[derive(Debug, Clone)]
+pub struct Authorized<P> {
+    pub namespace: Uuid,
+    pub membership: Membership,
+    permission: PhantomData<P>,
+}
+
+impl<P> Authorized<P> {
+    /// An audit event of the caller acting on the active organization.
+    pub fn audit_event(&self, action: &'static str) -> AuditEvent {
+        AuditEvent::new(action).by(self.membership.user.uuid, self.namespace)
+    }
+}
+
+#[axum::async_trait]
+impl<S, P> FromRequestParts<S> for Authorized<P>
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+    P: RequiredPermission,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser(user_uuid, scopes) =
+            AuthenticatedUser::from_request_parts(parts, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+        if let Err(e) = require_scope(&scopes, P::PERMISSION.scope()) {
+            let event = AuditEvent::new(DENIED_ACTION).actor(user_uuid);
+            return Err(record_denial::<S, P>(parts, state, event, e).await);
+        }
+        let app_state = AppState::from_ref(state);
+
+        let reference = parts
+            .headers
+            .get(ORGANIZATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+
+        let membership = resolve_membership(&app_state.db_conn, user_uuid, reference)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if !membership.role.allows(P::PERMISSION) {
+            let error = AuthError(
+                axum::http::StatusCode::FORBIDDEN,
+                format!(
+                    "Role '{}' in organization '{}' cannot {}",
+                    membership.role,
+                    membership.organization.name,
+                    P::PERMISSION
+                ),
+            );
+            let event = AuditEvent::new(DENIED_ACTION).by(user_uuid, membership.organization.uuid);
+            return Err(record_denial::<S, P>(parts, state, event, error).await);
+        }
+
+        Ok(Authorized {
+            namespace: membership.organization.uuid,
+            membership,
+            permission: PhantomData,
+        })
+    }
+}
+
+/// Audits a caller refused the permission `P`, targeting the requested path,
+/// and answers with the refusal.
+async fn record_denial<S, P>(
+    parts: &mut Parts,
+    state: &S,
+    event: AuditEvent,
+    error: AuthError,
+) -> Response
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+    P: RequiredPermission,
+{
+    let event = event
+        .target(parts.uri.path())
+        .detail(json!({ "permission": P::PERMISSION.to_string() }));
+    let Ok(audit) = Audit::from_request_parts(parts, state).await;
+    audit.record(event, error.into_response()).await
+}
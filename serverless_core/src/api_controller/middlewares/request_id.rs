@@ -0,0 +1,61 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+/// Header identifying a request across the platform's logs, its audit log and
+/// the function it invokes.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request ID accepted from a client.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Whether a client-supplied request ID is safe to log and store.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Middleware giving every request an `X-Request-Id`.
+///
+/// A valid ID sent by the client is kept, so requests can be traced through a
+/// proxy in front of the platform; otherwise a new one is generated. The ID is
+/// set on the request, for handlers and invoked functions, and echoed on the
+/// response.
+pub async fn assign_request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request IDs are visible ASCII");
+
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, value.clone());
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_only_short_plain_request_ids() {
+        assert!(is_valid_request_id("3f2c9a1e-req"));
+        assert!(is_valid_request_id("trace:01HZX.42"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id("id\nforged-log-line"));
+        assert!(!is_valid_request_id(&"a".repeat(129)));
+    }
+}
@@ -2,22 +2,62 @@ mod config;
 mod handlers;
 mod middlewares;
 
+use crate::artifacts::local::LocalArtifactStore;
+use crate::artifacts::s3::S3ArtifactStore;
+use crate::artifacts::{ArtifactError, ArtifactStore};
+use crate::lifecycle_manager::admin::{self, PlatformRole};
+use crate::lifecycle_manager::audit;
+use crate::lifecycle_manager::concurrency::ConcurrencyManager;
+use crate::lifecycle_manager::login_guard;
+use crate::mailer::{file::FileMailer, log::LogMailer, smtp::SmtpMailer, Mailer};
+use crate::utils::jwks::{JwksCache, JwksError};
+use crate::utils::jwt_keys::{JwtKeyError, JwtKeys};
+use crate::utils::oidc::{OidcClient, OidcError};
+use crate::utils::passwords::{PasswordPolicy, PasswordPolicyError};
+use crate::utils::secrets::SecretCipher;
 use axum::{
     extract::FromRef,
-    routing::{any, get, post},
+    middleware,
+    routing::{any, delete, get, patch, post, put},
     Router,
 };
+use config::artifact::ArtifactStoreConfig;
+use config::mail::MailBackendConfig;
 use config::{InvokConfig, InvokConfigError};
 use db_migrations::{Migrator, MigratorTrait};
 use handlers::{
-    auth::{login, register},
-    functions::{call_function, list_functions, upload_function},
+    admin::{
+        get_user, list_all_functions, list_users, node_capacity, set_user_role,
+        stop_function_instances, suspend_user, unlock_lockout, unsuspend_user,
+    },
+    audit::{
+        export_all_audit_events, export_audit_events, list_all_audit_events, list_audit_events,
+    },
+    auth::{
+        forgot_password, jwks, login, logout, refresh, register, reset_password, verify_email,
+        verify_email_link,
+    },
+    functions::{
+        call_function, create_api_key, delete_secret, download_artifact, get_function,
+        list_api_keys, list_functions, list_secrets, list_versions, patch_env, put_alias,
+        put_secrets, remove_function, revoke_api_key, rollback_function, upload_function,
+        upload_project,
+    },
+    organizations::{
+        accept_invitation, create_organization, invite_member, list_members, list_organizations,
+        remove_member, set_member_role,
+    },
+    quotas::{delete_namespace_quota, get_namespace_quota, get_usage, put_namespace_quota},
+    sso::{sso_callback, sso_start, sso_token},
+    tokens::{create_token, list_tokens, revoke_token},
 };
+use middlewares::request_id::assign_request_id;
 use redis::aio::MultiplexedConnection;
 use sea_orm::{Database, DatabaseConnection};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::info;
 
 /// Application state shared across handlers.
 #[derive(Clone, FromRef)]
@@ -28,10 +68,27 @@ pub struct AppState {
     pub cache_conn: MultiplexedConnection,
     /// Application configuration
     pub config: InvokConfig,
+    /// Node-local tracker of in-flight and queued invocations
+    pub concurrency: ConcurrencyManager,
+    /// Cipher for function secrets stored in the database
+    pub secret_cipher: SecretCipher,
+    /// Content-addressed store of uploaded function archives
+    pub artifacts: Arc<dyn ArtifactStore>,
+    /// Keys platform JWTs are signed and verified with
+    pub jwt_keys: JwtKeys,
+    /// Password requirements and hashing parameters
+    pub passwords: PasswordPolicy,
+    /// Delivers verification and password reset emails
+    pub mailer: Arc<dyn Mailer>,
+    /// Identity provider for single sign-on, when configured
+    pub oidc: Option<OidcClient>,
+    /// Keys of the external issuers functions accept JWTs from
+    pub jwks: JwksCache,
 }
 
 /// Custom error type for server initialization.
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum InvokAppError {
     #[error("Configuration error: {0}")]
     ConfigError(#[from] InvokConfigError),
@@ -42,6 +99,30 @@ pub enum InvokAppError {
     #[error("Database connection error: {0}")]
     DatabaseError(#[from] sea_orm::DbErr),
 
+    #[error("Secrets error: {0}")]
+    SecretError(#[from] crate::utils::secrets::SecretError),
+
+    #[error("Artifact store error: {0}")]
+    ArtifactError(#[from] ArtifactError),
+
+    #[error("JWT key error: {0}")]
+    JwtKeyError(#[from] JwtKeyError),
+
+    #[error("Password policy error: {0}")]
+    PasswordPolicyError(#[from] PasswordPolicyError),
+
+    #[error("JWKS client error: {0}")]
+    JwksError(#[from] JwksError),
+
+    #[error("Single sign-on error: {0}")]
+    OidcError(#[from] OidcError),
+
+    #[error("Login guard error: {0}")]
+    LoginGuardError(String),
+
+    #[error("Admin error: {0}")]
+    AdminError(String),
+
     #[error("Server error: {0}")]
     ServerError(#[from] std::io::Error),
 
@@ -74,22 +155,153 @@ pub async fn start_server() -> Result<(), InvokAppError> {
     // Run database migrations.
     Migrator::up(&db_conn, None).await?;
 
+    let secret_cipher = SecretCipher::new(&config.server_config.secrets_encryption_key)?;
+
+    let jwt_keys = JwtKeys::load(
+        std::path::Path::new(&config.server_config.jwt_keys_dir),
+        config.server_config.jwt_active_kid.as_deref(),
+        &config.mail_config.public_url,
+    )?;
+
+    let auth_config = &config.auth_config;
+    let passwords = PasswordPolicy::load(
+        auth_config.password_min_length,
+        auth_config.breached_passwords_file.as_deref(),
+        auth_config.argon2_memory_kib,
+        auth_config.argon2_iterations,
+        auth_config.argon2_parallelism,
+    )?;
+
+    let artifacts: Arc<dyn ArtifactStore> = match &config.artifact_config.store {
+        ArtifactStoreConfig::Local { path } => Arc::new(LocalArtifactStore::new(path)),
+        ArtifactStoreConfig::S3(settings) => Arc::new(S3ArtifactStore::new(settings)?),
+    };
+
+    let mail_config = &config.mail_config;
+    let mailer: Arc<dyn Mailer> = match &mail_config.backend {
+        MailBackendConfig::Log => Arc::new(LogMailer::new(&mail_config.from)),
+        MailBackendConfig::File { dir } => Arc::new(FileMailer::new(&mail_config.from, dir)),
+        MailBackendConfig::Smtp { host, port } => {
+            Arc::new(SmtpMailer::new(&mail_config.from, host, *port))
+        }
+    };
+
+    let oidc = match &config.oidc_config {
+        Some(oidc_config) => Some(OidcClient::new(
+            oidc_config.issuer.clone(),
+            oidc_config.client_id.clone(),
+            oidc_config.client_secret.clone(),
+            oidc_config.redirect_url.clone(),
+            oidc_config.scopes.clone(),
+        )?),
+        None => None,
+    };
+
     let app_state = AppState {
         db_conn,
         cache_conn,
         config: config.clone(),
+        concurrency: ConcurrencyManager::default(),
+        secret_cipher,
+        artifacts,
+        jwt_keys,
+        passwords,
+        mailer,
+        oidc,
+        jwks: JwksCache::new()?,
     };
 
+    // Delete audit events once past their retention
+    let retention_days = config.audit_config.retention_days;
+    if retention_days > 0 {
+        tokio::spawn(audit::purge_periodically(
+            app_state.db_conn.clone(),
+            retention_days,
+        ));
+    }
+
     // Create a router with all our routes
     let app = Router::new()
         // Auth routes
+        .route("/.well-known/jwks.json", get(jwks))
         .route("/auth/register", post(register))
         .route("/auth/login", post(login))
+        .route("/auth/refresh", post(refresh))
+        .route("/auth/logout", post(logout))
+        .route("/auth/verify", get(verify_email_link).post(verify_email))
+        .route("/auth/forgot", post(forgot_password))
+        .route("/auth/reset", post(reset_password))
+        .route("/auth/sso/start", post(sso_start))
+        .route("/auth/sso/callback", get(sso_callback))
+        .route("/auth/sso/token", post(sso_token))
+        .route("/auth/tokens", get(list_tokens).post(create_token))
+        .route("/auth/tokens/:token_uuid", delete(revoke_token))
+        // Organization routes; the active organization comes from `X-Invok-Org`
+        .route("/orgs", get(list_organizations).post(create_organization))
+        .route("/orgs/members", get(list_members))
+        .route(
+            "/orgs/members/:user_uuid",
+            put(set_member_role).delete(remove_member),
+        )
+        .route("/orgs/invitations", post(invite_member))
+        .route("/orgs/invitations/accept", post(accept_invitation))
+        // Audit log of the active organization
+        .route("/audit", get(list_audit_events))
+        .route("/audit/export", get(export_audit_events))
+        // Platform administration, for users with the `admin` platform role
+        .route("/admin/users", get(list_users))
+        .route("/admin/users/:user_uuid", get(get_user))
+        .route("/admin/users/:user_uuid/role", put(set_user_role))
+        .route("/admin/users/:user_uuid/suspend", post(suspend_user))
+        .route("/admin/users/:user_uuid/unsuspend", post(unsuspend_user))
+        .route("/admin/unlock", post(unlock_lockout))
+        .route("/admin/functions", get(list_all_functions))
+        .route(
+            "/admin/functions/:function_uuid/stop",
+            post(stop_function_instances),
+        )
+        .route("/admin/capacity", get(node_capacity))
+        .route(
+            "/admin/quotas/:namespace",
+            get(get_namespace_quota)
+                .put(put_namespace_quota)
+                .delete(delete_namespace_quota),
+        )
+        .route("/admin/audit", get(list_all_audit_events))
+        .route("/admin/audit/export", get(export_all_audit_events))
         // Function management routes
         .route("/invok/list", get(list_functions))
+        .route("/invok/usage", get(get_usage))
         .route("/invok/deploy", post(upload_function))
+        .route("/invok/deploy/project", post(upload_project))
+        .route(
+            "/invok/functions/:name",
+            get(get_function).delete(remove_function),
+        )
+        .route("/invok/functions/:name/versions", get(list_versions))
+        .route(
+            "/invok/functions/:name/versions/:version/artifact",
+            get(download_artifact),
+        )
+        .route("/invok/functions/:name/env", patch(patch_env))
+        .route(
+            "/invok/functions/:name/keys",
+            get(list_api_keys).post(create_api_key),
+        )
+        .route(
+            "/invok/functions/:name/keys/:key_uuid",
+            delete(revoke_api_key),
+        )
+        .route("/invok/functions/:name/aliases/:alias", put(put_alias))
+        .route(
+            "/invok/functions/:name/secrets",
+            get(list_secrets).put(put_secrets),
+        )
+        .route("/invok/functions/:name/secrets/:key", delete(delete_secret))
+        .route("/invok/functions/:name/rollback", post(rollback_function))
         // Function invocation routes
         .route("/invok/:namespace/:function_name", any(call_function))
+        .layer(middleware::from_fn(assign_request_id))
         .with_state(app_state);
 
     // Build socket address from configuration
@@ -105,8 +317,46 @@ pub async fn start_server() -> Result<(), InvokAppError> {
     info!("Server listening on {}", addr);
 
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
 
     Ok(())
 }
+
+/// Lifts the login lockout of an account or IP address.
+///
+/// Meant for operators, as `serverless-core unlock <email|ip>` run with the
+/// server's environment. Returns whether the target was locked out.
+pub async fn unlock_login(target: &str) -> Result<bool, InvokAppError> {
+    tracing_subscriber::fmt::init();
+
+    let config = InvokConfig::load()?;
+    let client = redis::Client::open(config.server_config.redis_url)?;
+    let mut cache_conn = client.get_multiplexed_async_connection().await?;
+
+    login_guard::unlock(&mut cache_conn, target)
+        .await
+        .map_err(|e| InvokAppError::LoginGuardError(e.to_string()))
+}
+
+/// Changes the platform role of a user, e.g. to make the first admin.
+///
+/// Meant for operators, as `serverless-core promote|demote <email>` run with
+/// the server's environment.
+pub async fn set_platform_role(email: &str, admin: bool) -> Result<(), InvokAppError> {
+    tracing_subscriber::fmt::init();
+
+    let config = InvokConfig::load()?;
+    let db_conn = Database::connect(config.server_config.database_url).await?;
+    Migrator::up(&db_conn, None).await?;
+
+    let role = if admin {
+        PlatformRole::Admin
+    } else {
+        PlatformRole::User
+    };
+    admin::set_role_by_email(&db_conn, email, role)
+        .await
+        .map_err(|e| InvokAppError::AdminError(e.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+pub mod local;
+pub mod s3;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Errors returned by an artifact store.
+#[derive(Debug, Error)]
+pub enum ArtifactError {
+    #[error("'{0}' is not a SHA-256 digest")]
+    InvalidDigest(String),
+    #[error("Artifact storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Artifact storage backend error: {0}")]
+    Backend(String),
+}
+
+/// Content-addressed storage for uploaded function archives.
+///
+/// Artifacts are keyed by the lowercase hex SHA-256 digest of their content, so
+/// storing the same archive twice keeps a single copy and a digest always refers
+/// to the same bytes.
+#[async_trait]
+pub trait ArtifactStore: Send + Sync {
+    /// Stores `content` under `digest`, replacing nothing if it already exists.
+    async fn put(&self, digest: &str, content: &[u8]) -> Result<(), ArtifactError>;
+
+    /// Returns the content stored under `digest`, or `None` if there is none.
+    async fn get(&self, digest: &str) -> Result<Option<Vec<u8>>, ArtifactError>;
+
+    /// Returns whether an artifact is stored under `digest`.
+    async fn exists(&self, digest: &str) -> Result<bool, ArtifactError>;
+
+    /// Removes the artifact stored under `digest`; missing artifacts are ignored.
+    async fn delete(&self, digest: &str) -> Result<(), ArtifactError>;
+}
+
+/// Computes the lowercase hex SHA-256 digest of `content`.
+pub fn sha256_digest(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/// Ensures `digest` is a lowercase hex SHA-256 digest, so it is safe to use in a
+/// file path or object key.
+pub fn validate_digest(digest: &str) -> Result<(), ArtifactError> {
+    if digest.len() == 64
+        && digest
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        Ok(())
+    } else {
+        Err(ArtifactError::InvalidDigest(digest.to_string()))
+    }
+}
+
+/// Stores an archive in `store` unless it is already there.
+///
+/// # Returns
+///
+/// The SHA-256 digest the archive is stored under.
+pub async fn store_artifact(
+    store: &dyn ArtifactStore,
+    content: &[u8],
+) -> Result<String, ArtifactError> {
+    let digest = sha256_digest(content);
+    if !store.exists(&digest).await? {
+        store.put(&digest, content).await?;
+    }
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_digest_is_valid() {
+        let digest = sha256_digest(b"hello");
+        assert_eq!(
+            digest,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!(validate_digest(&digest).is_ok());
+    }
+
+    #[test]
+    fn test_validate_digest_rejects_paths() {
+        for digest in ["", "../etc/passwd", &"A".repeat(64), &"0".repeat(63)] {
+            assert!(matches!(
+                validate_digest(digest),
+                Err(ArtifactError::InvalidDigest(_))
+            ));
+        }
+    }
+}
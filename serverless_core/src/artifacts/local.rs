@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::fs;
+
+use crate::artifacts::{validate_digest, ArtifactError, ArtifactStore};
+
+/// Artifact store keeping archives on the local filesystem.
+///
+/// Artifacts are laid out as `{root}/sha256/{first two hex digits}/{digest}` so no
+/// single directory grows too large. Writes go to a temporary file that is renamed
+/// into place, so readers never see a partially written artifact.
+#[derive(Debug, Clone)]
+pub struct LocalArtifactStore {
+    root: PathBuf,
+}
+
+impl LocalArtifactStore {
+    /// Creates a store rooted at `root`; the directory is created on first write.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, digest: &str) -> Result<PathBuf, ArtifactError> {
+        validate_digest(digest)?;
+        Ok(self.root.join("sha256").join(&digest[..2]).join(digest))
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for LocalArtifactStore {
+    async fn put(&self, digest: &str, content: &[u8]) -> Result<(), ArtifactError> {
+        let path = self.path(digest)?;
+        if fs::try_exists(&path).await? {
+            return Ok(());
+        }
+        let dir = path.parent().expect("artifact path has a parent");
+        fs::create_dir_all(dir).await?;
+
+        let temp_path = dir.join(format!(".{digest}.{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&temp_path, content).await?;
+        if let Err(e) = fs::rename(&temp_path, &path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, digest: &str) -> Result<Option<Vec<u8>>, ArtifactError> {
+        match fs::read(self.path(digest)?).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn exists(&self, digest: &str) -> Result<bool, ArtifactError> {
+        Ok(fs::try_exists(self.path(digest)?).await?)
+    }
+
+    async fn delete(&self, digest: &str) -> Result<(), ArtifactError> {
+        match fs::remove_file(self.path(digest)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::{sha256_digest, store_artifact};
+
+    #[tokio::test]
+    async fn test_local_store_roundtrip() {
+        let root = tempfile::tempdir().unwrap();
+        let store = LocalArtifactStore::new(root.path());
+
+        let digest = store_artifact(&store, b"archive").await.unwrap();
+        assert_eq!(digest, sha256_digest(b"archive"));
+        assert!(root
+            .path()
+            .join("sha256")
+            .join(&digest[..2])
+            .join(&digest)
+            .is_file());
+        assert!(store.exists(&digest).await.unwrap());
+        assert_eq!(
+            store.get(&digest).await.unwrap().as_deref(),
+            Some(&b"archive"[..])
+        );
+
+        store.delete(&digest).await.unwrap();
+        store.delete(&digest).await.unwrap();
+        assert!(!store.exists(&digest).await.unwrap());
+        assert_eq!(store.get(&digest).await.unwrap(), None);
+    }
+}
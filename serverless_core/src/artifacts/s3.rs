@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+
+use crate::artifacts::{validate_digest, ArtifactError, ArtifactStore};
+
+/// Connection settings for an S3-compatible bucket.
+///
+/// # Fields
+/// - `bucket`: Name of an existing bucket.
+/// - `endpoint`: Base URL of the service, e.g. `http://minio:9000`.
+/// - `region`: Region the bucket lives in.
+/// - `access_key`: Access key ID.
+/// - `secret_key`: Secret access key.
+#[derive(Debug, Clone)]
+pub struct S3Settings {
+    pub bucket: String,
+    pub endpoint: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// Artifact store keeping archives in an S3-compatible bucket.
+///
+/// Objects are stored under `sha256/{digest}` and addressed path-style, which
+/// works with AWS S3 as well as self-hosted services such as MinIO.
+pub struct S3ArtifactStore {
+    bucket: Box<Bucket>,
+}
+
+impl S3ArtifactStore {
+    /// Creates a store for the bucket described by `settings`.
+    pub fn new(settings: &S3Settings) -> Result<Self, ArtifactError> {
+        let region = Region::Custom {
+            region: settings.region.clone(),
+            endpoint: settings.endpoint.clone(),
+        };
+        let credentials = Credentials::new(
+            Some(&settings.access_key),
+            Some(&settings.secret_key),
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| ArtifactError::Backend(e.to_string()))?;
+        let bucket = Bucket::new(&settings.bucket, region, credentials)
+            .map_err(|e| ArtifactError::Backend(e.to_string()))?
+            .with_path_style();
+        Ok(Self { bucket })
+    }
+
+    fn key(digest: &str) -> Result<String, ArtifactError> {
+        validate_digest(digest)?;
+        Ok(format!("sha256/{digest}"))
+    }
+}
+
+/// Maps a non-success response status to an error.
+fn check_status(status: u16, action: &str, key: &str) -> Result<(), ArtifactError> {
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(ArtifactError::Backend(format!(
+            "{action} of {key} failed with status {status}"
+        )))
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for S3ArtifactStore {
+    async fn put(&self, digest: &str, content: &[u8]) -> Result<(), ArtifactError> {
+        let key = Self::key(digest)?;
+        let response = self
+            .bucket
+            .put_object(&key, content)
+            .await
+            .map_err(|e| ArtifactError::Backend(e.to_string()))?;
+        check_status(response.status_code(), "upload", &key)
+    }
+
+    async fn get(&self, digest: &str) -> Result<Option<Vec<u8>>, ArtifactError> {
+        let key = Self::key(digest)?;
+        let response = self
+            .bucket
+            .get_object(&key)
+            .await
+            .map_err(|e| ArtifactError::Backend(e.to_string()))?;
+        if response.status_code() == 404 {
+            return Ok(None);
+        }
+        check_status(response.status_code(), "download", &key)?;
+        Ok(Some(response.to_vec()))
+    }
+
+    async fn exists(&self, digest: &str) -> Result<bool, ArtifactError> {
+        let key = Self::key(digest)?;
+        let (_, status) = self
+            .bucket
+            .head_object(&key)
+            .await
+            .map_err(|e| ArtifactError::Backend(e.to_string()))?;
+        if status == 404 {
+            return Ok(false);
+        }
+        check_status(status, "lookup", &key)?;
+        Ok(true)
+    }
+
+    async fn delete(&self, digest: &str) -> Result<(), ArtifactError> {
+        let key = Self::key(digest)?;
+        let response = self
+            .bucket
+            .delete_object(&key)
+            .await
+            .map_err(|e| ArtifactError::Backend(e.to_string()))?;
+        // S3 answers 204 whether or not the object existed.
+        if response.status_code() == 404 {
+            return Ok(());
+        }
+        check_status(response.status_code(), "delete", &key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::store_artifact;
+    use std::env;
+
+    /// Runs against a local MinIO, e.g.
+    /// `docker run -p 9000:9000 minio/minio server /data` with a bucket named by
+    /// `ARTIFACT_S3_BUCKET`, then `cargo test -- --ignored test_s3_store_roundtrip`.
+    #[tokio::test]
+    #[ignore = "requires a running MinIO or S3 endpoint"]
+    async fn test_s3_store_roundtrip() {
+        let var = |name: &str, default: &str| env::var(name).unwrap_or_else(|_| default.into());
+        let store = S3ArtifactStore::new(&S3Settings {
+            bucket: var("ARTIFACT_S3_BUCKET", "invok-artifacts"),
+            endpoint: var("ARTIFACT_S3_ENDPOINT", "http://localhost:9000"),
+            region: var("ARTIFACT_S3_REGION", "us-east-1"),
+            access_key: var("ARTIFACT_S3_ACCESS_KEY", "minioadmin"),
+            secret_key: var("ARTIFACT_S3_SECRET_KEY", "minioadmin"),
+        })
+        .unwrap();
+
+        let content = uuid::Uuid::new_v4().to_string().into_bytes();
+        let digest = store_artifact(&store, &content).await.unwrap();
+        assert!(store.exists(&digest).await.unwrap());
+        assert_eq!(store.get(&digest).await.unwrap(), Some(content));
+
+        store.delete(&digest).await.unwrap();
+        assert!(!store.exists(&digest).await.unwrap());
+        assert_eq!(store.get(&digest).await.unwrap(), None);
+    }
+}
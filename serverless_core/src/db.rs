@@ -1,4 +1,17 @@
+pub(crate) mod access_token;
+pub(crate) mod alias;
+pub(crate) mod api_key;
+pub(crate) mod audit;
 pub(crate) mod auth;
 pub(crate) mod cache;
 pub(crate) mod function;
+pub(crate) mod identity;
+pub(crate) mod login_attempts;
 pub(crate) mod models;
+pub(crate) mod organization;
+pub(crate) mod quota;
+pub(crate) mod secret;
+pub(crate) mod session;
+pub(crate) mod sso_cache;
+pub(crate) mod usage;
+pub(crate) mod version;
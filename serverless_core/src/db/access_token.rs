@@ -0,0 +1,131 @@
+use chrono::Utc;
+use db_entities::{
+    access_token::{ActiveModel as AccessTokenModel, Column, Model},
+    auth::Model as AuthUser,
+    prelude::{AccessToken, Auth},
+};
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+pub struct AccessTokenDBRepo;
+
+impl AccessTokenDBRepo {
+    /// Stores a new personal access token.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `token` - The token to insert; its `id` is ignored.
+    ///
+    /// # Returns
+    ///
+    /// * The stored token, or an error of type `sea_orm::DbErr` if insertion fails.
+    pub async fn create_token(conn: &DbConn, token: Model) -> Result<Model, DbErr> {
+        AccessTokenModel {
+            uuid: Set(token.uuid),
+            auth_id: Set(token.auth_id),
+            name: Set(token.name),
+            prefix: Set(token.prefix),
+            token_hash: Set(token.token_hash),
+            scopes: Set(token.scopes),
+            created_at: Set(token.created_at),
+            expires_at: Set(token.expires_at),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Finds a token and its owner by the hash of the token's secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `token_hash` - The SHA-256 hash of the token.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((Model, owner))` if the token exists, revoked or not; otherwise, `None`.
+    pub async fn find_by_hash(
+        conn: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<(Model, Option<AuthUser>)>, DbErr> {
+        AccessToken::find()
+            .find_also_related(Auth)
+            .filter(Column::TokenHash.eq(token_hash))
+            .one(conn)
+            .await
+    }
+
+    /// Finds a user's token by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `uuid` - The token's ID.
+    /// * `auth_id` - The ID of the user owning the token.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the user owns such a token; otherwise, `None`.
+    pub async fn find_for_user(
+        conn: &DbConn,
+        uuid: Uuid,
+        auth_id: i32,
+    ) -> Result<Option<Model>, DbErr> {
+        AccessToken::find()
+            .filter(
+                Condition::all()
+                    .add(Column::Uuid.eq(uuid))
+                    .add(Column::AuthId.eq(auth_id)),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Lists a user's tokens that haven't been revoked, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `auth_id` - The ID of the user.
+    pub async fn list_for_user(conn: &DbConn, auth_id: i32) -> Result<Vec<Model>, DbErr> {
+        AccessToken::find()
+            .filter(
+                Condition::all()
+                    .add(Column::AuthId.eq(auth_id))
+                    .add(Column::RevokedAt.is_null()),
+            )
+            .order_by_desc(Column::CreatedAt)
+            .all(conn)
+            .await
+    }
+
+    /// Records that a token was just used.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `token` - The token that authenticated a request.
+    pub async fn touch(conn: &DbConn, token: Model) -> Result<Model, DbErr> {
+        let mut token = token.into_active_model();
+        token.last_used_at = Set(Some(Utc::now().fixed_offset()));
+        token.update(conn).await
+    }
+
+    /// Revokes a token, after which it no longer authenticates requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `token` - The token to revoke.
+    pub async fn revoke(conn: &DbConn, token: Model) -> Result<Model, DbErr> {
+        let mut token = token.into_active_model();
+        token.revoked_at = Set(Some(Utc::now().fixed_offset()));
+        token.update(conn).await
+    }
+}
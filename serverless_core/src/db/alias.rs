@@ -0,0 +1,91 @@
+use db_entities::function_alias::{ActiveModel as AliasModel, Column, Model};
+use db_entities::prelude::FunctionAlias;
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder,
+};
+
+pub struct FunctionAliasDBRepo;
+
+impl FunctionAliasDBRepo {
+    /// Finds an alias of a function by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `name` - The alias name (e.g. "prod").
+    pub async fn find_alias(
+        conn: &DbConn,
+        function_id: i32,
+        name: &str,
+    ) -> Result<Option<Model>, DbErr> {
+        FunctionAlias::find()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::Name.eq(name)),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Lists all aliases of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    pub async fn list_aliases(conn: &DbConn, function_id: i32) -> Result<Vec<Model>, DbErr> {
+        FunctionAlias::find()
+            .filter(Column::FunctionId.eq(function_id))
+            .order_by_asc(Column::Name)
+            .all(conn)
+            .await
+    }
+
+    /// Creates an alias or repoints an existing one.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `name` - The alias name.
+    /// * `version` - The version receiving the main share of traffic.
+    /// * `canary_version` - An optional second version receiving `canary_weight` percent.
+    /// * `canary_weight` - Percentage (0-100) of traffic routed to `canary_version`.
+    ///
+    /// # Returns
+    ///
+    /// * The saved alias.
+    pub async fn upsert_alias(
+        conn: &DbConn,
+        function_id: i32,
+        name: &str,
+        version: i32,
+        canary_version: Option<i32>,
+        canary_weight: i32,
+    ) -> Result<Model, DbErr> {
+        match Self::find_alias(conn, function_id, name).await? {
+            Some(alias) => {
+                let mut alias = alias.into_active_model();
+                alias.version = Set(version);
+                alias.canary_version = Set(canary_version);
+                alias.canary_weight = Set(canary_weight);
+                alias.update(conn).await
+            }
+            None => {
+                let alias = AliasModel {
+                    function_id: Set(function_id),
+                    name: Set(name.to_string()),
+                    version: Set(version),
+                    canary_version: Set(canary_version),
+                    canary_weight: Set(canary_weight),
+                    ..Default::default()
+                };
+                alias.insert(conn).await
+            }
+        }
+    }
+}
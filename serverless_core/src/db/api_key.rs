@@ -0,0 +1,134 @@
+use chrono::Utc;
+use db_entities::{
+    function_api_key::{ActiveModel as ApiKeyModel, Column, Model},
+    prelude::FunctionApiKey,
+};
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+pub struct ApiKeyDBRepo;
+
+impl ApiKeyDBRepo {
+    /// Stores a new API key of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `key` - The key to insert; its `id` is ignored.
+    ///
+    /// # Returns
+    ///
+    /// * The stored key, or an error of type `sea_orm::DbErr` if insertion fails.
+    pub async fn create_key(conn: &DbConn, key: Model) -> Result<Model, DbErr> {
+        ApiKeyModel {
+            uuid: Set(key.uuid),
+            function_id: Set(key.function_id),
+            name: Set(key.name),
+            prefix: Set(key.prefix),
+            key_hash: Set(key.key_hash),
+            created_at: Set(key.created_at),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Finds a function's key that hasn't been revoked by the hash of its secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function being invoked.
+    /// * `key_hash` - The SHA-256 hash of the key.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the function has such a key; otherwise, `None`.
+    pub async fn find_active_by_hash(
+        conn: &DbConn,
+        function_id: i32,
+        key_hash: &str,
+    ) -> Result<Option<Model>, DbErr> {
+        FunctionApiKey::find()
+            .filter(
+                Condition::all()
+                    .add(Column::KeyHash.eq(key_hash))
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::RevokedAt.is_null()),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Finds a function's key by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `uuid` - The key's ID.
+    /// * `function_id` - The ID of the function owning the key.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the function has such a key; otherwise, `None`.
+    pub async fn find_for_function(
+        conn: &DbConn,
+        uuid: Uuid,
+        function_id: i32,
+    ) -> Result<Option<Model>, DbErr> {
+        FunctionApiKey::find()
+            .filter(
+                Condition::all()
+                    .add(Column::Uuid.eq(uuid))
+                    .add(Column::FunctionId.eq(function_id)),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Lists a function's keys that haven't been revoked, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    pub async fn list_for_function(conn: &DbConn, function_id: i32) -> Result<Vec<Model>, DbErr> {
+        FunctionApiKey::find()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::RevokedAt.is_null()),
+            )
+            .order_by_desc(Column::CreatedAt)
+            .all(conn)
+            .await
+    }
+
+    /// Records that a key was just used.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `key` - The key that authenticated an invocation.
+    pub async fn touch(conn: &DbConn, key: Model) -> Result<Model, DbErr> {
+        let mut key = key.into_active_model();
+        key.last_used_at = Set(Some(Utc::now().fixed_offset()));
+        key.update(conn).await
+    }
+
+    /// Revokes a key, after which it no longer authenticates invocations.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `key` - The key to revoke.
+    pub async fn revoke(conn: &DbConn, key: Model) -> Result<Model, DbErr> {
+        let mut key = key.into_active_model();
+        key.revoked_at = Set(Some(Utc::now().fixed_offset()));
+        key.update(conn).await
+    }
+}
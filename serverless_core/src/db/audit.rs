@@ -0,0 +1,113 @@
+use chrono::{DateTime, FixedOffset};
+use db_entities::{
+    audit_event::{ActiveModel as AuditEventModel, Column, Model},
+    prelude::AuditEvent,
+};
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use uuid::Uuid;
+
+use crate::db::models::AuditFilter;
+
+pub struct AuditDBRepo;
+
+impl AuditDBRepo {
+    /// Appends an event to the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `event` - The event to insert; its `id` and `occurred_at` are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * The stored event, or an error of type `sea_orm::DbErr` if insertion fails.
+    pub async fn insert(conn: &DbConn, event: Model) -> Result<Model, DbErr> {
+        AuditEventModel {
+            actor_uuid: Set(event.actor_uuid),
+            namespace: Set(event.namespace),
+            action: Set(event.action),
+            target: Set(event.target),
+            ip: Set(event.ip),
+            request_id: Set(event.request_id),
+            outcome: Set(event.outcome),
+            detail: Set(event.detail),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Lists audit events matching a filter, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `namespace` - Only events of this organization; every event when `None`.
+    /// * `filter` - The filters the events must match.
+    /// * `limit` - The most events to return.
+    pub async fn find(
+        conn: &DbConn,
+        namespace: Option<Uuid>,
+        filter: &AuditFilter,
+        limit: u64,
+    ) -> Result<Vec<Model>, DbErr> {
+        let mut condition = Condition::all();
+        if let Some(namespace) = namespace {
+            condition = condition.add(Column::Namespace.eq(namespace));
+        }
+        if let Some(actor) = filter.actor {
+            condition = condition.add(Column::ActorUuid.eq(actor));
+        }
+        if let Some(action) = &filter.action {
+            condition = condition.add(
+                Condition::any()
+                    .add(Column::Action.eq(action.as_str()))
+                    .add(Column::Action.starts_with(format!("{action}."))),
+            );
+        }
+        if let Some(target) = &filter.target {
+            condition = condition.add(Column::Target.eq(target.as_str()));
+        }
+        if let Some(outcome) = &filter.outcome {
+            condition = condition.add(Column::Outcome.eq(outcome.as_str()));
+        }
+        if let Some(since) = filter.since {
+            condition = condition.add(Column::OccurredAt.gte(since));
+        }
+        if let Some(until) = filter.until {
+            condition = condition.add(Column::OccurredAt.lt(until));
+        }
+        if let Some(before) = filter.before {
+            condition = condition.add(Column::Id.lt(before));
+        }
+
+        AuditEvent::find()
+            .filter(condition)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(conn)
+            .await
+    }
+
+    /// Deletes the events that occurred before a cutoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `cutoff` - Events older than this are deleted.
+    ///
+    /// # Returns
+    ///
+    /// * The number of events deleted.
+    pub async fn delete_before(conn: &DbConn, cutoff: DateTime<FixedOffset>) -> Result<u64, DbErr> {
+        let result = AuditEvent::delete_many()
+            .filter(Column::OccurredAt.lt(cutoff))
+            .exec(conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -1,27 +1,37 @@
-use argon2::{
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
-};
 use db_entities::{
     auth::{ActiveModel as AuthModel, Column as AuthColumn, Model as AuthUser},
     prelude::Auth as AuthEntity,
 };
-use rand_core::OsRng;
+use db_migrations::Condition;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter,
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, Func},
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
 };
+
+use crate::db::models::UserFilter;
+use crate::db::organization::OrganizationDBRepo;
+use crate::utils::utils::like_pattern;
 use uuid::Uuid;
 
+/// Platform role of regular users, as opposed to operators.
+pub const PLATFORM_USER_ROLE: &str = "user";
+
 pub struct AuthDBRepo;
 
 impl AuthDBRepo {
-    /// Registers a new user with the provided email and password
+    /// Registers a new user with the provided email and password hash
+    ///
+    /// The user's personal organization is created along with them.
     ///
     /// # Arguments
     ///
     /// * `conn` - A reference to the database connection
     /// * `email` - The email address for the new user
-    /// * `password` - The password for the new user
+    /// * `password_hash` - The Argon2 hash of the new user's password
     ///
     /// # Returns
     ///
@@ -30,7 +40,7 @@ impl AuthDBRepo {
     pub async fn register(
         conn: &DbConn,
         email: String,
-        password: String,
+        password_hash: String,
     ) -> Result<AuthUser, DbErr> {
         // Check if user with this email already exists
         if AuthEntity::find()
@@ -42,50 +52,87 @@ impl AuthDBRepo {
             return Err(DbErr::Custom("Email already registered".to_string()));
         }
 
-        // Hash the password with Argon2
-        let hashed_password = Self::hash_password(&password)?;
-
         // Create the user model
         let user = AuthModel {
             id: Default::default(),
             email: Set(email),
-            password: Set(hashed_password),
+            password: Set(password_hash),
             uuid: Set(Uuid::new_v4()),
+            email_verified_at: Set(None),
+            role: Set(PLATFORM_USER_ROLE.to_string()),
+            suspended_at: Set(None),
         };
 
-        // Save the user to the database
-        user.insert(conn).await
+        // Save the user and their personal organization to the database
+        let txn = conn.begin().await?;
+        let user = user.insert(&txn).await?;
+        OrganizationDBRepo::create_personal(&txn, &user).await?;
+        txn.commit().await?;
+
+        Ok(user)
     }
 
-    /// Login a user with the provided email and password
+    /// Find a user by their email address
     ///
     /// # Arguments
     ///
     /// * `conn` - A reference to the database connection
     /// * `email` - The email address of the user
-    /// * `password` - The password for the user
     ///
     /// # Returns
     ///
-    /// * `Ok(AuthUser)` - The logged in user
-    /// * `Err(DbErr)` - If login fails (e.g., invalid credentials)
-    pub async fn login(conn: &DbConn, email: String, password: String) -> Result<AuthUser, DbErr> {
-        // Find the user by email
-        let user = match AuthEntity::find()
-            .filter(AuthColumn::Email.eq(&email))
+    /// * `Ok(Some(AuthUser))` - The user, if found
+    /// * `Ok(None)` - If no user with the email exists
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn find_by_email(conn: &DbConn, email: &str) -> Result<Option<AuthUser>, DbErr> {
+        AuthEntity::find()
+            .filter(AuthColumn::Email.eq(email))
             .one(conn)
-            .await?
-        {
-            Some(user) => user,
-            None => return Err(DbErr::Custom("Invalid credentials".to_string())),
-        };
+            .await
+    }
 
-        // Verify the password
-        if !Self::verify_password(&password, &user.password)? {
-            return Err(DbErr::Custom("Invalid credentials".to_string()));
-        }
+    /// Replace a user's password hash
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `user` - The user whose password changes
+    /// * `password_hash` - The new Argon2 hash of the password
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AuthUser)` - The updated user
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn update_password(
+        conn: &DbConn,
+        user: AuthUser,
+        password_hash: String,
+    ) -> Result<AuthUser, DbErr> {
+        let mut user: AuthModel = user.into();
+        user.password = Set(password_hash);
+        user.update(conn).await
+    }
 
-        Ok(user)
+    /// Mark a user's email address as verified
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `user` - The user whose email address was verified
+    /// * `verified_at` - When the address was verified
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AuthUser)` - The updated user
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn mark_email_verified(
+        conn: &DbConn,
+        user: AuthUser,
+        verified_at: DateTimeWithTimeZone,
+    ) -> Result<AuthUser, DbErr> {
+        let mut user: AuthModel = user.into();
+        user.email_verified_at = Set(Some(verified_at));
+        user.update(conn).await
     }
 
     /// Find a user by their UUID
@@ -107,26 +154,113 @@ impl AuthDBRepo {
             .await
     }
 
-    /// Hash a password using Argon2
-    fn hash_password(password: &str) -> Result<String, DbErr> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
+    /// Find the first suspended user among a user ID and a user UUID
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `id` - The ID of one user
+    /// * `uuid` - The UUID of another user, or of an organization that isn't one
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(AuthUser))` - A suspended user among them
+    /// * `Ok(None)` - If neither user is suspended
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn find_suspended(
+        conn: &DbConn,
+        id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<AuthUser>, DbErr> {
+        AuthEntity::find()
+            .filter(
+                Condition::any()
+                    .add(AuthColumn::Id.eq(id))
+                    .add(AuthColumn::Uuid.eq(uuid)),
+            )
+            .filter(AuthColumn::SuspendedAt.is_not_null())
+            .one(conn)
+            .await
+    }
+
+    /// List users matching a filter, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `filter` - The filters the users must match
+    /// * `limit` - The most users to return
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<AuthUser>)` - The matching users
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn search(
+        conn: &DbConn,
+        filter: &UserFilter,
+        limit: u64,
+    ) -> Result<Vec<AuthUser>, DbErr> {
+        let mut condition = Condition::all();
+        if let Some(q) = filter.q.as_deref().filter(|q| !q.is_empty()) {
+            condition = condition.add(
+                Expr::expr(Func::lower(Expr::col(AuthColumn::Email)))
+                    .like(like_pattern(&q.to_lowercase())),
+            );
+        }
+        match filter.suspended {
+            Some(true) => condition = condition.add(AuthColumn::SuspendedAt.is_not_null()),
+            Some(false) => condition = condition.add(AuthColumn::SuspendedAt.is_null()),
+            None => {}
+        }
+        if let Some(after) = filter.after {
+            condition = condition.add(AuthColumn::Id.gt(after));
+        }
 
-        // Hash the password with Argon2
-        argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map(|hash| hash.to_string())
-            .map_err(|e| DbErr::Custom(format!("Failed to hash password: {}", e)))
+        AuthEntity::find()
+            .filter(condition)
+            .order_by_asc(AuthColumn::Id)
+            .limit(limit)
+            .all(conn)
+            .await
     }
 
-    /// Verify a password against a previously hashed password
-    fn verify_password(password: &str, hash: &str) -> Result<bool, DbErr> {
-        let parsed_hash = PasswordHash::new(hash)
-            .map_err(|e| DbErr::Custom(format!("Failed to parse password hash: {}", e)))?;
+    /// Suspend a user, or lift their suspension
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `user` - The user to suspend or reinstate
+    /// * `suspended_at` - When the user was suspended, or `None` to reinstate them
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AuthUser)` - The updated user
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn set_suspended(
+        conn: &DbConn,
+        user: AuthUser,
+        suspended_at: Option<DateTimeWithTimeZone>,
+    ) -> Result<AuthUser, DbErr> {
+        let mut user: AuthModel = user.into();
+        user.suspended_at = Set(suspended_at);
+        user.update(conn).await
+    }
 
-        // Verify the password
-        Ok(Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok())
+    /// Change a user's platform role
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection
+    /// * `user` - The user whose role changes
+    /// * `role` - The new role, `user` or `admin`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AuthUser)` - The updated user
+    /// * `Err(DbErr)` - If an error occurs during the database operation
+    pub async fn set_role(conn: &DbConn, user: AuthUser, role: &str) -> Result<AuthUser, DbErr> {
+        let mut user: AuthModel = user.into();
+        user.role = Set(role.to_string());
+        user.update(conn).await
     }
 }
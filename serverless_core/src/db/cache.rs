@@ -1,6 +1,44 @@
-use redis::{aio::MultiplexedConnection, AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use redis::{
+    aio::MultiplexedConnection, AsyncCommands, ExistenceCheck, Script, SetExpiry, SetOptions,
+};
 use tracing::error;
 
+/// Extends the lock's expiry only if it is still held by the caller's token.
+const EXTEND_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+/// Deletes the lock only if it is still held by the caller's token.
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// A distributed lock held on a function while it is being changed.
+#[derive(Debug, Clone, Copy)]
+pub enum FunctionLock {
+    /// Held on an instance while its container is cold-started.
+    Start,
+    /// Held on a function while a new version of it is built and recorded.
+    Deploy,
+}
+
+impl FunctionLock {
+    fn as_str(self) -> &'static str {
+        match self {
+            FunctionLock::Start => "start",
+            FunctionLock::Deploy => "deploy",
+        }
+    }
+}
+
 pub struct FunctionCacheRepo;
 
 impl FunctionCacheRepo {
@@ -15,8 +53,8 @@ impl FunctionCacheRepo {
     ///
     /// * `Some(String)` containing the cached address if found, or `None` if not found or an error occurs.
     pub async fn get_function(conn: &mut MultiplexedConnection, name: &str) -> Option<String> {
-        match conn.get(name).await {
-            Ok(val) => Some(val),
+        match conn.get::<_, Option<String>>(name).await {
+            Ok(val) => val,
             Err(e) => {
                 error!("Failed to retrieve function '{}' from cache: {}", name, e);
                 None
@@ -24,6 +62,26 @@ impl FunctionCacheRepo {
         }
     }
 
+    /// Retrieves the cached addresses of several functions at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `names` - The keys representing the functions.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Option<String>>)` with one entry per key, `None` where nothing is cached.
+    pub async fn get_functions(
+        conn: &mut MultiplexedConnection,
+        names: &[String],
+    ) -> redis::RedisResult<Vec<Option<String>>> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        conn.mget(names).await
+    }
+
     /// Adds a function address to the cache with a specified time-to-live (TTL).
     ///
     /// The entry is only added if it does not already exist.
@@ -53,4 +111,248 @@ impl FunctionCacheRepo {
             e
         })
     }
+
+    /// Removes a function's cached address so no new requests are routed to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `name` - The key representing the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(String))` with the address that was cached, or `Ok(None)` if none was.
+    pub async fn remove_function(
+        conn: &mut MultiplexedConnection,
+        name: &str,
+    ) -> redis::RedisResult<Option<String>> {
+        conn.get_del(name).await.map_err(|e| {
+            error!("Failed to remove function '{}' from cache: {}", name, e);
+            e
+        })
+    }
+
+    /// Records the start of a request forwarded to the instance at `addr`.
+    ///
+    /// The counter expires after `ttl` seconds so a crashed core can't leave an
+    /// instance looking busy forever.
+    pub async fn begin_request(
+        conn: &mut MultiplexedConnection,
+        addr: &str,
+        ttl: u64,
+    ) -> redis::RedisResult<()> {
+        let key = Self::in_flight_key(addr);
+        redis::pipe()
+            .incr(&key, 1)
+            .ignore()
+            .expire(&key, ttl as i64)
+            .ignore()
+            .query_async(conn)
+            .await
+    }
+
+    /// Records the end of a request forwarded to the instance at `addr`.
+    pub async fn end_request(
+        conn: &mut MultiplexedConnection,
+        addr: &str,
+    ) -> redis::RedisResult<()> {
+        conn.decr(Self::in_flight_key(addr), 1).await
+    }
+
+    /// Returns the number of requests currently in flight to the instance at `addr`.
+    pub async fn in_flight(
+        conn: &mut MultiplexedConnection,
+        addr: &str,
+    ) -> redis::RedisResult<i64> {
+        conn.get::<_, Option<i64>>(Self::in_flight_key(addr))
+            .await
+            .map(|count| count.unwrap_or(0))
+    }
+
+    /// Attempts to take a lock on a function.
+    ///
+    /// Only one caller across all core instances can hold a lock at a time, so a
+    /// single container is started per cold function and a single version is
+    /// built per deploy. The lock expires on its own after `ttl_ms` in case the
+    /// holder dies before releasing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `lock` - Which lock to take.
+    /// * `name` - The key representing the function.
+    /// * `token` - A value unique to the caller, required to release the lock.
+    /// * `ttl_ms` - Lock expiry in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the lock was acquired, `Ok(false)` if someone else holds it.
+    pub async fn acquire_lock(
+        conn: &mut MultiplexedConnection,
+        lock: FunctionLock,
+        name: &str,
+        token: &str,
+        ttl_ms: u64,
+    ) -> redis::RedisResult<bool> {
+        let opts = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::PX(ttl_ms));
+        let acquired: Option<String> = conn
+            .set_options(Self::lock_key(lock, name), token, opts)
+            .await
+            .map_err(|e| {
+                error!(
+                    "Failed to acquire {} lock for '{}': {}",
+                    lock.as_str(),
+                    name,
+                    e
+                );
+                e
+            })?;
+        Ok(acquired.is_some())
+    }
+
+    /// Pushes back the expiry of a lock on a function, if it is still held by
+    /// `token`, to `ttl_ms` from now.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the lock was extended, `Ok(false)` if it is no longer held.
+    pub async fn extend_lock(
+        conn: &mut MultiplexedConnection,
+        lock: FunctionLock,
+        name: &str,
+        token: &str,
+        ttl_ms: u64,
+    ) -> redis::RedisResult<bool> {
+        Script::new(EXTEND_LOCK_SCRIPT)
+            .key(Self::lock_key(lock, name))
+            .arg(token)
+            .arg(ttl_ms)
+            .invoke_async::<i32>(conn)
+            .await
+            .map(|extended| extended == 1)
+            .map_err(|e| {
+                error!(
+                    "Failed to extend {} lock for '{}': {}",
+                    lock.as_str(),
+                    name,
+                    e
+                );
+                e
+            })
+    }
+
+    /// Releases a lock on a function if it is still held by `token`.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `lock` - Which lock to release.
+    /// * `name` - The key representing the function.
+    /// * `token` - The token used when the lock was acquired.
+    pub async fn release_lock(
+        conn: &mut MultiplexedConnection,
+        lock: FunctionLock,
+        name: &str,
+        token: &str,
+    ) -> redis::RedisResult<()> {
+        Script::new(RELEASE_LOCK_SCRIPT)
+            .key(Self::lock_key(lock, name))
+            .arg(token)
+            .invoke_async::<i32>(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                error!(
+                    "Failed to release {} lock for '{}': {}",
+                    lock.as_str(),
+                    name,
+                    e
+                );
+                e
+            })
+    }
+
+    /// Records that a function was just invoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `name` - The key representing the function.
+    /// * `at` - The invocation time, in RFC 3339 format.
+    pub async fn record_invocation(
+        conn: &mut MultiplexedConnection,
+        name: &str,
+        at: &str,
+    ) -> redis::RedisResult<()> {
+        conn.set(Self::last_invocation_key(name), at).await
+    }
+
+    /// Records the most recent error of a function, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `name` - The key representing the function.
+    /// * `error` - The error, serialized as JSON.
+    pub async fn record_error(
+        conn: &mut MultiplexedConnection,
+        name: &str,
+        error: &str,
+    ) -> redis::RedisResult<()> {
+        conn.set(Self::last_error_key(name), error).await
+    }
+
+    /// Returns the last invocation time and last error recorded for a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `name` - The key representing the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((last_invocation, last_error))`, each `None` if nothing was recorded.
+    pub async fn get_activity(
+        conn: &mut MultiplexedConnection,
+        name: &str,
+    ) -> redis::RedisResult<(Option<String>, Option<String>)> {
+        conn.mget(&[Self::last_invocation_key(name), Self::last_error_key(name)])
+            .await
+            .map(|values: Vec<Option<String>>| {
+                let mut values = values.into_iter();
+                (values.next().flatten(), values.next().flatten())
+            })
+    }
+
+    /// Removes the invocation activity recorded for a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `name` - The key representing the function.
+    pub async fn clear_activity(
+        conn: &mut MultiplexedConnection,
+        name: &str,
+    ) -> redis::RedisResult<()> {
+        conn.del(&[Self::last_invocation_key(name), Self::last_error_key(name)])
+            .await
+    }
+
+    fn lock_key(lock: FunctionLock, name: &str) -> String {
+        format!("{name}:{}-lock", lock.as_str())
+    }
+
+    fn in_flight_key(addr: &str) -> String {
+        format!("{addr}:in-flight")
+    }
+
+    fn last_invocation_key(name: &str) -> String {
+        format!("{name}:last-invocation")
+    }
+
+    fn last_error_key(name: &str) -> String {
+        format!("{name}:last-error")
+    }
 }
@@ -1,13 +1,20 @@
+use chrono::Utc;
+use db_entities::function::{ActiveModel as FunctionModel, Column, Model};
 use db_entities::prelude::Function;
-use db_entities::{
-    auth::Column as AuthColumn,
-    function::{ActiveModel as FunctionModel, Column, Model},
-    prelude::Auth as AuthEntity,
-};
 use db_migrations::Condition;
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, EntityTrait, QueryFilter};
+use sea_orm::{
+    prelude::Json,
+    sea_query::{Expr, Func},
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, DbConn, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
 use uuid::Uuid;
 
+use crate::db::models::FunctionFilter;
+use crate::utils::utils::like_pattern;
+
 pub struct FunctionDBRepo;
 
 impl FunctionDBRepo {
@@ -17,6 +24,7 @@ impl FunctionDBRepo {
     ///
     /// * `conn` - A reference to the database connection.
     /// * `name` - The name of the function to find.
+    /// * `user_uuid` - The namespace the function belongs to.
     ///
     /// # Returns
     ///
@@ -30,13 +38,111 @@ impl FunctionDBRepo {
             .filter(
                 Condition::all()
                     .add(Column::Name.eq(name))
-                    .add(Column::Uuid.eq(user_uuid)),
+                    .add(Column::Namespace.eq(user_uuid)),
+            )
+            .one(conn)
+            .await
+            .ok()?
+    }
+
+    /// Finds a function by its unique ID within a namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `uuid` - The function's ID.
+    /// * `user_uuid` - The namespace the function belongs to.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the function exists; otherwise, `None`.
+    pub async fn find_function_by_uuid(
+        conn: &DbConn,
+        uuid: Uuid,
+        user_uuid: Uuid,
+    ) -> Option<Model> {
+        Function::find()
+            .filter(
+                Condition::all()
+                    .add(Column::Uuid.eq(uuid))
+                    .add(Column::Namespace.eq(user_uuid)),
             )
             .one(conn)
             .await
             .ok()?
     }
 
+    /// Finds a function by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `id` - The ID of the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the function exists; otherwise, `None`.
+    pub async fn find_function_by_id(
+        conn: &DbConn,
+        id: i32,
+    ) -> Result<Option<Model>, sea_orm::DbErr> {
+        Function::find_by_id(id).one(conn).await
+    }
+
+    /// Finds a function by its unique ID, in any namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `uuid` - The function's ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the function exists; otherwise, `None`.
+    pub async fn find_any_function_by_uuid(
+        conn: &DbConn,
+        uuid: Uuid,
+    ) -> Result<Option<Model>, sea_orm::DbErr> {
+        Function::find()
+            .filter(Column::Uuid.eq(uuid))
+            .one(conn)
+            .await
+    }
+
+    /// Searches the functions of every namespace, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `filter` - The filters the functions must match.
+    /// * `limit` - The most functions to return.
+    pub async fn search_functions(
+        conn: &DbConn,
+        filter: &FunctionFilter,
+        limit: u64,
+    ) -> Result<Vec<Model>, sea_orm::DbErr> {
+        let mut condition = Condition::all();
+        if let Some(q) = filter.q.as_deref().filter(|q| !q.is_empty()) {
+            condition = condition.add(
+                Expr::expr(Func::lower(Expr::col(Column::Name)))
+                    .like(like_pattern(&q.to_lowercase())),
+            );
+        }
+        if let Some(namespace) = filter.namespace {
+            condition = condition.add(Column::Namespace.eq(namespace));
+        }
+        if let Some(after) = filter.after {
+            condition = condition.add(Column::Id.gt(after));
+        }
+
+        Function::find()
+            .filter(condition)
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(conn)
+            .await
+    }
+
     /// Finds functions by user's UUID in the database.
     ///
     /// # Arguments
@@ -51,58 +157,131 @@ impl FunctionDBRepo {
         conn: &DbConn,
         user_uuid: Uuid,
     ) -> Result<Vec<Model>, sea_orm::DbErr> {
-        // First find the user by UUID
-        let user = AuthEntity::find()
-            .filter(AuthColumn::Uuid.eq(user_uuid))
-            .one(conn)
-            .await?;
-
-        // If no user found, return empty list
-        let user = match user {
-            Some(user) => user,
-            None => return Ok(vec![]),
-        };
-
-        // Find all functions for this user
         Function::find()
-            .filter(Column::AuthId.eq(user.id))
+            .filter(Column::Namespace.eq(user_uuid))
             .all(conn)
             .await
     }
 
-    /// Creates a new function in the database for a specific user.
+    /// Counts the functions of a namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `namespace` - The UUID of the organization.
+    pub async fn count_functions(conn: &DbConn, namespace: Uuid) -> Result<u64, sea_orm::DbErr> {
+        Function::find()
+            .filter(Column::Namespace.eq(namespace))
+            .count(conn)
+            .await
+    }
+
+    /// Creates a new function in the database within a namespace.
     ///
     /// # Arguments
     ///
     /// * `conn` - A reference to the database connection.
     /// * `function` - The function model to insert.
-    /// * `user_uuid` - The UUID of the user who owns this function.
+    /// * `namespace` - The UUID of the organization that owns this function.
+    /// * `auth_id` - The ID of the user deploying it.
     ///
     /// # Returns
     ///
     /// * `Ok(())` on success, or an error of type `sea_orm::DbErr` if insertion fails.
-    pub async fn create_function_for_user(
+    pub async fn create_function(
         conn: &DbConn,
         function: Model,
-        user_uuid: Uuid,
+        namespace: Uuid,
+        auth_id: i32,
     ) -> Result<Model, sea_orm::DbErr> {
-        // Find the user by UUID
-        let user = AuthEntity::find()
-            .filter(AuthColumn::Uuid.eq(user_uuid))
-            .one(conn)
-            .await?
-            .ok_or_else(|| sea_orm::DbErr::Custom("User not found".to_string()))?;
-
-        // Create the function model record with the user's ID
+        // Create the function model record with its deployer and a fresh function ID
         let function_model = FunctionModel {
-            auth_id: Set(user.id),
+            auth_id: Set(auth_id),
             name: Set(function.name),
             runtime: Set(function.runtime),
-            uuid: Set(user_uuid),
+            uuid: Set(Uuid::new_v4()),
+            namespace: Set(namespace),
+            max_concurrency: Set(function.max_concurrency),
+            env: Set(function.env),
+            image_digest: Set(function.image_digest),
+            description: Set(function.description),
+            labels: Set(function.labels),
+            auth: Set(function.auth),
+            last_deployed_at: Set(Some(Utc::now().fixed_offset())),
             ..Default::default()
         };
 
         // Insert and return the created function
         function_model.insert(conn).await
     }
+
+    /// Records a new deploy of an existing function.
+    ///
+    /// Settings that come from the function's `config.json` and the deploying
+    /// user are replaced, and the deploy and update times are set to now.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function` - The function to update.
+    /// * `deployed` - The deployed settings: `auth_id`, `max_concurrency`, `env`,
+    ///   `image_digest`, `description`, `labels` and `auth` are taken from it.
+    ///
+    /// # Returns
+    ///
+    /// * The updated function, or an error of type `sea_orm::DbErr` if the update fails.
+    pub async fn record_deploy(
+        conn: &DbConn,
+        function: Model,
+        deployed: Model,
+    ) -> Result<Model, sea_orm::DbErr> {
+        let now = Utc::now().fixed_offset();
+        let mut function = function.into_active_model();
+        function.auth_id = Set(deployed.auth_id);
+        function.max_concurrency = Set(deployed.max_concurrency);
+        function.env = Set(deployed.env);
+        function.image_digest = Set(deployed.image_digest);
+        function.description = Set(deployed.description);
+        function.labels = Set(deployed.labels);
+        function.auth = Set(deployed.auth);
+        function.last_deployed_at = Set(Some(now));
+        function.updated_at = Set(now);
+        function.update(conn).await
+    }
+
+    /// Replaces the environment variables applied to a function's containers.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function` - The function to update.
+    /// * `env` - The environment variables, as a JSON object of strings.
+    ///
+    /// # Returns
+    ///
+    /// * The updated function, or an error of type `sea_orm::DbErr` if the update fails.
+    pub async fn update_env(
+        conn: &DbConn,
+        function: Model,
+        env: Json,
+    ) -> Result<Model, sea_orm::DbErr> {
+        let mut function = function.into_active_model();
+        function.env = Set(Some(env));
+        function.updated_at = Set(Utc::now().fixed_offset());
+        function.update(conn).await
+    }
+
+    /// Deletes a function and, through cascading foreign keys, its versions and aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function` - The function to delete.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success, or an error of type `sea_orm::DbErr` if deletion fails.
+    pub async fn delete_function(conn: &DbConn, function: Model) -> Result<(), sea_orm::DbErr> {
+        function.into_active_model().delete(conn).await.map(|_| ())
+    }
 }
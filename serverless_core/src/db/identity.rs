@@ -0,0 +1,115 @@
+use chrono::{DateTime, FixedOffset};
+use db_entities::{
+    auth::{ActiveModel as AuthModel, Model as AuthUser},
+    auth_identity::{ActiveModel as IdentityModel, Column, Model},
+    prelude::{Auth, AuthIdentity},
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::db::auth::PLATFORM_USER_ROLE;
+use crate::db::organization::OrganizationDBRepo;
+
+/// Stored in place of a password hash for accounts created through single
+/// sign-on; it never verifies.
+const NO_PASSWORD: &str = "!sso";
+
+pub struct IdentityDBRepo;
+
+impl IdentityDBRepo {
+    /// Finds the user an identity provider's subject is linked to.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `issuer` - The identity provider's issuer URL.
+    /// * `subject` - The user's `sub` claim at the provider.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(AuthUser))` if the subject is linked, `Ok(None)` otherwise.
+    pub async fn find_user(
+        conn: &DbConn,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<AuthUser>, DbErr> {
+        Ok(AuthIdentity::find()
+            .filter(Column::Issuer.eq(issuer))
+            .filter(Column::Subject.eq(subject))
+            .find_also_related(Auth)
+            .one(conn)
+            .await?
+            .and_then(|(_, user)| user))
+    }
+
+    /// Links an identity provider's subject to an existing user.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `auth_id` - The ID of the user.
+    /// * `issuer` - The identity provider's issuer URL.
+    /// * `subject` - The user's `sub` claim at the provider.
+    pub async fn link(
+        conn: &DbConn,
+        auth_id: i32,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Model, DbErr> {
+        IdentityModel {
+            auth_id: Set(auth_id),
+            issuer: Set(issuer.to_string()),
+            subject: Set(subject.to_string()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Creates a user signing in through an identity provider for the first time,
+    /// along with their personal organization and the link to the provider.
+    ///
+    /// The user has no password; they can set one through a password reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `email` - The email address from the ID token.
+    /// * `issuer` - The identity provider's issuer URL.
+    /// * `subject` - The user's `sub` claim at the provider.
+    /// * `verified_at` - When the email address was verified.
+    pub async fn create_user(
+        conn: &DbConn,
+        email: String,
+        issuer: &str,
+        subject: &str,
+        verified_at: DateTime<FixedOffset>,
+    ) -> Result<AuthUser, DbErr> {
+        let txn = conn.begin().await?;
+        let user = AuthModel {
+            id: Default::default(),
+            email: Set(email),
+            password: Set(NO_PASSWORD.to_string()),
+            uuid: Set(Uuid::new_v4()),
+            email_verified_at: Set(Some(verified_at)),
+            role: Set(PLATFORM_USER_ROLE.to_string()),
+            suspended_at: Set(None),
+        }
+        .insert(&txn)
+        .await?;
+        OrganizationDBRepo::create_personal(&txn, &user).await?;
+        IdentityModel {
+            auth_id: Set(user.id),
+            issuer: Set(issuer.to_string()),
+            subject: Set(subject.to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(user)
+    }
+}
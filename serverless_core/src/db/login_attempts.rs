@@ -0,0 +1,123 @@
+use redis::{aio::MultiplexedConnection, AsyncCommands, RedisResult};
+
+/// Whose failed logins a key counts: an account (by email) or a client IP address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptSubject {
+    Account,
+    Ip,
+}
+
+impl AttemptSubject {
+    fn as_str(self) -> &'static str {
+        match self {
+            AttemptSubject::Account => "account",
+            AttemptSubject::Ip => "ip",
+        }
+    }
+}
+
+fn failures_key(subject: AttemptSubject, id: &str) -> String {
+    format!("login:failures:{}:{}", subject.as_str(), id)
+}
+
+fn lock_key(subject: AttemptSubject, id: &str) -> String {
+    format!("login:locked:{}:{}", subject.as_str(), id)
+}
+
+fn backoff_key(subject: AttemptSubject, id: &str) -> String {
+    format!("login:backoff:{}:{}", subject.as_str(), id)
+}
+
+pub struct LoginAttemptCacheRepo;
+
+impl LoginAttemptCacheRepo {
+    /// Counts a failed login.
+    ///
+    /// The counter expires `window` seconds after the first failure it counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `subject` - Whether `id` is an account or an IP address.
+    /// * `id` - The normalized email or IP address.
+    /// * `window` - Seconds failures are counted over.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u64)` with the number of failures in the current window.
+    pub async fn record_failure(
+        conn: &mut MultiplexedConnection,
+        subject: AttemptSubject,
+        id: &str,
+        window: u64,
+    ) -> RedisResult<u64> {
+        let key = failures_key(subject, id);
+        let failures: u64 = conn.incr(&key, 1).await?;
+        if failures == 1 {
+            conn.expire::<_, ()>(&key, window as i64).await?;
+        }
+        Ok(failures)
+    }
+
+    /// Locks a subject out of logging in for `ttl` seconds.
+    pub async fn lock(
+        conn: &mut MultiplexedConnection,
+        subject: AttemptSubject,
+        id: &str,
+        ttl: u64,
+    ) -> RedisResult<()> {
+        conn.set_ex(lock_key(subject, id), 1, ttl).await
+    }
+
+    /// Makes a subject wait `ttl` seconds before its next login attempt.
+    pub async fn delay(
+        conn: &mut MultiplexedConnection,
+        subject: AttemptSubject,
+        id: &str,
+        ttl: u64,
+    ) -> RedisResult<()> {
+        conn.set_ex(backoff_key(subject, id), 1, ttl).await
+    }
+
+    /// How long a subject must still wait, whether locked out or delayed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some((seconds, locked)))` if the subject must wait, where `locked`
+    ///   tells a lockout from a progressive delay.
+    /// * `Ok(None)` if the subject may try now.
+    pub async fn wait_time(
+        conn: &mut MultiplexedConnection,
+        subject: AttemptSubject,
+        id: &str,
+    ) -> RedisResult<Option<(u64, bool)>> {
+        let locked: i64 = conn.ttl(lock_key(subject, id)).await?;
+        if locked > 0 {
+            return Ok(Some((locked as u64, true)));
+        }
+        let delayed: i64 = conn.ttl(backoff_key(subject, id)).await?;
+        if delayed > 0 {
+            return Ok(Some((delayed as u64, false)));
+        }
+        Ok(None)
+    }
+
+    /// Clears a subject's failures, delay and lockout.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - Whether the subject was locked out.
+    pub async fn clear(
+        conn: &mut MultiplexedConnection,
+        subject: AttemptSubject,
+        id: &str,
+    ) -> RedisResult<bool> {
+        let (_, locked, _): (u64, u64, u64) = redis::pipe()
+            .del(failures_key(subject, id))
+            .del(lock_key(subject, id))
+            .del(backoff_key(subject, id))
+            .query_async(conn)
+            .await?;
+        Ok(locked > 0)
+    }
+}
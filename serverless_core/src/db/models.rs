@@ -1,33 +1,187 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
+/// The `config.json` schema version this server understands.
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
 /// Represents a deployable function.
 ///
 /// # Fields
 /// - `name`: The unique name of the function.
 /// - `runtime`: The runtime environment required by the function (e.g., "go").
 /// - `content`: The zipped binary content of the function.
+/// - `user_uuid`: The namespace the function is deployed to.
+/// - `deployed_by`: The ID of the user deploying it.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeployableFunction {
     pub name: String,
     pub runtime: String,
     pub content: Vec<u8>,
     pub user_uuid: Uuid,
+    pub deployed_by: i32,
 }
 
 /// Represents the configuration for a function.
 ///
 /// This configuration is typically extracted from a JSON file
-/// bundled with the function's package.
+/// bundled with the function's package. Unknown fields are rejected so that
+/// typos don't silently fall back to defaults.
 ///
 /// # Fields
+/// - `schema_version`: Version of this schema; defaults to the current one.
 /// - `function_name`: The name of the function (should correspond to the `Function`'s name).
 /// - `runtime`: The runtime environment for the function.
 /// - `env`: Optional key-value pairs representing environment variables.
+/// - `max_concurrency`: Optional cap on in-flight requests; excess requests are queued or rejected.
+/// - `image`: Optional prebuilt image on the Docker host to deploy instead of building.
+/// - `resources`: Optional memory and CPU limits of the function's containers.
+/// - `routes`: Paths the function serves, relative to its invocation URL;
+///   only `/` is currently supported.
+/// - `triggers`: Events that invoke the function; only `http`, which every
+///   function has through its invocation URL.
+/// - `description`: Optional human-readable summary of the function.
+/// - `labels`: Optional key-value pairs used to organise functions.
+/// - `auth`: Who may invoke the function; anyone by default.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct DeployableFunctionConfig {
-    function_name: String,
-    runtime: String,
+    #[serde(default = "current_schema_version")]
+    pub(crate) schema_version: u32,
+    pub(crate) function_name: String,
+    pub(crate) runtime: String,
     pub(crate) env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub(crate) max_concurrency: Option<u32>,
+    #[serde(default)]
+    pub(crate) image: Option<String>,
+    #[serde(default)]
+    pub(crate) resources: Option<FunctionResources>,
+    #[serde(default)]
+    pub(crate) routes: Vec<String>,
+    #[serde(default)]
+    pub(crate) triggers: Vec<FunctionTrigger>,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) auth: FunctionAuth,
+}
+
+fn current_schema_version() -> u32 {
+    CONFIG_SCHEMA_VERSION
+}
+
+/// Container limits requested by a function.
+///
+/// # Fields
+/// - `memory_mb`: Memory limit in MB.
+/// - `cpus`: CPU limit as a (fractional) number of CPUs.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionResources {
+    pub(crate) memory_mb: Option<u32>,
+    pub(crate) cpus: Option<f64>,
+}
+
+/// An event source that invokes a function.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum FunctionTrigger {
+    /// Requests to the function's invocation URL.
+    Http,
+}
+
+/// Who may invoke a function through its invocation URL.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FunctionAuth {
+    /// Anyone.
+    #[default]
+    Public,
+    /// Callers presenting one of the function's API keys.
+    ApiKey,
+    /// Platform users of the function's organization whose credentials carry
+    /// the `invoke` scope.
+    Platform,
+    /// Callers presenting a JWT signed by an external issuer.
+    ///
+    /// # Fields
+    /// - `issuer`: The expected `iss` claim; its keys are found through OpenID discovery.
+    /// - `audience`: The expected `aud` claim, if any.
+    /// - `jwks_url`: Where to fetch the issuer's keys, when it doesn't support discovery.
+    Jwt {
+        issuer: String,
+        #[serde(default)]
+        audience: Option<String>,
+        #[serde(default)]
+        jwks_url: Option<String>,
+    },
+}
+
+/// A single problem found while validating a function.
+///
+/// # Fields
+/// - `field`: Path of the offending field, e.g. `resources.memory_mb`.
+/// - `message`: What is wrong with it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Filters for listing audit events; every filter that is set must match.
+///
+/// # Fields
+/// - `actor`: The user who acted.
+/// - `action`: An action such as `function.deploy`, or a prefix of one such as `function`.
+/// - `target`: What was acted on, e.g. a function name.
+/// - `outcome`: `success`, `denied` or `failure`.
+/// - `since` / `until`: Bounds on when the events occurred.
+/// - `before`: Only events older than the event with this ID, to page through results.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct AuditFilter {
+    pub actor: Option<Uuid>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub outcome: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub until: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub before: Option<i64>,
+}
+
+/// Filters for listing users as an operator; every filter that is set must match.
+///
+/// # Fields
+/// - `q`: Part of the user's email address, matched case-insensitively.
+/// - `suspended`: Only suspended users, or only active ones.
+/// - `after`: Only users registered after the user with this ID, to page through results.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct UserFilter {
+    pub q: Option<String>,
+    pub suspended: Option<bool>,
+    pub after: Option<i32>,
+}
+
+/// Filters for searching every namespace's functions as an operator.
+///
+/// # Fields
+/// - `q`: Part of the function's name, matched case-insensitively.
+/// - `namespace`: Only functions of this organization.
+/// - `after`: Only functions created after the function with this ID, to page through results.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct FunctionFilter {
+    pub q: Option<String>,
+    pub namespace: Option<Uuid>,
+    pub after: Option<i32>,
 }
@@ -0,0 +1,257 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entities::{
+    auth::Model as AuthUser,
+    organization::{ActiveModel as OrganizationModel, Column, Model},
+    organization_invitation::{
+        ActiveModel as InvitationModel, Column as InvitationColumn, Model as Invitation,
+    },
+    organization_member::{ActiveModel as MemberModel, Column as MemberColumn, Model as Member},
+    prelude::{Auth, Organization, OrganizationInvitation, OrganizationMember},
+};
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbConn, DbErr, EntityTrait,
+    IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+use uuid::Uuid;
+
+pub struct OrganizationDBRepo;
+
+impl OrganizationDBRepo {
+    /// Creates the personal organization of a newly registered user.
+    ///
+    /// The organization reuses the user's UUID, which is the namespace their
+    /// functions are invoked under, and the user becomes its owner.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A database connection or transaction.
+    /// * `user` - The user the organization belongs to.
+    pub async fn create_personal<C: ConnectionTrait>(
+        conn: &C,
+        user: &AuthUser,
+    ) -> Result<Model, DbErr> {
+        let organization = OrganizationModel {
+            uuid: Set(user.uuid),
+            name: Set(user.email.clone()),
+            personal: Set(true),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        MemberModel {
+            organization_id: Set(organization.id),
+            auth_id: Set(user.id),
+            role: Set("owner".to_string()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        Ok(organization)
+    }
+
+    /// Creates an organization owned by `owner_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `name` - The unique name of the organization.
+    /// * `owner_id` - The ID of the user creating it.
+    ///
+    /// # Returns
+    ///
+    /// * The created organization, or an error if the name is taken.
+    pub async fn create_organization(
+        conn: &DbConn,
+        name: &str,
+        owner_id: i32,
+    ) -> Result<Model, DbErr> {
+        let txn = conn.begin().await?;
+        let organization = OrganizationModel {
+            uuid: Set(Uuid::new_v4()),
+            name: Set(name.to_string()),
+            personal: Set(false),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        MemberModel {
+            organization_id: Set(organization.id),
+            auth_id: Set(owner_id),
+            role: Set("owner".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(organization)
+    }
+
+    /// Finds an organization by its ID.
+    pub async fn find_by_id(conn: &DbConn, id: i32) -> Result<Option<Model>, DbErr> {
+        Organization::find_by_id(id).one(conn).await
+    }
+
+    /// Finds an organization by name.
+    pub async fn find_by_name(conn: &DbConn, name: &str) -> Result<Option<Model>, DbErr> {
+        Organization::find()
+            .filter(Column::Name.eq(name))
+            .one(conn)
+            .await
+    }
+
+    /// Finds an organization by its UUID, which is also its namespace.
+    pub async fn find_by_uuid(conn: &DbConn, uuid: Uuid) -> Result<Option<Model>, DbErr> {
+        Organization::find()
+            .filter(Column::Uuid.eq(uuid))
+            .one(conn)
+            .await
+    }
+
+    /// Finds a user's membership of an organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `organization_id` - The ID of the organization.
+    /// * `auth_id` - The ID of the user.
+    pub async fn find_member(
+        conn: &DbConn,
+        organization_id: i32,
+        auth_id: i32,
+    ) -> Result<Option<Member>, DbErr> {
+        OrganizationMember::find()
+            .filter(
+                Condition::all()
+                    .add(MemberColumn::OrganizationId.eq(organization_id))
+                    .add(MemberColumn::AuthId.eq(auth_id)),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Lists the organizations a user belongs to, with their membership.
+    pub async fn list_for_user(
+        conn: &DbConn,
+        auth_id: i32,
+    ) -> Result<Vec<(Member, Option<Model>)>, DbErr> {
+        OrganizationMember::find()
+            .filter(MemberColumn::AuthId.eq(auth_id))
+            .order_by_asc(MemberColumn::CreatedAt)
+            .find_also_related(Organization)
+            .all(conn)
+            .await
+    }
+
+    /// Lists the members of an organization along with their user records.
+    pub async fn list_members(
+        conn: &DbConn,
+        organization_id: i32,
+    ) -> Result<Vec<(Member, Option<AuthUser>)>, DbErr> {
+        OrganizationMember::find()
+            .filter(MemberColumn::OrganizationId.eq(organization_id))
+            .order_by_asc(MemberColumn::CreatedAt)
+            .find_also_related(Auth)
+            .all(conn)
+            .await
+    }
+
+    /// Counts the owners of an organization.
+    pub async fn count_owners(conn: &DbConn, organization_id: i32) -> Result<u64, DbErr> {
+        OrganizationMember::find()
+            .filter(
+                Condition::all()
+                    .add(MemberColumn::OrganizationId.eq(organization_id))
+                    .add(MemberColumn::Role.eq("owner")),
+            )
+            .count(conn)
+            .await
+    }
+
+    /// Changes the role of a member.
+    pub async fn set_role(conn: &DbConn, member: Member, role: &str) -> Result<Member, DbErr> {
+        let mut member = member.into_active_model();
+        member.role = Set(role.to_string());
+        member.update(conn).await
+    }
+
+    /// Removes a member from an organization.
+    pub async fn remove_member(conn: &DbConn, member: Member) -> Result<(), DbErr> {
+        member.into_active_model().delete(conn).await.map(|_| ())
+    }
+
+    /// Stores an invitation to join an organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `invitation` - The invitation; only the organization, email, role, token
+    ///   hash, inviter and expiry are used.
+    pub async fn create_invitation(
+        conn: &DbConn,
+        invitation: Invitation,
+    ) -> Result<Invitation, DbErr> {
+        InvitationModel {
+            organization_id: Set(invitation.organization_id),
+            email: Set(invitation.email),
+            role: Set(invitation.role),
+            token_hash: Set(invitation.token_hash),
+            invited_by: Set(invitation.invited_by),
+            expires_at: Set(invitation.expires_at),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Finds an invitation by the SHA-256 hash of its token.
+    pub async fn find_invitation(
+        conn: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<Invitation>, DbErr> {
+        OrganizationInvitation::find()
+            .filter(InvitationColumn::TokenHash.eq(token_hash))
+            .one(conn)
+            .await
+    }
+
+    /// Marks an invitation as accepted and adds the user to the organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `invitation` - The invitation being accepted.
+    /// * `auth_id` - The ID of the user accepting it.
+    /// * `accepted_at` - When the invitation was accepted.
+    pub async fn accept_invitation(
+        conn: &DbConn,
+        invitation: Invitation,
+        auth_id: i32,
+        accepted_at: DateTime<FixedOffset>,
+    ) -> Result<Member, DbErr> {
+        let txn = conn.begin().await?;
+        let organization_id = invitation.organization_id;
+        let role = invitation.role.clone();
+
+        let mut invitation = invitation.into_active_model();
+        invitation.accepted_at = Set(Some(accepted_at));
+        invitation.update(&txn).await?;
+
+        let member = MemberModel {
+            organization_id: Set(organization_id),
+            auth_id: Set(auth_id),
+            role: Set(role),
+            created_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(member)
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::Utc;
+use db_entities::namespace_quota::{ActiveModel as QuotaModel, Column, Model};
+use db_entities::prelude::NamespaceQuota;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter,
+};
+use uuid::Uuid;
+
+pub struct NamespaceQuotaDBRepo;
+
+impl NamespaceQuotaDBRepo {
+    /// Finds the quota overrides of a namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `namespace` - The UUID of the organization.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Model)` if the namespace has overrides; otherwise, `None`.
+    pub async fn find_quota(conn: &DbConn, namespace: Uuid) -> Result<Option<Model>, DbErr> {
+        NamespaceQuota::find()
+            .filter(Column::Namespace.eq(namespace))
+            .one(conn)
+            .await
+    }
+
+    /// Stores the quota overrides of a namespace, replacing any existing ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `quota` - The overrides; limits left unset fall back to the server defaults.
+    ///   The ID and update time are assigned here.
+    pub async fn upsert_quota(conn: &DbConn, quota: Model) -> Result<Model, DbErr> {
+        match Self::find_quota(conn, quota.namespace).await? {
+            Some(existing) => {
+                let mut existing = existing.into_active_model();
+                existing.max_functions = Set(quota.max_functions);
+                existing.max_instances = Set(quota.max_instances);
+                existing.max_memory_mb = Set(quota.max_memory_mb);
+                existing.builds_per_hour = Set(quota.builds_per_hour);
+                existing.invocations_per_day = Set(quota.invocations_per_day);
+                existing.updated_at = Set(Utc::now().into());
+                existing.update(conn).await
+            }
+            None => {
+                let quota = QuotaModel {
+                    namespace: Set(quota.namespace),
+                    max_functions: Set(quota.max_functions),
+                    max_instances: Set(quota.max_instances),
+                    max_memory_mb: Set(quota.max_memory_mb),
+                    builds_per_hour: Set(quota.builds_per_hour),
+                    invocations_per_day: Set(quota.invocations_per_day),
+                    ..Default::default()
+                };
+                quota.insert(conn).await
+            }
+        }
+    }
+
+    /// Removes the quota overrides of a namespace.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if overrides were removed, `Ok(false)` if the namespace had none.
+    pub async fn delete_quota(conn: &DbConn, namespace: Uuid) -> Result<bool, DbErr> {
+        NamespaceQuota::delete_many()
+            .filter(Column::Namespace.eq(namespace))
+            .exec(conn)
+            .await
+            .map(|result| result.rows_affected > 0)
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::Utc;
+use db_entities::function_secret::{ActiveModel as SecretModel, Column, Model};
+use db_entities::prelude::FunctionSecret;
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder,
+};
+
+pub struct FunctionSecretDBRepo;
+
+impl FunctionSecretDBRepo {
+    /// Lists the encrypted secrets of a function, ordered by key.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    pub async fn list_secrets(conn: &DbConn, function_id: i32) -> Result<Vec<Model>, DbErr> {
+        FunctionSecret::find()
+            .filter(Column::FunctionId.eq(function_id))
+            .order_by_asc(Column::Key)
+            .all(conn)
+            .await
+    }
+
+    /// Stores an encrypted secret, replacing any existing value for the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `key` - The environment variable name.
+    /// * `nonce` - The base64 nonce used to encrypt the value.
+    /// * `ciphertext` - The base64 encrypted value.
+    pub async fn upsert_secret(
+        conn: &DbConn,
+        function_id: i32,
+        key: &str,
+        nonce: String,
+        ciphertext: String,
+    ) -> Result<Model, DbErr> {
+        let existing = FunctionSecret::find()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::Key.eq(key)),
+            )
+            .one(conn)
+            .await?;
+
+        match existing {
+            Some(secret) => {
+                let mut secret = secret.into_active_model();
+                secret.nonce = Set(nonce);
+                secret.ciphertext = Set(ciphertext);
+                secret.updated_at = Set(Utc::now().into());
+                secret.update(conn).await
+            }
+            None => {
+                let secret = SecretModel {
+                    function_id: Set(function_id),
+                    key: Set(key.to_string()),
+                    nonce: Set(nonce),
+                    ciphertext: Set(ciphertext),
+                    ..Default::default()
+                };
+                secret.insert(conn).await
+            }
+        }
+    }
+
+    /// Deletes a secret of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `key` - The environment variable name.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if a secret was deleted, `Ok(false)` if the key did not exist.
+    pub async fn delete_secret(conn: &DbConn, function_id: i32, key: &str) -> Result<bool, DbErr> {
+        let result = FunctionSecret::delete_many()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::Key.eq(key)),
+            )
+            .exec(conn)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
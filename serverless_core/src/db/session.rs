@@ -0,0 +1,205 @@
+use chrono::{DateTime, FixedOffset};
+use db_entities::{
+    auth::Model as AuthUser,
+    auth_session::{ActiveModel as SessionModel, Column, Model},
+    prelude::{Auth, AuthSession, RefreshToken},
+    refresh_token::{ActiveModel as RefreshTokenModel, Column as RefreshColumn, Model as Refresh},
+};
+use db_migrations::Condition;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait,
+    QueryFilter, TransactionTrait,
+};
+use uuid::Uuid;
+
+pub struct SessionDBRepo;
+
+impl SessionDBRepo {
+    /// Starts a session for a user along with its first refresh token.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `auth_id` - The ID of the user logging in.
+    /// * `token_hash` - The hash of the first refresh token.
+    /// * `expires_at` - When the refresh token expires.
+    ///
+    /// # Returns
+    ///
+    /// * The created session, or an error of type `sea_orm::DbErr` if insertion fails.
+    pub async fn create_session(
+        conn: &DbConn,
+        auth_id: i32,
+        token_hash: String,
+        expires_at: DateTime<FixedOffset>,
+    ) -> Result<Model, DbErr> {
+        let txn = conn.begin().await?;
+        let session = SessionModel {
+            uuid: Set(Uuid::new_v4()),
+            auth_id: Set(auth_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        RefreshTokenModel {
+            session_id: Set(session.id),
+            token_hash: Set(token_hash),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok(session)
+    }
+
+    /// Finds a session and its user by the session's UUID.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `uuid` - The session's UUID, as carried by access tokens.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((Model, user))` if the session exists, revoked or not; otherwise, `None`.
+    pub async fn find_session(
+        conn: &DbConn,
+        uuid: Uuid,
+    ) -> Result<Option<(Model, Option<AuthUser>)>, DbErr> {
+        AuthSession::find()
+            .find_also_related(Auth)
+            .filter(Column::Uuid.eq(uuid))
+            .one(conn)
+            .await
+    }
+
+    /// Finds a refresh token and the session it belongs to by the token's hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `token_hash` - The SHA-256 hash of the refresh token.
+    pub async fn find_refresh_token(
+        conn: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<(Refresh, Option<Model>)>, DbErr> {
+        RefreshToken::find()
+            .find_also_related(AuthSession)
+            .filter(RefreshColumn::TokenHash.eq(token_hash))
+            .one(conn)
+            .await
+    }
+
+    /// Rotates a refresh token: marks `used` as used and issues its successor in
+    /// the same session.
+    ///
+    /// The token is only marked if it is still unused, so of two concurrent
+    /// refreshes with the same token only one succeeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `used` - The refresh token being exchanged.
+    /// * `token_hash` - The hash of the new refresh token.
+    /// * `now` - The time of the refresh.
+    /// * `expires_at` - When the new refresh token expires.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the token was rotated, `false` if it had already been used.
+    pub async fn rotate(
+        conn: &DbConn,
+        used: &Refresh,
+        token_hash: String,
+        now: DateTime<FixedOffset>,
+        expires_at: DateTime<FixedOffset>,
+    ) -> Result<bool, DbErr> {
+        let txn = conn.begin().await?;
+        let marked = RefreshToken::update_many()
+            .col_expr(RefreshColumn::UsedAt, Expr::value(now))
+            .filter(
+                Condition::all()
+                    .add(RefreshColumn::Id.eq(used.id))
+                    .add(RefreshColumn::UsedAt.is_null()),
+            )
+            .exec(&txn)
+            .await?;
+        if marked.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        RefreshTokenModel {
+            session_id: Set(used.session_id),
+            token_hash: Set(token_hash),
+            created_at: Set(now),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        AuthSession::update_many()
+            .col_expr(Column::LastRefreshedAt, Expr::value(now))
+            .filter(Column::Id.eq(used.session_id))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    /// Revokes a session, invalidating its access and refresh tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `session_id` - The ID of the session.
+    /// * `now` - The time of the revocation.
+    pub async fn revoke_session(
+        conn: &DbConn,
+        session_id: i32,
+        now: DateTime<FixedOffset>,
+    ) -> Result<u64, DbErr> {
+        AuthSession::update_many()
+            .col_expr(Column::RevokedAt, Expr::value(now))
+            .filter(
+                Condition::all()
+                    .add(Column::Id.eq(session_id))
+                    .add(Column::RevokedAt.is_null()),
+            )
+            .exec(conn)
+            .await
+            .map(|result| result.rows_affected)
+    }
+
+    /// Revokes every active session of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `auth_id` - The ID of the user.
+    /// * `now` - The time of the revocation.
+    ///
+    /// # Returns
+    ///
+    /// * The number of sessions revoked.
+    pub async fn revoke_all_for_user(
+        conn: &DbConn,
+        auth_id: i32,
+        now: DateTime<FixedOffset>,
+    ) -> Result<u64, DbErr> {
+        AuthSession::update_many()
+            .col_expr(Column::RevokedAt, Expr::value(now))
+            .filter(
+                Condition::all()
+                    .add(Column::AuthId.eq(auth_id))
+                    .add(Column::RevokedAt.is_null()),
+            )
+            .exec(conn)
+            .await
+            .map(|result| result.rows_affected)
+    }
+}
@@ -0,0 +1,83 @@
+use redis::{aio::MultiplexedConnection, AsyncCommands, RedisResult};
+use serde::{de::DeserializeOwned, Serialize};
+
+fn state_key(state: &str) -> String {
+    format!("sso:state:{}", state)
+}
+
+fn code_key(code: &str) -> String {
+    format!("sso:code:{}", code)
+}
+
+/// Stores `value` as JSON under `key` for `ttl` seconds.
+async fn put<T: Serialize>(
+    conn: &mut MultiplexedConnection,
+    key: String,
+    value: &T,
+    ttl: u64,
+) -> RedisResult<()> {
+    let json = serde_json::to_string(value).map_err(|e| {
+        redis::RedisError::from((
+            redis::ErrorKind::TypeError,
+            "Failed to serialize SSO entry",
+            e.to_string(),
+        ))
+    })?;
+    conn.set_ex(key, json, ttl).await
+}
+
+/// Removes and returns the JSON value under `key`, so it can be used once.
+async fn take<T: DeserializeOwned>(
+    conn: &mut MultiplexedConnection,
+    key: String,
+) -> RedisResult<Option<T>> {
+    let json: Option<String> = conn.get_del(key).await?;
+    Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+pub struct SsoCacheRepo;
+
+impl SsoCacheRepo {
+    /// Saves a pending sign-in under the `state` sent to the identity provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `state` - The state parameter of the authorization request.
+    /// * `pending` - What the callback needs to finish the sign-in.
+    /// * `ttl` - Seconds the user has to sign in at the provider.
+    pub async fn put_state<T: Serialize>(
+        conn: &mut MultiplexedConnection,
+        state: &str,
+        pending: &T,
+        ttl: u64,
+    ) -> RedisResult<()> {
+        put(conn, state_key(state), pending, ttl).await
+    }
+
+    /// Removes and returns the pending sign-in of a `state`.
+    pub async fn take_state<T: DeserializeOwned>(
+        conn: &mut MultiplexedConnection,
+        state: &str,
+    ) -> RedisResult<Option<T>> {
+        take(conn, state_key(state)).await
+    }
+
+    /// Saves a one-time login code handed to the CLI after a successful sign-in.
+    pub async fn put_login_code<T: Serialize>(
+        conn: &mut MultiplexedConnection,
+        code: &str,
+        login: &T,
+        ttl: u64,
+    ) -> RedisResult<()> {
+        put(conn, code_key(code), login, ttl).await
+    }
+
+    /// Removes and returns the login a one-time code stands for.
+    pub async fn take_login_code<T: DeserializeOwned>(
+        conn: &mut MultiplexedConnection,
+        code: &str,
+    ) -> RedisResult<Option<T>> {
+        take(conn, code_key(code)).await
+    }
+}
@@ -0,0 +1,79 @@
+use redis::{aio::MultiplexedConnection, AsyncCommands, RedisResult};
+use uuid::Uuid;
+
+/// A usage counter kept per namespace over fixed time windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageCounter {
+    Builds,
+    Invocations,
+}
+
+impl UsageCounter {
+    /// The name the counter is stored and reported as.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UsageCounter::Builds => "builds",
+            UsageCounter::Invocations => "invocations",
+        }
+    }
+}
+
+fn counter_key(counter: UsageCounter, namespace: Uuid, window: i64) -> String {
+    format!("usage:{}:{}:{}", counter.as_str(), namespace, window)
+}
+
+pub struct UsageCacheRepo;
+
+impl UsageCacheRepo {
+    /// Counts one use in a namespace's current window.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A mutable reference to the Redis connection.
+    /// * `counter` - What is counted.
+    /// * `namespace` - The UUID of the organization.
+    /// * `window` - Index of the current window, e.g. the hour since the epoch.
+    /// * `ttl` - Seconds the counter is kept, at least the window's length.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u64)` with the number of uses in the window, including this one.
+    pub async fn increment(
+        conn: &mut MultiplexedConnection,
+        counter: UsageCounter,
+        namespace: Uuid,
+        window: i64,
+        ttl: u64,
+    ) -> RedisResult<u64> {
+        let key = counter_key(counter, namespace, window);
+        let (count,): (u64,) = redis::pipe()
+            .incr(&key, 1)
+            .expire(&key, ttl as i64)
+            .ignore()
+            .query_async(conn)
+            .await?;
+        Ok(count)
+    }
+
+    /// Takes back a use counted with [`UsageCacheRepo::increment`].
+    pub async fn decrement(
+        conn: &mut MultiplexedConnection,
+        counter: UsageCounter,
+        namespace: Uuid,
+        window: i64,
+    ) -> RedisResult<()> {
+        conn.decr(counter_key(counter, namespace, window), 1).await
+    }
+
+    /// Returns the number of uses in a namespace's window.
+    pub async fn get(
+        conn: &mut MultiplexedConnection,
+        counter: UsageCounter,
+        namespace: Uuid,
+        window: i64,
+    ) -> RedisResult<u64> {
+        conn.get::<_, Option<u64>>(counter_key(counter, namespace, window))
+            .await
+            .map(|count| count.unwrap_or(0))
+    }
+}
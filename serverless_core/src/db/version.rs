@@ -0,0 +1,147 @@
+use db_entities::function_version::{ActiveModel as VersionModel, Column, Model};
+use db_entities::prelude::FunctionVersion;
+use db_migrations::Condition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+pub struct FunctionVersionDBRepo;
+
+impl FunctionVersionDBRepo {
+    /// Finds the most recently deployed version of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Model))` with the latest version, or `Ok(None)` if the function has none.
+    pub async fn find_latest(conn: &DbConn, function_id: i32) -> Result<Option<Model>, DbErr> {
+        FunctionVersion::find()
+            .filter(Column::FunctionId.eq(function_id))
+            .order_by_desc(Column::Version)
+            .one(conn)
+            .await
+    }
+
+    /// Finds a specific version of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `version` - The version number.
+    pub async fn find_version(
+        conn: &DbConn,
+        function_id: i32,
+        version: i32,
+    ) -> Result<Option<Model>, DbErr> {
+        FunctionVersion::find()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::Version.eq(version)),
+            )
+            .one(conn)
+            .await
+    }
+
+    /// Finds the newest version of a function older than `version`.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    /// * `version` - The version to look before.
+    pub async fn find_previous(
+        conn: &DbConn,
+        function_id: i32,
+        version: i32,
+    ) -> Result<Option<Model>, DbErr> {
+        FunctionVersion::find()
+            .filter(
+                Condition::all()
+                    .add(Column::FunctionId.eq(function_id))
+                    .add(Column::Version.lt(version)),
+            )
+            .order_by_desc(Column::Version)
+            .one(conn)
+            .await
+    }
+
+    /// Checks whether any version of any function was built from an artifact.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `artifact_digest` - SHA-256 digest of the artifact.
+    pub async fn artifact_in_use(conn: &DbConn, artifact_digest: &str) -> Result<bool, DbErr> {
+        Ok(FunctionVersion::find()
+            .filter(Column::ArtifactDigest.eq(artifact_digest))
+            .one(conn)
+            .await?
+            .is_some())
+    }
+
+    /// Lists all versions of a function, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_id` - The ID of the function.
+    pub async fn list_versions(conn: &DbConn, function_id: i32) -> Result<Vec<Model>, DbErr> {
+        FunctionVersion::find()
+            .filter(Column::FunctionId.eq(function_id))
+            .order_by_desc(Column::Version)
+            .all(conn)
+            .await
+    }
+
+    /// Lists all versions of several functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `function_ids` - The IDs of the functions.
+    pub async fn list_versions_of(
+        conn: &DbConn,
+        function_ids: Vec<i32>,
+    ) -> Result<Vec<Model>, DbErr> {
+        if function_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        FunctionVersion::find()
+            .filter(Column::FunctionId.is_in(function_ids))
+            .all(conn)
+            .await
+    }
+
+    /// Records a newly built version of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - A reference to the database connection.
+    /// * `version` - The version to insert; its number must not already exist for the
+    ///   function. The ID and creation time are assigned by the database.
+    ///
+    /// # Returns
+    ///
+    /// * The created version, or an error if the version already exists.
+    pub async fn create_version(conn: &DbConn, version: Model) -> Result<Model, DbErr> {
+        let version_model = VersionModel {
+            function_id: Set(version.function_id),
+            version: Set(version.version),
+            image_tag: Set(version.image_tag),
+            artifact_digest: Set(version.artifact_digest),
+            build_digest: Set(version.build_digest),
+            memory_mb: Set(version.memory_mb),
+            cpu_millis: Set(version.cpu_millis),
+            ..Default::default()
+        };
+
+        version_model.insert(conn).await
+    }
+}
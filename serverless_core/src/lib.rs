@@ -1,5 +1,7 @@
 mod api_controller;
+mod artifacts;
 mod db;
 mod lifecycle_manager;
+mod mailer;
 mod utils;
-pub use api_controller::start_server;
+pub use api_controller::{set_platform_role, start_server, unlock_login};
@@ -1,3 +1,22 @@
+pub(crate) mod account_emails;
+pub(crate) mod admin;
+pub(crate) mod api_keys;
+pub(crate) mod audit;
+pub(crate) mod concurrency;
+pub(crate) mod delete;
 pub(crate) mod deploy;
-mod error;
+pub(crate) mod environment;
+pub(crate) mod error;
+pub(crate) mod invocation_auth;
 pub(crate) mod invoke;
+pub(crate) mod login_guard;
+pub(crate) mod organizations;
+pub(crate) mod quotas;
+pub(crate) mod rollout;
+pub(crate) mod secrets;
+pub(crate) mod sessions;
+pub(crate) mod sso;
+pub(crate) mod status;
+pub(crate) mod tokens;
+pub(crate) mod validation;
+pub(crate) mod versioning;
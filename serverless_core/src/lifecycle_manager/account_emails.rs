@@ -0,0 +1,315 @@
+use chrono::Utc;
+use db_entities::auth::Model as AuthUser;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::auth::AuthDBRepo;
+use crate::db::session::SessionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::login_guard::AUDIT_TARGET;
+use crate::mailer::{Email, Mailer};
+use crate::utils::jwt_keys::{JwtKeys, TokenUse};
+use crate::utils::passwords::PasswordPolicy;
+
+/// How long an email verification link is valid for, in seconds.
+const VERIFY_EMAIL_VALIDITY_SECS: u64 = 24 * 60 * 60;
+
+/// How long a password reset token is valid for, in seconds.
+const RESET_PASSWORD_VALIDITY_SECS: u64 = 60 * 60;
+
+/// What an emailed token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Purpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl Purpose {
+    /// The use tokens for this purpose are issued for.
+    fn token_use(self) -> TokenUse {
+        match self {
+            Purpose::VerifyEmail => TokenUse::VerifyEmail,
+            Purpose::ResetPassword => TokenUse::ResetPassword,
+        }
+    }
+}
+
+/// Claims of a verification or reset token.
+///
+/// Tokens are signed with the platform's JWT keys under the `typ` and `aud` of
+/// their purpose, so they are refused as access tokens. They carry a
+/// fingerprint of the state they change, so each one stops working once used.
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountTokenClaims {
+    sub: String,
+    purpose: Purpose,
+    /// Fingerprint of the user's email (verification) or password hash (reset)
+    fgp: String,
+    exp: u64,
+    iat: u64,
+}
+
+fn fingerprint(user: &AuthUser, purpose: Purpose) -> String {
+    let state = match purpose {
+        Purpose::VerifyEmail => &user.email,
+        Purpose::ResetPassword => &user.password,
+    };
+    hex::encode(&Sha256::digest(state.as_bytes())[..8])
+}
+
+fn invalid_token() -> ServelessCoreError {
+    ServelessCoreError::BadRequest("Invalid or expired token".to_string())
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Signs a token letting the holder act on `user` for `purpose`.
+fn issue_token(keys: &JwtKeys, user: &AuthUser, purpose: Purpose) -> ServelessCoreResult<String> {
+    let now = Utc::now().timestamp() as u64;
+    let validity = match purpose {
+        Purpose::VerifyEmail => VERIFY_EMAIL_VALIDITY_SECS,
+        Purpose::ResetPassword => RESET_PASSWORD_VALIDITY_SECS,
+    };
+    keys.issue(
+        purpose.token_use(),
+        &AccountTokenClaims {
+            sub: user.uuid.to_string(),
+            purpose,
+            fgp: fingerprint(user, purpose),
+            exp: now + validity,
+            iat: now,
+        },
+    )
+    .map_err(|e| ServelessCoreError::SystemError(format!("Failed to sign token: {e}")))
+}
+
+/// Verifies a token's signature, expiry and purpose, returning the user it names.
+fn read_token(
+    keys: &JwtKeys,
+    token: &str,
+    purpose: Purpose,
+) -> ServelessCoreResult<(Uuid, String)> {
+    let claims = keys
+        .verify::<AccountTokenClaims>(purpose.token_use(), token)
+        .map_err(|_| invalid_token())?;
+    if claims.purpose != purpose {
+        return Err(invalid_token());
+    }
+    let user_uuid = Uuid::parse_str(&claims.sub).map_err(|_| invalid_token())?;
+    Ok((user_uuid, claims.fgp))
+}
+
+/// Loads the user a token names, checking the token still matches their state.
+async fn token_user(
+    conn: &DatabaseConnection,
+    keys: &JwtKeys,
+    token: &str,
+    purpose: Purpose,
+) -> ServelessCoreResult<AuthUser> {
+    let (user_uuid, fgp) = read_token(keys, token, purpose)?;
+    let user = AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(invalid_token)?;
+    if !still_usable(&user, purpose, &fgp) {
+        return Err(invalid_token());
+    }
+    Ok(user)
+}
+
+/// Whether a token with fingerprint `fgp` can still act on `user`: the state it
+/// was issued for is unchanged, and a verification link is only good until the
+/// address is verified.
+fn still_usable(user: &AuthUser, purpose: Purpose, fgp: &str) -> bool {
+    let pending = purpose != Purpose::VerifyEmail || user.email_verified_at.is_none();
+    pending && fingerprint(user, purpose) == fgp
+}
+
+/// Emails `user` a link to verify their email address.
+pub async fn send_verification(
+    mailer: &dyn Mailer,
+    keys: &JwtKeys,
+    public_url: &str,
+    user: &AuthUser,
+) -> ServelessCoreResult<()> {
+    let token = issue_token(keys, user, Purpose::VerifyEmail)?;
+    let email = Email {
+        to: user.email.clone(),
+        subject: "Verify your Invok email address".to_string(),
+        body: format!(
+            "Confirm your email address by opening this link within 24 hours:\n\n\
+             {public_url}/auth/verify?token={token}\n\n\
+             Or run:\n\n    invok verify {token}\n\n\
+             If you didn't create an Invok account, ignore this email.\n"
+        ),
+    };
+    mailer
+        .send(&email)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(format!("Failed to send email: {e}")))
+}
+
+/// Marks the email address a verification token was sent to as verified.
+///
+/// The link works once: after the address is verified, its token is refused.
+pub async fn verify_email(
+    conn: &DatabaseConnection,
+    keys: &JwtKeys,
+    token: &str,
+) -> ServelessCoreResult<AuthUser> {
+    let user = token_user(conn, keys, token, Purpose::VerifyEmail).await?;
+    let user = AuthDBRepo::mark_email_verified(conn, user, Utc::now().fixed_offset())
+        .await
+        .map_err(db_error)?;
+    info!("Verified email address of user {}", user.uuid);
+    Ok(user)
+}
+
+/// Emails a password reset token to the account registered with `email`.
+///
+/// Unknown addresses are ignored without an error, so the endpoint doesn't
+/// reveal which addresses are registered.
+pub async fn request_password_reset(
+    conn: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    keys: &JwtKeys,
+    email: &str,
+) -> ServelessCoreResult<()> {
+    let Some(user) = AuthDBRepo::find_by_email(conn, email.trim())
+        .await
+        .map_err(db_error)?
+    else {
+        info!("Password reset requested for an unknown email address");
+        return Ok(());
+    };
+    let token = issue_token(keys, &user, Purpose::ResetPassword)?;
+    let email = Email {
+        to: user.email.clone(),
+        subject: "Reset your Invok password".to_string(),
+        body: format!(
+            "A password reset was requested for your Invok account. Within an hour, run:\n\n\
+             \x20   invok password reset {token}\n\n\
+             If you didn't ask for this, ignore this email; your password is unchanged.\n"
+        ),
+    };
+    mailer
+        .send(&email)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(format!("Failed to send email: {e}")))?;
+    info!(
+        target: AUDIT_TARGET,
+        "password.reset_requested user={}", user.uuid
+    );
+    Ok(())
+}
+
+/// Sets a new password with a reset token.
+///
+/// The token stops working once the password changes, every session of the
+/// user is signed out, and the email address counts as verified since the
+/// token was delivered to it.
+pub async fn reset_password(
+    conn: &DatabaseConnection,
+    keys: &JwtKeys,
+    passwords: &PasswordPolicy,
+    token: &str,
+    password: &str,
+) -> ServelessCoreResult<AuthUser> {
+    let user = token_user(conn, keys, token, Purpose::ResetPassword).await?;
+    passwords
+        .check(password, &user.email)
+        .map_err(ServelessCoreError::BadRequest)?;
+    let hash = passwords
+        .hash(password)
+        .map_err(|e| ServelessCoreError::SystemError(format!("Failed to hash password: {e}")))?;
+
+    let now = Utc::now().fixed_offset();
+    let mut user = AuthDBRepo::update_password(conn, user, hash)
+        .await
+        .map_err(db_error)?;
+    if user.email_verified_at.is_none() {
+        user = AuthDBRepo::mark_email_verified(conn, user, now)
+            .await
+            .map_err(db_error)?;
+    }
+    let revoked = SessionDBRepo::revoke_all_for_user(conn, user.id, now)
+        .await
+        .map_err(db_error)?;
+    warn!(
+        target: AUDIT_TARGET,
+        "password.reset user={} revoked_sessions={}", user.uuid, revoked
+    );
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> AuthUser {
+        AuthUser {
+            id: 1,
+            email: "me@example.com".to_string(),
+            password: "$argon2id$old".to_string(),
+            uuid: Uuid::new_v4(),
+            email_verified_at: None,
+            role: "user".to_string(),
+            suspended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_tokens_only_serve_their_purpose() {
+        let keys = JwtKeys::ephemeral();
+        let user = user();
+        let token = issue_token(&keys, &user, Purpose::VerifyEmail).unwrap();
+
+        let (user_uuid, fgp) = read_token(&keys, &token, Purpose::VerifyEmail).unwrap();
+        assert_eq!(user_uuid, user.uuid);
+        assert_eq!(fgp, fingerprint(&user, Purpose::VerifyEmail));
+        assert!(read_token(&keys, &token, Purpose::ResetPassword).is_err());
+        assert!(read_token(&keys, "not-a-token", Purpose::VerifyEmail).is_err());
+    }
+
+    #[test]
+    fn test_reset_tokens_stop_working_once_the_password_changes() {
+        let keys = JwtKeys::ephemeral();
+        let mut user = user();
+        let token = issue_token(&keys, &user, Purpose::ResetPassword).unwrap();
+        let (_, fgp) = read_token(&keys, &token, Purpose::ResetPassword).unwrap();
+        assert_eq!(fgp, fingerprint(&user, Purpose::ResetPassword));
+
+        assert!(still_usable(&user, Purpose::ResetPassword, &fgp));
+        user.password = "$argon2id$new".to_string();
+        assert!(!still_usable(&user, Purpose::ResetPassword, &fgp));
+    }
+
+    #[test]
+    fn test_verification_links_stop_working_once_used() {
+        let keys = JwtKeys::ephemeral();
+        let mut user = user();
+        let token = issue_token(&keys, &user, Purpose::VerifyEmail).unwrap();
+        let (_, fgp) = read_token(&keys, &token, Purpose::VerifyEmail).unwrap();
+        assert!(still_usable(&user, Purpose::VerifyEmail, &fgp));
+
+        user.email_verified_at = Some(Utc::now().fixed_offset());
+        assert!(!still_usable(&user, Purpose::VerifyEmail, &fgp));
+    }
+
+    #[test]
+    fn test_emailed_tokens_are_not_access_tokens() {
+        let keys = JwtKeys::ephemeral();
+        let user = user();
+        for purpose in [Purpose::VerifyEmail, Purpose::ResetPassword] {
+            let token = issue_token(&keys, &user, purpose).unwrap();
+            let access = keys.verify::<serde_json::Value>(TokenUse::Access, &token);
+            assert!(access.is_err());
+        }
+    }
+}
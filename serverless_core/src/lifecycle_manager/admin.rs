@@ -0,0 +1,483 @@
+use chrono::Utc;
+use db_entities::auth::Model as AuthUser;
+use db_entities::function::Model as FunctionModel;
+use redis::aio::MultiplexedConnection;
+use runtime::core::runner::{host_capacity, stop_container};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db::auth::{AuthDBRepo, PLATFORM_USER_ROLE};
+use crate::db::cache::FunctionCacheRepo;
+use crate::db::function::FunctionDBRepo;
+use crate::db::models::{FunctionFilter, UserFilter};
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::concurrency::{ConcurrencyManager, NodeLoad};
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::invoke::instance_key;
+use crate::utils::utils::container_name_from_addr;
+
+/// Users or functions returned by a listing unless the caller asks for fewer or more.
+const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// Most users or functions a single listing returns.
+const MAX_PAGE_SIZE: u64 = 1000;
+
+/// Seconds Docker waits for a force-stopped container before killing it.
+const FORCE_STOP_GRACE_PERIOD_SECS: i64 = 0;
+
+/// A user's role on the platform itself, apart from their roles in organizations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformRole {
+    /// Uses the platform through their organizations.
+    User,
+    /// Operates the platform: manages every user and function.
+    Admin,
+}
+
+impl PlatformRole {
+    /// The name the role is stored and shown as.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlatformRole::User => PLATFORM_USER_ROLE,
+            PlatformRole::Admin => "admin",
+        }
+    }
+
+    /// The platform role of a user; unknown stored roles grant nothing.
+    pub fn of(user: &AuthUser) -> Self {
+        user.role.parse().unwrap_or(PlatformRole::User)
+    }
+}
+
+impl FromStr for PlatformRole {
+    type Err = ServelessCoreError;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            PLATFORM_USER_ROLE => Ok(PlatformRole::User),
+            "admin" => Ok(PlatformRole::Admin),
+            _ => Err(ServelessCoreError::BadRequest(format!(
+                "Unknown platform role '{role}'; expected admin or user"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PlatformRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A user, as listed to operators.
+#[derive(Debug, Serialize)]
+pub struct UserSummary {
+    pub uuid: Uuid,
+    pub email: String,
+    pub role: PlatformRole,
+    pub email_verified_at: Option<String>,
+    pub suspended_at: Option<String>,
+}
+
+impl From<AuthUser> for UserSummary {
+    fn from(user: AuthUser) -> Self {
+        UserSummary {
+            role: PlatformRole::of(&user),
+            uuid: user.uuid,
+            email: user.email,
+            email_verified_at: user.email_verified_at.map(|at| at.to_rfc3339()),
+            suspended_at: user.suspended_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// A function of any namespace, as listed to operators.
+#[derive(Debug, Serialize)]
+pub struct FunctionSummary {
+    pub uuid: Uuid,
+    pub namespace: Uuid,
+    pub name: String,
+    pub runtime: String,
+    pub last_deployed_at: Option<String>,
+}
+
+impl From<FunctionModel> for FunctionSummary {
+    fn from(function: FunctionModel) -> Self {
+        FunctionSummary {
+            uuid: function.uuid,
+            namespace: function.namespace,
+            name: function.name,
+            runtime: function.runtime,
+            last_deployed_at: function.last_deployed_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// A page of users, with the `after` ID of the next page if there may be one.
+#[derive(Debug, Serialize)]
+pub struct UserPage {
+    pub users: Vec<UserSummary>,
+    pub next_after: Option<i32>,
+}
+
+/// A page of functions, with the `after` ID of the next page if there may be one.
+#[derive(Debug, Serialize)]
+pub struct FunctionPage {
+    pub functions: Vec<FunctionSummary>,
+    pub next_after: Option<i32>,
+}
+
+/// A user and the functions of their personal organization.
+#[derive(Debug, Serialize)]
+pub struct UserDetails {
+    #[serde(flatten)]
+    pub user: UserSummary,
+    pub functions: Vec<FunctionSummary>,
+}
+
+/// Instances of a function stopped by an operator.
+///
+/// # Fields
+/// - `function`, `namespace`: The function whose instances were stopped.
+/// - `instances_stopped`: Containers that were stopped.
+/// - `failures`: Instances that could not be stopped, with the reason.
+#[derive(Debug, Serialize)]
+pub struct StopReport {
+    pub function: String,
+    pub namespace: Uuid,
+    pub instances_stopped: Vec<String>,
+    pub failures: Vec<String>,
+}
+
+/// Resources of this node's Docker host and the invocations it is handling.
+///
+/// # Fields
+/// - `host`: Name of the Docker host.
+/// - `cpus`, `memory_mb`: Resources available to containers.
+/// - `containers_running`: Containers running on the host.
+/// - `load`: Requests in flight and queued on this node.
+#[derive(Debug, Serialize)]
+pub struct NodeCapacity {
+    pub host: Option<String>,
+    pub cpus: Option<i64>,
+    pub memory_mb: Option<i64>,
+    pub containers_running: Option<i64>,
+    pub load: NodeLoad,
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+fn page_size(limit: Option<u64>) -> u64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// The cursor of the page after one of `len` rows ending at `last_id`; a short
+/// page is the last one.
+fn next_cursor(len: usize, limit: u64, last_id: Option<i32>) -> Option<i32> {
+    if (len as u64) < limit {
+        None
+    } else {
+        last_id
+    }
+}
+
+async fn find_user(conn: &DatabaseConnection, user_uuid: Uuid) -> ServelessCoreResult<AuthUser> {
+    AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{user_uuid}' not found")))
+}
+
+/// Checks that a user isn't suspended, before they sign in or use a credential.
+pub fn ensure_active(user: &AuthUser) -> ServelessCoreResult<()> {
+    match user.suspended_at {
+        Some(_) => Err(ServelessCoreError::Forbidden(format!(
+            "Account {} is suspended",
+            user.email
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a function may be invoked.
+///
+/// Functions aren't served while the user who last deployed them is suspended,
+/// whichever organization they belong to, nor while the user whose personal
+/// organization they belong to is. Another member redeploying a team
+/// organization's function makes it available again.
+pub async fn ensure_function_active(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+) -> ServelessCoreResult<()> {
+    // A personal organization's UUID is its user's.
+    let suspended = AuthDBRepo::find_suspended(conn, function.auth_id, function.namespace)
+        .await
+        .map_err(db_error)?;
+    match suspended {
+        Some(_) => Err(ServelessCoreError::Forbidden(format!(
+            "'{}' is unavailable while its owner or last deployer is suspended",
+            function.name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Lists users, oldest first.
+///
+/// # Arguments
+///
+/// * `filter` - The filters the users must match
+/// * `limit` - The most users to return, up to 1000
+pub async fn list_users(
+    conn: &DatabaseConnection,
+    filter: &UserFilter,
+    limit: Option<u64>,
+) -> ServelessCoreResult<UserPage> {
+    let limit = page_size(limit);
+    let users = AuthDBRepo::search(conn, filter, limit)
+        .await
+        .map_err(db_error)?;
+    let next_after = next_cursor(users.len(), limit, users.last().map(|user| user.id));
+    Ok(UserPage {
+        users: users.into_iter().map(UserSummary::from).collect(),
+        next_after,
+    })
+}
+
+/// Describes a user along with the functions of their personal organization.
+pub async fn user_details(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<UserDetails> {
+    let user = find_user(conn, user_uuid).await?;
+    let functions = FunctionDBRepo::find_functions_by_user_uuid(conn, user.uuid)
+        .await
+        .map_err(db_error)?;
+    Ok(UserDetails {
+        user: user.into(),
+        functions: functions.into_iter().map(FunctionSummary::from).collect(),
+    })
+}
+
+/// Searches the functions of every namespace, oldest first.
+///
+/// # Arguments
+///
+/// * `filter` - The filters the functions must match
+/// * `limit` - The most functions to return, up to 1000
+pub async fn search_functions(
+    conn: &DatabaseConnection,
+    filter: &FunctionFilter,
+    limit: Option<u64>,
+) -> ServelessCoreResult<FunctionPage> {
+    let limit = page_size(limit);
+    let functions = FunctionDBRepo::search_functions(conn, filter, limit)
+        .await
+        .map_err(db_error)?;
+    let next_after = next_cursor(
+        functions.len(),
+        limit,
+        functions.last().map(|function| function.id),
+    );
+    Ok(FunctionPage {
+        functions: functions.into_iter().map(FunctionSummary::from).collect(),
+        next_after,
+    })
+}
+
+/// Suspends a user, or lifts their suspension.
+///
+/// Suspended users can't log in, refresh or start sessions, and their existing
+/// sessions and access tokens are refused. Functions they last deployed, and
+/// the functions of their personal organization, return `403`.
+///
+/// # Arguments
+///
+/// * `operator` - The admin making the change, who can't suspend themselves
+/// * `user_uuid` - The user to suspend or reinstate
+/// * `suspended` - Whether the user is suspended afterwards
+pub async fn set_suspended(
+    conn: &DatabaseConnection,
+    operator: Uuid,
+    user_uuid: Uuid,
+    suspended: bool,
+) -> ServelessCoreResult<UserSummary> {
+    if suspended && operator == user_uuid {
+        return Err(ServelessCoreError::BadRequest(
+            "Admins can't suspend themselves".to_string(),
+        ));
+    }
+    let user = find_user(conn, user_uuid).await?;
+    if user.suspended_at.is_some() == suspended {
+        return Ok(user.into());
+    }
+    let suspended_at = suspended.then(|| Utc::now().fixed_offset());
+    let user = AuthDBRepo::set_suspended(conn, user, suspended_at)
+        .await
+        .map_err(db_error)?;
+    info!("User '{}' suspended: {}", user.uuid, suspended);
+    Ok(user.into())
+}
+
+/// Changes a user's platform role.
+///
+/// # Arguments
+///
+/// * `operator` - The admin making the change, or `None` when run from the
+///   server's command line; admins can't demote themselves
+/// * `user` - The user whose role changes
+/// * `role` - The new role
+pub async fn set_role(
+    conn: &DatabaseConnection,
+    operator: Option<Uuid>,
+    user: AuthUser,
+    role: PlatformRole,
+) -> ServelessCoreResult<UserSummary> {
+    if operator == Some(user.uuid) && role != PlatformRole::Admin {
+        return Err(ServelessCoreError::BadRequest(
+            "Admins can't demote themselves".to_string(),
+        ));
+    }
+    let user = AuthDBRepo::set_role(conn, user, role.as_str())
+        .await
+        .map_err(db_error)?;
+    info!("User '{}' now has platform role {}", user.uuid, role);
+    Ok(user.into())
+}
+
+/// Changes the platform role of the user with the given UUID.
+pub async fn set_role_of(
+    conn: &DatabaseConnection,
+    operator: Uuid,
+    user_uuid: Uuid,
+    role: PlatformRole,
+) -> ServelessCoreResult<UserSummary> {
+    let user = find_user(conn, user_uuid).await?;
+    set_role(conn, Some(operator), user, role).await
+}
+
+/// Changes the platform role of the user with the given email address.
+pub async fn set_role_by_email(
+    conn: &DatabaseConnection,
+    email: &str,
+    role: PlatformRole,
+) -> ServelessCoreResult<UserSummary> {
+    let user = AuthDBRepo::find_by_email(conn, email)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{email}' not found")))?;
+    set_role(conn, None, user, role).await
+}
+
+/// Stops every running instance of a function at once, without draining.
+///
+/// The instances are taken out of the cache first, so the next invocation
+/// starts a fresh container.
+pub async fn stop_instances(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    function_uuid: Uuid,
+) -> ServelessCoreResult<StopReport> {
+    let function = FunctionDBRepo::find_any_function_by_uuid(conn, function_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            ServelessCoreError::FunctionNotRegistered(format!("Function '{function_uuid}'"))
+        })?;
+    let versions = FunctionVersionDBRepo::list_versions(conn, function.id)
+        .await
+        .map_err(db_error)?;
+
+    let mut report = StopReport {
+        function: function.name.clone(),
+        namespace: function.namespace,
+        instances_stopped: Vec::new(),
+        failures: Vec::new(),
+    };
+    for version in versions {
+        let key = instance_key(&function.name, function.namespace, version.version);
+        match FunctionCacheRepo::remove_function(cache_conn, &key).await {
+            Ok(Some(addr)) => {
+                let container_name = container_name_from_addr(&addr);
+                match stop_container(container_name, FORCE_STOP_GRACE_PERIOD_SECS).await {
+                    Ok(()) => report.instances_stopped.push(container_name.to_string()),
+                    Err(e) => report
+                        .failures
+                        .push(format!("instance {container_name}: {e}")),
+                }
+            }
+            Ok(None) => {}
+            Err(e) => report.failures.push(format!("cache entry {key}: {e}")),
+        }
+    }
+    info!(
+        "Force-stopped {} instances of function '{}'",
+        report.instances_stopped.len(),
+        function.name
+    );
+    Ok(report)
+}
+
+/// Reads the capacity of this node's Docker host and the load on this node.
+pub async fn node_capacity(concurrency: &ConcurrencyManager) -> ServelessCoreResult<NodeCapacity> {
+    let host = host_capacity()
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    Ok(NodeCapacity {
+        host: host.name,
+        cpus: host.cpus,
+        memory_mb: host.memory_bytes.map(|bytes| bytes / (1024 * 1024)),
+        containers_running: host.containers_running,
+        load: concurrency.load(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_platform_roles() {
+        assert_eq!(
+            "admin".parse::<PlatformRole>().unwrap(),
+            PlatformRole::Admin
+        );
+        assert_eq!("user".parse::<PlatformRole>().unwrap(), PlatformRole::User);
+        assert!("owner".parse::<PlatformRole>().is_err());
+    }
+
+    #[test]
+    fn test_suspended_users_are_rejected() {
+        let mut user = AuthUser {
+            id: 1,
+            email: "me@example.com".to_string(),
+            password: "$argon2id$hash".to_string(),
+            uuid: Uuid::new_v4(),
+            email_verified_at: None,
+            role: "admin".to_string(),
+            suspended_at: None,
+        };
+        assert_eq!(PlatformRole::of(&user), PlatformRole::Admin);
+        assert!(ensure_active(&user).is_ok());
+
+        user.suspended_at = Some(Utc::now().fixed_offset());
+        assert!(matches!(
+            ensure_active(&user),
+            Err(ServelessCoreError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_only_full_pages_have_a_next_cursor() {
+        assert_eq!(next_cursor(100, 100, Some(7)), Some(7));
+        assert_eq!(next_cursor(99, 100, Some(7)), None);
+        assert_eq!(next_cursor(0, 100, None), None);
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entities::function::Model as Function;
+use db_entities::function_api_key::Model as ApiKeyModel;
+use rand_core::{OsRng, RngCore};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::api_key::ApiKeyDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::tokens::hash_token;
+
+/// Prefix of every function API key, which tells them apart from platform credentials.
+pub const API_KEY_PREFIX: &str = "invok_key_";
+
+/// How many characters of a key are kept in clear to help recognise it.
+const DISPLAY_PREFIX_LENGTH: usize = API_KEY_PREFIX.len() + 8;
+
+/// The longest name a key can have.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// How often the last use of a key or access token is written back, so busy
+/// credentials don't cost a database write on every request.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// A newly created key. The secret is only ever returned here.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub uuid: Uuid,
+    pub name: String,
+    pub key: String,
+}
+
+/// A key as shown when listing, identified by its ID and clear prefix.
+#[derive(Debug, Serialize)]
+pub struct ApiKeySummary {
+    pub uuid: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Whether a credential last recorded as used at `last_used_at` should be
+/// touched again at `now`.
+pub(crate) fn needs_touch(
+    last_used_at: Option<DateTime<FixedOffset>>,
+    now: DateTime<FixedOffset>,
+) -> bool {
+    last_used_at.is_none_or(|at| now - at >= Duration::seconds(TOUCH_INTERVAL_SECS))
+}
+
+fn summarize(key: ApiKeyModel) -> ApiKeySummary {
+    ApiKeySummary {
+        uuid: key.uuid,
+        name: key.name,
+        prefix: key.prefix,
+        created_at: key.created_at.to_rfc3339(),
+        last_used_at: key.last_used_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+/// Authenticates an invocation carrying an API key of `function`.
+///
+/// # Returns
+///
+/// The key, or `None` when the function has no such key or it was revoked.
+pub async fn authenticate(
+    conn: &DatabaseConnection,
+    function: &Function,
+    key: &str,
+) -> ServelessCoreResult<Option<ApiKeySummary>> {
+    let Some(key) = ApiKeyDBRepo::find_active_by_hash(conn, function.id, &hash_token(key))
+        .await
+        .map_err(db_error)?
+    else {
+        return Ok(None);
+    };
+
+    // Usage tracking is best effort and must not fail the invocation.
+    if !needs_touch(key.last_used_at, Utc::now().fixed_offset()) {
+        return Ok(Some(summarize(key)));
+    }
+    let key = match ApiKeyDBRepo::touch(conn, key.clone()).await {
+        Ok(key) => key,
+        Err(e) => {
+            warn!("Failed to record API key use: {}", e);
+            key
+        }
+    };
+    Ok(Some(summarize(key)))
+}
+
+/// Creates an API key for invoking `function`.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `function` - The function the key invokes.
+/// * `name` - A name to recognise the key by.
+pub async fn create_key(
+    conn: &DatabaseConnection,
+    function: &Function,
+    name: &str,
+) -> ServelessCoreResult<CreatedApiKey> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(ServelessCoreError::BadRequest(format!(
+            "Key name must be between 1 and {MAX_NAME_LENGTH} characters"
+        )));
+    }
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let secret = format!("{API_KEY_PREFIX}{}", hex::encode(bytes));
+
+    let key = ApiKeyDBRepo::create_key(
+        conn,
+        ApiKeyModel {
+            id: 0,
+            uuid: Uuid::new_v4(),
+            function_id: function.id,
+            name: name.to_string(),
+            prefix: secret[..DISPLAY_PREFIX_LENGTH].to_string(),
+            key_hash: hash_token(&secret),
+            created_at: Utc::now().fixed_offset(),
+            last_used_at: None,
+            revoked_at: None,
+        },
+    )
+    .await
+    .map_err(db_error)?;
+
+    info!("Created API key '{}' of '{}'", key.name, function.name);
+    Ok(CreatedApiKey {
+        uuid: key.uuid,
+        name: key.name,
+        key: secret,
+    })
+}
+
+/// Lists a function's keys that haven't been revoked.
+pub async fn list_keys(
+    conn: &DatabaseConnection,
+    function: &Function,
+) -> ServelessCoreResult<Vec<ApiKeySummary>> {
+    Ok(ApiKeyDBRepo::list_for_function(conn, function.id)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(summarize)
+        .collect())
+}
+
+/// Revokes one of a function's keys.
+pub async fn revoke_key(
+    conn: &DatabaseConnection,
+    function: &Function,
+    key_uuid: Uuid,
+) -> ServelessCoreResult<ApiKeySummary> {
+    let not_found = || ServelessCoreError::NotFound(format!("API key '{key_uuid}' not found"));
+    let key = ApiKeyDBRepo::find_for_function(conn, key_uuid, function.id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+    if key.revoked_at.is_some() {
+        return Err(not_found());
+    }
+
+    let key = ApiKeyDBRepo::revoke(conn, key).await.map_err(db_error)?;
+    info!("Revoked API key '{}' of '{}'", key.name, function.name);
+    Ok(summarize(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_use_is_recorded_at_most_once_a_minute() {
+        let now = Utc::now().fixed_offset();
+        assert!(needs_touch(None, now));
+        assert!(!needs_touch(Some(now - Duration::seconds(59)), now));
+        assert!(needs_touch(Some(now - Duration::seconds(60)), now));
+    }
+}
@@ -0,0 +1,329 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use db_entities::audit_event::Model as AuditEventModel;
+use futures_util::stream::{self, Stream};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use serde_json::Value;
+use std::net::IpAddr;
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::audit::AuditDBRepo;
+use crate::db::models::AuditFilter;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// Events returned by a listing unless the caller asks for fewer or more.
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// Most events a single listing returns.
+pub const MAX_PAGE_SIZE: u64 = 1000;
+
+/// How often events past their retention are deleted.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How an audited action ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The action was carried out.
+    Success,
+    /// The caller's credentials or permissions didn't allow it.
+    Denied,
+    /// The request was invalid or the action failed.
+    Failure,
+}
+
+impl Outcome {
+    /// The name the outcome is stored and filtered by.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Denied => "denied",
+            Outcome::Failure => "failure",
+        }
+    }
+
+    /// The outcome a response reports through its status.
+    pub fn from_status(status: StatusCode) -> Self {
+        if status.is_success() || status.is_redirection() {
+            Outcome::Success
+        } else if matches!(
+            status,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+        ) {
+            Outcome::Denied
+        } else {
+            Outcome::Failure
+        }
+    }
+}
+
+/// Where an audited request came from.
+///
+/// # Fields
+/// - `ip`: The client's address.
+/// - `request_id`: The `X-Request-Id` of the request, to match events with logs.
+#[derive(Debug, Clone)]
+pub struct RequestOrigin {
+    pub ip: IpAddr,
+    pub request_id: Option<String>,
+}
+
+/// A management action to record in the audit log.
+///
+/// # Fields
+/// - `action`: What was done, as `<area>.<verb>`, e.g. `function.deploy`.
+/// - `actor`: The user who did it, once known.
+/// - `namespace`: The organization the action belongs to. Account actions belong
+///   to the user's personal organization, so its owner sees them.
+/// - `target`: What was acted on, e.g. a function name or an email address.
+/// - `detail`: Anything else worth keeping, such as the names of changed secrets.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub action: &'static str,
+    pub actor: Option<Uuid>,
+    pub namespace: Option<Uuid>,
+    pub target: Option<String>,
+    pub detail: Option<Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            actor: None,
+            namespace: None,
+            target: None,
+            detail: None,
+        }
+    }
+
+    /// Sets the user who acted, for actions that belong to no organization.
+    pub fn actor(mut self, actor: Uuid) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Sets the user who acted, and the organization the action belongs to.
+    pub fn by(mut self, actor: Uuid, namespace: Uuid) -> Self {
+        self.actor = Some(actor);
+        self.namespace = Some(namespace);
+        self
+    }
+
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+}
+
+/// An event of the audit log, as listed and exported.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub occurred_at: String,
+    pub actor: Option<Uuid>,
+    pub namespace: Option<Uuid>,
+    pub action: String,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    pub outcome: String,
+    pub detail: Option<Value>,
+}
+
+impl From<AuditEventModel> for AuditRecord {
+    fn from(event: AuditEventModel) -> Self {
+        AuditRecord {
+            id: event.id,
+            occurred_at: event.occurred_at.to_rfc3339(),
+            actor: event.actor_uuid,
+            namespace: event.namespace,
+            action: event.action,
+            target: event.target,
+            ip: event.ip,
+            request_id: event.request_id,
+            outcome: event.outcome,
+            detail: event.detail,
+        }
+    }
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Appends an event to the audit log.
+///
+/// The action has already happened by now, so a failure to record it is
+/// logged rather than failing the request.
+pub async fn record(
+    conn: &DatabaseConnection,
+    origin: &RequestOrigin,
+    event: AuditEvent,
+    outcome: Outcome,
+) {
+    let action = event.action;
+    let model = AuditEventModel {
+        id: 0,
+        occurred_at: Utc::now().fixed_offset(),
+        actor_uuid: event.actor,
+        namespace: event.namespace,
+        action: action.to_string(),
+        target: event.target,
+        ip: Some(origin.ip.to_string()),
+        request_id: origin.request_id.clone(),
+        outcome: outcome.as_str().to_string(),
+        detail: event.detail,
+    };
+    if let Err(e) = AuditDBRepo::insert(conn, model).await {
+        error!("Failed to record audit event {}: {}", action, e);
+    }
+}
+
+/// Checks a filter's values before it is used.
+fn check_filter(filter: &AuditFilter) -> ServelessCoreResult<()> {
+    if let Some(outcome) = &filter.outcome {
+        let known = [Outcome::Success, Outcome::Denied, Outcome::Failure]
+            .iter()
+            .any(|known| known.as_str() == outcome);
+        if !known {
+            return Err(ServelessCoreError::BadRequest(format!(
+                "Unknown outcome '{outcome}'; expected success, denied or failure"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Lists audit events, newest first.
+///
+/// # Arguments
+///
+/// * `namespace` - Only events of this organization; every event when `None`
+/// * `filter` - The filters the events must match
+/// * `limit` - The most events to return, up to [`MAX_PAGE_SIZE`]
+pub async fn list_events(
+    conn: &DatabaseConnection,
+    namespace: Option<Uuid>,
+    filter: &AuditFilter,
+    limit: Option<u64>,
+) -> ServelessCoreResult<Vec<AuditRecord>> {
+    check_filter(filter)?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let events = AuditDBRepo::find(conn, namespace, filter, limit)
+        .await
+        .map_err(db_error)?;
+    Ok(events.into_iter().map(AuditRecord::from).collect())
+}
+
+/// Exports every audit event matching a filter as newline-delimited JSON,
+/// newest first.
+///
+/// Events are read a page at a time, so large exports aren't held in memory.
+/// Each item of the stream is one page of lines.
+pub fn export_events(
+    conn: DatabaseConnection,
+    namespace: Option<Uuid>,
+    filter: AuditFilter,
+) -> ServelessCoreResult<impl Stream<Item = ServelessCoreResult<String>>> {
+    check_filter(&filter)?;
+    Ok(stream::try_unfold(
+        (conn, Some(filter)),
+        move |(conn, filter)| async move {
+            let Some(mut filter) = filter else {
+                return Ok(None);
+            };
+            let events = AuditDBRepo::find(&conn, namespace, &filter, MAX_PAGE_SIZE)
+                .await
+                .map_err(db_error)?;
+            if events.is_empty() {
+                return Ok(None);
+            }
+            let mut lines = String::new();
+            for event in &events {
+                let line = serde_json::to_string(&AuditRecord::from(event.clone()))
+                    .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+            // A short page is the last one.
+            let next = if (events.len() as u64) < MAX_PAGE_SIZE {
+                None
+            } else {
+                filter.before = events.last().map(|event| event.id);
+                Some(filter)
+            };
+            Ok(Some((lines, (conn, next))))
+        },
+    ))
+}
+
+/// Deletes the events older than the retention period.
+///
+/// # Returns
+///
+/// The number of events deleted.
+pub async fn purge_expired(
+    conn: &DatabaseConnection,
+    retention_days: u64,
+) -> ServelessCoreResult<u64> {
+    let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+    AuditDBRepo::delete_before(conn, cutoff.fixed_offset())
+        .await
+        .map_err(db_error)
+}
+
+/// Deletes expired events every hour, for as long as the server runs.
+pub async fn purge_periodically(conn: DatabaseConnection, retention_days: u64) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match purge_expired(&conn, retention_days).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("Deleted {} expired audit events", deleted),
+            Err(e) => error!("Failed to delete expired audit events: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcomes_follow_the_response_status() {
+        assert_eq!(Outcome::from_status(StatusCode::CREATED), Outcome::Success);
+        assert_eq!(Outcome::from_status(StatusCode::FOUND), Outcome::Success);
+        assert_eq!(Outcome::from_status(StatusCode::FORBIDDEN), Outcome::Denied);
+        assert_eq!(
+            Outcome::from_status(StatusCode::TOO_MANY_REQUESTS),
+            Outcome::Denied
+        );
+        assert_eq!(
+            Outcome::from_status(StatusCode::BAD_REQUEST),
+            Outcome::Failure
+        );
+        assert_eq!(
+            Outcome::from_status(StatusCode::INTERNAL_SERVER_ERROR),
+            Outcome::Failure
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_outcome_filters() {
+        let filter = |outcome: &str| AuditFilter {
+            outcome: Some(outcome.to_string()),
+            ..Default::default()
+        };
+        assert!(check_filter(&filter("denied")).is_ok());
+        assert!(check_filter(&filter("succeeded")).is_err());
+    }
+}
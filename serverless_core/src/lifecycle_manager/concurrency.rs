@@ -0,0 +1,332 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::time::timeout;
+use tracing::warn;
+
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// Limits applied to a single function's invocations on this node.
+///
+/// # Fields
+/// - `max_concurrency`: Maximum number of in-flight requests forwarded to the function.
+/// - `queue_size`: Maximum number of requests allowed to wait for a slot or a cold start.
+/// - `wait_timeout`: How long a queued request waits before being rejected.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimits {
+    pub max_concurrency: usize,
+    pub queue_size: usize,
+    pub wait_timeout: Duration,
+}
+
+/// Invocations this node is handling, across all functions.
+///
+/// # Fields
+/// - `functions`: Functions with requests in flight or queued.
+/// - `in_flight`: Requests currently forwarded to a function.
+/// - `queued`: Requests waiting for a slot or a cold start.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NodeLoad {
+    pub functions: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+/// Per-function bookkeeping for in-flight and queued requests.
+struct FunctionSlot {
+    max_concurrency: usize,
+    permits: Arc<Semaphore>,
+    queued: AtomicUsize,
+    ready: Notify,
+}
+
+impl FunctionSlot {
+    fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency,
+            permits: Arc::new(Semaphore::new(max_concurrency)),
+            queued: AtomicUsize::new(0),
+            ready: Notify::new(),
+        }
+    }
+
+    /// Reserves a place in the function's wait queue, failing if the queue is full.
+    fn enqueue(self: &Arc<Self>, queue_size: usize) -> bool {
+        self.queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < queue_size).then_some(queued + 1)
+            })
+            .is_ok()
+    }
+
+    /// Whether no request is in flight or queued for the function.
+    fn is_idle(&self) -> bool {
+        self.permits.available_permits() == self.max_concurrency
+            && self.queued.load(Ordering::SeqCst) == 0
+    }
+}
+
+/// Holds a place in a function's wait queue and gives it back when dropped.
+pub struct QueueGuard {
+    slot: Option<Arc<FunctionSlot>>,
+    manager: ConcurrencyManager,
+    function_key: String,
+}
+
+impl QueueGuard {
+    /// Waits until the instance being started is announced as ready, or until `poll`
+    /// elapses so the caller can re-check the shared cache (another node may have
+    /// started the instance).
+    pub async fn wait_ready(&self, poll: Duration) {
+        if let Some(slot) = &self.slot {
+            let _ = timeout(poll, slot.ready.notified()).await;
+        }
+    }
+}
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.manager.prune(&self.function_key);
+    }
+}
+
+/// Holds one of a function's invocation slots and gives it back when dropped.
+pub struct InvocationPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    manager: ConcurrencyManager,
+    function_key: String,
+}
+
+impl Drop for InvocationPermit {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        self.manager.prune(&self.function_key);
+    }
+}
+
+/// Tracks in-flight invocations and cold-start waiters per function on this node.
+///
+/// Cloning is cheap; all clones share the same state.
+#[derive(Clone, Default)]
+pub struct ConcurrencyManager {
+    functions: Arc<Mutex<HashMap<String, Arc<FunctionSlot>>>>,
+}
+
+impl ConcurrencyManager {
+    /// Returns the slot for `function_key`, replacing it if the configured
+    /// concurrency has changed since it was created.
+    fn slot(&self, function_key: &str, max_concurrency: usize) -> Arc<FunctionSlot> {
+        let mut functions = self.functions.lock().unwrap();
+        match functions.get(function_key) {
+            Some(slot) if slot.max_concurrency == max_concurrency => slot.clone(),
+            _ => {
+                let slot = Arc::new(FunctionSlot::new(max_concurrency));
+                functions.insert(function_key.to_string(), slot.clone());
+                slot
+            }
+        }
+    }
+
+    /// Forgets the slot of `function_key` once nothing uses it, so functions that
+    /// are no longer invoked don't accumulate.
+    ///
+    /// A slot still referenced elsewhere, e.g. by a request about to wait on it,
+    /// is kept.
+    fn prune(&self, function_key: &str) {
+        let mut functions = self.functions.lock().unwrap();
+        if let Some(slot) = functions.get(function_key) {
+            if Arc::strong_count(slot) == 1 && slot.is_idle() {
+                functions.remove(function_key);
+            }
+        }
+    }
+
+    /// Acquires an invocation slot for a function.
+    ///
+    /// If all `max_concurrency` slots are busy the request is queued, up to
+    /// `queue_size` waiters, for at most `wait_timeout`. The returned permit must be
+    /// held for the duration of the downstream request.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(InvocationPermit)` once a slot is available.
+    /// * `Err(ServelessCoreError::Overloaded)` if the queue is full or the wait timed out.
+    pub async fn acquire(
+        &self,
+        function_key: &str,
+        limits: &ConcurrencyLimits,
+    ) -> ServelessCoreResult<InvocationPermit> {
+        let permit = |permit| InvocationPermit {
+            permit: Some(permit),
+            manager: self.clone(),
+            function_key: function_key.to_string(),
+        };
+        let slot = self.slot(function_key, limits.max_concurrency);
+        if let Ok(acquired) = slot.permits.clone().try_acquire_owned() {
+            return Ok(permit(acquired));
+        }
+
+        let permits = slot.permits.clone();
+        let guard = self
+            .queue(slot, function_key, limits.queue_size)
+            .ok_or_else(|| {
+                warn!("Request queue full for function '{}'", function_key);
+                ServelessCoreError::Overloaded(function_key.to_string())
+            })?;
+        // Take the slot before leaving the queue, so it isn't pruned in between.
+        let acquired = timeout(limits.wait_timeout, permits.acquire_owned())
+            .await
+            .map(|acquired| acquired.map(permit));
+        drop(guard);
+
+        match acquired {
+            Ok(Ok(permit)) => Ok(permit),
+            _ => {
+                warn!(
+                    "Timed out waiting for a slot on function '{}'",
+                    function_key
+                );
+                Err(ServelessCoreError::Overloaded(function_key.to_string()))
+            }
+        }
+    }
+
+    /// Joins the queue of requests waiting for a function's cold start to finish.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(QueueGuard)` to wait on; the queue place is released when it is dropped.
+    /// * `Err(ServelessCoreError::Overloaded)` if too many requests are already waiting.
+    pub fn wait_for_start(
+        &self,
+        function_key: &str,
+        limits: &ConcurrencyLimits,
+    ) -> ServelessCoreResult<QueueGuard> {
+        let slot = self.slot(function_key, limits.max_concurrency);
+        self.queue(slot, function_key, limits.queue_size)
+            .ok_or_else(|| {
+                warn!("Cold start queue full for function '{}'", function_key);
+                ServelessCoreError::Overloaded(function_key.to_string())
+            })
+    }
+
+    /// Reserves a place in the wait queue of `slot`.
+    fn queue(
+        &self,
+        slot: Arc<FunctionSlot>,
+        function_key: &str,
+        queue_size: usize,
+    ) -> Option<QueueGuard> {
+        slot.enqueue(queue_size).then(|| QueueGuard {
+            slot: Some(slot),
+            manager: self.clone(),
+            function_key: function_key.to_string(),
+        })
+    }
+
+    /// Sums the in-flight and queued requests of every function on this node.
+    pub fn load(&self) -> NodeLoad {
+        let functions = self.functions.lock().unwrap();
+        functions
+            .values()
+            .fold(NodeLoad::default(), |mut load, slot| {
+                load.functions += 1;
+                load.in_flight += slot.max_concurrency - slot.permits.available_permits();
+                load.queued += slot.queued.load(Ordering::SeqCst);
+                load
+            })
+    }
+
+    /// Wakes every request waiting on the cold start of `function_key`.
+    pub fn notify_ready(&self, function_key: &str) {
+        if let Some(slot) = self.functions.lock().unwrap().get(function_key) {
+            slot.ready.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrency: usize, queue_size: usize) -> ConcurrencyLimits {
+        ConcurrencyLimits {
+            max_concurrency,
+            queue_size,
+            wait_timeout: Duration::from_millis(50),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acquire_rejects_when_queue_is_full() {
+        let manager = ConcurrencyManager::default();
+        let limits = limits(1, 0);
+
+        let _permit = manager.acquire("fn", &limits).await.unwrap();
+        let result = manager.acquire("fn", &limits).await;
+        assert!(matches!(result, Err(ServelessCoreError::Overloaded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_queues_until_permit_is_released() {
+        let manager = ConcurrencyManager::default();
+        let limits = limits(1, 1);
+
+        let permit = manager.acquire("fn", &limits).await.unwrap();
+        let waiter = {
+            let manager = manager.clone();
+            let limits = limits.clone();
+            tokio::spawn(async move { manager.acquire("fn", &limits).await.is_ok() })
+        };
+        drop(permit);
+        assert!(waiter.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cold_start_queue_is_bounded_and_released() {
+        let manager = ConcurrencyManager::default();
+        let limits = limits(1, 1);
+
+        let guard = manager.wait_for_start("fn", &limits).unwrap();
+        assert!(manager.wait_for_start("fn", &limits).is_err());
+        drop(guard);
+        assert!(manager.wait_for_start("fn", &limits).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_load_counts_in_flight_and_queued_requests() {
+        let manager = ConcurrencyManager::default();
+        let limits = limits(2, 1);
+
+        let _permit = manager.acquire("fn", &limits).await.unwrap();
+        let _other = manager.acquire("other", &limits).await.unwrap();
+        let _guard = manager.wait_for_start("fn", &limits).unwrap();
+        assert_eq!(
+            manager.load(),
+            NodeLoad {
+                functions: 2,
+                in_flight: 2,
+                queued: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idle_functions_are_pruned() {
+        let manager = ConcurrencyManager::default();
+        let limits = limits(1, 1);
+
+        let permit = manager.acquire("fn", &limits).await.unwrap();
+        let guard = manager.wait_for_start("fn", &limits).unwrap();
+        drop(permit);
+        assert_eq!(manager.load().functions, 1);
+        drop(guard);
+        assert_eq!(manager.load(), NodeLoad::default());
+    }
+}
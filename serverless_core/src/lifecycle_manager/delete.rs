@@ -0,0 +1,201 @@
+use db_entities::function_version::Model as VersionModel;
+use redis::aio::MultiplexedConnection;
+use runtime::core::provisioning::remove_image;
+use runtime::core::runner::stop_container;
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::artifacts::ArtifactStore;
+use crate::db::cache::FunctionCacheRepo;
+use crate::db::function::FunctionDBRepo;
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::invoke::{check_function_status, function_key, instance_key};
+use crate::utils::utils::container_name_from_addr;
+
+/// Seconds Docker waits for a deleted function's container to exit before killing it.
+const STOP_GRACE_PERIOD_SECS: i64 = 5;
+
+/// Summary of the resources removed when a function is deleted.
+///
+/// # Fields
+/// - `function`: The name of the deleted function.
+/// - `database_record`: Whether the function, its versions and aliases were removed.
+/// - `cache_entries`: Cache keys of running instances that were removed.
+/// - `instances_stopped`: Containers that were stopped.
+/// - `images_removed`: Docker image tags that were removed.
+/// - `artifacts_removed`: Digests of stored archives no other version uses anymore.
+/// - `failures`: Resources that could not be cleaned, with the reason.
+#[derive(Debug, Default, Serialize)]
+pub struct CleanupReport {
+    pub function: String,
+    pub database_record: bool,
+    pub cache_entries: Vec<String>,
+    pub instances_stopped: Vec<String>,
+    pub images_removed: Vec<String>,
+    pub artifacts_removed: Vec<String>,
+    pub failures: Vec<String>,
+}
+
+/// Deletes a function and every resource associated with it.
+///
+/// The database record is removed first so the function immediately stops being
+/// routable. Running instances of every version are then taken out of the cache
+/// and stopped, and the Docker images of all versions are removed, along with the
+/// stored archives no remaining version was built from. Failures after
+/// the database record is gone are collected in the report rather than aborting,
+/// so one missing image doesn't leave the remaining resources behind.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `cache_conn` - A mutable reference to the Redis connection.
+/// * `artifacts` - The store uploaded archives are kept in.
+/// * `name` - The ID or name of the function to delete.
+/// * `user_uuid` - The UUID of the user (namespace) who owns the function.
+///
+/// # Returns
+///
+/// A `CleanupReport` listing what was removed.
+pub async fn delete_function(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    artifacts: &dyn ArtifactStore,
+    name: &str,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<CleanupReport> {
+    let function = check_function_status(conn, name, user_uuid).await?;
+    let name = function.name.clone();
+    let versions = FunctionVersionDBRepo::list_versions(conn, function.id)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    FunctionDBRepo::delete_function(conn, function)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete function '{}': {}", name, e);
+            ServelessCoreError::SystemError(format!("Failed to delete function: {e}"))
+        })?;
+
+    let mut report = CleanupReport {
+        function: name.to_string(),
+        database_record: true,
+        ..Default::default()
+    };
+
+    let digests = stored_digests(&versions);
+
+    for version in versions {
+        let key = instance_key(&name, user_uuid, version.version);
+        match FunctionCacheRepo::remove_function(cache_conn, &key).await {
+            Ok(Some(addr)) => {
+                report.cache_entries.push(key);
+                let container_name = container_name_from_addr(&addr);
+                match stop_container(container_name, STOP_GRACE_PERIOD_SECS).await {
+                    Ok(()) => report.instances_stopped.push(container_name.to_string()),
+                    Err(e) => report
+                        .failures
+                        .push(format!("instance {container_name}: {e}")),
+                }
+            }
+            Ok(None) => {}
+            Err(e) => report.failures.push(format!("cache entry {key}: {e}")),
+        }
+
+        match remove_image(&version.image_tag).await {
+            Ok(()) => report.images_removed.push(version.image_tag),
+            Err(e) => report
+                .failures
+                .push(format!("image {}: {}", version.image_tag, e)),
+        }
+    }
+
+    let key = function_key(&name, user_uuid);
+    if let Err(e) = FunctionCacheRepo::clear_activity(cache_conn, &key).await {
+        report.failures.push(format!("activity of {key}: {e}"));
+    }
+
+    for digest in digests {
+        match FunctionVersionDBRepo::artifact_in_use(conn, &digest).await {
+            Ok(true) => {}
+            Ok(false) => match artifacts.delete(&digest).await {
+                Ok(()) => report.artifacts_removed.push(digest),
+                Err(e) => report.failures.push(format!("artifact {digest}: {e}")),
+            },
+            Err(e) => report.failures.push(format!("artifact {digest}: {e}")),
+        }
+    }
+
+    if report.failures.is_empty() {
+        info!("Function '{}' deleted with all resources", name);
+    } else {
+        warn!(
+            "Function '{}' deleted, but some resources were not cleaned: {:?}",
+            name, report.failures
+        );
+    }
+    Ok(report)
+}
+
+/// Lists the digests of the archives the given versions were built from, once each.
+///
+/// Identical archives share one stored artifact, possibly across functions.
+/// Versions uploaded before archives were kept have no digest and are skipped.
+fn stored_digests(versions: &[VersionModel]) -> Vec<String> {
+    let mut digests: Vec<String> = versions
+        .iter()
+        .filter_map(|version| version.artifact_digest.clone())
+        .collect();
+    digests.sort();
+    digests.dedup();
+    digests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: i32, artifact_digest: Option<&str>) -> VersionModel {
+        VersionModel {
+            id: version,
+            function_id: 1,
+            version,
+            image_tag: format!("hello:v{version}"),
+            created_at: chrono::Utc::now().into(),
+            artifact_digest: artifact_digest.map(str::to_string),
+            build_digest: None,
+            memory_mb: None,
+            cpu_millis: None,
+        }
+    }
+
+    #[test]
+    fn test_stored_digests_lists_each_archive_once() {
+        let versions = vec![
+            version(1, Some("bbb")),
+            version(2, None),
+            version(3, Some("aaa")),
+            version(4, Some("bbb")),
+        ];
+        assert_eq!(stored_digests(&versions), vec!["aaa", "bbb"]);
+    }
+
+    #[test]
+    fn test_stored_digests_skips_versions_without_archives() {
+        assert!(stored_digests(&[version(1, None)]).is_empty());
+        assert!(stored_digests(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_every_version_has_its_own_instance_key() {
+        let user_uuid = Uuid::new_v4();
+        let keys: Vec<String> = [1, 2, 3]
+            .iter()
+            .map(|v| instance_key("hello", user_uuid, *v))
+            .collect();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
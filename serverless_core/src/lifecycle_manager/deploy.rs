@@ -1,54 +1,217 @@
 use db_entities::function::Model as FunctionModel;
-use runtime::core::provisioning::provisioning;
-use sea_orm::DatabaseConnection;
+use db_entities::function_version::Model as VersionModel;
+use futures_util::stream::{self, StreamExt};
+use redis::aio::MultiplexedConnection;
+use runtime::core::provisioning::{image_digest, provisioning, tag_image};
+use sea_orm::{DatabaseConnection, SqlErr};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use shared_utils::template::{DOCKERFILE_TEMPLATE, MAIN_TEMPLATE};
-use shared_utils::{extract_zip_from_cursor, find_file_in_path, to_camel_case_handler};
-use std::collections::HashMap;
+use shared_utils::{
+    extract_archive, find_file_in_path, to_camel_case_handler, ExtractError, ExtractLimits,
+};
 use std::fs;
-use std::io::{Cursor, Write};
-use std::path::PathBuf;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{error, info};
+use uuid::Uuid;
 
+use crate::artifacts::{sha256_digest, store_artifact, ArtifactStore};
+use crate::db::alias::FunctionAliasDBRepo;
+use crate::db::cache::{FunctionCacheRepo, FunctionLock};
 use crate::db::function::FunctionDBRepo;
-use crate::db::models::{DeployableFunction, DeployableFunctionConfig};
+use crate::db::models::{DeployableFunction, DeployableFunctionConfig, FieldError};
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::environment::{
+    container_env, deploy_env, env_to_json, ignored_env_warning,
+};
 use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
-use crate::utils::utils::{create_fn_files_base, envs_to_string, generate_hash};
+use crate::lifecycle_manager::invoke::{
+    cold_start, function_key, holding_lock, instance_key, InstanceContext, InstanceSpec,
+};
+use crate::lifecycle_manager::quotas::{
+    check_function_count, check_instance_capacity, check_version_memory, count_build, Quota,
+};
+use crate::lifecycle_manager::rollout::{
+    discard_instance, discards_warm_instance, drain_instance, retired_version,
+};
+use crate::lifecycle_manager::secrets::decrypt_secrets;
+use crate::lifecycle_manager::validation::{parse_config, validate_function_name};
+use crate::lifecycle_manager::versioning::image_tag;
+use crate::utils::dockerfile::validate_dockerfile;
+use crate::utils::secrets::SecretCipher;
+use crate::utils::utils::create_fn_files_base;
+
+/// How long a deploy lock outlives its holder; it is renewed while the deploy runs.
+const DEPLOY_LOCK_TTL: Duration = Duration::from_secs(30);
+
+/// Resources a deploy needs to move traffic onto the new version.
+///
+/// # Fields
+/// - `cache_conn`: Redis connection holding the running instances.
+/// - `docker_compose_network_host`: Docker network new instances are attached to.
+/// - `drain_timeout`: How long replaced instances may finish in-flight requests.
+/// - `secret_cipher`: Cipher used to decrypt secrets for the new instance.
+#[derive(Clone)]
+pub struct RolloutContext {
+    pub cache_conn: MultiplexedConnection,
+    pub docker_compose_network_host: String,
+    pub drain_timeout: Duration,
+    pub secret_cipher: SecretCipher,
+}
+
+/// Server policy applied when unpacking and building an uploaded function.
+///
+/// # Fields
+/// - `disallowed_instructions`: Upper-case Dockerfile instructions that are rejected.
+/// - `allowed_image_prefixes`: Prefixes a prebuilt image reference must start with;
+///   when empty, prebuilt images are not accepted.
+/// - `extract_limits`: Size, entry count and compression ratio limits for the archive.
+#[derive(Debug, Clone, Default)]
+pub struct BuildPolicy {
+    pub disallowed_instructions: Vec<String>,
+    pub allowed_image_prefixes: Vec<String>,
+    pub extract_limits: ExtractLimits,
+}
+
+/// Result of deploying one function of a multi-function deploy.
+///
+/// # Fields
+/// - `function`: The name of the function.
+/// - `success`: Whether the function was deployed (or was already up to date).
+/// - `message`: The deploy's success message, or why it failed.
+/// - `errors`: The offending fields when the function failed validation.
+#[derive(Debug, Serialize)]
+pub struct DeployOutcome {
+    pub function: String,
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// How the image of a function version is produced.
+enum BuildSource {
+    /// The runtime's Dockerfile template wraps the function handler.
+    Template,
+    /// The archive ships its own Dockerfile with the given contents.
+    Dockerfile(String),
+    /// An image that already exists on the Docker host.
+    Image(String),
+}
+
+/// Computes the digest identifying everything that goes into a version's image:
+/// the archive, the runtime and its templates, and the build policy.
+///
+/// Two deploys with the same build digest produce the same image, so the second
+/// one can reuse the first's version instead of building again. The policy is
+/// included so that tightening it forces existing archives to be re-validated.
+///
+/// # Arguments
+///
+/// * `artifact_digest` - SHA-256 digest of the uploaded archive.
+/// * `runtime` - The runtime used by the function.
+/// * `policy` - The server's build policy.
+pub fn build_digest(artifact_digest: &str, runtime: &str, policy: &BuildPolicy) -> String {
+    let limits = &policy.extract_limits;
+    let parts = [
+        artifact_digest.to_string(),
+        runtime.to_string(),
+        DOCKERFILE_TEMPLATE.to_string(),
+        MAIN_TEMPLATE.to_string(),
+        policy.disallowed_instructions.join(","),
+        policy.allowed_image_prefixes.join(","),
+        format!(
+            "{}/{}/{}",
+            limits.max_total_bytes, limits.max_entries, limits.max_compression_ratio
+        ),
+    ];
+
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Length-prefix every part so adjacent parts can't be shifted into each other.
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
 
 /// Creates a function file structure and extracts its configuration.
 ///
 /// This function performs the following steps:
 /// 1. Creates a temporary directory for the function based on its name.
-/// 2. Creates the base function file (using a main template) and writes it to disk.
-/// 3. Extracts the provided ZIP content into the temporary directory.
-/// 4. Searches for and parses a `config.json` file within the extracted files.
+/// 2. Extracts the provided archive into the temporary directory.
+/// 3. Searches for and parses a `config.json` file within the extracted files.
+/// 4. Determines how the image is built: from a prebuilt `image` in the config,
+///    from a `Dockerfile` at the root of the archive, or from the runtime template,
+///    in which case the base function file is written (unless the archive has one).
 ///
 /// # Arguments
 ///
 /// * `name` - The name of the function.
 /// * `runtime` - The runtime used by the function (e.g. "go").
-/// * `function_content` - The zipped function content.
+/// * `function_content` - The archived function content.
+/// * `limits` - Limits enforced while extracting the archive.
 ///
 /// # Returns
 ///
 /// A tuple containing:
-/// - An optional map of environment variables extracted from the configuration.
+/// - The parsed function configuration.
 /// - The path to the function files.
+/// - How the function image is built.
 async fn create_function(
     name: &str,
     runtime: &str,
     function_content: Vec<u8>,
-) -> ServelessCoreResult<(Option<HashMap<String, String>>, PathBuf)> {
+    limits: &ExtractLimits,
+) -> ServelessCoreResult<(DeployableFunctionConfig, PathBuf, BuildSource)> {
     // Create a temporary directory for this function.
     let temp_dir = tempfile::tempdir()
         .map_err(|e| ServelessCoreError::SystemError(format!("Failed to create temp dir: {e}")))?
         .into_path()
         .join(name);
+    fs::create_dir(&temp_dir).map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    // Extract the function archive (zip, tar.gz or tar.zst) from memory.
+    let format = extract_archive(function_content, &temp_dir, limits).map_err(extract_error)?;
+    info!("Extracted {} archive of function '{}'", format, name);
+
+    // Locate and read the configuration file.
+    let config_file = find_file_in_path("config.json", &temp_dir).ok_or_else(|| {
+        ServelessCoreError::InvalidFunction(vec![FieldError::new(
+            "config",
+            "config.json is missing from the archive",
+        )])
+    })?;
+    let config_content = fs::read_to_string(config_file)
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    let config = parse_config(&config_content, name)?;
+
+    let dockerfile_path = temp_dir.join("Dockerfile");
+    let source = if let Some(image) = &config.image {
+        BuildSource::Image(image.clone())
+    } else if dockerfile_path.is_file() {
+        let dockerfile = fs::read_to_string(&dockerfile_path)
+            .map_err(|e| ServelessCoreError::BadFunction(format!("Unreadable Dockerfile: {e}")))?;
+        BuildSource::Dockerfile(dockerfile)
+    } else {
+        if !temp_dir.join("main.go").exists() {
+            write_main_file(&temp_dir, name, runtime)?;
+        }
+        BuildSource::Template
+    };
+
+    Ok((config, temp_dir, source))
+}
 
+/// Writes the base function file (e.g., main.go) from the runtime's main template.
+fn write_main_file(path: &Path, name: &str, runtime: &str) -> ServelessCoreResult<()> {
     // Convert function name into a CamelCase handler name.
     let handler_name = to_camel_case_handler(name);
 
-    // Create the base function file (e.g., main.go) using the provided template.
-    let file = create_fn_files_base(&temp_dir, name, runtime)
+    let file = create_fn_files_base(path, runtime)
         .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
     let mut file_writer = std::io::BufWriter::new(file);
     file_writer
@@ -61,36 +224,23 @@ async fn create_function(
         .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
     file_writer
         .flush()
-        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
-
-    // Extract the function ZIP content from an in-memory buffer.
-    let buffer = Cursor::new(function_content);
-    extract_zip_from_cursor(buffer, &temp_dir)
-        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
-
-    // Locate and read the configuration file.
-    let config_file = find_file_in_path("config.json", &temp_dir).ok_or(
-        ServelessCoreError::BadFunction("Function does not include config file".to_string()),
-    )?;
-    let config_content = fs::read_to_string(config_file)
-        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
-    let mut config: DeployableFunctionConfig = serde_json::from_str(&config_content)
-        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
-
-    Ok((config.env.take(), temp_dir))
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))
 }
 
-/// Provisions a Docker container for the function using the provided configuration.
+/// Provisions the Docker image for a function version.
 ///
-/// This function generates a Dockerfile by replacing placeholders in the template
-/// with the function's name and its environment variables, and then calls the provisioning
-/// routine to build the Docker image.
+/// Template functions get a Dockerfile generated from the runtime template with the
+/// function's name. Functions with their own Dockerfile are checked against the
+/// server's build policy before being built, and prebuilt images are tagged under
+/// the version's name if the policy allows their reference. Environment variables
+/// are not part of the image; they are applied when containers are created.
 ///
 /// # Arguments
 ///
 /// * `path` - The file path to the function files.
-/// * `name` - The function's name.
-/// * `envs` - A map of environment variables for the function.
+/// * `name` - The image tag of the function version.
+/// * `source` - How the image is produced.
+/// * `policy` - The server's policy for custom Dockerfiles and images.
 ///
 /// # Returns
 ///
@@ -98,10 +248,38 @@ async fn create_function(
 async fn provision_docker(
     path: PathBuf,
     name: &str,
-    envs: HashMap<String, String>,
+    source: BuildSource,
+    policy: &BuildPolicy,
 ) -> ServelessCoreResult<()> {
-    let mut dockerfile_content = DOCKERFILE_TEMPLATE.replace("{{FUNCTION}}", name);
-    dockerfile_content = dockerfile_content.replace("{{ENV}}", &envs_to_string(envs));
+    let dockerfile_content = match source {
+        BuildSource::Template => DOCKERFILE_TEMPLATE.replace("{{FUNCTION}}", name),
+        BuildSource::Dockerfile(dockerfile) => {
+            validate_dockerfile(&dockerfile, &policy.disallowed_instructions).map_err(|e| {
+                ServelessCoreError::InvalidFunction(vec![FieldError::new(
+                    "Dockerfile",
+                    e.to_string(),
+                )])
+            })?;
+            dockerfile
+        }
+        BuildSource::Image(image) => {
+            if !policy
+                .allowed_image_prefixes
+                .iter()
+                .any(|prefix| image.starts_with(prefix.as_str()))
+            {
+                return Err(ServelessCoreError::InvalidFunction(vec![FieldError::new(
+                    "image",
+                    format!("'{image}' is not allowed by the server policy"),
+                )]));
+            }
+            tag_image(&image, name).await.map_err(|e| {
+                ServelessCoreError::BadFunction(format!("Image '{image}' is not available: {e}"))
+            })?;
+            info!("Function docker image tagged from '{}'", image);
+            return Ok(());
+        }
+    };
 
     provisioning(&path, name, &dockerfile_content)
         .await
@@ -113,62 +291,486 @@ async fn provision_docker(
 /// Deploys a function by building its files, provisioning a Docker container, and
 /// registering it in the database if necessary.
 ///
+/// Every deploy produces a new immutable version with its own image tag, so
+/// earlier versions stay available for aliases and rollbacks. Traffic is only
+/// switched once the new version is built and running, so callers never hit a
+/// half-deployed function.
+///
+/// The uploaded archive is kept in the artifact store under its SHA-256 digest,
+/// which is recorded on the version. When the archive and build settings match
+/// the currently deployed version, nothing is built and no version is created.
+///
+/// Every other deploy counts against the namespace's hourly build quota, and a
+/// new function against its function quota. A version whose instance alone
+/// needs more memory than the namespace's memory quota is rejected, and the new
+/// instance is only started if it fits in the instance and memory quotas.
+///
+/// Deploys of the same function are serialized with a lock held across every
+/// node, so two of them never build the same version; a deploy started while
+/// another is running is refused with `Conflict`.
+///
 /// This function:
 /// 1. Creates the function's file structure and extracts its configuration.
-/// 2. Provisions the Docker container for the function using the configuration.
-/// 3. Registers the function in the database if it does not already exist.
+/// 2. Provisions the Docker image for the next version of the function.
+/// 3. Starts a fresh instance of the new version and caches its address; it is
+///    stopped again if a later step fails.
+/// 4. Registers the function in the database if it does not already exist.
+/// 5. Records the new version, which makes it the one served by default.
+/// 6. Drains and stops the previous version's instance unless an alias still uses it.
 ///
 /// # Arguments
 ///
 /// * `conn` - A reference to the database connection.
+/// * `artifacts` - The store uploaded archives are kept in.
+/// * `rollout` - Cache connection and settings used to swap instances.
+/// * `policy` - The server's policy for custom Dockerfiles and images.
+/// * `quota` - The quota of the function's namespace.
 /// * `function` - The function metadata and content.
 ///
 /// # Returns
 ///
-/// A success message indicating that the function was deployed.
+/// A success message indicating that the function was deployed, or that it was
+/// already up to date.
 pub async fn deploy_function(
     conn: &DatabaseConnection,
+    artifacts: &dyn ArtifactStore,
+    rollout: RolloutContext,
+    policy: &BuildPolicy,
+    quota: &Quota,
+    function: DeployableFunction,
+) -> ServelessCoreResult<String> {
+    validate_function_name(&function.name)
+        .map_err(|e| ServelessCoreError::InvalidFunction(vec![e]))?;
+
+    let mut cache_conn = rollout.cache_conn.clone();
+    let lock_key = function_key(&function.name, function.user_uuid);
+    let lock_token = Uuid::new_v4().to_string();
+    let acquired = FunctionCacheRepo::acquire_lock(
+        &mut cache_conn,
+        FunctionLock::Deploy,
+        &lock_key,
+        &lock_token,
+        DEPLOY_LOCK_TTL.as_millis() as u64,
+    )
+    .await
+    .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    if !acquired {
+        return Err(ServelessCoreError::Conflict(format!(
+            "Function '{}' is already being deployed; try again once that deploy finishes",
+            function.name
+        )));
+    }
+
+    let name = function.name.clone();
+    let deploy = deploy_version(conn, artifacts, rollout, policy, quota, function);
+    let result = holding_lock(
+        cache_conn.clone(),
+        FunctionLock::Deploy,
+        DEPLOY_LOCK_TTL,
+        &lock_key,
+        &lock_token,
+        deploy,
+    )
+    .await;
+    if let Err(e) = FunctionCacheRepo::release_lock(
+        &mut cache_conn,
+        FunctionLock::Deploy,
+        &lock_key,
+        &lock_token,
+    )
+    .await
+    {
+        error!("Failed to release the deploy lock of '{}': {}", name, e);
+    }
+    result
+}
+
+/// Builds, starts and records the next version of a function, for
+/// [`deploy_function`] once it holds the function's deploy lock.
+async fn deploy_version(
+    conn: &DatabaseConnection,
+    artifacts: &dyn ArtifactStore,
+    rollout: RolloutContext,
+    policy: &BuildPolicy,
+    quota: &Quota,
     function: DeployableFunction,
 ) -> ServelessCoreResult<String> {
+    let RolloutContext {
+        mut cache_conn,
+        docker_compose_network_host,
+        drain_timeout,
+        secret_cipher,
+    } = rollout;
     let name = function.name;
     let runtime = function.runtime;
     let content = function.content;
     let user_uuid = function.user_uuid;
+    let deployed_by = function.deployed_by;
+
+    let artifact_digest = sha256_digest(&content);
+    let build_digest = build_digest(&artifact_digest, &runtime, policy);
 
     // Create the function files and extract configuration.
-    let (envs, path) = create_function(&name, &runtime, content).await?;
-    // Ensure environment variables are available.
-    let envs = envs.ok_or_else(|| {
-        ServelessCoreError::BadFunction("Missing environment configuration in function".to_string())
-    })?;
-    // Build the function Docker image.
-    let uuid_short = generate_hash(user_uuid);
-    let function_image_name = format!("{name}-{uuid_short}");
-    provision_docker(path, &function_image_name, envs).await?;
+    let (mut config, path, source) =
+        create_function(&name, &runtime, content.clone(), &policy.extract_limits).await?;
+    let max_concurrency = config
+        .max_concurrency
+        .map(|n| n.min(i32::MAX as u32) as i32);
+    let config_env = config.env.take().unwrap_or_default();
+    let resources = config.resources.take().unwrap_or_default();
+    let memory_mb = resources.memory_mb.map(|mb| mb as i32);
+    let cpu_millis = resources.cpus.map(|cpus| (cpus * 1000.0).round() as i32);
+    let description = config.description.take();
+    let labels = (!config.labels.is_empty())
+        .then(|| serde_json::to_value(&config.labels).unwrap_or_default());
+    let auth = serde_json::to_value(&config.auth).ok();
 
-    // Register the function in the database if it's not already registered.
-    if FunctionDBRepo::find_function_by_name(conn, &name, user_uuid)
+    let existing = FunctionDBRepo::find_function_by_name(conn, &name, user_uuid).await;
+    let latest = match &existing {
+        Some(function) => FunctionVersionDBRepo::find_latest(conn, function.id)
+            .await
+            .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?,
+        None => None,
+    };
+
+    // A prebuilt image may have changed on the host under the same reference, so
+    // it is always tagged again.
+    if let Some(latest) = &latest {
+        let prebuilt = matches!(source, BuildSource::Image(_));
+        if !prebuilt && latest.build_digest.as_deref() == Some(build_digest.as_str()) {
+            info!(
+                "Function '{}' is unchanged, keeping version {}",
+                name, latest.version
+            );
+            return Ok(format!(
+                "Function '{}' is unchanged; version {} is already deployed",
+                name, latest.version
+            ));
+        }
+    }
+
+    if existing.is_none() {
+        check_function_count(conn, quota, user_uuid).await?;
+    }
+    check_version_memory(quota, memory_mb)?;
+    count_build(&mut cache_conn, quota, user_uuid).await?;
+
+    let previous_version = latest.map(|latest| latest.version);
+    let version = previous_version.map_or(1, |previous| previous + 1);
+
+    let (env, ignored_env) = deploy_env(existing.as_ref(), config_env)?;
+
+    // Build the function Docker image under a tag unique to this version.
+    let function_image_name = image_tag(&function_key(&name, user_uuid), version);
+    provision_docker(path, &function_image_name, source, policy).await?;
+    let image_digest = image_digest(&function_image_name)
         .await
-        .is_none()
-    {
-        // Create a function model for the user
-        let model = FunctionModel {
+        .map_err(|e| error!("Failed to inspect image '{}': {}", function_image_name, e))
+        .ok();
+
+    let secrets = match &existing {
+        Some(function) => decrypt_secrets(conn, &secret_cipher, function.id).await?,
+        None => Vec::new(),
+    };
+    let instance_env = container_env(env.clone(), secrets);
+
+    // Start the new version before it becomes routable, so the switch is instant.
+    let instance = InstanceContext {
+        db_conn: conn,
+        cipher: &secret_cipher,
+        docker_compose_network_host: &docker_compose_network_host,
+        quota,
+    };
+    let spec = InstanceSpec {
+        image: &function_image_name,
+        env: instance_env,
+        memory_mb,
+        cpu_millis,
+    };
+    let warm_key = instance_key(&name, user_uuid, version);
+    // An earlier deploy of this version number that failed may have left its
+    // instance cached; it runs an older build and must not be reused.
+    discard_instance(&mut cache_conn, &warm_key).await?;
+    // The new instance runs next to the one it replaces until the switch, so it
+    // needs room of its own.
+    check_instance_capacity(conn, &mut cache_conn, quota, user_uuid, memory_mb).await?;
+    cold_start(
+        &mut cache_conn,
+        &instance,
+        &name,
+        user_uuid,
+        &warm_key,
+        spec,
+    )
+    .await?;
+
+    let recorded = async {
+        store_artifact(artifacts, &content).await.map_err(|e| {
+            error!("Failed to store artifact of '{}': {}", name, e);
+            ServelessCoreError::SystemError(format!("Failed to store function archive: {e}"))
+        })?;
+
+        let deployed = FunctionModel {
             name: name.to_string(),
+            auth_id: deployed_by,
             runtime,
+            max_concurrency,
+            env: Some(env_to_json(&env)),
+            image_digest,
+            description,
+            labels,
+            auth,
             ..Default::default()
         };
 
-        // Save the function to the database for the authenticated user
-        FunctionDBRepo::create_function_for_user(conn, model, user_uuid)
+        // Register the function in the database if it's not already registered.
+        let function_id = if let Some(existing) = existing {
+            FunctionDBRepo::record_deploy(conn, existing, deployed)
+                .await
+                .map_err(|e| {
+                    error!("Failed to update function in database: {}", e);
+                    ServelessCoreError::SystemError(format!("Failed to update function: {e}"))
+                })?
+                .id
+        } else {
+            // Save the function to the database for the authenticated user
+            FunctionDBRepo::create_function(conn, deployed, user_uuid, deployed_by)
+                .await
+                .map_err(|e| {
+                    error!("Failed to register function in database: {}", e);
+                    ServelessCoreError::BadFunction(
+                        "Failed to register function in database".to_string(),
+                    )
+                })?
+                .id
+        };
+
+        let version_model = VersionModel {
+            function_id,
+            version,
+            image_tag: function_image_name,
+            artifact_digest: Some(artifact_digest),
+            build_digest: Some(build_digest),
+            memory_mb,
+            cpu_millis,
+            ..Default::default()
+        };
+        FunctionVersionDBRepo::create_version(conn, version_model)
             .await
             .map_err(|e| {
-                error!("Failed to register function in database: {}", e);
-                ServelessCoreError::BadFunction(
-                    "Failed to register function in database".to_string(),
-                )
+                error!("Failed to record version {} of '{}': {}", version, name, e);
+                match e.sql_err() {
+                    Some(SqlErr::UniqueConstraintViolation(_)) => ServelessCoreError::Conflict(
+                        format!("Version {version} of '{name}' was recorded by another deploy"),
+                    ),
+                    _ => ServelessCoreError::SystemError(format!(
+                        "Failed to record function version: {e}"
+                    )),
+                }
             })?;
+        Ok(function_id)
+    }
+    .await;
+    let function_id = match recorded {
+        Ok(function_id) => function_id,
+        Err(e) => {
+            // The version was never recorded, so its warmed-up instance is unreachable.
+            if discards_warm_instance(&e) {
+                let _ = discard_instance(&mut cache_conn, &warm_key).await;
+            }
+            return Err(e);
+        }
+    };
+
+    // Retire the instance that was serving default traffic, unless an alias pins it.
+    if previous_version.is_some() {
+        let aliases = FunctionAliasDBRepo::list_aliases(conn, function_id)
+            .await
+            .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+        if let Some(previous) = retired_version(previous_version, &aliases) {
+            tokio::spawn(drain_instance(
+                cache_conn,
+                instance_key(&name, user_uuid, previous),
+                drain_timeout,
+            ));
+        }
+    }
+
+    info!(
+        "Function '{}' deployed successfully as version {}",
+        name, version
+    );
+    let deployed = format!(
+        "Function '{}' deployed successfully as version {}",
+        name, version
+    );
+    Ok(match ignored_env_warning(&name, &ignored_env) {
+        Some(warning) => format!("{deployed}\nWarning: {warning}"),
+        None => deployed,
+    })
+}
+
+/// Reports an archive that failed to extract: only I/O errors are the server's
+/// fault, everything else is wrong with the upload.
+fn extract_error(e: ExtractError) -> ServelessCoreError {
+    match e {
+        ExtractError::Io(e) => ServelessCoreError::SystemError(e.to_string()),
+        e => ServelessCoreError::BadFunction(e.to_string()),
     }
+}
 
-    info!("Function '{}' deployed successfully", name);
-    Ok(format!("Function '{}' deployed successfully", name))
+/// Deploys several functions at once, building up to `parallelism` of them concurrently.
+///
+/// Each function is deployed independently with [`deploy_function`], so one
+/// function failing to build does not stop or roll back the others. Callers must
+/// not pass the same function twice.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `artifacts` - The store uploaded archives are kept in.
+/// * `rollout` - Cache connection and settings used to swap instances.
+/// * `policy` - The server's policy for custom Dockerfiles and images.
+/// * `quota` - The quota of the functions' namespace.
+/// * `functions` - The functions to deploy.
+/// * `parallelism` - Maximum number of functions deployed at the same time.
+///
+/// # Returns
+///
+/// One outcome per function, in the order the functions were given.
+pub async fn deploy_functions(
+    conn: &DatabaseConnection,
+    artifacts: &dyn ArtifactStore,
+    rollout: RolloutContext,
+    policy: &BuildPolicy,
+    quota: &Quota,
+    functions: Vec<DeployableFunction>,
+    parallelism: usize,
+) -> Vec<DeployOutcome> {
+    deploy_each(functions, parallelism, |function| {
+        let rollout = rollout.clone();
+        async move {
+            let name = function.name.clone();
+            let result = deploy_function(conn, artifacts, rollout, policy, quota, function).await;
+            (name, result)
+        }
+    })
+    .await
+}
+
+/// Runs `deploy` on up to `parallelism` functions at a time and reports the
+/// outcome of each, in the order the functions were given.
+async fn deploy_each<T, F, Fut>(
+    functions: Vec<T>,
+    parallelism: usize,
+    deploy: F,
+) -> Vec<DeployOutcome>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = (String, ServelessCoreResult<String>)>,
+{
+    stream::iter(functions)
+        .map(deploy)
+        .buffered(parallelism.max(1))
+        .map(|(name, result)| deploy_outcome(name, result))
+        .collect()
+        .await
+}
+
+/// Reports the result of deploying one function; validation failures carry
+/// their offending fields.
+fn deploy_outcome(name: String, result: ServelessCoreResult<String>) -> DeployOutcome {
+    match result {
+        Ok(message) => DeployOutcome {
+            function: name,
+            success: true,
+            message,
+            errors: Vec::new(),
+        },
+        Err(e) => {
+            error!("Error deploying function {}: {}", name, e);
+            let message = e.to_string();
+            let errors = match e {
+                ServelessCoreError::InvalidFunction(errors) => errors,
+                _ => Vec::new(),
+            };
+            DeployOutcome {
+                function: name,
+                success: false,
+                message,
+                errors,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_digest_tracks_archive_runtime_and_policy() {
+        let policy = BuildPolicy::default();
+        let digest = build_digest("abc", "go", &policy);
+        assert_eq!(digest, build_digest("abc", "go", &policy));
+        assert_ne!(digest, build_digest("abd", "go", &policy));
+        assert_ne!(digest, build_digest("abc", "rust", &policy));
+
+        let stricter = BuildPolicy {
+            disallowed_instructions: vec!["RUN".to_string()],
+            ..Default::default()
+        };
+        assert_ne!(digest, build_digest("abc", "go", &stricter));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_each_reports_every_function_in_order() {
+        let functions = vec!["slow", "broken", "fast"];
+        let outcomes = deploy_each(functions, 3, |name| async move {
+            let result = match name {
+                "slow" => {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok("deployed slow".to_string())
+                }
+                "broken" => Err(ServelessCoreError::InvalidFunction(vec![FieldError::new(
+                    "runtime",
+                    "unsupported",
+                )])),
+                _ => Ok("deployed fast".to_string()),
+            };
+            (name.to_string(), result)
+        })
+        .await;
+
+        let names: Vec<_> = outcomes.iter().map(|o| o.function.as_str()).collect();
+        assert_eq!(names, vec!["slow", "broken", "fast"]);
+        assert!(outcomes[0].success && outcomes[2].success);
+        assert!(!outcomes[1].success);
+        assert_eq!(outcomes[1].errors.len(), 1);
+        assert_eq!(outcomes[1].errors[0].field, "runtime");
+    }
+
+    #[test]
+    fn test_malformed_archives_are_the_uploaders_fault() {
+        let duplicate = extract_error(ExtractError::DuplicateEntry("config.json".to_string()));
+        let conflicting = extract_error(ExtractError::ConflictingEntry("src".to_string()));
+        assert!(matches!(duplicate, ServelessCoreError::BadFunction(_)));
+        assert!(matches!(conflicting, ServelessCoreError::BadFunction(_)));
+
+        let disk_full = extract_error(ExtractError::Io(std::io::Error::other("disk full")));
+        assert!(matches!(disk_full, ServelessCoreError::SystemError(_)));
+    }
+
+    #[test]
+    fn test_failed_deploys_keep_their_reason() {
+        let outcome = deploy_outcome(
+            "hello".to_string(),
+            Err(ServelessCoreError::Conflict(
+                "already deploying".to_string(),
+            )),
+        );
+        assert!(!outcome.success);
+        assert!(outcome.message.contains("already deploying"));
+        assert!(outcome.errors.is_empty());
+    }
 }
@@ -0,0 +1,252 @@
+use db_entities::function::Model as FunctionModel;
+use sea_orm::prelude::Json;
+use sea_orm::DatabaseConnection;
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+use crate::db::function::FunctionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::secrets::decrypt_secrets;
+use crate::utils::secrets::{is_valid_env_key, SecretCipher};
+
+/// Reads the environment variables stored on a function record.
+///
+/// Non-string values are ignored; a missing column yields an empty map.
+pub fn stored_env(function: &FunctionModel) -> BTreeMap<String, String> {
+    function
+        .env
+        .as_ref()
+        .and_then(Json::as_object)
+        .map(|env| {
+            env.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ensures every key is a valid environment variable name.
+pub fn validate_env<'a>(keys: impl IntoIterator<Item = &'a String>) -> ServelessCoreResult<()> {
+    match keys.into_iter().find(|key| !is_valid_env_key(key)) {
+        Some(key) => Err(ServelessCoreError::BadFunction(format!(
+            "Invalid environment variable name '{key}'"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Applies a patch to a set of environment variables.
+///
+/// Keys mapped to a value are set, keys mapped to `None` are removed.
+pub fn apply_env_patch(
+    mut env: BTreeMap<String, String>,
+    patch: HashMap<String, Option<String>>,
+) -> BTreeMap<String, String> {
+    for (key, value) in patch {
+        match value {
+            Some(value) => env.insert(key, value),
+            None => env.remove(&key),
+        };
+    }
+    env
+}
+
+/// Builds the `KEY=value` list passed to a new container.
+///
+/// Secrets take precedence over plain environment variables with the same name.
+pub fn container_env(
+    mut env: BTreeMap<String, String>,
+    secrets: Vec<(String, String)>,
+) -> Vec<String> {
+    env.extend(secrets);
+    env.into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+/// Converts environment variables into the JSON object stored on the function record.
+pub fn env_to_json(env: &BTreeMap<String, String>) -> Json {
+    Json::Object(
+        env.iter()
+            .map(|(key, value)| (key.clone(), Json::String(value.clone())))
+            .collect(),
+    )
+}
+
+/// Loads the environment of a function's containers: its stored variables plus
+/// its decrypted secrets.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `cipher` - The cipher used to decrypt secrets.
+/// * `function_id` - The ID of the function.
+pub async fn instance_env(
+    conn: &DatabaseConnection,
+    cipher: &SecretCipher,
+    function_id: i32,
+) -> ServelessCoreResult<Vec<String>> {
+    let function = FunctionDBRepo::find_function_by_id(conn, function_id)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .ok_or_else(|| ServelessCoreError::FunctionNotRegistered(function_id.to_string()))?;
+    let secrets = decrypt_secrets(conn, cipher, function_id).await?;
+    Ok(container_env(stored_env(&function), secrets))
+}
+
+/// Chooses the environment a deploy runs with.
+///
+/// Variables from `config.json` only seed the environment of a new function.
+/// Afterwards the environment is managed through the API, so a redeploy never
+/// reverts a variable that was changed or removed there.
+///
+/// # Arguments
+///
+/// * `existing` - The function being redeployed, if it already exists.
+/// * `config_env` - The `env` of the uploaded `config.json`.
+///
+/// # Returns
+///
+/// The environment, and the `config.json` variables a redeploy ignored because
+/// the stored environment lacks them or holds another value, sorted by name.
+pub fn deploy_env(
+    existing: Option<&FunctionModel>,
+    config_env: HashMap<String, String>,
+) -> ServelessCoreResult<(BTreeMap<String, String>, Vec<String>)> {
+    validate_env(config_env.keys())?;
+    match existing {
+        Some(function) => {
+            let env = stored_env(function);
+            let mut ignored: Vec<String> = config_env
+                .into_iter()
+                .filter(|(key, value)| env.get(key) != Some(value))
+                .map(|(key, _)| key)
+                .collect();
+            ignored.sort();
+            if !ignored.is_empty() {
+                info!(
+                    "Keeping the stored environment of function '{}'; config.json env only applies on its first deploy",
+                    function.name
+                );
+            }
+            Ok((env, ignored))
+        }
+        None => Ok((config_env.into_iter().collect(), Vec::new())),
+    }
+}
+
+/// Warns that a redeploy of `name` ignored the `config.json` variables `ignored`.
+pub fn ignored_env_warning(name: &str, ignored: &[String]) -> Option<String> {
+    if ignored.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "config.json env was ignored for {}: the environment of '{name}' is changed with PATCH /invok/functions/{name}/env",
+        ignored.join(", ")
+    ))
+}
+
+/// Updates the environment variables of a function without rebuilding its image.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `function` - The function to update.
+/// * `patch` - Variables to set, or to remove when mapped to `None`.
+///
+/// # Returns
+///
+/// The function's environment after the update.
+pub async fn update_env(
+    conn: &DatabaseConnection,
+    function: FunctionModel,
+    patch: HashMap<String, Option<String>>,
+) -> ServelessCoreResult<BTreeMap<String, String>> {
+    validate_env(patch.keys())?;
+
+    let name = function.name.clone();
+    let env = apply_env_patch(stored_env(&function), patch);
+    FunctionDBRepo::update_env(conn, function, env_to_json(&env))
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    info!("Environment of function '{}' updated", name);
+    Ok(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_env_patch_sets_and_removes() {
+        let patch = HashMap::from([
+            ("A".to_string(), Some("2".to_string())),
+            ("B".to_string(), None),
+            ("C".to_string(), Some("3".to_string())),
+        ]);
+        let result = apply_env_patch(env(&[("A", "1"), ("B", "1")]), patch);
+        assert_eq!(result, env(&[("A", "2"), ("C", "3")]));
+    }
+
+    #[test]
+    fn test_container_env_prefers_secrets() {
+        let secrets = vec![("TOKEN".to_string(), "secret".to_string())];
+        let result = container_env(env(&[("TOKEN", "plain"), ("MODE", "prod")]), secrets);
+        assert_eq!(result, vec!["MODE=prod", "TOKEN=secret"]);
+    }
+
+    #[test]
+    fn test_deploy_env_seeds_new_functions_only() {
+        let config = HashMap::from([
+            ("A".to_string(), "config".to_string()),
+            ("B".to_string(), "api".to_string()),
+            ("C".to_string(), "config".to_string()),
+        ]);
+        let (seeded, ignored) = deploy_env(None, config.clone()).unwrap();
+        assert_eq!(
+            seeded,
+            env(&[("A", "config"), ("B", "api"), ("C", "config")])
+        );
+        assert!(ignored.is_empty());
+
+        let function = FunctionModel {
+            env: Some(env_to_json(&env(&[("A", "api"), ("B", "api")]))),
+            ..Default::default()
+        };
+        let (kept, ignored) = deploy_env(Some(&function), config).unwrap();
+        assert_eq!(kept, env(&[("A", "api"), ("B", "api")]));
+        assert_eq!(ignored, vec!["A", "C"]);
+        assert!(ignored_env_warning("hello", &ignored)
+            .unwrap()
+            .contains("ignored for A, C"));
+        assert!(ignored_env_warning("hello", &[]).is_none());
+    }
+
+    #[test]
+    fn test_deploy_env_rejects_invalid_names() {
+        let config = HashMap::from([("1BAD".to_string(), "x".to_string())]);
+        assert!(matches!(
+            deploy_env(None, config.clone()),
+            Err(ServelessCoreError::BadFunction(_))
+        ));
+        assert!(deploy_env(Some(&FunctionModel::default()), config).is_err());
+    }
+
+    #[test]
+    fn test_stored_env_roundtrip() {
+        let function = FunctionModel {
+            env: Some(env_to_json(&env(&[("A", "x=y")]))),
+            ..Default::default()
+        };
+        assert_eq!(stored_env(&function), env(&[("A", "x=y")]));
+        assert!(stored_env(&FunctionModel::default()).is_empty());
+    }
+}
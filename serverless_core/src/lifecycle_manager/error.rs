@@ -1,8 +1,10 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
 use thiserror::Error;
+
+use crate::db::models::FieldError;
 use tracing::{debug, error};
 
 /// A custom result type using our defined `Error`.
@@ -11,8 +13,10 @@ pub type ServelessCoreResult<T> = core::result::Result<T, ServelessCoreError>;
 /// Custom error type for function-related failures.
 ///
 /// Variants cover cases such as a function not being registered,
-/// failure to start a function, malformed function input, or
-/// system-level errors.
+/// failure to start a function, malformed function input (optionally with a
+/// list of the offending fields), a function
+/// at its concurrency limit, requests the caller isn't allowed to make or
+/// must retry later, or system-level errors.
 #[derive(Debug, Error)]
 pub enum ServelessCoreError {
     #[error("Function not found: {0}")]
@@ -21,10 +25,52 @@ pub enum ServelessCoreError {
     FunctionFailedToStart(String),
     #[error("Bad function: {0}")]
     BadFunction(String),
+    #[error("Invalid function: {}", describe_fields(.0))]
+    InvalidFunction(Vec<FieldError>),
+    #[error("Function overloaded: {0}")]
+    Overloaded(String),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String, u64),
     #[error("System error: {0}")]
     SystemError(String),
 }
 
+impl ServelessCoreError {
+    /// Whether the error was caused by the uploaded function rather than the server.
+    pub fn is_client_error(&self) -> bool {
+        matches!(
+            self,
+            ServelessCoreError::BadFunction(_)
+                | ServelessCoreError::InvalidFunction(_)
+                | ServelessCoreError::BadRequest(_)
+                | ServelessCoreError::NotFound(_)
+                | ServelessCoreError::Unauthorized(_)
+                | ServelessCoreError::Forbidden(_)
+                | ServelessCoreError::Conflict(_)
+                | ServelessCoreError::TooManyRequests(..)
+        )
+    }
+}
+
+/// Joins field errors into a single human-readable line.
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{} {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl IntoResponse for ServelessCoreError {
     fn into_response(self) -> Response {
         debug!("Converting error into response: {:?}", self);
@@ -40,6 +86,50 @@ impl IntoResponse for ServelessCoreError {
             ServelessCoreError::BadFunction(b) => {
                 (StatusCode::BAD_REQUEST, format!("Bad function: {b}")).into_response()
             }
+            ServelessCoreError::InvalidFunction(errors) => (
+                StatusCode::BAD_REQUEST,
+                axum::Json(serde_json::json!({
+                    "error": "Invalid function",
+                    "errors": errors,
+                })),
+            )
+                .into_response(),
+            ServelessCoreError::Overloaded(f) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Function is at capacity, try again later: {f}"),
+            )
+                .into_response(),
+            ServelessCoreError::BadRequest(m) => (
+                StatusCode::BAD_REQUEST,
+                axum::Json(serde_json::json!({ "error": m })),
+            )
+                .into_response(),
+            ServelessCoreError::NotFound(m) => (
+                StatusCode::NOT_FOUND,
+                axum::Json(serde_json::json!({ "error": m })),
+            )
+                .into_response(),
+            ServelessCoreError::Unauthorized(m) => (
+                StatusCode::UNAUTHORIZED,
+                axum::Json(serde_json::json!({ "error": m })),
+            )
+                .into_response(),
+            ServelessCoreError::Forbidden(m) => (
+                StatusCode::FORBIDDEN,
+                axum::Json(serde_json::json!({ "error": m })),
+            )
+                .into_response(),
+            ServelessCoreError::Conflict(m) => (
+                StatusCode::CONFLICT,
+                axum::Json(serde_json::json!({ "error": m })),
+            )
+                .into_response(),
+            ServelessCoreError::TooManyRequests(m, retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                axum::Json(serde_json::json!({ "error": m, "retry_after": retry_after })),
+            )
+                .into_response(),
             ServelessCoreError::SystemError(s) => {
                 error!("System error occurred: {}", s);
                 (
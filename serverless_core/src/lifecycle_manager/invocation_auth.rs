@@ -0,0 +1,292 @@
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use db_entities::function::Model as Function;
+use sea_orm::DatabaseConnection;
+use serde_json::{Map, Value};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db::models::FunctionAuth;
+use crate::lifecycle_manager::api_keys;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::organizations::resolve_membership;
+use crate::lifecycle_manager::tokens::TOKEN_PREFIX;
+use crate::utils::jwks::{JwksCache, JwksError};
+use crate::utils::jwt_keys::JwtKeys;
+
+/// Header API keys may be sent in, instead of `Authorization: Bearer`.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Prefix of the headers carrying the caller's identity to the function.
+/// Callers can't set them; any they send are dropped.
+const IDENTITY_HEADER_PREFIX: &str = "x-invok-";
+
+/// Who invoked a function, as verified by its auth mode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Caller {
+    /// A caller of a public function.
+    Anonymous,
+    /// A caller presenting one of the function's API keys.
+    ApiKey { key_uuid: Uuid },
+    /// A platform user of the function's organization.
+    User { user_uuid: Uuid },
+    /// A caller presenting a JWT from the function's issuer.
+    Jwt { claims: Map<String, Value> },
+}
+
+impl Caller {
+    /// The auth mode the caller was verified with.
+    pub fn mode(&self) -> &'static str {
+        match self {
+            Caller::Anonymous => "public",
+            Caller::ApiKey { .. } => "api_key",
+            Caller::User { .. } => "platform",
+            Caller::Jwt { .. } => "jwt",
+        }
+    }
+}
+
+/// The auth mode of a function; functions deployed without one are public.
+pub fn function_auth(function: &Function) -> ServelessCoreResult<FunctionAuth> {
+    match &function.auth {
+        None => Ok(FunctionAuth::Public),
+        Some(auth) => serde_json::from_value(auth.clone()).map_err(|e| {
+            ServelessCoreError::SystemError(format!(
+                "Stored auth settings of '{}' are invalid: {e}",
+                function.name
+            ))
+        }),
+    }
+}
+
+/// The token of an `Authorization: Bearer` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Authenticates an invocation with one of the function's API keys.
+pub async fn authenticate_api_key(
+    conn: &DatabaseConnection,
+    function: &Function,
+    headers: &HeaderMap,
+) -> ServelessCoreResult<Caller> {
+    let key = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| bearer_token(headers))
+        .ok_or_else(|| {
+            ServelessCoreError::Unauthorized(format!(
+                "'{}' requires an API key in the {} header",
+                function.name, API_KEY_HEADER
+            ))
+        })?;
+    match api_keys::authenticate(conn, function, key).await? {
+        Some(key) => Ok(Caller::ApiKey { key_uuid: key.uuid }),
+        None => Err(ServelessCoreError::Unauthorized(
+            "Invalid or revoked API key".to_string(),
+        )),
+    }
+}
+
+/// Checks that an authenticated platform user belongs to the organization
+/// owning the function, whatever their role.
+pub async fn authorize_member(
+    conn: &DatabaseConnection,
+    function: &Function,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<Caller> {
+    match resolve_membership(conn, user_uuid, Some(&function.namespace.to_string())).await {
+        Ok(_) => Ok(Caller::User { user_uuid }),
+        Err(ServelessCoreError::NotFound(_)) => Err(ServelessCoreError::Forbidden(format!(
+            "Only members of the organization owning '{}' can invoke it",
+            function.name
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Authenticates an invocation with a JWT from the function's issuer.
+///
+/// # Arguments
+///
+/// * `jwks` - Cache of external issuers' keys
+/// * `function` - The function being invoked
+/// * `issuer`, `audience`, `jwks_url` - The function's `jwt` auth settings
+/// * `headers` - The invocation's headers
+pub async fn authenticate_jwt(
+    jwks: &JwksCache,
+    function: &Function,
+    issuer: &str,
+    audience: Option<&str>,
+    jwks_url: Option<&str>,
+    headers: &HeaderMap,
+) -> ServelessCoreResult<Caller> {
+    let token = bearer_token(headers).ok_or_else(|| {
+        ServelessCoreError::Unauthorized(format!(
+            "'{}' requires a bearer token from {}",
+            function.name, issuer
+        ))
+    })?;
+    match jwks.verify(issuer, audience, jwks_url, token).await {
+        Ok(claims) => Ok(Caller::Jwt { claims }),
+        Err(JwksError::InvalidToken(reason)) => {
+            info!("Rejected token for '{}': {}", function.name, reason);
+            Err(ServelessCoreError::Unauthorized(
+                "Invalid or expired token".to_string(),
+            ))
+        }
+        Err(e) => Err(ServelessCoreError::SystemError(e.to_string())),
+    }
+}
+
+/// Whether a bearer token is a platform credential: a personal access token
+/// or a JWT signed with the platform's keys.
+fn platform_credential(token: &str, platform_keys: &JwtKeys) -> bool {
+    token.starts_with(TOKEN_PREFIX) || platform_keys.signed(token)
+}
+
+/// Replaces any identity headers the caller sent with the verified identity.
+///
+/// API keys and platform credentials are removed in every mode, so a function
+/// never sees secrets that work elsewhere on the platform. Other `Authorization`
+/// headers are kept for public functions, which may check them themselves, and
+/// for tokens from a `jwt` function's issuer, which the function may pass on.
+/// JWT claims are sent as base64-encoded JSON in `X-Invok-Claims`.
+pub fn forward_identity(headers: &mut HeaderMap, caller: &Caller, platform_keys: &JwtKeys) {
+    let spoofed = headers
+        .keys()
+        .filter(|name| name.as_str().starts_with(IDENTITY_HEADER_PREFIX))
+        .cloned()
+        .collect::<Vec<_>>();
+    for name in spoofed {
+        headers.remove(name);
+    }
+    headers.remove(API_KEY_HEADER);
+    let credential_used = matches!(caller, Caller::ApiKey { .. } | Caller::User { .. });
+    let platform_token =
+        bearer_token(headers).is_some_and(|token| platform_credential(token, platform_keys));
+    if credential_used || platform_token {
+        headers.remove(header::AUTHORIZATION);
+    }
+
+    let mut set = |name: &'static str, value: &str| {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    };
+    set("x-invok-auth", caller.mode());
+    match caller {
+        Caller::Anonymous => {}
+        Caller::ApiKey { key_uuid } => set("x-invok-api-key-id", &key_uuid.to_string()),
+        Caller::User { user_uuid } => set("x-invok-user", &user_uuid.to_string()),
+        Caller::Jwt { claims } => {
+            if let Some(subject) = claims.get("sub").and_then(Value::as_str) {
+                set("x-invok-subject", subject);
+            }
+            let json = Value::Object(claims.clone()).to_string();
+            set("x-invok-claims", &BASE64.encode(json));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::jwt_keys::TokenUse;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parses_bearer_tokens() {
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Bearer abc")])),
+            Some("abc")
+        );
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Basic abc")])),
+            None
+        );
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Bearer ")])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_forwards_only_verified_identity() {
+        let keys = JwtKeys::ephemeral();
+        let user_uuid = Uuid::new_v4();
+        let mut forwarded = headers(&[
+            ("authorization", "Bearer invok_pat_secret"),
+            ("x-invok-user", "someone-else"),
+            ("x-invok-org", "acme"),
+            ("content-type", "application/json"),
+        ]);
+        forward_identity(&mut forwarded, &Caller::User { user_uuid }, &keys);
+        assert_eq!(forwarded["x-invok-auth"], "platform");
+        assert_eq!(forwarded["x-invok-user"], user_uuid.to_string().as_str());
+        assert!(forwarded.get("authorization").is_none());
+        assert!(forwarded.get("x-invok-org").is_none());
+        assert_eq!(forwarded["content-type"], "application/json");
+
+        let claims = serde_json::json!({"sub": "user-1", "role": "admin"});
+        let mut forwarded = headers(&[
+            ("authorization", "Bearer external.jwt.token"),
+            ("x-api-key", "invok_key_secret"),
+        ]);
+        let caller = Caller::Jwt {
+            claims: claims.as_object().unwrap().clone(),
+        };
+        forward_identity(&mut forwarded, &caller, &keys);
+        assert!(forwarded.get("x-api-key").is_none());
+        assert_eq!(forwarded["x-invok-subject"], "user-1");
+        assert_eq!(forwarded["authorization"], "Bearer external.jwt.token");
+        let decoded = BASE64
+            .decode(forwarded["x-invok-claims"].as_bytes())
+            .unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&decoded).unwrap(), claims);
+    }
+
+    #[test]
+    fn test_public_functions_never_see_platform_credentials() {
+        let keys = JwtKeys::ephemeral();
+        let platform_jwt = keys
+            .issue(TokenUse::Access, &serde_json::json!({"sub": "user-1"}))
+            .unwrap();
+        let platform_jwt = format!("Bearer {platform_jwt}");
+        for authorization in ["Bearer invok_pat_secret", platform_jwt.as_str()] {
+            let mut forwarded = headers(&[
+                ("authorization", authorization),
+                ("x-api-key", "invok_key_secret"),
+            ]);
+            forward_identity(&mut forwarded, &Caller::Anonymous, &keys);
+            assert_eq!(forwarded["x-invok-auth"], "public");
+            assert!(forwarded.get("authorization").is_none());
+            assert!(forwarded.get("x-api-key").is_none());
+        }
+
+        // The function's own credentials are left for it to check.
+        let mut forwarded = headers(&[("authorization", "Basic dXNlcjpwYXNz")]);
+        forward_identity(&mut forwarded, &Caller::Anonymous, &keys);
+        assert_eq!(forwarded["authorization"], "Basic dXNlcjpwYXNz");
+
+        // A jwt function trusting the platform as its issuer gets no platform token either.
+        let mut forwarded = headers(&[("authorization", platform_jwt.as_str())]);
+        let caller = Caller::Jwt {
+            claims: serde_json::Map::new(),
+        };
+        forward_identity(&mut forwarded, &caller, &keys);
+        assert!(forwarded.get("authorization").is_none());
+    }
+}
@@ -1,67 +1,291 @@
-use crate::db::cache::FunctionCacheRepo;
+use crate::db::cache::{FunctionCacheRepo, FunctionLock};
 use crate::db::function::FunctionDBRepo;
+use crate::lifecycle_manager::concurrency::{ConcurrencyLimits, ConcurrencyManager};
+use crate::lifecycle_manager::environment::instance_env;
 use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::quotas::{check_instance_capacity, count_invocation, Quota};
+use crate::utils::secrets::SecretCipher;
 use crate::utils::utils::{generate_hash, random_container_name, random_port};
+use db_entities::function::Model as FunctionModel;
+use db_entities::function_version::Model as VersionModel;
 use redis::aio::MultiplexedConnection;
 use runtime::core::runner::{runner, ContainerDetails};
 use sea_orm::DatabaseConnection;
+use std::future::Future;
+use std::time::{Duration, Instant};
 use tracing::{error, info};
 use uuid::Uuid;
-const TIMEOUT_DEFAULT_IN_SECONDS: u64 = 50;
+pub(crate) const TIMEOUT_DEFAULT_IN_SECONDS: u64 = 50;
+/// How often requests waiting on another node's cold start re-check the cache.
+const COLD_START_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long the cold-start lock outlives its holder; it is renewed while the start runs.
+const START_LOCK_TTL: Duration = Duration::from_secs(10);
+
+/// Shared resources needed to create a function container.
+///
+/// # Fields
+/// - `db_conn`: Database connection used to load the function's secrets.
+/// - `cipher`: Cipher used to decrypt the secrets.
+/// - `docker_compose_network_host`: Docker network the container is attached to.
+/// - `quota`: Quota of the function's namespace, enforced by [`start_function`].
+pub struct InstanceContext<'a> {
+    pub db_conn: &'a DatabaseConnection,
+    pub cipher: &'a SecretCipher,
+    pub docker_compose_network_host: &'a str,
+    pub quota: &'a Quota,
+}
+
+/// What a new container of a function version runs.
+///
+/// # Fields
+/// - `image`: The image tag of the version.
+/// - `env`: `KEY=value` environment variables, including decrypted secrets.
+/// - `memory_mb`: Memory limit requested by the version, if any.
+/// - `cpu_millis`: CPU limit requested by the version in thousandths of a CPU, if any.
+pub struct InstanceSpec<'a> {
+    pub image: &'a str,
+    pub env: Vec<String>,
+    pub memory_mb: Option<i32>,
+    pub cpu_millis: Option<i32>,
+}
+
 /// Checks if a function is registered in the database.
 ///
-/// Returns `Ok(())` if the function exists; otherwise, returns an error
-/// indicating that the function is not registered.
+/// Returns the function record if it exists; otherwise, returns an error
+/// indicating that the function is not registered. Functions can be referred to
+/// by their ID or by their name; a reference that parses as a UUID is looked up
+/// as an ID first.
 ///
 /// # Arguments
 ///
 /// * `conn` - A reference to the database connection.
-/// * `name` - The name of the function to check.
+/// * `name` - The ID or name of the function to check.
 /// * `user_uuid` - The UUID of the user (namespace) to verify function ownership.
 pub async fn check_function_status(
     conn: &DatabaseConnection,
     name: &str,
     user_uuid: Uuid,
-) -> ServelessCoreResult<()> {
-    let function = FunctionDBRepo::find_function_by_name(conn, name, user_uuid).await;
-    if function.is_none() {
-        error!("Function '{}' not found in namespace '{}'", name, user_uuid);
-        return Err(ServelessCoreError::FunctionNotRegistered(format!(
-            "Function '{}' not found in namespace '{}'",
-            name, user_uuid
-        )));
+) -> ServelessCoreResult<FunctionModel> {
+    if let Ok(uuid) = Uuid::parse_str(name) {
+        if let Some(function) = FunctionDBRepo::find_function_by_uuid(conn, uuid, user_uuid).await {
+            return Ok(function);
+        }
     }
-    Ok(())
+
+    FunctionDBRepo::find_function_by_name(conn, name, user_uuid)
+        .await
+        .ok_or_else(|| {
+            error!("Function '{}' not found in namespace '{}'", name, user_uuid);
+            ServelessCoreError::FunctionNotRegistered(format!(
+                "Function '{}' not found in namespace '{}'",
+                name, user_uuid
+            ))
+        })
+}
+
+/// Builds the cache key identifying a function's running instance.
+pub fn function_key(name: &str, user_uuid: Uuid) -> String {
+    // Generate a shorter hash of the UUID for better container names
+    let uuid_short = generate_hash(user_uuid);
+
+    // Create a unique function name based on function name and user's UUID hash
+    format!("{name}-{uuid_short}")
+}
+
+/// Builds the cache key identifying the running instance of one version of a function.
+///
+/// Each version runs its own instances, so they are cached and locked separately.
+pub fn instance_key(name: &str, user_uuid: Uuid, version: i32) -> String {
+    format!("{}-v{version}", function_key(name, user_uuid))
 }
 
 /// Starts a function service if it's not already running.
 ///
 /// This function first checks if the function is already running by querying the
 /// cache repository. If a running instance is found, it returns the cached address.
-/// Otherwise, it generates a random port, starts the function container using the
-/// Docker runner, caches the new function's address, and returns it.
+/// Otherwise, it takes the function's distributed start lock so that only one
+/// container is started per cold function. Requests that lose the race join a
+/// bounded queue and are released once the instance is cached.
+///
+/// The start lock is taken per version, since each version runs its own
+/// instance, but the queue is shared by every version of the function: its
+/// size and wait timeout are set per function, so a canary split can't double
+/// them. A start finishing wakes the waiters of every version; those waiting
+/// on another version find no instance cached yet and keep waiting.
+///
+/// Every call counts against the namespace's daily invocation quota, and a cold
+/// start is refused while the namespace is at its instance or memory quota.
 ///
 /// # Arguments
 ///
 /// * `cache_conn` - A mutable reference to the Redis multiplexed connection.
+/// * `instance` - Resources used to create the container on a cold start.
+/// * `concurrency` - Node-local tracker of waiting and in-flight requests.
+/// * `limits` - Queue size and wait timeout for this function.
 /// * `name` - The name of the function to start.
 /// * `user_uuid` - The UUID of the user (namespace) who owns this function.
+/// * `version` - The version of the function to run.
 ///
 /// # Returns
 ///
 /// A `Result` containing the function's address (e.g., "localhost:PORT") on success,
-/// or an error if the function fails to start.
+/// or an error if the function fails to start, too many requests are waiting or
+/// the namespace is over its quota.
 pub async fn start_function(
     cache_conn: &mut MultiplexedConnection,
+    instance: &InstanceContext<'_>,
+    concurrency: &ConcurrencyManager,
+    limits: &ConcurrencyLimits,
     name: &str,
     user_uuid: Uuid,
-    docker_compose_network_host: String,
+    version: &VersionModel,
 ) -> ServelessCoreResult<String> {
-    // Generate a shorter hash of the UUID for better container names
-    let uuid_short = generate_hash(user_uuid);
+    let function_key = function_key(name, user_uuid);
+    let instance_key = instance_key(name, user_uuid, version.version);
+    count_invocation(cache_conn, instance.quota, user_uuid).await?;
 
-    // Create a unique function name based on function name and user's UUID hash
-    let function_key = format!("{name}-{uuid_short}");
+    // Check if the function is already running.
+    if let Some(addr) = FunctionCacheRepo::get_function(cache_conn, &instance_key).await {
+        info!(
+            "Function '{}' for user '{}' already running at: {}",
+            name, user_uuid, addr
+        );
+        return Ok(addr);
+    }
+
+    let lock_token = Uuid::new_v4().to_string();
+    let deadline = Instant::now() + limits.wait_timeout;
+    let mut queue_guard = None;
+
+    loop {
+        let acquired = FunctionCacheRepo::acquire_lock(
+            cache_conn,
+            FunctionLock::Start,
+            &instance_key,
+            &lock_token,
+            START_LOCK_TTL.as_millis() as u64,
+        )
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+        if acquired {
+            let lock_conn = cache_conn.clone();
+            let start = async {
+                // Waiting requests share this start, so the quota is checked once per instance.
+                check_instance_capacity(
+                    instance.db_conn,
+                    cache_conn,
+                    instance.quota,
+                    user_uuid,
+                    version.memory_mb,
+                )
+                .await?;
+                let env =
+                    instance_env(instance.db_conn, instance.cipher, version.function_id).await?;
+                let spec = InstanceSpec {
+                    image: &version.image_tag,
+                    env,
+                    memory_mb: version.memory_mb,
+                    cpu_millis: version.cpu_millis,
+                };
+                cold_start(cache_conn, instance, name, user_uuid, &instance_key, spec).await
+            };
+            let result = holding_lock(
+                lock_conn,
+                FunctionLock::Start,
+                START_LOCK_TTL,
+                &instance_key,
+                &lock_token,
+                start,
+            )
+            .await;
+            let _ = FunctionCacheRepo::release_lock(
+                cache_conn,
+                FunctionLock::Start,
+                &instance_key,
+                &lock_token,
+            )
+            .await;
+            concurrency.notify_ready(&function_key);
+            return result;
+        }
+
+        // Another request is starting the function; wait in the queue for it.
+        let guard = match queue_guard.take() {
+            Some(guard) => guard,
+            None => concurrency.wait_for_start(&function_key, limits)?,
+        };
+        if Instant::now() >= deadline {
+            error!(
+                "Timed out waiting for function '{}' for user '{}' to start",
+                name, user_uuid
+            );
+            return Err(ServelessCoreError::Overloaded(name.to_string()));
+        }
+        guard.wait_ready(COLD_START_POLL_INTERVAL).await;
+
+        if let Some(addr) = FunctionCacheRepo::get_function(cache_conn, &instance_key).await {
+            return Ok(addr);
+        }
+        queue_guard = Some(guard);
+    }
+}
+
+/// Runs `work` while renewing `lock` on `key` every third of its `ttl`.
+///
+/// A container may take longer to start, and an image longer to build, than the
+/// lock's TTL; renewing it keeps other nodes from taking the lock meanwhile.
+pub(crate) async fn holding_lock<T>(
+    mut cache_conn: MultiplexedConnection,
+    lock: FunctionLock,
+    ttl: Duration,
+    key: &str,
+    token: &str,
+    work: impl Future<Output = T>,
+) -> T {
+    let renew = async {
+        let ttl_ms = ttl.as_millis() as u64;
+        let mut interval = tokio::time::interval(ttl / 3);
+        // The first tick completes immediately, right after the lock was taken.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match FunctionCacheRepo::extend_lock(&mut cache_conn, lock, key, token, ttl_ms).await {
+                Ok(true) => {}
+                _ => {
+                    error!("Lost the {:?} lock of '{}' while holding it", lock, key);
+                    break;
+                }
+            }
+        }
+        std::future::pending().await
+    };
+    tokio::select! {
+        result = work => result,
+        result = renew => result,
+    }
+}
+
+/// Starts a new container for the function and registers it in the cache.
+///
+/// The spec's environment holds the function's `KEY=value` environment variables
+/// and decrypted secrets. They are passed to the container at creation, so they
+/// never end up in the image and can change without a rebuild.
+///
+/// Must only be called while holding the function's start lock, or for a version
+/// that is not routable yet (e.g. while warming up a deploy).
+pub(crate) async fn cold_start(
+    cache_conn: &mut MultiplexedConnection,
+    instance: &InstanceContext<'_>,
+    name: &str,
+    user_uuid: Uuid,
+    instance_key: &str,
+    spec: InstanceSpec<'_>,
+) -> ServelessCoreResult<String> {
+    // The previous lock holder may have finished between our cache miss and lock.
+    if let Some(addr) = FunctionCacheRepo::get_function(cache_conn, instance_key).await {
+        return Ok(addr);
+    }
 
     // Generate a random port and prepare the service address.
     let container_details = ContainerDetails {
@@ -69,20 +293,16 @@ pub async fn start_function(
         bind_port: random_port(),
         container_name: random_container_name(),
         timeout: TIMEOUT_DEFAULT_IN_SECONDS,
-        docker_compose_network_host,
+        docker_compose_network_host: instance.docker_compose_network_host.to_string(),
+        env: spec.env,
+        memory_mb: spec.memory_mb.and_then(|mb| u32::try_from(mb).ok()),
+        cpu_millis: spec
+            .cpu_millis
+            .and_then(|millis| u32::try_from(millis).ok()),
     };
 
-    // Check if the function is already running.
-    if let Some(addr) = FunctionCacheRepo::get_function(cache_conn, &function_key).await {
-        info!(
-            "Function '{}' for user '{}' already running at: {}",
-            name, user_uuid, addr
-        );
-        return Ok(addr);
-    }
-
     // Attempt to run the function container with a timeout slightly longer than the cache TTL.
-    runner(&function_key, container_details.clone())
+    runner(spec.image, container_details.clone())
         .await
         .map_err(|e| {
             error!(
@@ -99,7 +319,7 @@ pub async fn start_function(
     );
     let _ = FunctionCacheRepo::add_function(
         cache_conn,
-        &function_key,
+        instance_key,
         &function_address,
         container_details.timeout,
     )
@@ -0,0 +1,199 @@
+use redis::aio::MultiplexedConnection;
+use std::net::IpAddr;
+use tracing::{info, warn};
+
+use crate::db::login_attempts::{AttemptSubject, LoginAttemptCacheRepo};
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// Tracing target of security events an operator may need to review.
+pub const AUDIT_TARGET: &str = "invok::audit";
+
+/// Failed logins of an account before each further attempt is delayed.
+const FREE_ATTEMPTS: u64 = 2;
+
+/// Longest delay between attempts before the account is locked.
+const MAX_DELAY_SECS: u64 = 30;
+
+/// Limits on failed logins.
+///
+/// # Fields
+/// - `max_failed_logins`: Failed logins of an account before it is locked.
+/// - `max_failed_logins_per_ip`: Failed logins from an IP address before it is locked.
+/// - `window`: Seconds failed logins are counted over.
+/// - `lockout_duration`: Seconds a locked account or IP address waits.
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    pub max_failed_logins: u64,
+    pub max_failed_logins_per_ip: u64,
+    pub window: u64,
+    pub lockout_duration: u64,
+}
+
+/// Seconds an account waits before its next attempt after `failures` failed
+/// logins: nothing for the first few, then doubling up to a cap.
+fn delay_after(failures: u64) -> u64 {
+    if failures <= FREE_ATTEMPTS {
+        return 0;
+    }
+    let exponent = (failures - FREE_ATTEMPTS - 1).min(6) as u32;
+    (1u64 << exponent).min(MAX_DELAY_SECS)
+}
+
+/// Normalizes an email so differently-cased spellings share their counters.
+fn account_id(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn cache_error(e: redis::RedisError) -> ServelessCoreError {
+    ServelessCoreError::SystemError(format!("Login attempt tracking failed: {e}"))
+}
+
+/// Checks that a login for `email` from `ip` may be attempted now.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the attempt may go ahead
+/// * `Err(ServelessCoreError::TooManyRequests)` - If the account or IP address
+///   is locked out or must wait, with the seconds left
+pub async fn check_allowed(
+    conn: &mut MultiplexedConnection,
+    email: &str,
+    ip: IpAddr,
+) -> ServelessCoreResult<()> {
+    let subjects = [
+        (AttemptSubject::Account, account_id(email)),
+        (AttemptSubject::Ip, ip.to_string()),
+    ];
+    for (subject, id) in subjects {
+        if let Some((seconds, locked)) = LoginAttemptCacheRepo::wait_time(conn, subject, &id)
+            .await
+            .map_err(cache_error)?
+        {
+            let message = if locked {
+                format!("Too many failed logins, try again in {seconds} seconds")
+            } else {
+                format!("Wait {seconds} seconds before trying to log in again")
+            };
+            return Err(ServelessCoreError::TooManyRequests(message, seconds));
+        }
+    }
+    Ok(())
+}
+
+/// Counts a failed login, delaying or locking out the account and IP address
+/// once they pass their limits.
+pub async fn record_failure(
+    conn: &mut MultiplexedConnection,
+    policy: &LockoutPolicy,
+    email: &str,
+    ip: IpAddr,
+) -> ServelessCoreResult<()> {
+    let account = account_id(email);
+    let failures = LoginAttemptCacheRepo::record_failure(
+        conn,
+        AttemptSubject::Account,
+        &account,
+        policy.window,
+    )
+    .await
+    .map_err(cache_error)?;
+    if failures >= policy.max_failed_logins {
+        LoginAttemptCacheRepo::lock(
+            conn,
+            AttemptSubject::Account,
+            &account,
+            policy.lockout_duration,
+        )
+        .await
+        .map_err(cache_error)?;
+        warn!(
+            target: AUDIT_TARGET,
+            "login.account_locked email={} ip={} failures={} duration={}s",
+            account,
+            ip,
+            failures,
+            policy.lockout_duration
+        );
+    } else {
+        let delay = delay_after(failures);
+        if delay > 0 {
+            LoginAttemptCacheRepo::delay(conn, AttemptSubject::Account, &account, delay)
+                .await
+                .map_err(cache_error)?;
+        }
+    }
+
+    // IP addresses are only locked, not delayed, as many users may share one
+    let ip_id = ip.to_string();
+    let failures =
+        LoginAttemptCacheRepo::record_failure(conn, AttemptSubject::Ip, &ip_id, policy.window)
+            .await
+            .map_err(cache_error)?;
+    if failures >= policy.max_failed_logins_per_ip {
+        LoginAttemptCacheRepo::lock(conn, AttemptSubject::Ip, &ip_id, policy.lockout_duration)
+            .await
+            .map_err(cache_error)?;
+        warn!(
+            target: AUDIT_TARGET,
+            "login.ip_locked ip={} failures={} duration={}s",
+            ip,
+            failures,
+            policy.lockout_duration
+        );
+    }
+    Ok(())
+}
+
+/// Clears an account's failed logins after it logged in.
+///
+/// The IP address keeps its count, so logging in to one account doesn't
+/// reset guessing against others.
+pub async fn record_success(
+    conn: &mut MultiplexedConnection,
+    email: &str,
+) -> ServelessCoreResult<()> {
+    LoginAttemptCacheRepo::clear(conn, AttemptSubject::Account, &account_id(email))
+        .await
+        .map_err(cache_error)?;
+    Ok(())
+}
+
+/// Lifts the lockout of an account or IP address, as an operator action.
+///
+/// # Arguments
+///
+/// * `target` - An IP address, or otherwise the email of an account
+///
+/// # Returns
+///
+/// Whether the target was locked out.
+pub async fn unlock(conn: &mut MultiplexedConnection, target: &str) -> ServelessCoreResult<bool> {
+    let (subject, id) = match target.trim().parse::<IpAddr>() {
+        Ok(ip) => (AttemptSubject::Ip, ip.to_string()),
+        Err(_) => (AttemptSubject::Account, account_id(target)),
+    };
+    let was_locked = LoginAttemptCacheRepo::clear(conn, subject, &id)
+        .await
+        .map_err(cache_error)?;
+    info!(
+        target: AUDIT_TARGET,
+        "login.unlocked subject={:?} id={} was_locked={}", subject, id, was_locked
+    );
+    Ok(was_locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_grow_after_the_free_attempts_and_are_capped() {
+        let delays: Vec<u64> = (1..=10).map(delay_after).collect();
+        assert_eq!(delays, vec![0, 0, 1, 2, 4, 8, 16, 30, 30, 30]);
+    }
+
+    #[test]
+    fn test_accounts_are_counted_case_insensitively() {
+        assert_eq!(account_id(" Me@Example.com "), "me@example.com");
+    }
+}
@@ -0,0 +1,602 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entities::auth::Model as AuthUser;
+use db_entities::organization::Model as OrganizationModel;
+use db_entities::organization_invitation::Model as InvitationModel;
+use rand_core::{OsRng, RngCore};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db::auth::AuthDBRepo;
+use crate::db::organization::OrganizationDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::tokens::Scope;
+
+/// How long an invitation can be accepted for.
+const INVITATION_VALIDITY_DAYS: i64 = 7;
+
+/// A member's role in an organization, from least to most privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Developer,
+    Admin,
+    Owner,
+}
+
+/// Something a member can be allowed to do within an organization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// List and inspect functions, their versions, artifacts and secret names.
+    ReadFunctions,
+    /// Deploy functions and change their environment, secrets and aliases.
+    DeployFunctions,
+    /// Delete functions.
+    DeleteFunctions,
+    /// Invite members, change their roles and remove them.
+    ManageMembers,
+    /// Read and export the organization's audit log.
+    ViewAudit,
+}
+
+impl Role {
+    /// The name the role is stored and shown as.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Developer => "developer",
+            Role::Admin => "admin",
+            Role::Owner => "owner",
+        }
+    }
+
+    /// Whether members with this role have `permission`.
+    pub fn allows(self, permission: Permission) -> bool {
+        match permission {
+            Permission::ReadFunctions => true,
+            Permission::DeployFunctions => self >= Role::Developer,
+            Permission::DeleteFunctions | Permission::ManageMembers | Permission::ViewAudit => {
+                self >= Role::Admin
+            }
+        }
+    }
+}
+
+impl Permission {
+    /// The scope a personal access token needs to use this permission.
+    pub fn scope(self) -> Scope {
+        match self {
+            Permission::ReadFunctions | Permission::ViewAudit => Scope::Read,
+            Permission::DeployFunctions => Scope::Deploy,
+            Permission::DeleteFunctions | Permission::ManageMembers => Scope::Admin,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ServelessCoreError;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "viewer" => Ok(Role::Viewer),
+            "developer" => Ok(Role::Developer),
+            "admin" => Ok(Role::Admin),
+            "owner" => Ok(Role::Owner),
+            _ => Err(ServelessCoreError::BadRequest(format!(
+                "Unknown role '{role}'; expected owner, admin, developer or viewer"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Permission::ReadFunctions => "read functions",
+            Permission::DeployFunctions => "deploy functions",
+            Permission::DeleteFunctions => "delete functions",
+            Permission::ManageMembers => "manage members",
+            Permission::ViewAudit => "view the audit log",
+        })
+    }
+}
+
+/// The organization a request acts on and the caller's role in it.
+///
+/// # Fields
+/// - `user`: The calling user.
+/// - `organization`: The organization, whose UUID is the namespace of its functions.
+/// - `role`: The caller's role in the organization.
+#[derive(Debug, Clone)]
+pub struct Membership {
+    pub user: AuthUser,
+    pub organization: OrganizationModel,
+    pub role: Role,
+}
+
+/// An organization the caller belongs to.
+#[derive(Debug, Serialize)]
+pub struct OrganizationSummary {
+    pub uuid: Uuid,
+    pub name: String,
+    pub personal: bool,
+    pub role: Role,
+}
+
+/// A member of an organization.
+#[derive(Debug, Serialize)]
+pub struct MemberSummary {
+    pub user_uuid: Uuid,
+    pub email: String,
+    pub role: Role,
+    pub joined_at: String,
+}
+
+/// A newly created invitation. The token is only ever returned here.
+#[derive(Debug, Serialize)]
+pub struct CreatedInvitation {
+    pub email: String,
+    pub role: Role,
+    pub token: String,
+    pub expires_at: String,
+}
+
+/// Checks that a member with role `actor` may move a member from `current` to
+/// `new`, where `None` means not being a member.
+///
+/// Admins manage everyone but owners; only owners can grant or take away the
+/// owner role.
+fn check_role_change(
+    actor: Role,
+    current: Option<Role>,
+    new: Option<Role>,
+) -> Result<(), &'static str> {
+    if !actor.allows(Permission::ManageMembers) {
+        return Err("Only owners and admins can manage members");
+    }
+    if actor != Role::Owner && (current == Some(Role::Owner) || new == Some(Role::Owner)) {
+        return Err("Only owners can grant or revoke the owner role");
+    }
+    Ok(())
+}
+
+/// Hashes an invitation token for storage.
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+async fn find_user(conn: &DatabaseConnection, user_uuid: Uuid) -> ServelessCoreResult<AuthUser> {
+    AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{user_uuid}' not found")))
+}
+
+fn parse_role(role: &str) -> ServelessCoreResult<Role> {
+    role.parse()
+        .map_err(|_| ServelessCoreError::SystemError(format!("Stored role '{role}' is invalid")))
+}
+
+/// Resolves the organization a request acts on and the caller's role in it.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `user_uuid` - The calling user.
+/// * `reference` - The organization's name or UUID; the caller's personal
+///   organization when `None`.
+pub async fn resolve_membership(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+    reference: Option<&str>,
+) -> ServelessCoreResult<Membership> {
+    let user = find_user(conn, user_uuid).await?;
+    let reference = reference.unwrap_or_default();
+    let organization = match Uuid::parse_str(reference) {
+        Ok(uuid) => OrganizationDBRepo::find_by_uuid(conn, uuid).await,
+        Err(_) if reference.is_empty() => OrganizationDBRepo::find_by_uuid(conn, user_uuid).await,
+        Err(_) => OrganizationDBRepo::find_by_name(conn, reference).await,
+    }
+    .map_err(db_error)?;
+
+    // Organizations the caller doesn't belong to are reported as missing so
+    // their names can't be probed.
+    let not_found =
+        || ServelessCoreError::NotFound(format!("Organization '{reference}' not found"));
+    let organization = organization.ok_or_else(not_found)?;
+    let member = OrganizationDBRepo::find_member(conn, organization.id, user.id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+
+    Ok(Membership {
+        role: parse_role(&member.role)?,
+        user,
+        organization,
+    })
+}
+
+/// Creates an organization owned by the caller.
+///
+/// Names follow the same rules as function names, which keeps them distinct
+/// from personal organizations, named after their owner's email.
+pub async fn create_organization(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+    name: &str,
+) -> ServelessCoreResult<OrganizationSummary> {
+    shared_utils::validate_function_name(name).map_err(|e| {
+        ServelessCoreError::BadRequest(format!("Invalid organization name '{name}': {e}"))
+    })?;
+    let user = find_user(conn, user_uuid).await?;
+    if OrganizationDBRepo::find_by_name(conn, name)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err(ServelessCoreError::Conflict(format!(
+            "Organization '{name}' already exists"
+        )));
+    }
+
+    let organization = OrganizationDBRepo::create_organization(conn, name, user.id)
+        .await
+        .map_err(db_error)?;
+    info!("Organization '{}' created by {}", name, user.email);
+    Ok(OrganizationSummary {
+        uuid: organization.uuid,
+        name: organization.name,
+        personal: false,
+        role: Role::Owner,
+    })
+}
+
+/// Lists the organizations the caller belongs to, personal one included.
+pub async fn list_organizations(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<Vec<OrganizationSummary>> {
+    let user = find_user(conn, user_uuid).await?;
+    let memberships = OrganizationDBRepo::list_for_user(conn, user.id)
+        .await
+        .map_err(db_error)?;
+
+    let mut organizations = Vec::with_capacity(memberships.len());
+    for (member, organization) in memberships {
+        if let Some(organization) = organization {
+            organizations.push(OrganizationSummary {
+                uuid: organization.uuid,
+                name: organization.name,
+                personal: organization.personal,
+                role: parse_role(&member.role)?,
+            });
+        }
+    }
+    Ok(organizations)
+}
+
+/// Lists the members of the organization a membership belongs to.
+pub async fn list_members(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+) -> ServelessCoreResult<Vec<MemberSummary>> {
+    let members = OrganizationDBRepo::list_members(conn, membership.organization.id)
+        .await
+        .map_err(db_error)?;
+
+    let mut summaries = Vec::with_capacity(members.len());
+    for (member, user) in members {
+        if let Some(user) = user {
+            summaries.push(MemberSummary {
+                user_uuid: user.uuid,
+                email: user.email,
+                role: parse_role(&member.role)?,
+                joined_at: member.created_at.to_rfc3339(),
+            });
+        }
+    }
+    Ok(summaries)
+}
+
+/// Invites someone to the caller's organization with a role.
+///
+/// The returned token is what the invitee accepts; only its hash is stored.
+/// Personal organizations can't have other members.
+pub async fn invite_member(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+    email: &str,
+    role: Role,
+) -> ServelessCoreResult<CreatedInvitation> {
+    if membership.organization.personal {
+        return Err(ServelessCoreError::BadRequest(
+            "Personal organizations can't have other members; create an organization first"
+                .to_string(),
+        ));
+    }
+    if email.trim().is_empty() {
+        return Err(ServelessCoreError::BadRequest(
+            "Email is required".to_string(),
+        ));
+    }
+    check_role_change(membership.role, None, Some(role))
+        .map_err(|e| ServelessCoreError::Forbidden(e.to_string()))?;
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    let now = Utc::now().fixed_offset();
+    let expires_at = now + Duration::days(INVITATION_VALIDITY_DAYS);
+
+    let invitation = OrganizationDBRepo::create_invitation(
+        conn,
+        InvitationModel {
+            id: 0,
+            organization_id: membership.organization.id,
+            email: email.trim().to_lowercase(),
+            role: role.as_str().to_string(),
+            token_hash: hash_token(&token),
+            invited_by: membership.user.id,
+            created_at: now,
+            expires_at,
+            accepted_at: None,
+        },
+    )
+    .await
+    .map_err(db_error)?;
+
+    info!(
+        "{} invited {} to organization '{}' as {}",
+        membership.user.email, invitation.email, membership.organization.name, role
+    );
+    Ok(CreatedInvitation {
+        email: invitation.email,
+        role,
+        token,
+        expires_at: invitation.expires_at.to_rfc3339(),
+    })
+}
+
+/// Checks that an invitation can still be accepted by `email` at `now`.
+fn check_invitation(
+    invitation: &InvitationModel,
+    email: &str,
+    now: DateTime<FixedOffset>,
+) -> ServelessCoreResult<()> {
+    if invitation.accepted_at.is_some() {
+        return Err(ServelessCoreError::Conflict(
+            "Invitation was already accepted".to_string(),
+        ));
+    }
+    if invitation.expires_at <= now {
+        return Err(ServelessCoreError::Forbidden(
+            "Invitation has expired".to_string(),
+        ));
+    }
+    if !invitation.email.eq_ignore_ascii_case(email) {
+        return Err(ServelessCoreError::Forbidden(
+            "Invitation was sent to a different email".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Accepts an invitation on behalf of the caller.
+///
+/// # Returns
+///
+/// The organization joined, with the caller's new role.
+pub async fn accept_invitation(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+    token: &str,
+) -> ServelessCoreResult<OrganizationSummary> {
+    let user = find_user(conn, user_uuid).await?;
+    let invitation = OrganizationDBRepo::find_invitation(conn, &hash_token(token))
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound("Invitation not found".to_string()))?;
+    let now = Utc::now().fixed_offset();
+    check_invitation(&invitation, &user.email, now)?;
+
+    let organization = OrganizationDBRepo::find_by_id(conn, invitation.organization_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound("Organization not found".to_string()))?;
+    if OrganizationDBRepo::find_member(conn, organization.id, user.id)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err(ServelessCoreError::Conflict(format!(
+            "Already a member of organization '{}'",
+            organization.name
+        )));
+    }
+
+    let member = OrganizationDBRepo::accept_invitation(conn, invitation, user.id, now)
+        .await
+        .map_err(db_error)?;
+    info!("{} joined organization '{}'", user.email, organization.name);
+    Ok(OrganizationSummary {
+        uuid: organization.uuid,
+        name: organization.name,
+        personal: organization.personal,
+        role: parse_role(&member.role)?,
+    })
+}
+
+/// Finds another member of the caller's organization.
+async fn find_target(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+    target_uuid: Uuid,
+) -> ServelessCoreResult<db_entities::organization_member::Model> {
+    let target = find_user(conn, target_uuid).await?;
+    OrganizationDBRepo::find_member(conn, membership.organization.id, target.id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            ServelessCoreError::NotFound(format!(
+                "User '{target_uuid}' is not a member of organization '{}'",
+                membership.organization.name
+            ))
+        })
+}
+
+/// Fails if the member being demoted or removed is the organization's last owner.
+async fn ensure_other_owner(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+    current: Role,
+) -> ServelessCoreResult<()> {
+    if current != Role::Owner {
+        return Ok(());
+    }
+    let owners = OrganizationDBRepo::count_owners(conn, membership.organization.id)
+        .await
+        .map_err(db_error)?;
+    if owners <= 1 {
+        return Err(ServelessCoreError::Conflict(
+            "An organization must keep at least one owner".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Changes the role of a member of the caller's organization.
+pub async fn change_role(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+    target_uuid: Uuid,
+    role: Role,
+) -> ServelessCoreResult<MemberSummary> {
+    let target = find_target(conn, membership, target_uuid).await?;
+    let current = parse_role(&target.role)?;
+    check_role_change(membership.role, Some(current), Some(role))
+        .map_err(|e| ServelessCoreError::Forbidden(e.to_string()))?;
+    if role != Role::Owner {
+        ensure_other_owner(conn, membership, current).await?;
+    }
+
+    let target = OrganizationDBRepo::set_role(conn, target, role.as_str())
+        .await
+        .map_err(db_error)?;
+    let user = find_user(conn, target_uuid).await?;
+    info!(
+        "{} changed the role of {} in organization '{}' to {}",
+        membership.user.email, user.email, membership.organization.name, role
+    );
+    Ok(MemberSummary {
+        user_uuid: user.uuid,
+        email: user.email,
+        role,
+        joined_at: target.created_at.to_rfc3339(),
+    })
+}
+
+/// Removes a member from the caller's organization. Any member may remove
+/// themselves, which is how an organization is left.
+pub async fn remove_member(
+    conn: &DatabaseConnection,
+    membership: &Membership,
+    target_uuid: Uuid,
+) -> ServelessCoreResult<()> {
+    let target = find_target(conn, membership, target_uuid).await?;
+    let current = parse_role(&target.role)?;
+    if target_uuid != membership.user.uuid {
+        check_role_change(membership.role, Some(current), None)
+            .map_err(|e| ServelessCoreError::Forbidden(e.to_string()))?;
+    } else if membership.organization.personal {
+        return Err(ServelessCoreError::BadRequest(
+            "You can't leave your personal organization".to_string(),
+        ));
+    }
+    ensure_other_owner(conn, membership, current).await?;
+
+    OrganizationDBRepo::remove_member(conn, target)
+        .await
+        .map_err(db_error)?;
+    info!(
+        "User '{}' removed from organization '{}'",
+        target_uuid, membership.organization.name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_grant_increasing_permissions() {
+        let allowed = |role: Role| {
+            [
+                Permission::ReadFunctions,
+                Permission::DeployFunctions,
+                Permission::DeleteFunctions,
+                Permission::ManageMembers,
+                Permission::ViewAudit,
+            ]
+            .into_iter()
+            .filter(|permission| role.allows(*permission))
+            .count()
+        };
+        assert_eq!(allowed(Role::Viewer), 1);
+        assert_eq!(allowed(Role::Developer), 2);
+        assert_eq!(allowed(Role::Admin), 5);
+        assert_eq!(allowed(Role::Owner), 5);
+        assert_eq!("developer".parse::<Role>().unwrap(), Role::Developer);
+        assert!("root".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn test_only_owners_manage_owners() {
+        assert!(check_role_change(Role::Developer, Some(Role::Viewer), None).is_err());
+        assert!(check_role_change(Role::Admin, None, Some(Role::Developer)).is_ok());
+        assert!(check_role_change(Role::Admin, Some(Role::Viewer), Some(Role::Admin)).is_ok());
+        assert!(check_role_change(Role::Admin, None, Some(Role::Owner)).is_err());
+        assert!(check_role_change(Role::Admin, Some(Role::Owner), None).is_err());
+        assert!(check_role_change(Role::Owner, Some(Role::Owner), Some(Role::Admin)).is_ok());
+    }
+
+    #[test]
+    fn test_invitations_are_single_use_and_expire() {
+        let now = Utc::now().fixed_offset();
+        let invitation = InvitationModel {
+            id: 1,
+            organization_id: 1,
+            email: "dev@example.com".to_string(),
+            role: "developer".to_string(),
+            token_hash: hash_token("token"),
+            invited_by: 1,
+            created_at: now,
+            expires_at: now + Duration::days(1),
+            accepted_at: None,
+        };
+        assert!(check_invitation(&invitation, "Dev@Example.com", now).is_ok());
+        assert!(check_invitation(&invitation, "other@example.com", now).is_err());
+        assert!(check_invitation(&invitation, "dev@example.com", now + Duration::days(2)).is_err());
+
+        let accepted = InvitationModel {
+            accepted_at: Some(now),
+            ..invitation
+        };
+        assert!(check_invitation(&accepted, "dev@example.com", now).is_err());
+    }
+}
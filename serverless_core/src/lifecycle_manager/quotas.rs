@@ -0,0 +1,530 @@
+use chrono::Utc;
+use db_entities::namespace_quota::Model as QuotaModel;
+use redis::aio::MultiplexedConnection;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::cache::FunctionCacheRepo;
+use crate::db::function::FunctionDBRepo;
+use crate::db::organization::OrganizationDBRepo;
+use crate::db::quota::NamespaceQuotaDBRepo;
+use crate::db::usage::{UsageCacheRepo, UsageCounter};
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::invoke::{instance_key, TIMEOUT_DEFAULT_IN_SECONDS};
+
+/// Memory of an instance whose version doesn't request any; the runtime's default limit.
+pub const DEFAULT_INSTANCE_MEMORY_MB: u64 = 256;
+
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+
+/// Limits on what a namespace may use. A limit of 0 means unlimited.
+///
+/// # Fields
+/// - `max_functions`: Functions the namespace may have.
+/// - `max_instances`: Instances of its functions that may run at the same time.
+/// - `max_memory_mb`: Total memory of its running instances.
+/// - `builds_per_hour`: Function builds per clock hour (UTC).
+/// - `invocations_per_day`: Function invocations per day (UTC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Quota {
+    pub max_functions: u64,
+    pub max_instances: u64,
+    pub max_memory_mb: u64,
+    pub builds_per_hour: u64,
+    pub invocations_per_day: u64,
+}
+
+impl Quota {
+    /// Applies a namespace's overrides on top of these limits.
+    pub fn with_overrides(self, overrides: &QuotaModel) -> Self {
+        let pick = |value: Option<i64>, default: u64| {
+            value.map_or(default, |value| u64::try_from(value).unwrap_or(0))
+        };
+        Self {
+            max_functions: pick(overrides.max_functions, self.max_functions),
+            max_instances: pick(overrides.max_instances, self.max_instances),
+            max_memory_mb: pick(overrides.max_memory_mb, self.max_memory_mb),
+            builds_per_hour: pick(overrides.builds_per_hour, self.builds_per_hour),
+            invocations_per_day: pick(overrides.invocations_per_day, self.invocations_per_day),
+        }
+    }
+}
+
+/// Limits set for one namespace; unset ones fall back to the server defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaOverrides {
+    pub max_functions: Option<u64>,
+    pub max_instances: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    pub builds_per_hour: Option<u64>,
+    pub invocations_per_day: Option<u64>,
+}
+
+impl From<&QuotaModel> for QuotaOverrides {
+    fn from(model: &QuotaModel) -> Self {
+        let limit = |value: Option<i64>| value.map(|value| u64::try_from(value).unwrap_or(0));
+        Self {
+            max_functions: limit(model.max_functions),
+            max_instances: limit(model.max_instances),
+            max_memory_mb: limit(model.max_memory_mb),
+            builds_per_hour: limit(model.builds_per_hour),
+            invocations_per_day: limit(model.invocations_per_day),
+        }
+    }
+}
+
+/// The quota of a namespace as shown to operators.
+///
+/// # Fields
+/// - `namespace`: The UUID of the organization.
+/// - `overrides`: The limits set for the namespace.
+/// - `effective`: The limits enforced, with the server defaults filled in.
+#[derive(Debug, Serialize)]
+pub struct NamespaceQuota {
+    pub namespace: Uuid,
+    pub overrides: QuotaOverrides,
+    pub effective: Quota,
+}
+
+/// How much of one limit a namespace uses.
+///
+/// # Fields
+/// - `used`: The current usage.
+/// - `limit`: The limit, or `None` when unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct UsageItem {
+    pub used: u64,
+    pub limit: Option<u64>,
+}
+
+impl UsageItem {
+    fn new(used: u64, limit: u64) -> Self {
+        Self {
+            used,
+            limit: (limit > 0).then_some(limit),
+        }
+    }
+}
+
+/// What a namespace uses against its quota.
+#[derive(Debug, Serialize)]
+pub struct Usage {
+    pub namespace: Uuid,
+    pub functions: UsageItem,
+    pub instances: UsageItem,
+    pub memory_mb: UsageItem,
+    pub builds_this_hour: UsageItem,
+    pub invocations_today: UsageItem,
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+fn cache_error(e: redis::RedisError) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Whether `requested` more fits next to `used` under `limit`; 0 is unlimited.
+fn fits(limit: u64, used: u64, requested: u64) -> bool {
+    limit == 0 || used.saturating_add(requested) <= limit
+}
+
+/// The index of the window of `length` seconds containing `now`, and the
+/// seconds left until the next one starts.
+fn window(now: i64, length: i64) -> (i64, u64) {
+    let index = now.div_euclid(length);
+    let remaining = length - now.rem_euclid(length);
+    (index, remaining as u64)
+}
+
+/// Memory an instance of a version is limited to, in MB.
+fn instance_memory_mb(memory_mb: Option<i32>) -> u64 {
+    memory_mb
+        .and_then(|mb| u64::try_from(mb).ok())
+        .unwrap_or(DEFAULT_INSTANCE_MEMORY_MB)
+}
+
+fn to_column(limit: Option<u64>) -> Option<i64> {
+    limit.map(|limit| i64::try_from(limit).unwrap_or(i64::MAX))
+}
+
+/// Resolves the quota enforced on a namespace: its overrides over `defaults`.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `defaults` - The server's default quota.
+/// * `namespace` - The UUID of the organization.
+pub async fn effective_quota(
+    conn: &DatabaseConnection,
+    defaults: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<Quota> {
+    let overrides = NamespaceQuotaDBRepo::find_quota(conn, namespace)
+        .await
+        .map_err(db_error)?;
+    Ok(match overrides {
+        Some(overrides) => defaults.with_overrides(&overrides),
+        None => *defaults,
+    })
+}
+
+/// Checks that a namespace may create one more function.
+pub async fn check_function_count(
+    conn: &DatabaseConnection,
+    quota: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<()> {
+    if quota.max_functions == 0 {
+        return Ok(());
+    }
+    let functions = FunctionDBRepo::count_functions(conn, namespace)
+        .await
+        .map_err(db_error)?;
+    if !fits(quota.max_functions, functions, 1) {
+        return Err(ServelessCoreError::Forbidden(format!(
+            "Namespace '{namespace}' has reached its quota of {} functions",
+            quota.max_functions
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a single instance of a version fits in the namespace's memory quota.
+///
+/// A version asking for more memory than the whole quota could never start, so
+/// it is rejected when deployed rather than on every invocation.
+pub fn check_version_memory(quota: &Quota, memory_mb: Option<i32>) -> ServelessCoreResult<()> {
+    let memory_mb = instance_memory_mb(memory_mb);
+    if !fits(quota.max_memory_mb, 0, memory_mb) {
+        return Err(ServelessCoreError::Forbidden(format!(
+            "An instance needs {memory_mb} MB, more than the quota of {} MB",
+            quota.max_memory_mb
+        )));
+    }
+    Ok(())
+}
+
+/// Counts one use of a per-window quota, rejecting it once the window's limit is reached.
+///
+/// Rejected uses are not counted, so callers can retry once the window resets.
+async fn count_use(
+    cache_conn: &mut MultiplexedConnection,
+    counter: UsageCounter,
+    namespace: Uuid,
+    limit: u64,
+    window_length: i64,
+) -> ServelessCoreResult<()> {
+    let (index, remaining) = window(Utc::now().timestamp(), window_length);
+    let used =
+        UsageCacheRepo::increment(cache_conn, counter, namespace, index, window_length as u64)
+            .await
+            .map_err(cache_error)?;
+    if fits(limit, used, 0) {
+        return Ok(());
+    }
+
+    let _ = UsageCacheRepo::decrement(cache_conn, counter, namespace, index).await;
+    warn!(
+        "Namespace '{}' reached its quota of {} {}",
+        namespace,
+        limit,
+        counter.as_str()
+    );
+    let message = match counter {
+        UsageCounter::Builds => format!("Build quota of {limit} per hour reached"),
+        UsageCounter::Invocations => format!("Invocation quota of {limit} per day reached"),
+    };
+    Err(ServelessCoreError::TooManyRequests(message, remaining))
+}
+
+/// Counts a build of a namespace's function against its hourly quota.
+pub async fn count_build(
+    cache_conn: &mut MultiplexedConnection,
+    quota: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<()> {
+    count_use(
+        cache_conn,
+        UsageCounter::Builds,
+        namespace,
+        quota.builds_per_hour,
+        SECONDS_PER_HOUR,
+    )
+    .await
+}
+
+/// Counts an invocation of a namespace's function against its daily quota.
+pub async fn count_invocation(
+    cache_conn: &mut MultiplexedConnection,
+    quota: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<()> {
+    count_use(
+        cache_conn,
+        UsageCounter::Invocations,
+        namespace,
+        quota.invocations_per_day,
+        SECONDS_PER_DAY,
+    )
+    .await
+}
+
+/// Counts the running instances of a namespace's functions and their memory in MB.
+async fn running_instances(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    namespace: Uuid,
+) -> ServelessCoreResult<(u64, u64)> {
+    let functions = FunctionDBRepo::find_functions_by_user_uuid(conn, namespace)
+        .await
+        .map_err(db_error)?;
+    let versions = FunctionVersionDBRepo::list_versions_of(
+        conn,
+        functions.iter().map(|function| function.id).collect(),
+    )
+    .await
+    .map_err(db_error)?;
+
+    let mut keys = Vec::with_capacity(versions.len());
+    let mut memory = Vec::with_capacity(versions.len());
+    for version in &versions {
+        if let Some(function) = functions.iter().find(|f| f.id == version.function_id) {
+            keys.push(instance_key(&function.name, namespace, version.version));
+            memory.push(instance_memory_mb(version.memory_mb));
+        }
+    }
+    let addresses = FunctionCacheRepo::get_functions(cache_conn, &keys)
+        .await
+        .map_err(cache_error)?;
+
+    Ok(addresses
+        .iter()
+        .zip(memory)
+        .filter(|(addr, _)| addr.is_some())
+        .fold((0, 0), |(count, total), (_, mb)| (count + 1, total + mb)))
+}
+
+/// Checks that a namespace may start one more instance needing `memory_mb`.
+///
+/// Instances stop on their own once idle, so the caller is asked to retry after
+/// an instance's idle timeout.
+pub async fn check_instance_capacity(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    quota: &Quota,
+    namespace: Uuid,
+    memory_mb: Option<i32>,
+) -> ServelessCoreResult<()> {
+    if quota.max_instances == 0 && quota.max_memory_mb == 0 {
+        return Ok(());
+    }
+    let (instances, used_mb) = running_instances(conn, cache_conn, namespace).await?;
+    let memory_mb = instance_memory_mb(memory_mb);
+
+    let message = if !fits(quota.max_instances, instances, 1) {
+        format!(
+            "Instance quota of {} reached; {} instances are running",
+            quota.max_instances, instances
+        )
+    } else if !fits(quota.max_memory_mb, used_mb, memory_mb) {
+        format!(
+            "Memory quota of {} MB reached; {} MB in use, {} MB needed",
+            quota.max_memory_mb, used_mb, memory_mb
+        )
+    } else {
+        return Ok(());
+    };
+    Err(ServelessCoreError::TooManyRequests(
+        message,
+        TIMEOUT_DEFAULT_IN_SECONDS,
+    ))
+}
+
+/// Reports what a namespace uses against its quota.
+pub async fn usage(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    quota: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<Usage> {
+    let functions = FunctionDBRepo::count_functions(conn, namespace)
+        .await
+        .map_err(db_error)?;
+    let (instances, memory_mb) = running_instances(conn, cache_conn, namespace).await?;
+
+    let now = Utc::now().timestamp();
+    let (hour, _) = window(now, SECONDS_PER_HOUR);
+    let (day, _) = window(now, SECONDS_PER_DAY);
+    let builds = UsageCacheRepo::get(cache_conn, UsageCounter::Builds, namespace, hour)
+        .await
+        .map_err(cache_error)?;
+    let invocations = UsageCacheRepo::get(cache_conn, UsageCounter::Invocations, namespace, day)
+        .await
+        .map_err(cache_error)?;
+
+    Ok(Usage {
+        namespace,
+        functions: UsageItem::new(functions, quota.max_functions),
+        instances: UsageItem::new(instances, quota.max_instances),
+        memory_mb: UsageItem::new(memory_mb, quota.max_memory_mb),
+        builds_this_hour: UsageItem::new(builds, quota.builds_per_hour),
+        invocations_today: UsageItem::new(invocations, quota.invocations_per_day),
+    })
+}
+
+/// Returns the quota overrides and effective quota of a namespace.
+pub async fn namespace_quota(
+    conn: &DatabaseConnection,
+    defaults: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<NamespaceQuota> {
+    let overrides = NamespaceQuotaDBRepo::find_quota(conn, namespace)
+        .await
+        .map_err(db_error)?;
+    Ok(NamespaceQuota {
+        namespace,
+        effective: overrides
+            .as_ref()
+            .map_or(*defaults, |overrides| defaults.with_overrides(overrides)),
+        overrides: overrides.as_ref().map(Into::into).unwrap_or_default(),
+    })
+}
+
+/// Sets the quota overrides of a namespace, replacing any previous ones.
+pub async fn set_quota(
+    conn: &DatabaseConnection,
+    defaults: &Quota,
+    namespace: Uuid,
+    overrides: QuotaOverrides,
+) -> ServelessCoreResult<NamespaceQuota> {
+    OrganizationDBRepo::find_by_uuid(conn, namespace)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            ServelessCoreError::NotFound(format!("Namespace '{namespace}' not found"))
+        })?;
+
+    let stored = NamespaceQuotaDBRepo::upsert_quota(
+        conn,
+        QuotaModel {
+            namespace,
+            max_functions: to_column(overrides.max_functions),
+            max_instances: to_column(overrides.max_instances),
+            max_memory_mb: to_column(overrides.max_memory_mb),
+            builds_per_hour: to_column(overrides.builds_per_hour),
+            invocations_per_day: to_column(overrides.invocations_per_day),
+            id: 0,
+            updated_at: Utc::now().fixed_offset(),
+        },
+    )
+    .await
+    .map_err(db_error)?;
+    Ok(NamespaceQuota {
+        namespace,
+        overrides: (&stored).into(),
+        effective: defaults.with_overrides(&stored),
+    })
+}
+
+/// Removes the quota overrides of a namespace, so the server defaults apply again.
+pub async fn reset_quota(
+    conn: &DatabaseConnection,
+    defaults: &Quota,
+    namespace: Uuid,
+) -> ServelessCoreResult<NamespaceQuota> {
+    if !NamespaceQuotaDBRepo::delete_quota(conn, namespace)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(ServelessCoreError::NotFound(format!(
+            "Namespace '{namespace}' has no quota overrides"
+        )));
+    }
+    Ok(NamespaceQuota {
+        namespace,
+        overrides: QuotaOverrides::default(),
+        effective: *defaults,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(max_functions: Option<i64>, invocations_per_day: Option<i64>) -> QuotaModel {
+        QuotaModel {
+            id: 1,
+            namespace: Uuid::new_v4(),
+            max_functions,
+            max_instances: None,
+            max_memory_mb: None,
+            builds_per_hour: None,
+            invocations_per_day,
+            updated_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_overrides_replace_only_the_limits_they_set() {
+        let defaults = Quota {
+            max_functions: 50,
+            max_instances: 20,
+            max_memory_mb: 4096,
+            builds_per_hour: 30,
+            invocations_per_day: 0,
+        };
+        let quota = defaults.with_overrides(&overrides(Some(5), Some(1000)));
+        assert_eq!(quota.max_functions, 5);
+        assert_eq!(quota.invocations_per_day, 1000);
+        assert_eq!(quota.max_instances, 20);
+        assert_eq!(quota.builds_per_hour, 30);
+
+        // An override of 0 lifts the limit.
+        assert_eq!(
+            defaults
+                .with_overrides(&overrides(Some(0), None))
+                .max_functions,
+            0
+        );
+    }
+
+    #[test]
+    fn test_zero_limits_are_unlimited() {
+        assert!(fits(0, u64::MAX, 1));
+        assert!(fits(2, 1, 1));
+        assert!(!fits(2, 2, 1));
+        assert!(fits(3, 3, 0));
+        assert!(!fits(3, 4, 0));
+        assert_eq!(UsageItem::new(3, 0).limit, None);
+        assert_eq!(UsageItem::new(3, 10).limit, Some(10));
+    }
+
+    #[test]
+    fn test_windows_end_on_clock_boundaries() {
+        assert_eq!(window(0, SECONDS_PER_HOUR), (0, 3600));
+        assert_eq!(window(3599, SECONDS_PER_HOUR), (0, 1));
+        assert_eq!(window(3600, SECONDS_PER_HOUR), (1, 3600));
+        assert_eq!(window(SECONDS_PER_DAY + 60, SECONDS_PER_DAY), (1, 86340));
+    }
+
+    #[test]
+    fn test_versions_larger_than_the_memory_quota_are_rejected() {
+        let quota = Quota {
+            max_memory_mb: 512,
+            ..Default::default()
+        };
+        assert!(check_version_memory(&quota, Some(512)).is_ok());
+        assert!(check_version_memory(&quota, None).is_ok());
+        assert!(matches!(
+            check_version_memory(&quota, Some(1024)),
+            Err(ServelessCoreError::Forbidden(_))
+        ));
+        assert!(check_version_memory(&Quota::default(), Some(1 << 20)).is_ok());
+    }
+}
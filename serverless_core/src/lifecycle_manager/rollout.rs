@@ -0,0 +1,247 @@
+use db_entities::function::Model as FunctionModel;
+use db_entities::function_alias::Model as AliasModel;
+use redis::aio::MultiplexedConnection;
+use runtime::core::runner::stop_container;
+use sea_orm::DatabaseConnection;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::cache::FunctionCacheRepo;
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::invoke::instance_key;
+use crate::utils::utils::container_name_from_addr;
+
+/// Expiry of an instance's in-flight request counter, comfortably above the
+/// downstream request timeout.
+pub const IN_FLIGHT_TTL_SECS: u64 = 60;
+/// How often a draining instance is checked for remaining in-flight requests.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Seconds Docker waits for a drained container to exit before killing it.
+const STOP_GRACE_PERIOD_SECS: i64 = 5;
+
+/// The version whose instance a deploy retires: the one that served default
+/// traffic until now, unless an alias still routes some of its traffic there.
+///
+/// # Arguments
+///
+/// * `previous` - The version that was the newest before the deploy, if any.
+/// * `aliases` - The aliases of the function.
+pub fn retired_version(previous: Option<i32>, aliases: &[AliasModel]) -> Option<i32> {
+    previous.filter(|&previous| {
+        !aliases
+            .iter()
+            .any(|alias| alias.version == previous || alias.canary_version == Some(previous))
+    })
+}
+
+/// Whether a draining instance can be stopped: once it has no requests left
+/// in flight, or once its drain timed out.
+fn drained(in_flight: i64, timed_out: bool) -> bool {
+    in_flight <= 0 || timed_out
+}
+
+/// Whether the warmed-up instance of a failed deploy is discarded.
+///
+/// It is, unless the version was recorded by a concurrent deploy, in which
+/// case the instance cached under its key is that deploy's.
+pub fn discards_warm_instance(error: &ServelessCoreError) -> bool {
+    !matches!(error, ServelessCoreError::Conflict(_))
+}
+
+/// Takes an instance out of rotation and stops it once it is idle.
+///
+/// The cached address is removed first so new requests cold-start (or reuse) the
+/// current version, then in-flight requests to the old container are given up to
+/// `drain_timeout` to finish before the container is stopped.
+///
+/// # Arguments
+///
+/// * `cache_conn` - The Redis connection.
+/// * `instance_key` - The cache key of the instance to drain.
+/// * `drain_timeout` - Maximum time to wait for in-flight requests.
+pub async fn drain_instance(
+    mut cache_conn: MultiplexedConnection,
+    instance_key: String,
+    drain_timeout: Duration,
+) {
+    let addr = match FunctionCacheRepo::remove_function(&mut cache_conn, &instance_key).await {
+        Ok(Some(addr)) => addr,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to take '{}' out of rotation: {}", instance_key, e);
+            return;
+        }
+    };
+
+    let deadline = Instant::now() + drain_timeout;
+    loop {
+        match FunctionCacheRepo::in_flight(&mut cache_conn, &addr).await {
+            Ok(count) if drained(count, Instant::now() >= deadline) => {
+                if count > 0 {
+                    warn!(
+                        "Drain of '{}' timed out with {} requests in flight",
+                        addr, count
+                    );
+                }
+                break;
+            }
+            Ok(_) => tokio::time::sleep(DRAIN_POLL_INTERVAL).await,
+            Err(e) => {
+                error!("Failed to read in-flight requests for '{}': {}", addr, e);
+                break;
+            }
+        }
+    }
+
+    let container_name = container_name_from_addr(&addr);
+    match stop_container(container_name, STOP_GRACE_PERIOD_SECS).await {
+        Ok(()) => info!("Drained and stopped instance '{}'", container_name),
+        Err(e) => warn!(
+            "Failed to stop drained instance '{}': {}",
+            container_name, e
+        ),
+    }
+}
+
+/// Stops an instance that never served traffic, without draining it.
+///
+/// Used for the warmed-up instance of a version whose deploy didn't complete, so
+/// a later deploy reusing the version number can't pick it up from the cache.
+///
+/// # Arguments
+///
+/// * `cache_conn` - The Redis connection.
+/// * `instance_key` - The cache key of the instance to stop.
+///
+/// # Returns
+///
+/// An error if the cache entry could not be removed; failing to stop the
+/// container is only logged, as it is no longer reachable.
+pub async fn discard_instance(
+    cache_conn: &mut MultiplexedConnection,
+    instance_key: &str,
+) -> ServelessCoreResult<()> {
+    let addr = FunctionCacheRepo::remove_function(cache_conn, instance_key)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    if let Some(addr) = addr {
+        let container_name = container_name_from_addr(&addr);
+        match stop_container(container_name, STOP_GRACE_PERIOD_SECS).await {
+            Ok(()) => info!("Discarded instance '{}'", container_name),
+            Err(e) => warn!(
+                "Failed to stop discarded instance '{}': {}",
+                container_name, e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Takes every running instance of a function out of rotation so that the next
+/// invocation starts a fresh container, e.g. after its secrets changed.
+///
+/// Instances are drained in the background; in-flight requests are allowed to
+/// finish for up to `drain_timeout`.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `cache_conn` - The Redis connection.
+/// * `function` - The function whose instances are recycled.
+/// * `user_uuid` - The UUID of the user (namespace) who owns the function.
+/// * `drain_timeout` - Maximum time to wait for in-flight requests.
+pub async fn recycle_instances(
+    conn: &DatabaseConnection,
+    cache_conn: MultiplexedConnection,
+    function: &FunctionModel,
+    user_uuid: Uuid,
+    drain_timeout: Duration,
+) -> ServelessCoreResult<()> {
+    let versions = FunctionVersionDBRepo::list_versions(conn, function.id)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    let versions = versions.iter().map(|version| version.version);
+    for key in instance_keys(&function.name, user_uuid, versions) {
+        tokio::spawn(drain_instance(cache_conn.clone(), key, drain_timeout));
+    }
+    info!("Recycling instances of function '{}'", function.name);
+    Ok(())
+}
+
+/// The cache keys of the instances of every given version of a function.
+fn instance_keys(
+    name: &str,
+    user_uuid: Uuid,
+    versions: impl IntoIterator<Item = i32>,
+) -> Vec<String> {
+    versions
+        .into_iter()
+        .map(|version| instance_key(name, user_uuid, version))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(version: i32, canary_version: Option<i32>) -> AliasModel {
+        AliasModel {
+            id: 1,
+            function_id: 1,
+            name: "prod".to_string(),
+            version,
+            canary_version,
+            canary_weight: canary_version.map_or(0, |_| 10),
+        }
+    }
+
+    #[test]
+    fn test_unaliased_previous_version_is_retired() {
+        assert_eq!(retired_version(Some(3), &[]), Some(3));
+        assert_eq!(retired_version(Some(3), &[alias(2, None)]), Some(3));
+        assert_eq!(retired_version(None, &[]), None);
+    }
+
+    #[test]
+    fn test_aliased_previous_version_keeps_running() {
+        assert_eq!(retired_version(Some(3), &[alias(3, None)]), None);
+        assert_eq!(retired_version(Some(3), &[alias(2, Some(3))]), None);
+    }
+
+    #[test]
+    fn test_draining_waits_for_in_flight_requests_until_the_timeout() {
+        assert!(!drained(2, false));
+        assert!(drained(0, false));
+        // Expired counters can be left slightly negative.
+        assert!(drained(-1, false));
+        assert!(drained(2, true));
+    }
+
+    #[test]
+    fn test_failed_warm_up_is_discarded() {
+        assert!(discards_warm_instance(
+            &ServelessCoreError::FunctionFailedToStart("hello".to_string())
+        ));
+        assert!(discards_warm_instance(&ServelessCoreError::SystemError(
+            "database unavailable".to_string()
+        )));
+        assert!(!discards_warm_instance(&ServelessCoreError::Conflict(
+            "version 3 was recorded by another deploy".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_recycling_covers_every_version() {
+        let user_uuid = Uuid::new_v4();
+        assert_eq!(
+            instance_keys("hello", user_uuid, [1, 2]),
+            vec![
+                instance_key("hello", user_uuid, 1),
+                instance_key("hello", user_uuid, 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, FixedOffset};
+use db_entities::function::Model as FunctionModel;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+use crate::db::secret::FunctionSecretDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::utils::secrets::{is_valid_env_key, SecretCipher};
+
+/// Encrypts and stores secrets for a function, replacing existing values.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `cipher` - The cipher used to encrypt values at rest.
+/// * `function` - The function the secrets belong to.
+/// * `secrets` - Environment variable names and their plaintext values.
+///
+/// # Returns
+///
+/// The keys that were stored.
+pub async fn set_secrets(
+    conn: &DatabaseConnection,
+    cipher: &SecretCipher,
+    function: &FunctionModel,
+    secrets: HashMap<String, String>,
+) -> ServelessCoreResult<Vec<String>> {
+    if let Some(key) = secrets.keys().find(|key| !is_valid_env_key(key)) {
+        return Err(ServelessCoreError::BadFunction(format!(
+            "Invalid secret name '{key}'"
+        )));
+    }
+
+    let mut keys = Vec::with_capacity(secrets.len());
+    for (key, value) in secrets {
+        let (nonce, ciphertext) = cipher
+            .encrypt(&value)
+            .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+        FunctionSecretDBRepo::upsert_secret(conn, function.id, &key, nonce, ciphertext)
+            .await
+            .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+        keys.push(key);
+    }
+    keys.sort();
+
+    info!("Secrets {:?} set for function '{}'", keys, function.name);
+    Ok(keys)
+}
+
+/// Removes a secret from a function.
+///
+/// Returns `FunctionNotRegistered` if the function has no secret named `key`.
+pub async fn unset_secret(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+    key: &str,
+) -> ServelessCoreResult<()> {
+    let deleted = FunctionSecretDBRepo::delete_secret(conn, function.id, key)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    if !deleted {
+        return Err(ServelessCoreError::FunctionNotRegistered(format!(
+            "Secret '{}' not found for function '{}'",
+            key, function.name
+        )));
+    }
+
+    info!("Secret '{}' unset for function '{}'", key, function.name);
+    Ok(())
+}
+
+/// Lists the secret names of a function and when each was last updated.
+/// Values are never returned.
+pub async fn list_secret_keys(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+) -> ServelessCoreResult<Vec<(String, DateTime<FixedOffset>)>> {
+    FunctionSecretDBRepo::list_secrets(conn, function.id)
+        .await
+        .map(|secrets| {
+            secrets
+                .into_iter()
+                .map(|secret| (secret.key, secret.updated_at))
+                .collect()
+        })
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))
+}
+
+/// Decrypts a function's secrets into `(key, value)` pairs for a new container.
+pub async fn decrypt_secrets(
+    conn: &DatabaseConnection,
+    cipher: &SecretCipher,
+    function_id: i32,
+) -> ServelessCoreResult<Vec<(String, String)>> {
+    let secrets = FunctionSecretDBRepo::list_secrets(conn, function_id)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    secrets
+        .into_iter()
+        .map(|secret| {
+            cipher
+                .decrypt(&secret.nonce, &secret.ciphertext)
+                .map(|value| (secret.key.clone(), value))
+                .map_err(|e| {
+                    error!("Failed to decrypt secret '{}': {}", secret.key, e);
+                    ServelessCoreError::SystemError(e.to_string())
+                })
+        })
+        .collect()
+}
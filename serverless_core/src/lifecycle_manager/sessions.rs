@@ -0,0 +1,287 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entities::auth::Model as AuthUser;
+use db_entities::auth_session::Model as SessionModel;
+use db_entities::refresh_token::Model as RefreshTokenModel;
+use rand_core::{OsRng, RngCore};
+use sea_orm::DatabaseConnection;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::session::SessionDBRepo;
+use crate::lifecycle_manager::admin::ensure_active;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// How long an access token (JWT) is valid for, in seconds.
+pub const ACCESS_TOKEN_VALIDITY_SECS: u64 = 15 * 60;
+
+/// How long a refresh token can be exchanged for, in days.
+const REFRESH_TOKEN_VALIDITY_DAYS: i64 = 30;
+
+/// A session with a fresh refresh token, to be returned along with an access token.
+///
+/// # Fields
+/// - `session_uuid`: The session, carried by access tokens as their `sid` claim.
+/// - `user`: The session's user.
+/// - `refresh_token`: The new refresh token; only its hash is stored.
+#[derive(Debug)]
+pub struct IssuedSession {
+    pub session_uuid: Uuid,
+    pub user: AuthUser,
+    pub refresh_token: String,
+}
+
+/// Why a refresh token was refused.
+#[derive(Debug, PartialEq)]
+enum RefreshRejection {
+    /// The session was logged out or revoked.
+    Revoked,
+    /// The token was already exchanged, so it was likely stolen.
+    Reused,
+    /// The token is past its expiry.
+    Expired,
+}
+
+/// Hashes a refresh token for storage and lookup.
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn new_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Checks that a refresh token of `session` can be exchanged at `now`.
+fn check_refresh(
+    token: &RefreshTokenModel,
+    session: &SessionModel,
+    now: DateTime<FixedOffset>,
+) -> Result<(), RefreshRejection> {
+    if session.revoked_at.is_some() {
+        return Err(RefreshRejection::Revoked);
+    }
+    if token.used_at.is_some() {
+        return Err(RefreshRejection::Reused);
+    }
+    if token.expires_at <= now {
+        return Err(RefreshRejection::Expired);
+    }
+    Ok(())
+}
+
+/// Revokes a session whose refresh token was presented twice, since either
+/// the legitimate client or an attacker holds a copy.
+async fn revoke_reused(
+    conn: &DatabaseConnection,
+    session: &SessionModel,
+    now: DateTime<FixedOffset>,
+) -> ServelessCoreResult<ServelessCoreError> {
+    warn!(
+        "Refresh token reuse detected; revoking session {}",
+        session.uuid
+    );
+    SessionDBRepo::revoke_session(conn, session.id, now)
+        .await
+        .map_err(db_error)?;
+    Ok(ServelessCoreError::Unauthorized(
+        "Refresh token was already used; the session has been revoked, log in again".to_string(),
+    ))
+}
+
+/// Starts a session for a user who just logged in or registered.
+pub async fn start_session(
+    conn: &DatabaseConnection,
+    user: AuthUser,
+) -> ServelessCoreResult<IssuedSession> {
+    ensure_active(&user)?;
+    let refresh_token = new_refresh_token();
+    let expires_at = Utc::now().fixed_offset() + Duration::days(REFRESH_TOKEN_VALIDITY_DAYS);
+    let session =
+        SessionDBRepo::create_session(conn, user.id, hash_token(&refresh_token), expires_at)
+            .await
+            .map_err(db_error)?;
+    Ok(IssuedSession {
+        session_uuid: session.uuid,
+        user,
+        refresh_token,
+    })
+}
+
+/// Exchanges a refresh token for a new one in the same session.
+///
+/// Each refresh token can be exchanged once. Presenting one again revokes the
+/// whole session, which signs out both the legitimate client and whoever
+/// copied the token.
+pub async fn refresh_session(
+    conn: &DatabaseConnection,
+    refresh_token: &str,
+) -> ServelessCoreResult<IssuedSession> {
+    let invalid = || ServelessCoreError::Unauthorized("Invalid refresh token".to_string());
+    let (token, session) = SessionDBRepo::find_refresh_token(conn, &hash_token(refresh_token))
+        .await
+        .map_err(db_error)?
+        .ok_or_else(invalid)?;
+    let session = session.ok_or_else(invalid)?;
+    let now = Utc::now().fixed_offset();
+
+    match check_refresh(&token, &session, now) {
+        Ok(()) => {}
+        Err(RefreshRejection::Reused) => return Err(revoke_reused(conn, &session, now).await?),
+        Err(RefreshRejection::Revoked) => {
+            return Err(ServelessCoreError::Unauthorized(
+                "Session has been revoked, log in again".to_string(),
+            ))
+        }
+        Err(RefreshRejection::Expired) => {
+            return Err(ServelessCoreError::Unauthorized(
+                "Session has expired, log in again".to_string(),
+            ))
+        }
+    }
+
+    let user = SessionDBRepo::find_session(conn, session.uuid)
+        .await
+        .map_err(db_error)?
+        .and_then(|(_, user)| user)
+        .ok_or_else(invalid)?;
+    ensure_active(&user)?;
+
+    let next = new_refresh_token();
+    let expires_at = now + Duration::days(REFRESH_TOKEN_VALIDITY_DAYS);
+    if !SessionDBRepo::rotate(conn, &token, hash_token(&next), now, expires_at)
+        .await
+        .map_err(db_error)?
+    {
+        // Another request exchanged the same token first.
+        return Err(revoke_reused(conn, &session, now).await?);
+    }
+
+    Ok(IssuedSession {
+        session_uuid: session.uuid,
+        user,
+        refresh_token: next,
+    })
+}
+
+/// Logs out the session a refresh token belongs to, or every session of its
+/// user when `everywhere` is set.
+///
+/// # Returns
+///
+/// The user logged out and the number of sessions revoked; unknown tokens
+/// revoke nothing.
+pub async fn logout(
+    conn: &DatabaseConnection,
+    refresh_token: &str,
+    everywhere: bool,
+) -> ServelessCoreResult<(Option<Uuid>, u64)> {
+    let Some((_, Some(session))) =
+        SessionDBRepo::find_refresh_token(conn, &hash_token(refresh_token))
+            .await
+            .map_err(db_error)?
+    else {
+        return Ok((None, 0));
+    };
+    let user_uuid = SessionDBRepo::find_session(conn, session.uuid)
+        .await
+        .map_err(db_error)?
+        .and_then(|(_, user)| user)
+        .map(|user| user.uuid);
+    let now = Utc::now().fixed_offset();
+
+    let revoked = if everywhere {
+        SessionDBRepo::revoke_all_for_user(conn, session.auth_id, now).await
+    } else {
+        SessionDBRepo::revoke_session(conn, session.id, now).await
+    }
+    .map_err(db_error)?;
+    info!(
+        "Logged out {} session(s) of user {}",
+        revoked, session.auth_id
+    );
+    Ok((user_uuid, revoked))
+}
+
+/// Whether an access token's session is still active and belongs to `user_uuid`.
+///
+/// Sessions of suspended users are refused with `Forbidden`.
+pub async fn is_active(
+    conn: &DatabaseConnection,
+    session_uuid: Uuid,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<bool> {
+    let session = SessionDBRepo::find_session(conn, session_uuid)
+        .await
+        .map_err(db_error)?;
+    match session {
+        Some((session, Some(user))) if session.revoked_at.is_none() && user.uuid == user_uuid => {
+            ensure_active(&user)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> SessionModel {
+        let now = Utc::now().fixed_offset();
+        SessionModel {
+            id: 1,
+            uuid: Uuid::new_v4(),
+            auth_id: 1,
+            created_at: now,
+            last_refreshed_at: now,
+            revoked_at: None,
+        }
+    }
+
+    fn token(expires_at: DateTime<FixedOffset>) -> RefreshTokenModel {
+        RefreshTokenModel {
+            id: 1,
+            session_id: 1,
+            token_hash: hash_token("refresh"),
+            created_at: Utc::now().fixed_offset(),
+            expires_at,
+            used_at: None,
+        }
+    }
+
+    #[test]
+    fn test_refresh_tokens_are_single_use() {
+        let now = Utc::now().fixed_offset();
+        let fresh = token(now + Duration::days(1));
+        assert_eq!(check_refresh(&fresh, &session(), now), Ok(()));
+
+        let mut used = fresh.clone();
+        used.used_at = Some(now);
+        assert_eq!(
+            check_refresh(&used, &session(), now),
+            Err(RefreshRejection::Reused)
+        );
+    }
+
+    #[test]
+    fn test_revoked_sessions_and_expired_tokens_are_refused() {
+        let now = Utc::now().fixed_offset();
+        let mut revoked = session();
+        revoked.revoked_at = Some(now);
+        let fresh = token(now + Duration::days(1));
+        assert_eq!(
+            check_refresh(&fresh, &revoked, now),
+            Err(RefreshRejection::Revoked)
+        );
+        assert_eq!(
+            check_refresh(&token(now), &session(), now),
+            Err(RefreshRejection::Expired)
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use chrono::Utc;
+use db_entities::auth::Model as AuthUser;
+use redis::aio::MultiplexedConnection;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::auth::AuthDBRepo;
+use crate::db::identity::IdentityDBRepo;
+use crate::db::sso_cache::SsoCacheRepo;
+use crate::lifecycle_manager::admin::ensure_active;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::login_guard::AUDIT_TARGET;
+use crate::lifecycle_manager::sessions::{self, IssuedSession};
+use crate::utils::oidc::{pkce_challenge, random_token, IdTokenClaims, OidcClient};
+
+/// How long the user has to sign in at the identity provider, in seconds.
+const SIGN_IN_TTL_SECS: u64 = 10 * 60;
+
+/// How long the CLI has to redeem its one-time login code, in seconds.
+const LOGIN_CODE_TTL_SECS: u64 = 60;
+
+/// A sign-in waiting for the identity provider's callback.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingSignIn {
+    nonce: String,
+    code_verifier: String,
+    client_redirect: String,
+    client_state: String,
+    client_challenge: String,
+}
+
+/// A completed sign-in waiting for the CLI to redeem its login code.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLogin {
+    user_uuid: Uuid,
+    client_challenge: String,
+}
+
+fn cache_error(e: redis::RedisError) -> ServelessCoreError {
+    ServelessCoreError::SystemError(format!("SSO state storage failed: {e}"))
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Checks that the CLI's redirect target is a loopback HTTP listener, so a
+/// login code can't be sent to another host.
+fn check_client_redirect(redirect: &str) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(redirect).map_err(|e| format!("Invalid redirect_uri: {e}"))?;
+    let loopback = matches!(
+        url.host_str(),
+        Some("127.0.0.1") | Some("localhost") | Some("[::1]")
+    );
+    if url.scheme() != "http" || !loopback || url.port().is_none() {
+        return Err("redirect_uri must be http://127.0.0.1:<port>/...".to_string());
+    }
+    if !url.username().is_empty() || url.password().is_some() || url.fragment().is_some() {
+        return Err("redirect_uri must not carry credentials or a fragment".to_string());
+    }
+    Ok(url)
+}
+
+/// Checks a PKCE challenge is an S256 digest (43 base64url characters).
+fn is_pkce_challenge(challenge: &str) -> bool {
+    challenge.len() == 43
+        && challenge
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Starts a sign-in for the CLI listening at `client_redirect`.
+///
+/// # Arguments
+///
+/// * `client_redirect` - Loopback URL the browser returns to with a login code
+/// * `client_state` - Value echoed back to `client_redirect`
+/// * `client_challenge` - PKCE challenge the login code will be redeemed with
+///
+/// # Returns
+///
+/// The identity provider URL to open in the browser.
+pub async fn start_sign_in(
+    cache: &mut MultiplexedConnection,
+    oidc: &OidcClient,
+    client_redirect: &str,
+    client_state: &str,
+    client_challenge: &str,
+) -> ServelessCoreResult<String> {
+    check_client_redirect(client_redirect).map_err(ServelessCoreError::BadRequest)?;
+    if !is_pkce_challenge(client_challenge) {
+        return Err(ServelessCoreError::BadRequest(
+            "code_challenge must be an S256 PKCE challenge".to_string(),
+        ));
+    }
+    if client_state.is_empty() || client_state.len() > 256 {
+        return Err(ServelessCoreError::BadRequest(
+            "state must be between 1 and 256 characters".to_string(),
+        ));
+    }
+
+    let state = random_token();
+    let pending = PendingSignIn {
+        nonce: random_token(),
+        code_verifier: random_token(),
+        client_redirect: client_redirect.to_string(),
+        client_state: client_state.to_string(),
+        client_challenge: client_challenge.to_string(),
+    };
+    let url = oidc
+        .authorization_url(&state, &pending.nonce, &pending.code_verifier)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    SsoCacheRepo::put_state(cache, &state, &pending, SIGN_IN_TTL_SECS)
+        .await
+        .map_err(cache_error)?;
+    Ok(url)
+}
+
+/// Finishes a sign-in when the identity provider redirects back.
+///
+/// # Arguments
+///
+/// * `state` - The state the sign-in was started with
+/// * `outcome` - The authorization code, or the provider's error
+///
+/// # Returns
+///
+/// The CLI's URL to redirect the browser to, carrying a one-time login code
+/// or an `error`.
+pub async fn finish_sign_in(
+    cache: &mut MultiplexedConnection,
+    conn: &DatabaseConnection,
+    oidc: &OidcClient,
+    state: &str,
+    outcome: Result<&str, &str>,
+) -> ServelessCoreResult<String> {
+    let pending: PendingSignIn = SsoCacheRepo::take_state(cache, state)
+        .await
+        .map_err(cache_error)?
+        .ok_or_else(|| {
+            ServelessCoreError::BadRequest(
+                "Sign-in expired or was already used; start again".to_string(),
+            )
+        })?;
+    let mut redirect =
+        check_client_redirect(&pending.client_redirect).map_err(ServelessCoreError::BadRequest)?;
+
+    let result = match outcome {
+        Ok(code) => sign_in(cache, conn, oidc, &pending, code).await,
+        Err(error) => Err(ServelessCoreError::Unauthorized(format!(
+            "The identity provider refused the sign-in: {error}"
+        ))),
+    };
+    match result {
+        Ok(login_code) => {
+            redirect
+                .query_pairs_mut()
+                .append_pair("code", &login_code)
+                .append_pair("state", &pending.client_state);
+        }
+        Err(ServelessCoreError::SystemError(e)) => {
+            return Err(ServelessCoreError::SystemError(e));
+        }
+        Err(e) => {
+            warn!("Single sign-on failed: {}", e);
+            redirect
+                .query_pairs_mut()
+                .append_pair("error", &e.to_string())
+                .append_pair("state", &pending.client_state);
+        }
+    }
+    Ok(redirect.to_string())
+}
+
+/// Redeems the authorization code and issues a one-time login code for the user.
+async fn sign_in(
+    cache: &mut MultiplexedConnection,
+    conn: &DatabaseConnection,
+    oidc: &OidcClient,
+    pending: &PendingSignIn,
+    code: &str,
+) -> ServelessCoreResult<String> {
+    let claims = oidc
+        .exchange_code(code, &pending.code_verifier, &pending.nonce)
+        .await
+        .map_err(|e| ServelessCoreError::Unauthorized(e.to_string()))?;
+    let user = resolve_user(conn, &claims).await?;
+    ensure_active(&user)?;
+
+    let login_code = random_token();
+    let login = PendingLogin {
+        user_uuid: user.uuid,
+        client_challenge: pending.client_challenge.clone(),
+    };
+    SsoCacheRepo::put_login_code(cache, &login_code, &login, LOGIN_CODE_TTL_SECS)
+        .await
+        .map_err(cache_error)?;
+    info!(
+        target: AUDIT_TARGET,
+        "sso.signed_in user={} issuer={} subject={}", user.uuid, claims.iss, claims.sub
+    );
+    Ok(login_code)
+}
+
+/// The email address of an ID token, if the provider vouches for it.
+///
+/// A missing `email_verified` claim counts as unverified.
+fn verified_email(email: Option<&str>, email_verified: Option<bool>) -> ServelessCoreResult<&str> {
+    let email = email.ok_or_else(|| {
+        ServelessCoreError::Forbidden(
+            "The identity provider didn't share an email address".to_string(),
+        )
+    })?;
+    if email_verified != Some(true) {
+        return Err(ServelessCoreError::Forbidden(
+            "The identity provider hasn't verified this email address".to_string(),
+        ));
+    }
+    Ok(email)
+}
+
+/// Finds the user an ID token stands for, linking or creating them on first sign-in.
+///
+/// A known subject signs in as its linked user. Otherwise the token's email
+/// address is matched to an existing account, or a new account is created.
+/// The provider must explicitly report the address as verified; otherwise
+/// anyone could claim an account by registering its address with the provider.
+async fn resolve_user(
+    conn: &DatabaseConnection,
+    claims: &IdTokenClaims,
+) -> ServelessCoreResult<AuthUser> {
+    if let Some(user) = IdentityDBRepo::find_user(conn, &claims.iss, &claims.sub)
+        .await
+        .map_err(db_error)?
+    {
+        return Ok(user);
+    }
+
+    let email = verified_email(claims.email.as_deref(), claims.email_verified)?;
+
+    let now = Utc::now().fixed_offset();
+    let user = match AuthDBRepo::find_by_email(conn, email)
+        .await
+        .map_err(db_error)?
+    {
+        Some(user) => {
+            IdentityDBRepo::link(conn, user.id, &claims.iss, &claims.sub)
+                .await
+                .map_err(db_error)?;
+            if user.email_verified_at.is_none() {
+                AuthDBRepo::mark_email_verified(conn, user, now)
+                    .await
+                    .map_err(db_error)?
+            } else {
+                user
+            }
+        }
+        None => IdentityDBRepo::create_user(conn, email.to_string(), &claims.iss, &claims.sub, now)
+            .await
+            .map_err(db_error)?,
+    };
+    info!(
+        target: AUDIT_TARGET,
+        "sso.linked user={} issuer={} subject={}", user.uuid, claims.iss, claims.sub
+    );
+    Ok(user)
+}
+
+/// Exchanges a one-time login code for a session.
+///
+/// # Arguments
+///
+/// * `login_code` - The code the browser delivered to the CLI
+/// * `code_verifier` - The PKCE verifier of the challenge the sign-in started with
+pub async fn redeem_login_code(
+    cache: &mut MultiplexedConnection,
+    conn: &DatabaseConnection,
+    login_code: &str,
+    code_verifier: &str,
+) -> ServelessCoreResult<IssuedSession> {
+    let invalid = || ServelessCoreError::Unauthorized("Invalid or expired login code".to_string());
+    let login: PendingLogin = SsoCacheRepo::take_login_code(cache, login_code)
+        .await
+        .map_err(cache_error)?
+        .ok_or_else(invalid)?;
+    if pkce_challenge(code_verifier) != login.client_challenge {
+        return Err(invalid());
+    }
+    let user = AuthDBRepo::find_by_uuid(conn, login.user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(invalid)?;
+    sessions::start_session(conn, user).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_loopback_redirects_are_accepted() {
+        assert!(check_client_redirect("http://127.0.0.1:53682/callback").is_ok());
+        assert!(check_client_redirect("http://localhost:8400/").is_ok());
+        for redirect in [
+            "https://127.0.0.1:53682/callback",
+            "http://evil.example.com:80/callback",
+            "http://127.0.0.1/callback",
+            "http://user:pw@127.0.0.1:53682/",
+            "http://127.0.0.1.evil.example.com:53682/",
+            "not a url",
+        ] {
+            assert!(check_client_redirect(redirect).is_err(), "{redirect}");
+        }
+    }
+
+    #[test]
+    fn test_only_verified_emails_are_linked() {
+        let email = Some("dev@example.com");
+        assert_eq!(verified_email(email, Some(true)).ok(), email);
+        assert!(verified_email(email, None).is_err());
+        assert!(verified_email(email, Some(false)).is_err());
+        assert!(verified_email(None, Some(true)).is_err());
+    }
+
+    #[test]
+    fn test_challenges_must_be_s256_digests() {
+        assert!(is_pkce_challenge(&pkce_challenge("verifier")));
+        assert!(!is_pkce_challenge("plain-verifier"));
+        assert!(!is_pkce_challenge(&"=".repeat(43)));
+    }
+}
@@ -0,0 +1,263 @@
+use chrono::Utc;
+use db_entities::function::Model as FunctionModel;
+use redis::aio::MultiplexedConnection;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::cache::FunctionCacheRepo;
+use crate::db::models::FunctionAuth;
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::lifecycle_manager::invocation_auth::function_auth;
+use crate::lifecycle_manager::invoke::{function_key, instance_key};
+
+/// The most recent error of a function.
+///
+/// # Fields
+/// - `at`: When the error happened, in RFC 3339 format.
+/// - `message`: What went wrong.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunctionFailure {
+    pub at: String,
+    pub message: String,
+}
+
+/// A running instance of one version of a function.
+///
+/// # Fields
+/// - `version`: The version the instance runs.
+/// - `address`: Where the instance is reachable.
+/// - `in_flight`: Requests currently being handled by the instance.
+#[derive(Debug, Serialize)]
+pub struct RunningInstance {
+    pub version: i32,
+    pub address: String,
+    pub in_flight: i64,
+}
+
+/// Whether a function currently has running instances.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionState {
+    /// At least one instance is running.
+    Running,
+    /// The function is deployed but no instance is running.
+    Idle,
+    /// The function has no deployed version.
+    NotDeployed,
+}
+
+impl FunctionState {
+    /// Derives the state from the latest deployed version and whether any
+    /// instance of the function is running.
+    fn of(latest_version: Option<i32>, running: bool) -> Self {
+        match (latest_version, running) {
+            (None, _) => FunctionState::NotDeployed,
+            (Some(_), false) => FunctionState::Idle,
+            (Some(_), true) => FunctionState::Running,
+        }
+    }
+}
+
+/// Metadata and live status of a function.
+///
+/// # Fields
+/// - `name`, `runtime`, `uuid`, `description`, `labels`: The function's metadata.
+/// - `namespace`: The namespace the function is invoked under.
+/// - `max_concurrency`: Concurrent requests allowed per instance, if set.
+/// - `auth`: Who may invoke the function.
+/// - `created_at`, `updated_at`, `last_deployed_at`: Lifecycle times, in RFC 3339 format.
+/// - `image_digest`: Digest of the image built by the last deploy.
+/// - `latest_version`: The most recently deployed version, if any.
+/// - `status`: Whether the function has running instances.
+/// - `running_instances`: The running instances of every version.
+/// - `last_invocation_at`: When the function was last invoked.
+/// - `last_error`: The most recent error returned or raised by the function.
+#[derive(Debug, Serialize)]
+pub struct FunctionDetails {
+    pub name: String,
+    pub runtime: String,
+    pub uuid: Uuid,
+    pub namespace: Uuid,
+    pub description: Option<String>,
+    pub labels: Option<serde_json::Value>,
+    pub max_concurrency: Option<i32>,
+    pub auth: FunctionAuth,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_deployed_at: Option<String>,
+    pub image_digest: Option<String>,
+    pub latest_version: Option<i32>,
+    pub status: FunctionState,
+    pub running_instances: Vec<RunningInstance>,
+    pub last_invocation_at: Option<String>,
+    pub last_error: Option<FunctionFailure>,
+}
+
+/// Records that a function was invoked.
+///
+/// Activity tracking is best effort, so failures are logged rather than returned.
+///
+/// # Arguments
+///
+/// * `cache_conn` - A mutable reference to the Redis connection.
+/// * `name` - The name of the function.
+/// * `user_uuid` - The UUID of the user (namespace) who owns the function.
+pub async fn record_invocation(
+    cache_conn: &mut MultiplexedConnection,
+    name: &str,
+    user_uuid: Uuid,
+) {
+    let key = function_key(name, user_uuid);
+    let now = Utc::now().to_rfc3339();
+    if let Err(e) = FunctionCacheRepo::record_invocation(cache_conn, &key, &now).await {
+        warn!("Failed to record invocation of '{}': {}", name, e);
+    }
+}
+
+/// Records the most recent error of a function.
+///
+/// Activity tracking is best effort, so failures are logged rather than returned.
+///
+/// # Arguments
+///
+/// * `cache_conn` - A mutable reference to the Redis connection.
+/// * `name` - The name of the function.
+/// * `user_uuid` - The UUID of the user (namespace) who owns the function.
+/// * `message` - What went wrong.
+pub async fn record_failure(
+    cache_conn: &mut MultiplexedConnection,
+    name: &str,
+    user_uuid: Uuid,
+    message: String,
+) {
+    let key = function_key(name, user_uuid);
+    let failure = FunctionFailure {
+        at: Utc::now().to_rfc3339(),
+        message,
+    };
+    let Ok(failure) = serde_json::to_string(&failure) else {
+        return;
+    };
+    if let Err(e) = FunctionCacheRepo::record_error(cache_conn, &key, &failure).await {
+        warn!("Failed to record error of '{}': {}", name, e);
+    }
+}
+
+/// Collects the metadata and live status of a function.
+///
+/// Running instances are looked up in the cache for every version, since aliases
+/// can keep older versions serving traffic.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `cache_conn` - A mutable reference to the Redis connection.
+/// * `function` - The function to describe.
+/// * `user_uuid` - The UUID of the user (namespace) who owns the function.
+///
+/// # Returns
+///
+/// The function's `FunctionDetails`.
+pub async fn function_details(
+    conn: &DatabaseConnection,
+    cache_conn: &mut MultiplexedConnection,
+    function: FunctionModel,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<FunctionDetails> {
+    let versions = FunctionVersionDBRepo::list_versions(conn, function.id)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+
+    let mut running_instances = Vec::new();
+    for version in &versions {
+        let key = instance_key(&function.name, user_uuid, version.version);
+        if let Some(address) = FunctionCacheRepo::get_function(cache_conn, &key).await {
+            let in_flight = FunctionCacheRepo::in_flight(cache_conn, &address)
+                .await
+                .unwrap_or(0)
+                .max(0);
+            running_instances.push(RunningInstance {
+                version: version.version,
+                address,
+                in_flight,
+            });
+        }
+    }
+
+    let latest_version = versions.iter().map(|version| version.version).max();
+    let status = FunctionState::of(latest_version, !running_instances.is_empty());
+
+    let key = function_key(&function.name, user_uuid);
+    let (last_invocation_at, last_error) = FunctionCacheRepo::get_activity(cache_conn, &key)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load activity of '{}': {}", function.name, e);
+            (None, None)
+        });
+    let auth = function_auth(&function)?;
+
+    Ok(FunctionDetails {
+        name: function.name,
+        runtime: function.runtime,
+        uuid: function.uuid,
+        namespace: function.namespace,
+        description: function.description,
+        labels: function.labels,
+        max_concurrency: function.max_concurrency,
+        auth,
+        created_at: function.created_at.to_rfc3339(),
+        updated_at: function.updated_at.to_rfc3339(),
+        last_deployed_at: function.last_deployed_at.map(|at| at.to_rfc3339()),
+        image_digest: function.image_digest,
+        latest_version,
+        status,
+        running_instances,
+        last_invocation_at,
+        last_error: last_error.and_then(|error| parse_failure(&error)),
+    })
+}
+
+/// Parses an error recorded by `record_failure`; entries that can't be read
+/// are left out of the details instead of failing the request.
+fn parse_failure(error: &str) -> Option<FunctionFailure> {
+    serde_json::from_str(error).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_follows_deploys_and_running_instances() {
+        assert_eq!(FunctionState::of(None, false), FunctionState::NotDeployed);
+        assert_eq!(FunctionState::of(None, true), FunctionState::NotDeployed);
+        assert_eq!(FunctionState::of(Some(2), false), FunctionState::Idle);
+        assert_eq!(FunctionState::of(Some(2), true), FunctionState::Running);
+    }
+
+    #[test]
+    fn test_state_serializes_in_snake_case() {
+        let state = serde_json::to_value(FunctionState::NotDeployed).unwrap();
+        assert_eq!(state, "not_deployed");
+    }
+
+    #[test]
+    fn test_recorded_failures_round_trip() {
+        let failure = FunctionFailure {
+            at: Utc::now().to_rfc3339(),
+            message: "container exited".to_string(),
+        };
+        let parsed = parse_failure(&serde_json::to_string(&failure).unwrap()).unwrap();
+        assert_eq!(parsed.at, failure.at);
+        assert_eq!(parsed.message, failure.message);
+    }
+
+    #[test]
+    fn test_unreadable_failures_are_left_out() {
+        assert!(parse_failure("container exited").is_none());
+        assert!(parse_failure(r#"{"message":"no timestamp"}"#).is_none());
+    }
+}
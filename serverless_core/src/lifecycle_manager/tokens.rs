@@ -0,0 +1,448 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entities::access_token::Model as AccessTokenModel;
+use rand_core::{OsRng, RngCore};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::access_token::AccessTokenDBRepo;
+use crate::db::auth::AuthDBRepo;
+use crate::lifecycle_manager::admin::ensure_active;
+use crate::lifecycle_manager::api_keys::needs_touch;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// Prefix of every personal access token, which tells them apart from JWTs.
+pub const TOKEN_PREFIX: &str = "invok_pat_";
+
+/// How many characters of a token are kept in clear to help recognise it.
+const DISPLAY_PREFIX_LENGTH: usize = TOKEN_PREFIX.len() + 8;
+
+/// How long a token is valid for when no expiry is requested.
+const DEFAULT_VALIDITY_DAYS: u32 = 90;
+
+/// The longest validity a token can be created with.
+const MAX_VALIDITY_DAYS: u32 = 365;
+
+/// The longest name a token can have.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Something a personal access token can be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// List and inspect functions and organizations.
+    Read,
+    /// Deploy functions and change their environment, secrets and aliases.
+    Deploy,
+    /// Invoke functions that require platform authentication.
+    Invoke,
+    /// Everything else: deleting functions, managing members, organizations and tokens.
+    Admin,
+}
+
+impl Scope {
+    /// The name the scope is stored and shown as.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Deploy => "deploy",
+            Scope::Invoke => "invoke",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ServelessCoreError;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "read" => Ok(Scope::Read),
+            "deploy" => Ok(Scope::Deploy),
+            "invoke" => Ok(Scope::Invoke),
+            "admin" => Ok(Scope::Admin),
+            _ => Err(ServelessCoreError::BadRequest(format!(
+                "Unknown scope '{scope}'; expected read, deploy, invoke or admin"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The scopes a request was authenticated with.
+///
+/// Logged-in sessions carry every scope; personal access tokens carry the
+/// scopes they were created with. `admin` grants every other scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scopes(BTreeSet<Scope>);
+
+impl Scopes {
+    /// Every scope, as granted to logged-in sessions.
+    pub fn all() -> Self {
+        Scopes(BTreeSet::from([
+            Scope::Read,
+            Scope::Deploy,
+            Scope::Invoke,
+            Scope::Admin,
+        ]))
+    }
+
+    /// Parses a comma-separated list of scopes, as stored in the database.
+    pub fn parse(scopes: &str) -> ServelessCoreResult<Self> {
+        scopes
+            .split(',')
+            .map(str::trim)
+            .filter(|scope| !scope.is_empty())
+            .map(Scope::from_str)
+            .collect::<Result<_, _>>()
+            .map(Scopes)
+    }
+
+    /// Whether these scopes grant `scope`.
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.0.contains(&Scope::Admin) || self.0.contains(&scope)
+    }
+
+    /// Whether these scopes grant every scope in `other`.
+    pub fn covers(&self, other: &Scopes) -> bool {
+        other.0.iter().all(|scope| self.allows(*scope))
+    }
+
+    /// The scopes as a list of names.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.0.iter().map(|scope| scope.as_str()).collect()
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.names().join(","))
+    }
+}
+
+/// A newly created token. The secret is only ever returned here.
+#[derive(Debug, Serialize)]
+pub struct CreatedToken {
+    pub uuid: Uuid,
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<&'static str>,
+    pub expires_at: Option<String>,
+}
+
+/// A token as shown when listing, identified by its ID and clear prefix.
+#[derive(Debug, Serialize)]
+pub struct TokenSummary {
+    pub uuid: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<&'static str>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+/// Hashes a token for storage and lookup. Tokens are random, so a plain
+/// digest is enough to keep them unusable if the database leaks.
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn db_error(e: sea_orm::DbErr) -> ServelessCoreError {
+    ServelessCoreError::SystemError(e.to_string())
+}
+
+/// Checks that a token hasn't been revoked and hasn't expired at `now`.
+///
+/// Tokens created before every token had an expiry live for the longest
+/// validity, counted from their creation.
+fn check_token(token: &AccessTokenModel, now: DateTime<FixedOffset>) -> Result<(), &'static str> {
+    if token.revoked_at.is_some() {
+        return Err("Token has been revoked");
+    }
+    let expires_at = token
+        .expires_at
+        .unwrap_or(token.created_at + Duration::days(i64::from(MAX_VALIDITY_DAYS)));
+    if expires_at <= now {
+        return Err("Token has expired");
+    }
+    Ok(())
+}
+
+/// Works out when a token created at `now` expires. Every token expires, after
+/// 90 days unless asked otherwise and after at most 365.
+fn token_expiry(
+    now: DateTime<FixedOffset>,
+    expires_in_days: Option<u32>,
+) -> ServelessCoreResult<DateTime<FixedOffset>> {
+    let validity = expires_in_days.unwrap_or(DEFAULT_VALIDITY_DAYS);
+    if !(1..=MAX_VALIDITY_DAYS).contains(&validity) {
+        return Err(ServelessCoreError::BadRequest(format!(
+            "Tokens must be valid for between 1 and {MAX_VALIDITY_DAYS} days"
+        )));
+    }
+    Ok(now + Duration::days(i64::from(validity)))
+}
+
+fn summarize(token: AccessTokenModel) -> ServelessCoreResult<TokenSummary> {
+    Ok(TokenSummary {
+        scopes: Scopes::parse(&token.scopes)?.names(),
+        uuid: token.uuid,
+        name: token.name,
+        prefix: token.prefix,
+        created_at: token.created_at.to_rfc3339(),
+        expires_at: token.expires_at.map(|at| at.to_rfc3339()),
+        last_used_at: token.last_used_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Authenticates a request carrying a personal access token.
+///
+/// # Returns
+///
+/// The token owner's UUID and the token's scopes, or `None` when the token is
+/// unknown, revoked or expired.
+pub async fn authenticate(
+    conn: &DatabaseConnection,
+    token: &str,
+) -> ServelessCoreResult<Option<(Uuid, Scopes)>> {
+    let Some((token, Some(user))) = AccessTokenDBRepo::find_by_hash(conn, &hash_token(token))
+        .await
+        .map_err(db_error)?
+    else {
+        return Ok(None);
+    };
+    let now = Utc::now().fixed_offset();
+    if let Err(reason) = check_token(&token, now) {
+        info!(
+            "Rejected token '{}' of {}: {}",
+            token.name, user.email, reason
+        );
+        return Ok(None);
+    }
+
+    ensure_active(&user)?;
+
+    let scopes = Scopes::parse(&token.scopes)?;
+    // Usage tracking is best effort and must not fail the request.
+    if needs_touch(token.last_used_at, now) {
+        if let Err(e) = AccessTokenDBRepo::touch(conn, token).await {
+            warn!("Failed to record token use: {}", e);
+        }
+    }
+    Ok(Some((user.uuid, scopes)))
+}
+
+/// Creates a personal access token for the caller.
+///
+/// A token can't be granted scopes the caller's own credentials lack, so a
+/// token can't be used to mint a more powerful one.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `user_uuid` - The calling user.
+/// * `caller_scopes` - The scopes the caller authenticated with.
+/// * `name` - A name to recognise the token by.
+/// * `scopes` - The names of the scopes to grant.
+/// * `expires_in_days` - How long the token is valid for, from 1 to 365 days.
+pub async fn create_token(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+    caller_scopes: &Scopes,
+    name: &str,
+    scopes: &[String],
+    expires_in_days: Option<u32>,
+) -> ServelessCoreResult<CreatedToken> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(ServelessCoreError::BadRequest(format!(
+            "Token name must be between 1 and {MAX_NAME_LENGTH} characters"
+        )));
+    }
+    let scopes = Scopes::parse(&scopes.join(","))?;
+    if scopes.0.is_empty() {
+        return Err(ServelessCoreError::BadRequest(
+            "At least one scope is required".to_string(),
+        ));
+    }
+    if !caller_scopes.covers(&scopes) {
+        return Err(ServelessCoreError::Forbidden(
+            "A token can't grant scopes its creator lacks".to_string(),
+        ));
+    }
+    let now = Utc::now().fixed_offset();
+    let expires_at = token_expiry(now, expires_in_days)?;
+
+    let user = AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{user_uuid}' not found")))?;
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let secret = format!("{TOKEN_PREFIX}{}", hex::encode(bytes));
+
+    let token = AccessTokenDBRepo::create_token(
+        conn,
+        AccessTokenModel {
+            id: 0,
+            uuid: Uuid::new_v4(),
+            auth_id: user.id,
+            name: name.to_string(),
+            prefix: secret[..DISPLAY_PREFIX_LENGTH].to_string(),
+            token_hash: hash_token(&secret),
+            scopes: scopes.to_string(),
+            created_at: now,
+            expires_at: Some(expires_at),
+            last_used_at: None,
+            revoked_at: None,
+        },
+    )
+    .await
+    .map_err(db_error)?;
+
+    info!(
+        "{} created token '{}' with scopes {}",
+        user.email, token.name, scopes
+    );
+    Ok(CreatedToken {
+        uuid: token.uuid,
+        name: token.name,
+        token: secret,
+        scopes: scopes.names(),
+        expires_at: token.expires_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Lists the caller's tokens that haven't been revoked.
+pub async fn list_tokens(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+) -> ServelessCoreResult<Vec<TokenSummary>> {
+    let user = AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{user_uuid}' not found")))?;
+    AccessTokenDBRepo::list_for_user(conn, user.id)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(summarize)
+        .collect()
+}
+
+/// Revokes one of the caller's tokens.
+pub async fn revoke_token(
+    conn: &DatabaseConnection,
+    user_uuid: Uuid,
+    token_uuid: Uuid,
+) -> ServelessCoreResult<TokenSummary> {
+    let user = AuthDBRepo::find_by_uuid(conn, user_uuid)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServelessCoreError::NotFound(format!("User '{user_uuid}' not found")))?;
+    let not_found = || ServelessCoreError::NotFound(format!("Token '{token_uuid}' not found"));
+    let token = AccessTokenDBRepo::find_for_user(conn, token_uuid, user.id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+    if token.revoked_at.is_some() {
+        return Err(not_found());
+    }
+
+    let token = AccessTokenDBRepo::revoke(conn, token)
+        .await
+        .map_err(db_error)?;
+    info!("{} revoked token '{}'", user.email, token.name);
+    summarize(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(expires_at: Option<DateTime<FixedOffset>>) -> AccessTokenModel {
+        let now = Utc::now().fixed_offset();
+        AccessTokenModel {
+            id: 1,
+            uuid: Uuid::new_v4(),
+            auth_id: 1,
+            name: "ci".to_string(),
+            prefix: "invok_pat_00000000".to_string(),
+            token_hash: String::new(),
+            scopes: "deploy".to_string(),
+            created_at: now,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_admin_scope_grants_everything() {
+        let deploy = Scopes::parse("deploy,read").unwrap();
+        assert!(deploy.allows(Scope::Read));
+        assert!(deploy.allows(Scope::Deploy));
+        assert!(!deploy.allows(Scope::Invoke));
+        assert!(!deploy.allows(Scope::Admin));
+
+        let admin = Scopes::parse("admin").unwrap();
+        assert!(admin.covers(&Scopes::all()));
+        assert!(!deploy.covers(&admin));
+        assert_eq!(deploy.to_string(), "read,deploy");
+        assert!(Scopes::parse("read,root").is_err());
+    }
+
+    #[test]
+    fn test_revoked_and_expired_tokens_are_rejected() {
+        let now = Utc::now().fixed_offset();
+        assert!(check_token(&token(None), now).is_ok());
+        assert!(check_token(&token(Some(now + Duration::days(1))), now).is_ok());
+        assert!(check_token(&token(Some(now - Duration::seconds(1))), now).is_err());
+
+        let mut revoked = token(None);
+        revoked.revoked_at = Some(now);
+        assert_eq!(check_token(&revoked, now), Err("Token has been revoked"));
+    }
+
+    #[test]
+    fn test_tokens_without_expiry_expire_after_the_longest_validity() {
+        let now = Utc::now().fixed_offset();
+        let mut legacy = token(None);
+        legacy.created_at = now - Duration::days(364);
+        assert!(check_token(&legacy, now).is_ok());
+        legacy.created_at = now - Duration::days(365);
+        assert_eq!(check_token(&legacy, now), Err("Token has expired"));
+    }
+
+    #[test]
+    fn test_every_token_expires() {
+        let now = Utc::now().fixed_offset();
+        assert_eq!(token_expiry(now, None).unwrap(), now + Duration::days(90));
+        assert_eq!(
+            token_expiry(now, Some(365)).unwrap(),
+            now + Duration::days(365)
+        );
+        assert!(token_expiry(now, Some(0)).is_err());
+        assert!(token_expiry(now, Some(366)).is_err());
+    }
+
+    #[test]
+    fn test_tokens_are_stored_hashed() {
+        let hash = hash_token("invok_pat_secret");
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, "invok_pat_secret");
+        assert_eq!(hash, hash_token("invok_pat_secret"));
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+
+use crate::db::models::{
+    DeployableFunctionConfig, FieldError, FunctionAuth, CONFIG_SCHEMA_VERSION,
+};
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+use crate::utils::jwks::check_fetch_url;
+use crate::utils::secrets::is_valid_env_key;
+
+/// Runtimes functions can be built for.
+pub const SUPPORTED_RUNTIMES: &[&str] = &["go"];
+
+/// Bounds of the memory limit a function may request, in MB.
+const MEMORY_MB_RANGE: std::ops::RangeInclusive<u32> = 64..=8192;
+
+/// Bounds of the CPU limit a function may request, in CPUs.
+const CPUS_RANGE: std::ops::RangeInclusive<f64> = 0.1..=8.0;
+
+/// Longest description accepted, in characters.
+const MAX_DESCRIPTION_LENGTH: usize = 512;
+
+/// Most labels a function may have.
+const MAX_LABELS: usize = 32;
+
+/// Longest label key and value accepted, in characters.
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Checks that a label key is 1 to 63 lowercase letters, digits, `-`, `_` or `.`,
+/// starting and ending with a letter or digit.
+fn is_valid_label_key(key: &str) -> bool {
+    let alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    !key.is_empty()
+        && key.len() <= MAX_LABEL_LENGTH
+        && key.starts_with(alphanumeric)
+        && key.ends_with(alphanumeric)
+        && key
+            .chars()
+            .all(|c| alphanumeric(c) || matches!(c, '-' | '_' | '.'))
+}
+
+/// Checks that a function name is safe to use as a DNS label, a Docker image
+/// name, a route segment and a cache key.
+pub fn validate_function_name(name: &str) -> Result<(), FieldError> {
+    shared_utils::validate_function_name(name).map_err(|message| FieldError::new("name", message))
+}
+
+/// Checks a parsed function configuration against the schema's rules.
+///
+/// # Arguments
+///
+/// * `config` - The parsed configuration.
+/// * `name` - The name the function is deployed under.
+///
+/// # Returns
+///
+/// Every violation found, or an empty list if the configuration is valid.
+pub fn validate_config(config: &DeployableFunctionConfig, name: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if config.schema_version != CONFIG_SCHEMA_VERSION {
+        errors.push(FieldError::new(
+            "schema_version",
+            format!("unsupported version, expected {CONFIG_SCHEMA_VERSION}"),
+        ));
+    }
+    if config.function_name != name {
+        errors.push(FieldError::new(
+            "function_name",
+            format!("must match the uploaded function name '{name}'"),
+        ));
+    }
+    if !SUPPORTED_RUNTIMES.contains(&config.runtime.as_str()) {
+        errors.push(FieldError::new(
+            "runtime",
+            format!("must be one of: {}", SUPPORTED_RUNTIMES.join(", ")),
+        ));
+    }
+
+    let mut env_keys = config
+        .env
+        .iter()
+        .flat_map(|env| env.keys())
+        .collect::<Vec<_>>();
+    env_keys.sort();
+    for key in env_keys.into_iter().filter(|key| !is_valid_env_key(key)) {
+        errors.push(FieldError::new(
+            format!("env.{key}"),
+            "is not a valid environment variable name",
+        ));
+    }
+
+    if config.max_concurrency == Some(0) {
+        errors.push(FieldError::new("max_concurrency", "must be at least 1"));
+    }
+    if matches!(&config.image, Some(image) if image.trim().is_empty()) {
+        errors.push(FieldError::new("image", "must not be empty"));
+    }
+
+    if let Some(resources) = &config.resources {
+        if matches!(resources.memory_mb, Some(mb) if !MEMORY_MB_RANGE.contains(&mb)) {
+            errors.push(FieldError::new(
+                "resources.memory_mb",
+                format!(
+                    "must be between {} and {}",
+                    MEMORY_MB_RANGE.start(),
+                    MEMORY_MB_RANGE.end()
+                ),
+            ));
+        }
+        if matches!(resources.cpus, Some(cpus) if !CPUS_RANGE.contains(&cpus)) {
+            errors.push(FieldError::new(
+                "resources.cpus",
+                format!(
+                    "must be between {} and {}",
+                    CPUS_RANGE.start(),
+                    CPUS_RANGE.end()
+                ),
+            ));
+        }
+    }
+
+    if matches!(&config.description, Some(description) if description.chars().count() > MAX_DESCRIPTION_LENGTH)
+    {
+        errors.push(FieldError::new(
+            "description",
+            format!("must be at most {MAX_DESCRIPTION_LENGTH} characters"),
+        ));
+    }
+    if config.labels.len() > MAX_LABELS {
+        errors.push(FieldError::new(
+            "labels",
+            format!("must have at most {MAX_LABELS} entries"),
+        ));
+    }
+    for (key, value) in &config.labels {
+        if !is_valid_label_key(key) {
+            errors.push(FieldError::new(
+                format!("labels.{key}"),
+                "key must be 1 to 63 lowercase letters, digits, '-', '_' or '.', starting and ending with a letter or digit",
+            ));
+        } else if value.chars().count() > MAX_LABEL_LENGTH || value.chars().any(char::is_control) {
+            errors.push(FieldError::new(
+                format!("labels.{key}"),
+                "value must be at most 63 characters without control characters",
+            ));
+        }
+    }
+
+    if let FunctionAuth::Jwt {
+        issuer,
+        audience,
+        jwks_url,
+    } = &config.auth
+    {
+        if let Err(reason) = check_fetch_url(issuer) {
+            errors.push(FieldError::new("auth.issuer", reason));
+        }
+        if let Some(Err(reason)) = jwks_url.as_deref().map(check_fetch_url) {
+            errors.push(FieldError::new("auth.jwks_url", reason));
+        }
+        if matches!(audience, Some(audience) if audience.trim().is_empty()) {
+            errors.push(FieldError::new("auth.audience", "must not be empty"));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (index, route) in config.routes.iter().enumerate() {
+        let field = format!("routes[{index}]");
+        if !route.starts_with('/') {
+            errors.push(FieldError::new(field, "must start with '/'"));
+        } else if route.chars().any(|c| c.is_whitespace() || c.is_control())
+            || route.split('/').any(|segment| segment == "..")
+        {
+            errors.push(FieldError::new(
+                field,
+                "must not contain whitespace or '..' segments",
+            ));
+        } else if !seen.insert(route) {
+            errors.push(FieldError::new(field, "is listed more than once"));
+        } else if route != "/" {
+            // Requests are only forwarded to the root of the function's
+            // invocation URL, so any other path would never be served.
+            errors.push(FieldError::new(field, "only '/' is currently supported"));
+        }
+    }
+
+    errors
+}
+
+/// Parses and validates a function's `config.json`.
+///
+/// Syntax errors, wrong types, unknown fields and rule violations are all
+/// reported as `InvalidFunction` with the path of the offending field.
+///
+/// # Arguments
+///
+/// * `content` - The contents of `config.json`.
+/// * `name` - The name the function is deployed under.
+pub fn parse_config(content: &str, name: &str) -> ServelessCoreResult<DeployableFunctionConfig> {
+    let deserializer = &mut serde_json::Deserializer::from_str(content);
+    let config: DeployableFunctionConfig =
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let field = match e.path().to_string() {
+                // The root, or no position at all for syntax errors.
+                path if path == "." || path == "?" => "config".to_string(),
+                path => path,
+            };
+            ServelessCoreError::InvalidFunction(vec![FieldError::new(field, e.inner().to_string())])
+        })?;
+
+    let errors = validate_config(&config, name);
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(ServelessCoreError::InvalidFunction(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: ServelessCoreResult<DeployableFunctionConfig>) -> Vec<String> {
+        match result {
+            Err(ServelessCoreError::InvalidFunction(errors)) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            other => panic!("expected validation errors, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validate_function_name() {
+        for name in ["hello", "hello-world", "a1", &"a".repeat(63)] {
+            assert_eq!(validate_function_name(name), Ok(()), "{name}");
+        }
+        for name in [
+            "",
+            "Hello",
+            "1abc",
+            "abc-",
+            "a_b",
+            "a.b",
+            "../x",
+            &"a".repeat(64),
+        ] {
+            assert!(validate_function_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_config_accepts_scaffolded_and_full_configs() {
+        let scaffolded = r#"{"function_name":"hello","runtime":"go","env":{}}"#;
+        let config = parse_config(scaffolded, "hello").unwrap();
+        assert_eq!(config.schema_version, CONFIG_SCHEMA_VERSION);
+
+        let full = r#"{
+            "schema_version": 1,
+            "function_name": "hello",
+            "runtime": "go",
+            "env": {"MODE": "prod"},
+            "max_concurrency": 10,
+            "resources": {"memory_mb": 512, "cpus": 0.5},
+            "routes": ["/"],
+            "triggers": [{"type": "http"}],
+            "description": "Says hello",
+            "labels": {"team": "platform", "app.kubernetes.io_name": "hello"},
+            "auth": {"mode": "jwt", "issuer": "https://auth.example.com/", "audience": "hello"}
+        }"#;
+        assert!(parse_config(full, "hello").is_ok());
+        assert_eq!(
+            parse_config(scaffolded, "hello").unwrap().auth,
+            FunctionAuth::Public
+        );
+    }
+
+    #[test]
+    fn test_parse_config_checks_auth() {
+        let config = |auth: &str| {
+            format!(r#"{{"function_name":"hello","runtime":"go","env":null,"auth":{auth}}}"#)
+        };
+        assert_eq!(
+            parse_config(&config(r#"{"mode":"api_key"}"#), "hello")
+                .unwrap()
+                .auth,
+            FunctionAuth::ApiKey
+        );
+        assert_eq!(
+            fields(parse_config(&config(r#"{"mode":"password"}"#), "hello")),
+            vec!["auth.mode"]
+        );
+        assert_eq!(
+            fields(parse_config(
+                &config(
+                    r#"{"mode":"jwt","issuer":"http://auth.example.com","audience":" ","jwks_url":"keys.json"}"#
+                ),
+                "hello"
+            )),
+            vec!["auth.issuer", "auth.jwks_url", "auth.audience"]
+        );
+        assert_eq!(
+            fields(parse_config(
+                &config(
+                    r#"{"mode":"jwt","issuer":"https://localhost:8443","jwks_url":"https://169.254.169.254/keys"}"#
+                ),
+                "hello"
+            )),
+            vec!["auth.issuer", "auth.jwks_url"]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_reports_field_paths() {
+        assert_eq!(
+            fields(parse_config(
+                r#"{"function_name":"hello","runtime":"go","env":null,"resources":{"memory_mb":"lots"}}"#,
+                "hello"
+            )),
+            vec!["resources.memory_mb"]
+        );
+        assert_eq!(
+            fields(parse_config(
+                r#"{"function_name":"hello","runtime":"go","env":null,"timeout":3}"#,
+                "hello"
+            )),
+            vec!["timeout"]
+        );
+        assert_eq!(fields(parse_config("{not json", "hello")), vec!["config"]);
+        assert_eq!(
+            fields(parse_config(
+                r#"{"function_name":"hello","runtime":"go","env":null,"triggers":[{"type":"cron"}]}"#,
+                "hello"
+            )),
+            vec!["triggers[0].type"]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_collects_every_violation() {
+        let config = r#"{
+            "schema_version": 2,
+            "function_name": "other",
+            "runtime": "cobol",
+            "env": {"1BAD": "x"},
+            "max_concurrency": 0,
+            "resources": {"memory_mb": 1, "cpus": 64},
+            "routes": ["items", "/a", "/a", "/../etc"],
+            "description": "DESCRIPTION",
+            "labels": {"team": "payments", "Bad Key": "x", "tier": "TIER"}
+        }"#
+        .replace("DESCRIPTION", &"d".repeat(513))
+        .replace("TIER", &"t".repeat(64));
+        assert_eq!(
+            fields(parse_config(&config, "hello")),
+            vec![
+                "schema_version",
+                "function_name",
+                "runtime",
+                "env.1BAD",
+                "max_concurrency",
+                "resources.memory_mb",
+                "resources.cpus",
+                "description",
+                "labels.Bad Key",
+                "labels.tier",
+                "routes[0]",
+                "routes[1]",
+                "routes[2]",
+                "routes[3]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use db_entities::function::Model as FunctionModel;
+use db_entities::function_alias::Model as AliasModel;
+use db_entities::function_version::Model as VersionModel;
+use rand::Rng;
+use sea_orm::DatabaseConnection;
+use tracing::{error, info};
+
+use crate::artifacts::ArtifactStore;
+use crate::db::alias::FunctionAliasDBRepo;
+use crate::db::version::FunctionVersionDBRepo;
+use crate::lifecycle_manager::error::{ServelessCoreError, ServelessCoreResult};
+
+/// Returns the Docker image tag for a given version of a function.
+///
+/// # Arguments
+///
+/// * `function_key` - The namespaced function key (`{name}-{uuid_short}`).
+/// * `version` - The version number.
+pub fn image_tag(function_key: &str, version: i32) -> String {
+    format!("{function_key}:v{version}")
+}
+
+/// Picks which version an alias routes a request to.
+///
+/// `roll` is a number in `0..100`; requests rolling below the canary weight go to
+/// the canary version, everything else goes to the alias's main version.
+pub fn pick_version(alias: &AliasModel, roll: i32) -> i32 {
+    match alias.canary_version {
+        Some(canary) if roll < alias.canary_weight => canary,
+        _ => alias.version,
+    }
+}
+
+/// Resolves the version of a function that should serve an invocation.
+///
+/// Without an alias the most recently deployed version is used. With an alias,
+/// the alias's traffic split decides between its main and canary versions.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `function` - The function being invoked.
+/// * `alias` - The optional alias from `name@alias`.
+pub async fn resolve_version(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+    alias: Option<&str>,
+) -> ServelessCoreResult<VersionModel> {
+    let version = match alias {
+        None => FunctionVersionDBRepo::find_latest(conn, function.id).await,
+        Some(alias_name) => {
+            let alias = FunctionAliasDBRepo::find_alias(conn, function.id, alias_name)
+                .await
+                .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+                .ok_or_else(|| {
+                    ServelessCoreError::FunctionNotRegistered(format!(
+                        "Alias '{}' not found for function '{}'",
+                        alias_name, function.name
+                    ))
+                })?;
+            let version = pick_version(&alias, rand::thread_rng().gen_range(0..100));
+            FunctionVersionDBRepo::find_version(conn, function.id, version).await
+        }
+    };
+
+    version
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .ok_or_else(|| {
+            error!("Function '{}' has no deployable version", function.name);
+            ServelessCoreError::FunctionNotRegistered(format!(
+                "No deployed version of function '{}'",
+                function.name
+            ))
+        })
+}
+
+/// Ensures a version exists for the function, returning a `BadFunction` error otherwise.
+async fn ensure_version_exists(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+    version: i32,
+) -> ServelessCoreResult<()> {
+    FunctionVersionDBRepo::find_version(conn, function.id, version)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .map(|_| ())
+        .ok_or_else(|| {
+            ServelessCoreError::BadFunction(format!(
+                "Version {} of function '{}' does not exist",
+                version, function.name
+            ))
+        })
+}
+
+/// Loads the archive a version of a function was deployed from.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `artifacts` - The store uploaded archives are kept in.
+/// * `function` - The function owning the version.
+/// * `version` - The version number.
+///
+/// # Returns
+///
+/// The archive's content, or `FunctionNotRegistered` if the version does not exist
+/// or was deployed before archives were stored.
+pub async fn version_artifact(
+    conn: &DatabaseConnection,
+    artifacts: &dyn ArtifactStore,
+    function: &FunctionModel,
+    version: i32,
+) -> ServelessCoreResult<Vec<u8>> {
+    let not_found = || {
+        ServelessCoreError::FunctionNotRegistered(format!(
+            "No stored archive for version {} of function '{}'",
+            version, function.name
+        ))
+    };
+
+    let digest = FunctionVersionDBRepo::find_version(conn, function.id, version)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .and_then(|version| version.artifact_digest)
+        .ok_or_else(not_found)?;
+    artifacts
+        .get(&digest)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .ok_or_else(not_found)
+}
+
+/// Creates or repoints an alias, optionally splitting traffic with a canary version.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `function` - The function owning the alias.
+/// * `alias` - The alias name.
+/// * `version` - The version receiving the main share of traffic.
+/// * `canary_version` - An optional version receiving `canary_weight` percent of traffic.
+/// * `canary_weight` - Percentage (0-100) of traffic routed to the canary.
+pub async fn set_alias(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+    alias: &str,
+    version: i32,
+    canary_version: Option<i32>,
+    canary_weight: i32,
+) -> ServelessCoreResult<AliasModel> {
+    if alias.is_empty() || alias.contains('@') || alias.contains('/') {
+        return Err(ServelessCoreError::BadFunction(format!(
+            "Invalid alias name '{alias}'"
+        )));
+    }
+    if !(0..=100).contains(&canary_weight) {
+        return Err(ServelessCoreError::BadFunction(
+            "Canary weight must be between 0 and 100".to_string(),
+        ));
+    }
+
+    ensure_version_exists(conn, function, version).await?;
+    if let Some(canary) = canary_version {
+        ensure_version_exists(conn, function, canary).await?;
+    }
+    let canary_weight = if canary_version.is_some() {
+        canary_weight
+    } else {
+        0
+    };
+
+    let alias = FunctionAliasDBRepo::upsert_alias(
+        conn,
+        function.id,
+        alias,
+        version,
+        canary_version,
+        canary_weight,
+    )
+    .await
+    .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?;
+    info!(
+        "Alias '{}' of function '{}' now points at version {}",
+        alias.name, function.name, alias.version
+    );
+    Ok(alias)
+}
+
+/// Repoints an alias to an earlier version and clears any canary split.
+///
+/// # Arguments
+///
+/// * `conn` - A reference to the database connection.
+/// * `function` - The function owning the alias.
+/// * `alias` - The alias to roll back.
+/// * `version` - The version to roll back to; defaults to the newest version older
+///   than the one the alias currently points at.
+pub async fn rollback_alias(
+    conn: &DatabaseConnection,
+    function: &FunctionModel,
+    alias: &str,
+    version: Option<i32>,
+) -> ServelessCoreResult<AliasModel> {
+    let current = FunctionAliasDBRepo::find_alias(conn, function.id, alias)
+        .await
+        .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+        .ok_or_else(|| {
+            ServelessCoreError::FunctionNotRegistered(format!(
+                "Alias '{}' not found for function '{}'",
+                alias, function.name
+            ))
+        })?;
+
+    let target = match version {
+        Some(version) => version,
+        None => FunctionVersionDBRepo::find_previous(conn, function.id, current.version)
+            .await
+            .map_err(|e| ServelessCoreError::SystemError(e.to_string()))?
+            .map(|v| v.version)
+            .ok_or_else(|| {
+                ServelessCoreError::BadFunction(format!(
+                    "Alias '{}' is already at the oldest version",
+                    alias
+                ))
+            })?,
+    };
+
+    set_alias(conn, function, alias, target, None, 0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(version: i32, canary_version: Option<i32>, canary_weight: i32) -> AliasModel {
+        AliasModel {
+            id: 1,
+            function_id: 1,
+            name: "prod".to_string(),
+            version,
+            canary_version,
+            canary_weight,
+        }
+    }
+
+    #[test]
+    fn test_image_tag() {
+        assert_eq!(image_tag("hello-abc", 3), "hello-abc:v3");
+    }
+
+    #[test]
+    fn test_pick_version_without_canary() {
+        let alias = alias(2, None, 50);
+        assert_eq!(pick_version(&alias, 0), 2);
+        assert_eq!(pick_version(&alias, 99), 2);
+    }
+
+    #[test]
+    fn test_pick_version_splits_by_weight() {
+        let alias = alias(2, Some(3), 10);
+        assert_eq!(pick_version(&alias, 0), 3);
+        assert_eq!(pick_version(&alias, 9), 3);
+        assert_eq!(pick_version(&alias, 10), 2);
+        assert_eq!(pick_version(&alias, 99), 2);
+    }
+}
@@ -0,0 +1,112 @@
+pub mod file;
+pub mod log;
+pub mod smtp;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use thiserror::Error;
+
+/// Errors returned by a mailer.
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[error("Invalid email: {0}")]
+    InvalidMessage(String),
+    #[error("Mail I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("SMTP server refused the message: {0}")]
+    Rejected(String),
+    #[error("Timed out talking to the SMTP server")]
+    Timeout,
+}
+
+/// A plain-text email.
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers emails the platform sends to its users.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Sends `email` from the configured sender address.
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// The bare address of a mailbox such as `Invok <no-reply@example.com>`.
+pub fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+/// Renders `email` as an RFC 5322 message with CRLF line endings.
+///
+/// Header values holding a line break are refused, so user input can't inject
+/// headers or recipients.
+pub fn render(from: &str, email: &Email) -> Result<String, MailError> {
+    for (name, value) in [
+        ("From", from),
+        ("To", &email.to),
+        ("Subject", &email.subject),
+    ] {
+        if value.contains(['\r', '\n']) {
+            return Err(MailError::InvalidMessage(format!(
+                "{name} header contains a line break"
+            )));
+        }
+    }
+    let domain = address(from).rsplit('@').next().unwrap_or("localhost");
+    let body = email.body.replace("\r\n", "\n").replace('\n', "\r\n");
+    Ok(format!(
+        "From: {from}\r\n\
+         To: {to}\r\n\
+         Subject: {subject}\r\n\
+         Date: {date}\r\n\
+         Message-ID: <{id}@{domain}>\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\
+         \r\n\
+         {body}\r\n",
+        to = email.to,
+        subject = email.subject,
+        date = Utc::now().to_rfc2822(),
+        id = uuid::Uuid::new_v4(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email {
+            to: "user@example.com".to_string(),
+            subject: "Verify your email".to_string(),
+            body: "Hello\nworld".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_renders_crlf_messages() {
+        let message = render("Invok <no-reply@invok.dev>", &email()).unwrap();
+        assert!(message.starts_with("From: Invok <no-reply@invok.dev>\r\nTo: user@example.com\r\n"));
+        assert!(message.contains("@invok.dev>\r\n"));
+        assert!(message.ends_with("\r\n\r\nHello\r\nworld\r\n"));
+        assert_eq!(address("Invok <no-reply@invok.dev>"), "no-reply@invok.dev");
+        assert_eq!(address(" no-reply@invok.dev "), "no-reply@invok.dev");
+    }
+
+    #[test]
+    fn test_refuses_header_injection() {
+        let mut injected = email();
+        injected.to = "user@example.com\r\nBcc: victim@example.com".to_string();
+        assert!(matches!(
+            render("no-reply@invok.dev", &injected),
+            Err(MailError::InvalidMessage(_))
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::PathBuf;
+use tokio::fs;
+use tracing::info;
+
+use crate::mailer::{render, Email, MailError, Mailer};
+
+/// Mailer that saves each email as an `.eml` file in a directory.
+///
+/// Files are named after the time they were written, so the newest email
+/// sorts last; they open in any mail client.
+#[derive(Debug, Clone)]
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    /// Creates a mailer saving emails sent from `from` in `dir`; the directory
+    /// is created on first write.
+    pub fn new(from: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            from: from.into(),
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = render(&self.from, email)?;
+        fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%3f"),
+            uuid::Uuid::new_v4().simple()
+        ));
+        fs::write(&path, message).await?;
+        info!("Saved email to {} at {}", email.to, path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::mailer::{render, Email, MailError, Mailer};
+
+/// Mailer that writes emails to the server log instead of sending them.
+///
+/// Meant for development, where the links in verification and reset emails
+/// can be copied from the log.
+#[derive(Debug, Clone)]
+pub struct LogMailer {
+    from: String,
+}
+
+impl LogMailer {
+    /// Creates a mailer logging emails sent from `from`.
+    pub fn new(from: impl Into<String>) -> Self {
+        Self { from: from.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = render(&self.from, email)?;
+        info!("Email to {}:\n{}", email.to, message);
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::mailer::{address, render, Email, MailError, Mailer};
+
+/// Longest time a whole SMTP conversation may take.
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Mailer delivering through an SMTP server.
+///
+/// Speaks plain SMTP without TLS or authentication, so it is meant for a relay
+/// on the local network, such as a mail sidecar or MailHog in development.
+#[derive(Debug, Clone)]
+pub struct SmtpMailer {
+    from: String,
+    host: String,
+    port: u16,
+}
+
+impl SmtpMailer {
+    /// Creates a mailer sending emails from `from` through `host:port`.
+    pub fn new(from: impl Into<String>, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            from: from.into(),
+            host: host.into(),
+            port,
+        }
+    }
+
+    async fn deliver(&self, email: &Email) -> Result<(), MailError> {
+        let message = render(&self.from, email)?;
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        expect_reply(&mut reader, 220).await?;
+        command(&mut writer, &mut reader, "EHLO invok", 250).await?;
+        command(
+            &mut writer,
+            &mut reader,
+            &format!("MAIL FROM:<{}>", address(&self.from)),
+            250,
+        )
+        .await?;
+        command(
+            &mut writer,
+            &mut reader,
+            &format!("RCPT TO:<{}>", address(&email.to)),
+            250,
+        )
+        .await?;
+        command(&mut writer, &mut reader, "DATA", 354).await?;
+        writer.write_all(dot_stuff(&message).as_bytes()).await?;
+        command(&mut writer, &mut reader, ".", 250).await?;
+        // The message is accepted; a failed goodbye doesn't matter
+        let _ = command(&mut writer, &mut reader, "QUIT", 221).await;
+        Ok(())
+    }
+}
+
+/// Escapes lines starting with a dot, which would otherwise end the message early.
+fn dot_stuff(message: &str) -> String {
+    let stuffed = message.replace("\r\n.", "\r\n..");
+    match stuffed.strip_prefix('.') {
+        Some(rest) => format!("..{rest}"),
+        None => stuffed,
+    }
+}
+
+/// Sends an SMTP command and checks the server's reply code.
+async fn command<W, R>(
+    writer: &mut W,
+    reader: &mut R,
+    line: &str,
+    expected: u16,
+) -> Result<(), MailError>
+where
+    W: AsyncWriteExt + Unpin,
+    R: AsyncBufReadExt + Unpin,
+{
+    writer.write_all(format!("{line}\r\n").as_bytes()).await?;
+    writer.flush().await?;
+    expect_reply(reader, expected).await
+}
+
+/// Reads a possibly multi-line reply and checks its code.
+async fn expect_reply<R>(reader: &mut R, expected: u16) -> Result<(), MailError>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(MailError::Rejected("connection closed".to_string()));
+        }
+        let code = line.get(..3).and_then(|code| code.parse::<u16>().ok());
+        if code != Some(expected) {
+            return Err(MailError::Rejected(line.trim_end().to_string()));
+        }
+        // "250-..." continues the reply, "250 ..." ends it
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        tokio::time::timeout(SMTP_TIMEOUT, self.deliver(email))
+            .await
+            .map_err(|_| MailError::Timeout)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_stuffs_lines_starting_with_a_dot() {
+        assert_eq!(
+            dot_stuff(".hidden\r\nok\r\n.\r\n"),
+            "..hidden\r\nok\r\n..\r\n"
+        );
+        assert_eq!(dot_stuff("plain\r\n"), "plain\r\n");
+    }
+}
@@ -1,7 +1,39 @@
 #[tokio::main]
 async fn main() {
-    if let Err(err) = serverless_core::start_server().await {
-        eprintln!("Error starting server: {}", err);
-        std::process::exit(1);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {
+            if let Err(err) = serverless_core::start_server().await {
+                eprintln!("Error starting server: {}", err);
+                std::process::exit(1);
+            }
+        }
+        [command, target] if command == "unlock" => {
+            match serverless_core::unlock_login(target).await {
+                Ok(true) => println!("Unlocked {}", target),
+                Ok(false) => println!("{} was not locked out", target),
+                Err(err) => {
+                    eprintln!("Error unlocking {}: {}", target, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        [command, email] if command == "promote" || command == "demote" => {
+            let admin = command == "promote";
+            match serverless_core::set_platform_role(email, admin).await {
+                Ok(()) if admin => println!("{} is now a platform admin", email),
+                Ok(()) => println!("{} is no longer a platform admin", email),
+                Err(err) => {
+                    eprintln!("Error changing the role of {}: {}", email, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => {
+            eprintln!(
+                "Usage: serverless-core [unlock <email|ip> | promote <email> | demote <email>]"
+            );
+            std::process::exit(2);
+        }
     }
 }
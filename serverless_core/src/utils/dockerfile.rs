@@ -0,0 +1,115 @@
+use thiserror::Error;
+
+/// Reasons a user-supplied Dockerfile is rejected.
+#[derive(Debug, Error, PartialEq)]
+pub enum DockerfileError {
+    #[error("Dockerfile has no FROM instruction")]
+    MissingFrom,
+    #[error("Dockerfile instruction {instruction} on line {line} is not allowed")]
+    DisallowedInstruction { instruction: String, line: usize },
+    #[error("Dockerfile parser directive '{0}' is not allowed")]
+    ParserDirective(String),
+}
+
+/// Returns the instructions of a Dockerfile with the line each one starts on.
+///
+/// Comments and blank lines are skipped and `\` line continuations are joined, so
+/// an instruction split across lines is only reported once.
+fn instructions(content: &str) -> Vec<(usize, String)> {
+    let mut instructions = Vec::new();
+    let mut continued = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        let was_continued = continued;
+        continued = trimmed.ends_with('\\');
+        if was_continued || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(keyword) = trimmed.split_whitespace().next() {
+            instructions.push((index + 1, keyword.to_ascii_uppercase()));
+        }
+    }
+    instructions
+}
+
+/// Validates a user-supplied Dockerfile against the server's build policy.
+///
+/// # Arguments
+///
+/// * `content` - The Dockerfile contents.
+/// * `disallowed` - Upper-case instruction keywords that may not be used.
+///
+/// # Returns
+///
+/// `Ok(())` if the Dockerfile may be built, otherwise the first violation found.
+pub fn validate_dockerfile(content: &str, disallowed: &[String]) -> Result<(), DockerfileError> {
+    // Parser directives such as `# syntax=` can swap in an arbitrary build frontend.
+    for line in content.lines() {
+        let Some(directive) = line.trim().strip_prefix('#') else {
+            break;
+        };
+        if let Some((name, _)) = directive.split_once('=') {
+            if name.trim().eq_ignore_ascii_case("syntax") {
+                return Err(DockerfileError::ParserDirective(name.trim().to_string()));
+            }
+        }
+    }
+
+    let instructions = instructions(content);
+    if let Some((line, instruction)) = instructions
+        .iter()
+        .find(|(_, instruction)| disallowed.contains(instruction))
+    {
+        return Err(DockerfileError::DisallowedInstruction {
+            instruction: instruction.clone(),
+            line: *line,
+        });
+    }
+    if !instructions
+        .iter()
+        .any(|(_, instruction)| instruction == "FROM")
+    {
+        return Err(DockerfileError::MissingFrom);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Vec<String> {
+        vec!["ADD".to_string(), "VOLUME".to_string()]
+    }
+
+    #[test]
+    fn test_accepts_allowed_instructions() {
+        let dockerfile = "# build\nFROM golang:1.22\nRUN apt-get update && \\\n    add-apt-repository x\nCMD [\"./main\"]\n";
+        assert_eq!(validate_dockerfile(dockerfile, &policy()), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_disallowed_instruction_case_insensitively() {
+        let dockerfile = "FROM alpine\n\nadd https://example.com/x /x\n";
+        assert_eq!(
+            validate_dockerfile(dockerfile, &policy()),
+            Err(DockerfileError::DisallowedInstruction {
+                instruction: "ADD".to_string(),
+                line: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_missing_from_and_syntax_directive() {
+        assert_eq!(
+            validate_dockerfile("RUN true\n", &policy()),
+            Err(DockerfileError::MissingFrom)
+        );
+        assert_eq!(
+            validate_dockerfile("# syntax=evil/frontend\nFROM alpine\n", &policy()),
+            Err(DockerfileError::ParserDirective("syntax".to_string()))
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use hyper::client::connect::dns::Name;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::utils::oidc::ACCEPTED_ALGORITHMS;
+
+/// Longest time a request for an issuer's keys may take.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long fetched keys are used before they are fetched again.
+const KEYS_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Shortest time between two fetches of the same keys, so tokens naming
+/// unknown keys can't make the platform hammer an issuer.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Most redirects followed while fetching an issuer's keys.
+const MAX_REDIRECTS: usize = 5;
+
+/// Errors raised while verifying a token from an external issuer.
+#[derive(Debug, Error)]
+pub enum JwksError {
+    #[error("Failed to fetch the issuer's keys: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+    #[error("Refusing to fetch keys from {0}: {1}")]
+    ForbiddenUrl(String, &'static str),
+}
+
+/// Whether an address belongs to the public internet rather than to the
+/// platform's own host or network.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    let shared = a == 100 && (64..128).contains(&b);
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || shared
+        || a == 0
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    let unique_local = first & 0xfe00 == 0xfc00;
+    let link_local = first & 0xffc0 == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+/// Checks that the platform may fetch keys from `url`: it must use HTTPS and
+/// must not name a loopback, private or link-local host, so a function's auth
+/// settings can't point the platform at its own network.
+///
+/// Host names are checked again once resolved, by the client's resolver.
+pub fn check_fetch_url(url: &str) -> Result<(), &'static str> {
+    let url = Url::parse(url).map_err(|_| "must be an https:// URL")?;
+    check_url(&url)
+}
+
+fn check_url(url: &Url) -> Result<(), &'static str> {
+    if url.scheme() != "https" {
+        return Err("must be an https:// URL");
+    }
+    let host = url.host_str().ok_or("must be an https:// URL")?;
+    let public = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    };
+    if public {
+        Ok(())
+    } else {
+        Err("must not point at a loopback, private or link-local host")
+    }
+}
+
+/// Resolves host names with the system resolver, keeping only their public
+/// addresses, so a name pointing at the platform's network is refused when
+/// keys are fetched.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Verifies JWTs issued by identity providers outside the platform, for
+/// functions that authenticate their callers with them.
+///
+/// Each issuer's keys are cached and fetched again once stale, or when a
+/// token names a key that isn't known yet.
+#[derive(Clone)]
+pub struct JwksCache {
+    http: reqwest::Client,
+    jwks_urls: Arc<RwLock<HashMap<String, String>>>,
+    keys: Arc<RwLock<HashMap<String, CachedKeys>>>,
+}
+
+impl JwksCache {
+    pub fn new() -> Result<Self, JwksError> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(FETCH_TIMEOUT)
+                .dns_resolver(Arc::new(PublicResolver))
+                .redirect(Policy::custom(|attempt| {
+                    if attempt.previous().len() >= MAX_REDIRECTS {
+                        attempt.error("too many redirects")
+                    } else if let Err(reason) = check_url(attempt.url()) {
+                        attempt.error(reason)
+                    } else {
+                        attempt.follow()
+                    }
+                }))
+                .build()?,
+            jwks_urls: Arc::new(RwLock::new(HashMap::new())),
+            keys: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// The URL of an issuer's keys, found through OpenID discovery.
+    async fn discover(&self, issuer: &str) -> Result<String, JwksError> {
+        if let Some(url) = self.jwks_urls.read().await.get(issuer) {
+            return Ok(url.clone());
+        }
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        check_fetch_url(&discovery_url)
+            .map_err(|reason| JwksError::ForbiddenUrl(discovery_url.clone(), reason))?;
+        let discovery: Discovery = self
+            .http
+            .get(&discovery_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        self.jwks_urls
+            .write()
+            .await
+            .insert(issuer.to_string(), discovery.jwks_uri.clone());
+        Ok(discovery.jwks_uri)
+    }
+
+    /// The keys at `url`, fetched again when stale or when `kid` isn't among them.
+    async fn keys(&self, url: &str, kid: Option<&str>) -> Result<JwkSet, JwksError> {
+        if let Some(cached) = self.keys.read().await.get(url) {
+            let age = cached.fetched_at.elapsed();
+            let known = kid.is_none_or(|kid| cached.keys.find(kid).is_some());
+            if (known && age < KEYS_TTL) || age < MIN_REFRESH_INTERVAL {
+                return Ok(cached.keys.clone());
+            }
+        }
+        check_fetch_url(url).map_err(|reason| JwksError::ForbiddenUrl(url.to_string(), reason))?;
+        let keys: JwkSet = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        info!("Fetched {} signing keys from {}", keys.keys.len(), url);
+        self.keys.write().await.insert(
+            url.to_string(),
+            CachedKeys {
+                keys: keys.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+        Ok(keys)
+    }
+
+    /// Verifies a token's signature, issuer, audience and expiry.
+    ///
+    /// # Arguments
+    ///
+    /// * `issuer` - The expected `iss` claim
+    /// * `audience` - The expected `aud` claim; not checked when `None`
+    /// * `jwks_url` - Where the issuer's keys are; found through discovery when `None`
+    /// * `token` - The JWT to verify
+    ///
+    /// # Returns
+    ///
+    /// The token's claims.
+    pub async fn verify(
+        &self,
+        issuer: &str,
+        audience: Option<&str>,
+        jwks_url: Option<&str>,
+        token: &str,
+    ) -> Result<Map<String, Value>, JwksError> {
+        let header = decode_header(token).map_err(|e| JwksError::InvalidToken(e.to_string()))?;
+        let url = match jwks_url {
+            Some(url) => url.to_string(),
+            None => self.discover(issuer).await?,
+        };
+        let keys = self.keys(&url, header.kid.as_deref()).await?;
+        verify_token(&keys, issuer, audience, token)
+    }
+}
+
+/// Checks a token against a set of keys.
+fn verify_token(
+    keys: &JwkSet,
+    issuer: &str,
+    audience: Option<&str>,
+    token: &str,
+) -> Result<Map<String, Value>, JwksError> {
+    let invalid = |reason: String| JwksError::InvalidToken(reason);
+    let header = decode_header(token).map_err(|e| invalid(e.to_string()))?;
+    if !ACCEPTED_ALGORITHMS.contains(&header.alg) {
+        return Err(invalid(format!(
+            "algorithm {:?} isn't accepted",
+            header.alg
+        )));
+    }
+    let jwk = match &header.kid {
+        Some(kid) => keys.find(kid),
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| invalid("signed by an unknown key".to_string()))?;
+    let key = DecodingKey::from_jwk(jwk).map_err(|e| invalid(e.to_string()))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    match audience {
+        Some(audience) => validation.set_audience(&[audience]),
+        None => validation.validate_aud = false,
+    }
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+    Ok(decode::<Map<String, Value>>(token, &key, &validation)
+        .map_err(|e| invalid(e.to_string()))?
+        .claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::jwt_keys::JwtKeys;
+    use serde_json::json;
+
+    fn token(keys: &JwtKeys, claims: Value) -> String {
+        keys.sign(&claims).unwrap()
+    }
+
+    #[test]
+    fn test_verifies_issuer_audience_and_signature() {
+        let issuer_keys = JwtKeys::ephemeral();
+        let jwks = issuer_keys.jwks();
+        let exp = chrono::Utc::now().timestamp() + 60;
+        let issuer = "https://auth.example.com/";
+        let claims = json!({"iss": issuer, "sub": "user-1", "aud": "orders", "exp": exp});
+
+        let valid = token(&issuer_keys, claims.clone());
+        let verified = verify_token(&jwks, issuer, Some("orders"), &valid).unwrap();
+        assert_eq!(verified["sub"], "user-1");
+        assert!(verify_token(&jwks, issuer, None, &valid).is_ok());
+
+        assert!(verify_token(&jwks, issuer, Some("billing"), &valid).is_err());
+        assert!(verify_token(&jwks, "https://evil.example.com/", None, &valid).is_err());
+        let forged = token(&JwtKeys::ephemeral(), claims);
+        assert!(verify_token(&jwks, issuer, None, &forged).is_err());
+        let expired = token(
+            &issuer_keys,
+            json!({"iss": issuer, "sub": "user-1", "exp": exp - 3600}),
+        );
+        assert!(verify_token(&jwks, issuer, None, &expired).is_err());
+    }
+
+    #[test]
+    fn test_keys_are_only_fetched_from_public_https_urls() {
+        assert!(check_fetch_url("https://auth.example.com/.well-known/jwks.json").is_ok());
+        assert!(check_fetch_url("https://8.8.8.8/keys").is_ok());
+        assert!(check_fetch_url("https://[2606:4700::1111]/keys").is_ok());
+
+        assert_eq!(
+            check_fetch_url("http://auth.example.com/keys"),
+            Err("must be an https:// URL")
+        );
+        assert!(check_fetch_url("keys.json").is_err());
+        for url in [
+            "https://localhost/keys",
+            "https://api.localhost./keys",
+            "https://127.0.0.1/keys",
+            "https://10.0.0.5/keys",
+            "https://172.16.0.1/keys",
+            "https://192.168.1.1/keys",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/keys",
+            "https://0.0.0.0/keys",
+            "https://[::1]/keys",
+            "https://[fd00::1]/keys",
+            "https://[fe80::1]/keys",
+            "https://[::ffff:127.0.0.1]/keys",
+        ] {
+            assert!(check_fetch_url(url).is_err(), "{url} was allowed");
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use chrono::Utc;
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// PEM tag of PKCS#8 private keys, as written by `openssl genpkey`.
+const PRIVATE_KEY_TAG: &str = "PRIVATE KEY";
+
+/// Errors raised while loading JWT signing keys.
+#[derive(Debug, Error)]
+pub enum JwtKeyError {
+    #[error("Failed to access JWT keys: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JWT key '{0}': {1}")]
+    InvalidKey(String, String),
+    #[error("Active JWT key '{0}' was not found")]
+    MissingActiveKey(String),
+    #[error("Failed to generate a JWT key")]
+    Generate,
+}
+
+/// An Ed25519 key pair identified by its `kid`.
+struct SigningKey {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    public_key: Vec<u8>,
+}
+
+impl SigningKey {
+    /// Reads a key pair from its PKCS#8 DER encoding.
+    fn from_pkcs8(kid: &str, der: &[u8]) -> Result<Self, JwtKeyError> {
+        let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+            .map_err(|e| JwtKeyError::InvalidKey(kid.to_string(), e.to_string()))?;
+        let public_key = pair.public_key().as_ref().to_vec();
+        Ok(Self {
+            encoding: EncodingKey::from_ed_der(der),
+            decoding: DecodingKey::from_ed_der(&public_key),
+            public_key,
+        })
+    }
+}
+
+/// What a platform JWT may be used for.
+///
+/// Each use has its own `typ` header and `aud` claim, so a token issued for
+/// one use is refused for every other, including by services verifying
+/// tokens with the published keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenUse {
+    /// An access token authenticating API requests.
+    Access,
+    /// An emailed link verifying a user's address.
+    VerifyEmail,
+    /// An emailed token resetting a user's password.
+    ResetPassword,
+}
+
+impl TokenUse {
+    /// The `typ` header of tokens issued for this use.
+    pub fn typ(self) -> &'static str {
+        match self {
+            TokenUse::Access => "at+jwt",
+            TokenUse::VerifyEmail => "invok-verify-email+jwt",
+            TokenUse::ResetPassword => "invok-reset-password+jwt",
+        }
+    }
+
+    /// The `aud` claim of tokens issued for this use.
+    pub fn audience(self) -> &'static str {
+        match self {
+            TokenUse::Access => "invok-api",
+            TokenUse::VerifyEmail => "invok-verify-email",
+            TokenUse::ResetPassword => "invok-reset-password",
+        }
+    }
+}
+
+/// The claims every platform JWT carries, around those of its use.
+#[derive(Serialize)]
+struct Registered<'a, T> {
+    iss: &'a str,
+    aud: &'a str,
+    #[serde(flatten)]
+    claims: &'a T,
+}
+
+/// The keys platform JWTs are signed and verified with.
+///
+/// Tokens are signed with EdDSA by the active key and carry its ID in their
+/// `kid` header. Every loaded key verifies tokens, so a key can be rotated by
+/// adding a new one, making it active, and removing the old one once the
+/// tokens it signed have expired.
+///
+/// Tokens name the platform as their `iss` and their use as their `typ` and
+/// `aud`, all of which are checked when they are verified.
+#[derive(Clone)]
+pub struct JwtKeys {
+    active: String,
+    issuer: String,
+    keys: Arc<BTreeMap<String, SigningKey>>,
+}
+
+impl JwtKeys {
+    /// Loads every `<kid>.pem` Ed25519 private key in `dir`.
+    ///
+    /// When the directory holds no key, one is generated and saved there. The
+    /// active key is `active_kid` if given, otherwise the one whose ID sorts last,
+    /// so keys named after their creation time rotate in naturally. Tokens are
+    /// issued by `issuer`, the platform's public URL.
+    pub fn load(dir: &Path, active_kid: Option<&str>, issuer: &str) -> Result<Self, JwtKeyError> {
+        fs::create_dir_all(dir)?;
+
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("pem") {
+                continue;
+            }
+            let Some(kid) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let pem = pem::parse(fs::read(&path)?)
+                .map_err(|e| JwtKeyError::InvalidKey(kid.to_string(), e.to_string()))?;
+            if pem.tag() != PRIVATE_KEY_TAG {
+                return Err(JwtKeyError::InvalidKey(
+                    kid.to_string(),
+                    format!(
+                        "expected a PKCS#8 '{PRIVATE_KEY_TAG}', found '{}'",
+                        pem.tag()
+                    ),
+                ));
+            }
+            keys.push((kid.to_string(), pem.contents().to_vec()));
+        }
+
+        if keys.is_empty() {
+            let kid = Utc::now().format("%Y%m%d%H%M%S").to_string();
+            let der = generate_pkcs8()?;
+            let path = dir.join(format!("{kid}.pem"));
+            fs::write(
+                &path,
+                pem::encode(&pem::Pem::new(PRIVATE_KEY_TAG, der.clone())),
+            )?;
+            restrict_permissions(&path);
+            warn!(
+                "No JWT signing key found; generated '{}'. Share {} between every node",
+                kid,
+                dir.display()
+            );
+            keys.push((kid, der));
+        }
+
+        let keys = Self::from_keys(keys, active_kid, issuer)?;
+        info!(
+            "Loaded {} JWT key(s); signing with '{}'",
+            keys.keys.len(),
+            keys.active
+        );
+        Ok(keys)
+    }
+
+    /// Builds the key set from `(kid, PKCS#8 DER)` pairs.
+    fn from_keys(
+        keys: Vec<(String, Vec<u8>)>,
+        active_kid: Option<&str>,
+        issuer: &str,
+    ) -> Result<Self, JwtKeyError> {
+        let keys = keys
+            .into_iter()
+            .map(|(kid, der)| SigningKey::from_pkcs8(&kid, &der).map(|key| (kid, key)))
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        let active = match active_kid {
+            Some(kid) if keys.contains_key(kid) => kid.to_string(),
+            Some(kid) => return Err(JwtKeyError::MissingActiveKey(kid.to_string())),
+            None => keys
+                .keys()
+                .next_back()
+                .cloned()
+                .ok_or_else(|| JwtKeyError::MissingActiveKey(String::new()))?,
+        };
+        Ok(Self {
+            active,
+            issuer: issuer.to_string(),
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// A key set of one freshly generated key, for tests.
+    #[cfg(test)]
+    pub(crate) fn ephemeral() -> Self {
+        let keys = vec![("test".to_string(), generate_pkcs8().unwrap())];
+        Self::from_keys(keys, None, "https://invok.test").unwrap()
+    }
+
+    /// Issues a token for `token_use` carrying `claims`, signed with the active key.
+    pub fn issue<T: Serialize>(&self, token_use: TokenUse, claims: &T) -> Result<String, JwtError> {
+        let registered = Registered {
+            iss: &self.issuer,
+            aud: token_use.audience(),
+            claims,
+        };
+        self.sign_with_typ(token_use.typ(), &registered)
+    }
+
+    /// Signs arbitrary claims, for tests standing in for other issuers.
+    #[cfg(test)]
+    pub(crate) fn sign<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        self.sign_with_typ("JWT", claims)
+    }
+
+    fn sign_with_typ<T: Serialize>(&self, typ: &str, claims: &T) -> Result<String, JwtError> {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.typ = Some(typ.to_string());
+        header.kid = Some(self.active.clone());
+        encode(&header, claims, &self.keys[&self.active].encoding)
+    }
+
+    /// Verifies a token issued for `token_use` with the key named by its `kid`
+    /// header and returns its claims.
+    ///
+    /// Tokens of another use, or from another issuer, are refused.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token_use: TokenUse,
+        token: &str,
+    ) -> Result<T, JwtError> {
+        let header = decode_header(token)?;
+        if header.typ.as_deref() != Some(token_use.typ()) {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.keys.get(kid))
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidToken))?;
+        let mut validation = jsonwebtoken::Validation::new(Algorithm::EdDSA);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[token_use.audience()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        decode::<T>(token, &key.decoding, &validation).map(|data| data.claims)
+    }
+
+    /// Whether a token was signed with one of the platform's keys, whatever its
+    /// use and even if it has expired.
+    pub fn signed(&self, token: &str) -> bool {
+        let Some(key) = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .and_then(|kid| self.keys.get(&kid))
+        else {
+            return false;
+        };
+        let mut validation = jsonwebtoken::Validation::new(Algorithm::EdDSA);
+        validation.set_required_spec_claims::<&str>(&[]);
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        decode::<serde_json::Value>(token, &key.decoding, &validation).is_ok()
+    }
+
+    /// The public keys, for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .keys
+                .iter()
+                .map(|(kid, key)| Jwk {
+                    common: CommonParameters {
+                        public_key_use: Some(PublicKeyUse::Signature),
+                        key_algorithm: Some(KeyAlgorithm::EdDSA),
+                        key_id: Some(kid.clone()),
+                        ..Default::default()
+                    },
+                    algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: BASE64_URL.encode(&key.public_key),
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Generates an Ed25519 private key in PKCS#8 DER encoding.
+fn generate_pkcs8() -> Result<Vec<u8>, JwtKeyError> {
+    Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map(|der| der.as_ref().to_vec())
+        .map_err(|_| JwtKeyError::Generate)
+}
+
+/// Makes a generated private key readable by its owner only.
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
+        warn!(
+            "Failed to restrict permissions of {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const ISSUER: &str = "https://invok.example.com";
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Claims {
+        sub: String,
+        exp: u64,
+    }
+
+    fn claims() -> Claims {
+        Claims {
+            sub: "user".to_string(),
+            exp: Utc::now().timestamp() as u64 + 60,
+        }
+    }
+
+    #[test]
+    fn test_tokens_verify_across_rotation() {
+        let old = ("2026-a".to_string(), generate_pkcs8().unwrap());
+        let new = ("2026-b".to_string(), generate_pkcs8().unwrap());
+
+        let before = JwtKeys::from_keys(vec![old.clone()], None, ISSUER).unwrap();
+        let token = before.issue(TokenUse::Access, &claims()).unwrap();
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some("2026-a")
+        );
+
+        // The new key signs, while tokens from the old one still verify.
+        let during = JwtKeys::from_keys(vec![old, new.clone()], None, ISSUER).unwrap();
+        assert_eq!(during.active, "2026-b");
+        assert_eq!(
+            during.verify::<Claims>(TokenUse::Access, &token).unwrap(),
+            claims()
+        );
+
+        // Once the old key is removed, its tokens are refused.
+        let after = JwtKeys::from_keys(vec![new], None, ISSUER).unwrap();
+        assert!(after.verify::<Claims>(TokenUse::Access, &token).is_err());
+    }
+
+    #[test]
+    fn test_jwks_lists_every_public_key() {
+        let keys = JwtKeys::from_keys(
+            vec![
+                ("a".to_string(), generate_pkcs8().unwrap()),
+                ("b".to_string(), generate_pkcs8().unwrap()),
+            ],
+            Some("a"),
+            ISSUER,
+        )
+        .unwrap();
+        let jwks = keys.jwks();
+        assert_eq!(jwks.keys.len(), 2);
+
+        // Other services verify with the published key alone.
+        let token = keys.issue(TokenUse::Access, &claims()).unwrap();
+        let jwk = jwks.find("a").unwrap();
+        let decoding = DecodingKey::from_jwk(jwk).unwrap();
+        let mut validation = jsonwebtoken::Validation::new(Algorithm::EdDSA);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_audience(&[TokenUse::Access.audience()]);
+        assert!(decode::<Claims>(&token, &decoding, &validation).is_ok());
+
+        assert!(matches!(
+            JwtKeys::from_keys(vec![], Some("missing"), ISSUER),
+            Err(JwtKeyError::MissingActiveKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_tokens_are_refused_for_other_uses_and_issuers() {
+        let keys = JwtKeys::ephemeral();
+        let token = keys.issue(TokenUse::VerifyEmail, &claims()).unwrap();
+        assert_eq!(
+            decode_header(&token).unwrap().typ.as_deref(),
+            Some("invok-verify-email+jwt")
+        );
+        assert!(keys.verify::<Claims>(TokenUse::VerifyEmail, &token).is_ok());
+        assert!(keys.verify::<Claims>(TokenUse::Access, &token).is_err());
+        assert!(keys
+            .verify::<Claims>(TokenUse::ResetPassword, &token)
+            .is_err());
+
+        // Same keys, another deployment's public URL
+        let elsewhere = JwtKeys {
+            issuer: "https://other.example.com".to_string(),
+            ..keys.clone()
+        };
+        assert!(elsewhere
+            .verify::<Claims>(TokenUse::VerifyEmail, &token)
+            .is_err());
+
+        // Claims without an issuer or audience, signed with the same keys
+        let bare = keys.sign_with_typ("at+jwt", &claims()).unwrap();
+        assert!(keys.verify::<Claims>(TokenUse::Access, &bare).is_err());
+
+        // Refused or not, every token the keys signed is recognized as the platform's.
+        assert!(keys.signed(&token) && keys.signed(&bare));
+        assert!(!JwtKeys::ephemeral().signed(&token));
+        assert!(!keys.signed("not-a-token"));
+    }
+}
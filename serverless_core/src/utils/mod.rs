@@ -1 +1,8 @@
+pub(crate) mod dockerfile;
+pub(crate) mod jwks;
+pub(crate) mod jwt_keys;
+pub(crate) mod oidc;
+pub(crate) mod passwords;
+pub(crate) mod secrets;
+#[allow(clippy::module_inception)]
 pub(crate) mod utils;
@@ -0,0 +1,211 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use rand_core::OsRng;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::info;
+
+/// Longest password accepted, so hashing stays bounded.
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Errors raised while setting up the password policy.
+#[derive(Debug, Error)]
+pub enum PasswordPolicyError {
+    #[error("Failed to read breached passwords file '{0}': {1}")]
+    BreachedList(String, std::io::Error),
+    #[error("Invalid Argon2 parameters: {0}")]
+    InvalidParams(argon2::Error),
+    #[error("Failed to hash password: {0}")]
+    Hash(argon2::password_hash::Error),
+}
+
+/// How passwords are checked and hashed.
+///
+/// Passwords are hashed with Argon2id using the configured parameters. Hashes
+/// made with other parameters still verify and are flagged by
+/// [`PasswordPolicy::needs_rehash`], so raising the cost upgrades users as
+/// they log in.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    min_length: usize,
+    breached: Arc<HashSet<String>>,
+    params: Params,
+    /// Hash verified against when the user is unknown, so the response time
+    /// doesn't reveal which emails are registered.
+    dummy_hash: Arc<String>,
+}
+
+impl PasswordPolicy {
+    /// Builds the policy, reading the breached passwords file if one is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_length` - Minimum password length in characters
+    /// * `breached_passwords_file` - File of breached passwords, one per line
+    /// * `memory_kib`, `iterations`, `parallelism` - Argon2 cost parameters
+    pub fn load(
+        min_length: usize,
+        breached_passwords_file: Option<&str>,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Result<Self, PasswordPolicyError> {
+        let breached = match breached_passwords_file {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .map_err(|e| PasswordPolicyError::BreachedList(path.to_string(), e))?;
+                let breached = parse_breached_list(&contents);
+                info!("Loaded {} breached passwords from {}", breached.len(), path);
+                breached
+            }
+            None => HashSet::new(),
+        };
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(PasswordPolicyError::InvalidParams)?;
+        Self::new(min_length, breached, params)
+    }
+
+    fn new(
+        min_length: usize,
+        breached: HashSet<String>,
+        params: Params,
+    ) -> Result<Self, PasswordPolicyError> {
+        let mut policy = Self {
+            min_length,
+            breached: Arc::new(breached),
+            params,
+            dummy_hash: Arc::new(String::new()),
+        };
+        policy.dummy_hash = Arc::new(
+            policy
+                .hash("invok-dummy-password")
+                .map_err(PasswordPolicyError::Hash)?,
+        );
+        Ok(policy)
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// Checks a new password against the policy.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the password is acceptable
+    /// * `Err(String)` - Why the password was rejected
+    pub fn check(&self, password: &str, email: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(format!(
+                "Password must be at least {} characters",
+                self.min_length
+            ));
+        }
+        if length > MAX_PASSWORD_LENGTH {
+            return Err(format!(
+                "Password must be at most {} characters",
+                MAX_PASSWORD_LENGTH
+            ));
+        }
+        if password.eq_ignore_ascii_case(email.trim()) {
+            return Err("Password must not be the email address".to_string());
+        }
+        if self.breached.contains(&password.to_lowercase()) {
+            return Err(
+                "Password appears in a list of breached passwords, choose another".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Hashes a password with the configured parameters.
+    pub fn hash(&self, password: &str) -> Result<String, argon2::password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    }
+
+    /// Verifies a password against a stored hash; unparsable hashes never match.
+    pub fn verify(&self, password: &str, hash: &str) -> bool {
+        PasswordHash::new(hash).is_ok_and(|parsed| {
+            self.argon2()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+    }
+
+    /// Spends the time of a verification for a login whose user doesn't exist.
+    pub fn verify_unknown_user(&self, password: &str) {
+        self.verify(password, &self.dummy_hash);
+    }
+
+    /// Whether a stored hash was made with other parameters than the configured ones.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                parsed.algorithm != Algorithm::Argon2id.ident()
+                    || params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+/// Reads a breached passwords list: one password per line, `#` comments allowed.
+/// Entries are compared case-insensitively.
+fn parse_breached_list(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so tests stay fast.
+    fn params(t_cost: u32) -> Params {
+        Params::new(Params::MIN_M_COST * 4, t_cost, 1, None).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_short_breached_and_email_passwords() {
+        let breached = parse_breached_list("# top passwords\nPassword123\n\nletmein1\n");
+        let policy = PasswordPolicy::new(8, breached, params(1)).unwrap();
+
+        assert!(policy.check("short", "a@b.c").is_err());
+        assert!(policy.check(&"x".repeat(200), "a@b.c").is_err());
+        assert!(policy.check("password123", "a@b.c").is_err());
+        assert!(policy.check("me@example.com", "ME@example.com").is_err());
+        assert!(policy
+            .check("correct horse battery", "me@example.com")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_hashes_with_changed_params_verify_but_need_rehash() {
+        let old = PasswordPolicy::new(8, HashSet::new(), params(1)).unwrap();
+        let new = PasswordPolicy::new(8, HashSet::new(), params(2)).unwrap();
+
+        let hash = old.hash("correct horse").unwrap();
+        assert!(new.verify("correct horse", &hash));
+        assert!(!new.verify("wrong horse", &hash));
+        assert!(new.needs_rehash(&hash));
+        assert!(!old.needs_rehash(&hash));
+        assert!(!new.verify("correct horse", "not a hash"));
+    }
+}
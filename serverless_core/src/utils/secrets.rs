@@ -0,0 +1,108 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use thiserror::Error;
+
+/// Errors raised while encrypting or decrypting secrets.
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Secrets encryption key must be 32 bytes")]
+    InvalidKey,
+    #[error("Failed to encrypt secret")]
+    Encrypt,
+    #[error("Failed to decrypt secret: {0}")]
+    Decrypt(String),
+}
+
+/// Encrypts function secrets at rest with AES-256-GCM.
+///
+/// Every value is sealed with a fresh random nonce; both are stored base64-encoded.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    /// Creates a cipher from a 32-byte key.
+    pub fn new(key: &[u8]) -> Result<Self, SecretError> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| SecretError::InvalidKey)?;
+        Ok(Self { cipher })
+    }
+
+    /// Encrypts a value, returning `(nonce, ciphertext)` as base64 strings.
+    pub fn encrypt(&self, plaintext: &str) -> Result<(String, String), SecretError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| SecretError::Encrypt)?;
+        Ok((BASE64.encode(nonce), BASE64.encode(ciphertext)))
+    }
+
+    /// Decrypts a value produced by [`SecretCipher::encrypt`].
+    pub fn decrypt(&self, nonce: &str, ciphertext: &str) -> Result<String, SecretError> {
+        let nonce = BASE64
+            .decode(nonce)
+            .map_err(|e| SecretError::Decrypt(e.to_string()))?;
+        if nonce.len() != 12 {
+            return Err(SecretError::Decrypt("invalid nonce length".to_string()));
+        }
+        let ciphertext = BASE64
+            .decode(ciphertext)
+            .map_err(|e| SecretError::Decrypt(e.to_string()))?;
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| SecretError::Decrypt("authentication failed".to_string()))?;
+        String::from_utf8(plaintext).map_err(|e| SecretError::Decrypt(e.to_string()))
+    }
+}
+
+/// Returns true if `key` is a valid environment variable name
+/// (`[A-Za-z_][A-Za-z0-9_]*`).
+pub fn is_valid_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let cipher = SecretCipher::new(&[7u8; 32]).unwrap();
+        let (nonce, ciphertext) = cipher.encrypt("s3cr3t \"value\"\n").unwrap();
+        assert!(!ciphertext.contains("s3cr3t"));
+        assert_eq!(
+            cipher.decrypt(&nonce, &ciphertext).unwrap(),
+            "s3cr3t \"value\"\n"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let (nonce, ciphertext) = SecretCipher::new(&[1u8; 32])
+            .unwrap()
+            .encrypt("value")
+            .unwrap();
+        let other = SecretCipher::new(&[2u8; 32]).unwrap();
+        assert!(other.decrypt(&nonce, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_invalid_key_length() {
+        assert!(SecretCipher::new(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_env_key() {
+        assert!(is_valid_env_key("DATABASE_URL"));
+        assert!(is_valid_env_key("_private1"));
+        assert!(!is_valid_env_key(""));
+        assert!(!is_valid_env_key("1KEY"));
+        assert!(!is_valid_env_key("KEY=VALUE"));
+        assert!(!is_valid_env_key("KEY\nRUN"));
+    }
+}
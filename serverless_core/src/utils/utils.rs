@@ -11,52 +11,13 @@ use reqwest::header::HeaderMap as ReqwestHeaderMap;
 use reqwest::Client;
 use reqwest::StatusCode as ReqwestStatusCode;
 use std::collections::HashMap;
-use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::Path;
 use std::time::Duration;
-use tracing::{debug, error, warn};
+use tracing::{debug, error};
 use urlencoding::encode;
 use uuid::Uuid;
 
-/// A RAII guard that runs a closure when dropped.
-///
-/// This is useful for deferring code until the scope exits.
-pub struct ScopeCall<F: FnMut()> {
-    pub c: Option<F>,
-}
-
-impl<F: FnMut()> Drop for ScopeCall<F> {
-    fn drop(&mut self) {
-        // If the closure exists, call it. Otherwise log a warning.
-        if let Some(mut callback) = self.c.take() {
-            callback();
-        } else {
-            warn!("ScopeCall callback was already taken or missing on drop.");
-        }
-    }
-}
-
-/// Returns a `ScopeCall` that will execute the provided closure when dropped.
-///
-/// # Examples
-/// ```
-/// let _deferred = defer_fn(|| println!("This will run when _deferred goes out of scope"));
-/// ```
-pub fn defer_fn<T: FnMut()>(c: T) -> ScopeCall<T> {
-    ScopeCall { c: Some(c) }
-}
-
-/// Converts a map of environment variables into a string in the format:
-/// `ENV key="value"\n` for each variable.
-pub fn envs_to_string(envs: HashMap<String, String>) -> String {
-    let mut envs_str = String::new();
-    for (key, value) in envs {
-        envs_str.push_str(&format!("ENV {}=\"{}\"\n", key, value));
-    }
-    envs_str
-}
-
 /// Converts a reqwest status code into an Axum status code.
 /// Falls back to `INTERNAL_SERVER_ERROR` if the conversion fails.
 fn convert_status_code(reqwest_status: ReqwestStatusCode) -> AxumStatusCode {
@@ -104,6 +65,22 @@ pub fn random_container_name() -> String {
     format!("c-{}", random_string)
 }
 
+/// Extracts the container name from a cached function address of the form
+/// `{container_name}:{port}`.
+pub fn container_name_from_addr(addr: &str) -> &str {
+    addr.split(':').next().unwrap_or(addr)
+}
+
+/// Builds a `LIKE` pattern matching values that contain `text`, with the
+/// wildcards in `text` itself escaped.
+pub fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
 /// Generates a random port number (as a string) in the range 8000-8999.
 ///
 /// Note: This function does not guarantee that the returned port is available.
@@ -247,34 +224,18 @@ pub async fn make_request(
     response
 }
 
-/// Creates a base file structure for a function.
-///
-/// If the specified path already exists, an error is returned. Otherwise, the
-/// directory is created and a `main.go` file is initialized in that directory.
+/// Creates the base `main.go` file of a template-built function.
 ///
 /// # Arguments
 ///
-/// * `path` - The directory path where the function files will be created.
-/// * `name` - The name of the function (used in error messages).
+/// * `path` - The existing function directory.
 /// * `_runtime` - The runtime (currently unused, but reserved for future use).
 ///
 /// # Returns
 ///
 /// A `Result` containing the created `File` handle for `main.go` or an `std::io::Error`.
-pub fn create_fn_files_base(path: &PathBuf, name: &str, _runtime: &str) -> std::io::Result<File> {
-    if path.exists() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::AlreadyExists,
-            format!("Folder '{}' already exists.", name),
-        ));
-    }
-
-    fs::create_dir(path)?;
-
-    let main_file_path = path.join("main.go");
-    let main_file = File::create(&main_file_path)?;
-
-    Ok(main_file)
+pub fn create_fn_files_base(path: &Path, _runtime: &str) -> std::io::Result<File> {
+    File::create(path.join("main.go"))
 }
 
 pub fn generate_hash(source: Uuid) -> String {
@@ -0,0 +1,919 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use tar::{Archive as TarArchive, Builder as TarBuilder, EntryType};
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::compress_dir_with_excludes;
+
+/// Unix file type bits and the symlink file type.
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Entries smaller than this are not subject to the compression ratio limit, since
+/// small, highly repetitive files legitimately compress very well.
+const RATIO_CHECK_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Errors raised while extracting an uploaded function archive.
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("I/O error while extracting archive: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Unsupported archive format, expected zip, tar.gz or tar.zst")]
+    UnsupportedFormat,
+
+    #[error("Archive entry '{0}' escapes the extraction directory")]
+    PathTraversal(String),
+
+    #[error("Archive entry '{0}' has an absolute path")]
+    AbsolutePath(String),
+
+    #[error("Archive entry '{0}' is a symbolic link, which is not allowed")]
+    Symlink(String),
+
+    #[error("Archive has {0} entries, more than the allowed {1}")]
+    TooManyEntries(usize, usize),
+
+    #[error("Archive expands to more than the allowed {0} bytes")]
+    TooLarge(u64),
+
+    #[error("Archive entry '{0}' exceeds the allowed compression ratio of {1}")]
+    CompressionRatio(String, u64),
+
+    #[error("Archive has more than one entry for '{0}'")]
+    DuplicateEntry(String),
+
+    #[error("Archive entry '{0}' uses a path both as a file and as a directory")]
+    ConflictingEntry(String),
+}
+
+impl From<ZipError> for ExtractError {
+    fn from(e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => ExtractError::Io(e),
+            e => ExtractError::InvalidArchive(e.to_string()),
+        }
+    }
+}
+
+/// Archive formats accepted for function uploads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 3] = [
+        ArchiveFormat::Zip,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarZst,
+    ];
+
+    /// Detects the format of an archive from its leading magic bytes.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some(Self::Zip),
+            [0x1f, 0x8b, ..] => Some(Self::TarGz),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Self::TarZst),
+            _ => None,
+        }
+    }
+
+    /// The file extension used for archives of this format, including the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => ".zip",
+            Self::TarGz => ".tar.gz",
+            Self::TarZst => ".tar.zst",
+        }
+    }
+
+    /// The MIME type used when uploading archives of this format.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::TarGz => "application/gzip",
+            Self::TarZst => "application/zstd",
+        }
+    }
+
+    /// Strips a supported archive extension from a file name.
+    pub fn strip_extension(file_name: &str) -> Option<&str> {
+        Self::ALL
+            .iter()
+            .find_map(|format| file_name.strip_suffix(format.extension()))
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.extension()[1..])
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = ExtractError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or(ExtractError::UnsupportedFormat)
+    }
+}
+
+/// Limits applied while extracting an archive.
+///
+/// # Fields
+/// - `max_total_bytes`: Maximum number of bytes written across all entries.
+/// - `max_entries`: Maximum number of entries (files and directories) in the archive.
+/// - `max_compression_ratio`: Maximum uncompressed-to-compressed size ratio of an entry.
+#[derive(Debug, Clone)]
+pub struct ExtractLimits {
+    pub max_total_bytes: u64,
+    pub max_entries: usize,
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: 100 * 1024 * 1024,
+            max_entries: 1000,
+            max_compression_ratio: 100,
+        }
+    }
+}
+
+/// Resolves an archive entry name to a path relative to the extraction directory.
+///
+/// Absolute paths and any `..` component are rejected rather than normalised, so a
+/// crafted entry can never resolve outside the directory.
+///
+/// # Returns
+///
+/// The relative path, which is empty for entries such as `./`.
+pub fn sanitize_entry_path(name: &str) -> Result<PathBuf, ExtractError> {
+    if name.contains('\0') {
+        return Err(ExtractError::InvalidArchive(format!(
+            "entry name '{}' contains a NUL byte",
+            name.escape_default()
+        )));
+    }
+
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return Err(ExtractError::PathTraversal(name.to_string())),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(ExtractError::AbsolutePath(name.to_string()))
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Tracks the bytes written during an extraction against its limits.
+struct ExtractBudget<'a> {
+    limits: &'a ExtractLimits,
+    written: u64,
+    /// Ratio cap over the whole archive, for formats compressed as a single stream.
+    archive_ratio_cap: Option<u64>,
+}
+
+impl<'a> ExtractBudget<'a> {
+    fn new(limits: &'a ExtractLimits) -> Self {
+        Self {
+            limits,
+            written: 0,
+            archive_ratio_cap: None,
+        }
+    }
+
+    /// A budget whose compression ratio is enforced over the whole archive of
+    /// `archive_size` bytes rather than per entry.
+    fn for_stream(limits: &'a ExtractLimits, archive_size: u64) -> Self {
+        Self {
+            archive_ratio_cap: Some(ratio_cap(archive_size, limits.max_compression_ratio)),
+            ..Self::new(limits)
+        }
+    }
+
+    /// Copies one entry to `out_path`, stopping as soon as the archive-wide size
+    /// limit or the entry's compression ratio limit is exceeded.
+    ///
+    /// `compressed_size` is the entry's size inside the archive, or `None` when the
+    /// format doesn't record it per entry.
+    fn copy_entry(
+        &mut self,
+        name: &str,
+        reader: &mut impl Read,
+        compressed_size: Option<u64>,
+        out_path: &Path,
+    ) -> Result<(), ExtractError> {
+        let remaining = self.limits.max_total_bytes.saturating_sub(self.written);
+        let entry_ratio_cap =
+            compressed_size.map(|size| ratio_cap(size, self.limits.max_compression_ratio));
+        let archive_ratio_cap = self
+            .archive_ratio_cap
+            .map(|cap| cap.saturating_sub(self.written));
+        let ratio_cap = match (entry_ratio_cap, archive_ratio_cap) {
+            (Some(entry), Some(archive)) => Some(entry.min(archive)),
+            (entry, archive) => entry.or(archive),
+        };
+        let cap = ratio_cap.map_or(remaining, |ratio_cap| ratio_cap.min(remaining));
+
+        // Read one byte past the cap so an oversized entry is detected without
+        // trusting the sizes declared in the archive headers.
+        let mut outfile = File::create(out_path)?;
+        let copied = io::copy(&mut reader.take(cap.saturating_add(1)), &mut outfile)?;
+        if copied > cap {
+            drop(outfile);
+            let _ = fs::remove_file(out_path);
+            return Err(match ratio_cap {
+                Some(ratio_cap) if copied > ratio_cap => ExtractError::CompressionRatio(
+                    name.to_string(),
+                    self.limits.max_compression_ratio,
+                ),
+                _ => ExtractError::TooLarge(self.limits.max_total_bytes),
+            });
+        }
+        self.written += copied;
+        Ok(())
+    }
+}
+
+/// The paths an extraction has created, so an archive can't list the same file
+/// twice or use one path both as a file and as a directory.
+#[derive(Default)]
+struct ExtractedPaths {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl ExtractedPaths {
+    /// Records the directory entry `name`, extracted to `path`, and its parents.
+    fn add_dir(&mut self, name: &str, path: &Path) -> Result<(), ExtractError> {
+        for dir in path.ancestors().filter(|dir| !dir.as_os_str().is_empty()) {
+            if self.files.contains(dir) {
+                return Err(ExtractError::ConflictingEntry(name.to_string()));
+            }
+            self.dirs.insert(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Records the file entry `name`, extracted to `path`, and its parents.
+    fn add_file(&mut self, name: &str, path: &Path) -> Result<(), ExtractError> {
+        if self.dirs.contains(path) {
+            return Err(ExtractError::ConflictingEntry(name.to_string()));
+        }
+        if let Some(parent) = path.parent() {
+            self.add_dir(name, parent)?;
+        }
+        if !self.files.insert(path.to_path_buf()) {
+            return Err(ExtractError::DuplicateEntry(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// The largest number of bytes `compressed_size` bytes may expand to under `ratio`.
+fn ratio_cap(compressed_size: u64, ratio: u64) -> u64 {
+    compressed_size
+        .max(1)
+        .saturating_mul(ratio)
+        .max(RATIO_CHECK_MIN_BYTES)
+}
+
+/// Safely extracts a ZIP archive held in memory into `dest_dir`.
+///
+/// Entries with absolute paths, `..` components or symlink file types are rejected,
+/// as are files listed twice or paths used both as a file and as a directory, and
+/// the archive must stay within `limits` for entry count, total extracted size
+/// and per-entry compression ratio. Sizes are enforced on the bytes actually
+/// decompressed, not on the sizes the archive declares.
+///
+/// # Arguments
+///
+/// * `cursor` - The ZIP archive.
+/// * `dest_dir` - The directory to extract into.
+/// * `limits` - The limits to enforce.
+pub fn extract_zip_from_cursor(
+    cursor: Cursor<Vec<u8>>,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<(), ExtractError> {
+    let mut archive = ZipArchive::new(cursor)?;
+    if archive.len() > limits.max_entries {
+        return Err(ExtractError::TooManyEntries(
+            archive.len(),
+            limits.max_entries,
+        ));
+    }
+
+    let mut budget = ExtractBudget::new(limits);
+    let mut extracted = ExtractedPaths::default();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let file_name = file.name().to_string();
+        let relative_path = sanitize_entry_path(&file_name)?;
+
+        if file
+            .unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+        {
+            return Err(ExtractError::Symlink(file_name));
+        }
+
+        let out_path = dest_dir.join(&relative_path);
+        if file.is_dir() || relative_path.as_os_str().is_empty() {
+            extracted.add_dir(&file_name, &relative_path)?;
+            fs::create_dir_all(&out_path)?;
+        } else {
+            extracted.add_file(&file_name, &relative_path)?;
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let compressed_size = file.compressed_size();
+            budget.copy_entry(&file_name, &mut file, Some(compressed_size), &out_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Safely extracts a tar stream into `dest_dir`.
+///
+/// Only regular files and directories are accepted; symlinks, hard links and
+/// special files are rejected, as are files listed twice or paths used both as
+/// a file and as a directory. File permissions are preserved without the setuid,
+/// setgid and sticky bits. The compression ratio limit applies to the archive as
+/// a whole, since tar archives are compressed as a single stream.
+fn extract_tar(
+    reader: impl Read,
+    archive_size: u64,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<(), ExtractError> {
+    let mut archive = TarArchive::new(reader);
+    let mut budget = ExtractBudget::for_stream(limits, archive_size);
+    let mut extracted = ExtractedPaths::default();
+
+    for (index, entry) in archive.entries()?.enumerate() {
+        if index >= limits.max_entries {
+            return Err(ExtractError::TooManyEntries(index + 1, limits.max_entries));
+        }
+
+        let mut entry = entry?;
+        let raw_name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let relative_path = sanitize_entry_path(&raw_name)?;
+        let out_path = dest_dir.join(&relative_path);
+
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                extracted.add_dir(&raw_name, &relative_path)?;
+                fs::create_dir_all(&out_path)?
+            }
+            EntryType::Regular | EntryType::Continuous if !relative_path.as_os_str().is_empty() => {
+                extracted.add_file(&raw_name, &relative_path)?;
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                budget.copy_entry(&raw_name, &mut entry, None, &out_path)?;
+                #[cfg(unix)]
+                if let Ok(mode) = entry.header().mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))?;
+                }
+            }
+            EntryType::Symlink | EntryType::Link => return Err(ExtractError::Symlink(raw_name)),
+            entry_type => {
+                return Err(ExtractError::InvalidArchive(format!(
+                    "entry '{raw_name}' has unsupported type {entry_type:?}"
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Safely extracts an uploaded function archive into `dest_dir`.
+///
+/// The format is detected from the archive's magic bytes, and every format is
+/// subject to the same path, symlink and size checks.
+///
+/// # Arguments
+///
+/// * `content` - The archive bytes.
+/// * `dest_dir` - The directory to extract into.
+/// * `limits` - The limits to enforce.
+///
+/// # Returns
+///
+/// The detected archive format.
+pub fn extract_archive(
+    content: Vec<u8>,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<ArchiveFormat, ExtractError> {
+    let format = ArchiveFormat::detect(&content).ok_or(ExtractError::UnsupportedFormat)?;
+    let archive_size = content.len() as u64;
+
+    match format {
+        ArchiveFormat::Zip => extract_zip_from_cursor(Cursor::new(content), dest_dir, limits)?,
+        ArchiveFormat::TarGz => extract_tar(
+            GzDecoder::new(content.as_slice()),
+            archive_size,
+            dest_dir,
+            limits,
+        )?,
+        ArchiveFormat::TarZst => {
+            let decoder = zstd::Decoder::new(content.as_slice())?;
+            extract_tar(decoder, archive_size, dest_dir, limits)?
+        }
+    }
+    Ok(format)
+}
+
+/// Appends the contents of a directory to a tar archive, preserving file modes
+/// and skipping files whose name is in `excludes`.
+fn append_dir_to_tar<W: Write>(
+    tar: &mut TarBuilder<W>,
+    src_dir: &Path,
+    base_path: &Path,
+    excludes: &[&str],
+) -> io::Result<()> {
+    for entry in fs::read_dir(src_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = path
+            .strip_prefix(base_path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        if path.is_dir() {
+            tar.append_dir(name, &path)?;
+            append_dir_to_tar(tar, &path, base_path, excludes)?;
+        } else if !excludes.iter().any(|exclude| entry.file_name() == *exclude) {
+            tar.append_path_with_name(&path, name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compresses the contents of a directory into an archive of the given format,
+/// excluding files whose name is in `excludes`.
+///
+/// # Arguments
+///
+/// * `src_dir` - The source directory to compress.
+/// * `format` - The archive format to produce.
+/// * `excludes` - A list of file names to exclude from compression.
+///
+/// # Returns
+///
+/// The archive bytes.
+pub fn compress_dir(
+    src_dir: &Path,
+    format: ArchiveFormat,
+    excludes: &[&str],
+) -> io::Result<Vec<u8>> {
+    match format {
+        ArchiveFormat::Zip => {
+            let mut dest_zip = Cursor::new(Vec::new());
+            compress_dir_with_excludes(src_dir, &mut dest_zip, excludes)?;
+            Ok(dest_zip.into_inner())
+        }
+        ArchiveFormat::TarGz => {
+            let mut tar = TarBuilder::new(GzEncoder::new(Vec::new(), Compression::default()));
+            append_dir_to_tar(&mut tar, src_dir, src_dir, excludes)?;
+            tar.into_inner()?.finish()
+        }
+        ArchiveFormat::TarZst => {
+            let mut tar = TarBuilder::new(zstd::Encoder::new(Vec::new(), 0)?);
+            append_dir_to_tar(&mut tar, src_dir, src_dir, excludes)?;
+            tar.into_inner()?.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    /// Builds an in-memory ZIP archive from `(name, content)` entries.
+    fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, content) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Rewrites the external attributes of every regular file into a symlink mode.
+    fn mark_as_symlinks(mut archive: Vec<u8>) -> Vec<u8> {
+        let regular = (0o100644u32 << 16).to_le_bytes();
+        let symlink = (0o120777u32 << 16).to_le_bytes();
+        for i in 0..archive.len().saturating_sub(3) {
+            if archive[i..i + 4] == regular {
+                archive[i..i + 4].copy_from_slice(&symlink);
+            }
+        }
+        archive
+    }
+
+    fn extract(
+        archive: Vec<u8>,
+        limits: &ExtractLimits,
+    ) -> (tempfile::TempDir, Result<(), ExtractError>) {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        let result = extract_zip_from_cursor(Cursor::new(archive), &dest, limits);
+        (dir, result)
+    }
+
+    /// Lists every file under `dir`, recursively.
+    fn files_under(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn test_extracts_nested_files() {
+        let archive = zip_archive(&[
+            ("config.json", b"{}"),
+            ("./src/handler.go", b"package main"),
+        ]);
+        let (dir, result) = extract(archive, &ExtractLimits::default());
+        result.unwrap();
+        let out = dir.path().join("out");
+        assert_eq!(fs::read(out.join("config.json")).unwrap(), b"{}");
+        assert_eq!(
+            fs::read(out.join("src/handler.go")).unwrap(),
+            b"package main"
+        );
+    }
+
+    #[test]
+    fn test_rejects_traversal_and_absolute_paths() {
+        let (dir, result) = extract(zip_archive(&[("../evil", b"x")]), &ExtractLimits::default());
+        assert!(matches!(result, Err(ExtractError::PathTraversal(_))));
+        assert!(!dir.path().join("evil").exists());
+
+        let (_dir, result) = extract(
+            zip_archive(&[("/etc/evil", b"x")]),
+            &ExtractLimits::default(),
+        );
+        assert!(matches!(result, Err(ExtractError::AbsolutePath(_))));
+    }
+
+    #[test]
+    fn test_rejects_paths_that_are_both_files_and_directories() {
+        let conflicts: [&[(&str, &[u8])]; 2] = [
+            &[("src", b"file"), ("src/main.go", b"package main")],
+            &[("src/main.go", b"package main"), ("src", b"file")],
+        ];
+        for entries in conflicts {
+            let (_dir, result) = extract(zip_archive(entries), &ExtractLimits::default());
+            assert!(
+                matches!(result, Err(ExtractError::ConflictingEntry(_))),
+                "{entries:?}: unexpected {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_symlinks() {
+        let archive = mark_as_symlinks(zip_archive(&[("link", b"/etc/passwd")]));
+        let (_dir, result) = extract(archive, &ExtractLimits::default());
+        assert!(matches!(result, Err(ExtractError::Symlink(_))));
+    }
+
+    #[test]
+    fn test_rejects_too_many_entries() {
+        let limits = ExtractLimits {
+            max_entries: 2,
+            ..Default::default()
+        };
+        let archive = zip_archive(&[("a", b"1"), ("b", b"2"), ("c", b"3")]);
+        let (_dir, result) = extract(archive, &limits);
+        assert!(matches!(result, Err(ExtractError::TooManyEntries(3, 2))));
+    }
+
+    #[test]
+    fn test_rejects_zip_bomb_by_ratio_and_total_size() {
+        let zeros = vec![0u8; 4 * 1024 * 1024];
+
+        let (dir, result) = extract(zip_archive(&[("bomb", &zeros)]), &ExtractLimits::default());
+        assert!(matches!(
+            result,
+            Err(ExtractError::CompressionRatio(_, 100))
+        ));
+        assert!(files_under(&dir.path().join("out")).is_empty());
+
+        let limits = ExtractLimits {
+            max_total_bytes: 1024,
+            ..Default::default()
+        };
+        let archive = zip_archive(&[("a", &[1u8; 800]), ("b", &[2u8; 800])]);
+        let (_dir, result) = extract(archive, &limits);
+        assert!(matches!(result, Err(ExtractError::TooLarge(1024))));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        let (_dir, result) = extract(b"not a zip".to_vec(), &ExtractLimits::default());
+        assert!(matches!(result, Err(ExtractError::InvalidArchive(_))));
+    }
+
+    /// Builds an in-memory tar archive with raw entry names and types.
+    fn tar_archive(entries: &[(&str, EntryType, &[u8], u32)]) -> Vec<u8> {
+        let mut tar = TarBuilder::new(Vec::new());
+        for (name, entry_type, content, mode) in entries {
+            let mut header = tar::Header::new_gnu();
+            // Write the name directly so `..` and absolute paths can be crafted.
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(*entry_type);
+            header.set_size(content.len() as u64);
+            header.set_mode(*mode);
+            header.set_cksum();
+            tar.append(&header, *content).unwrap();
+        }
+        tar.into_inner().unwrap()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn extract_any(
+        archive: Vec<u8>,
+        limits: &ExtractLimits,
+    ) -> (tempfile::TempDir, Result<ArchiveFormat, ExtractError>) {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        let result = extract_archive(archive, &dest, limits);
+        (dir, result)
+    }
+
+    #[test]
+    fn test_detects_formats_by_magic_bytes() {
+        assert_eq!(
+            ArchiveFormat::detect(&zip_archive(&[("a", b"1")])),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::detect(&gzip(b"x")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::detect(&zstd::encode_all(&b"x"[..], 0).unwrap()),
+            Some(ArchiveFormat::TarZst)
+        );
+        assert_eq!(ArchiveFormat::detect(b"plain text"), None);
+        assert_eq!(
+            "tar.zst".parse::<ArchiveFormat>().unwrap(),
+            ArchiveFormat::TarZst
+        );
+        assert_eq!(
+            ArchiveFormat::strip_extension("hello.tar.gz"),
+            Some("hello")
+        );
+        assert_eq!(ArchiveFormat::strip_extension("hello.rar"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compressed_tar_roundtrip_preserves_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let src = tempfile::tempdir().unwrap();
+        fs::create_dir(src.path().join("bin")).unwrap();
+        let script = src.path().join("bin/run.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(src.path().join("go.sum"), "excluded").unwrap();
+
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::TarZst] {
+            let archive = compress_dir(src.path(), format, &["go.sum"]).unwrap();
+            let (dir, result) = extract_any(archive, &ExtractLimits::default());
+            assert_eq!(result.unwrap(), format);
+
+            let out = dir.path().join("out");
+            let mode = fs::metadata(out.join("bin/run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+            assert!(!out.join("go.sum").exists());
+        }
+    }
+
+    #[test]
+    fn test_tar_rejects_traversal_links_and_special_files() {
+        let cases = [
+            (
+                "../evil",
+                EntryType::Regular,
+                ExtractError::PathTraversal(String::new()),
+            ),
+            (
+                "/etc/evil",
+                EntryType::Regular,
+                ExtractError::AbsolutePath(String::new()),
+            ),
+            (
+                "link",
+                EntryType::Symlink,
+                ExtractError::Symlink(String::new()),
+            ),
+            (
+                "fifo",
+                EntryType::Fifo,
+                ExtractError::InvalidArchive(String::new()),
+            ),
+        ];
+        for (name, entry_type, expected) in cases {
+            let archive = gzip(&tar_archive(&[(name, entry_type, b"x", 0o644)]));
+            let (dir, result) = extract_any(archive, &ExtractLimits::default());
+            let err = result.unwrap_err();
+            assert_eq!(
+                std::mem::discriminant(&err),
+                std::mem::discriminant(&expected),
+                "{name}: unexpected {err:?}"
+            );
+            assert!(files_under(dir.path()).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_tar_strips_special_mode_bits_and_enforces_limits() {
+        let archive = gzip(&tar_archive(&[("suid", EntryType::Regular, b"x", 0o4755)]));
+        let (dir, result) = extract_any(archive, &ExtractLimits::default());
+        result.unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.path().join("out/suid"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o7777, 0o755);
+        }
+
+        let zeros = vec![0u8; 4 * 1024 * 1024];
+        let bomb = gzip(&tar_archive(&[("bomb", EntryType::Regular, &zeros, 0o644)]));
+        let (_dir, result) = extract_any(bomb, &ExtractLimits::default());
+        assert!(matches!(
+            result,
+            Err(ExtractError::CompressionRatio(_, 100))
+        ));
+
+        let limits = ExtractLimits {
+            max_entries: 1,
+            ..Default::default()
+        };
+        let archive = gzip(&tar_archive(&[
+            ("a", EntryType::Regular, b"1", 0o644),
+            ("b", EntryType::Regular, b"2", 0o644),
+        ]));
+        let (_dir, result) = extract_any(archive, &limits);
+        assert!(matches!(result, Err(ExtractError::TooManyEntries(2, 1))));
+    }
+
+    fn entry_name() -> impl Strategy<Value = String> {
+        let component = prop_oneof!["\\.\\.", "\\.", "[a-z]{1,4}", Just(String::new())];
+        (any::<bool>(), prop::collection::vec(component, 1..6)).prop_map(|(absolute, parts)| {
+            let name = parts.join("/");
+            if absolute {
+                format!("/{name}")
+            } else {
+                name
+            }
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// Whatever the entry names, extraction either fails or only writes files
+        /// inside the destination directory.
+        #[test]
+        fn prop_extraction_stays_inside_destination(names in prop::collection::vec(entry_name(), 1..5)) {
+            let entries = names
+                .iter()
+                .filter(|name| !name.is_empty() && !name.ends_with('/'))
+                .map(|name| (name.as_str(), b"x".as_slice()))
+                .collect::<Vec<_>>();
+            prop_assume!(!entries.is_empty());
+
+            let (dir, result) = extract(zip_archive(&entries), &ExtractLimits::default());
+            let dest = dir.path().join("out");
+            for file in files_under(dir.path()) {
+                prop_assert!(file.starts_with(&dest), "{:?} escaped {:?}", file, dest);
+            }
+            if result.is_ok() {
+                prop_assert!(entries.iter().all(|(name, _)| sanitize_entry_path(name).is_ok()));
+            }
+        }
+
+        /// Tar entries get the same guarantee as zip entries.
+        #[test]
+        fn prop_tar_extraction_stays_inside_destination(names in prop::collection::vec(entry_name(), 1..5)) {
+            let entries = names
+                .iter()
+                .filter(|name| !name.is_empty() && name.len() < 100)
+                .map(|name| (name.as_str(), EntryType::Regular, b"x".as_slice(), 0o644))
+                .collect::<Vec<_>>();
+            prop_assume!(!entries.is_empty());
+
+            let (dir, _result) = extract_any(gzip(&tar_archive(&entries)), &ExtractLimits::default());
+            let dest = dir.path().join("out");
+            for file in files_under(dir.path()) {
+                prop_assert!(file.starts_with(&dest), "{:?} escaped {:?}", file, dest);
+            }
+        }
+
+        /// Arbitrary bytes, bare or behind any supported magic number, never panic
+        /// the extractor.
+        #[test]
+        fn prop_arbitrary_bytes_do_not_panic(
+            magic in prop_oneof![
+                Just(Vec::new()),
+                Just(b"PK\x03\x04".to_vec()),
+                Just(vec![0x1f, 0x8b]),
+                Just(vec![0x28, 0xb5, 0x2f, 0xfd]),
+            ],
+            bytes in prop::collection::vec(any::<u8>(), 0..512),
+        ) {
+            let archive = [magic, bytes].concat();
+            let (_dir, _result) = extract_any(archive, &ExtractLimits::default());
+        }
+    }
+
+    #[test]
+    fn test_tar_rejects_duplicate_and_conflicting_entries() {
+        let duplicate = tar_archive(&[
+            ("config.json", EntryType::Regular, b"{}", 0o644),
+            (
+                "./config.json",
+                EntryType::Regular,
+                b"{\"runtime\":\"x\"}",
+                0o644,
+            ),
+        ]);
+        let (_dir, result) = extract_any(gzip(&duplicate), &ExtractLimits::default());
+        assert!(matches!(result, Err(ExtractError::DuplicateEntry(_))));
+
+        let conflicting = tar_archive(&[
+            ("bin/", EntryType::Directory, b"", 0o755),
+            ("bin", EntryType::Regular, b"x", 0o644),
+        ]);
+        let (_dir, result) = extract_any(gzip(&conflicting), &ExtractLimits::default());
+        assert!(matches!(result, Err(ExtractError::ConflictingEntry(_))));
+
+        // Directories may be listed again, and before or after their files.
+        let repeated_dirs = tar_archive(&[
+            ("bin/run.sh", EntryType::Regular, b"x", 0o755),
+            ("bin/", EntryType::Directory, b"", 0o755),
+            ("bin/", EntryType::Directory, b"", 0o755),
+        ]);
+        let (_dir, result) = extract_any(gzip(&repeated_dirs), &ExtractLimits::default());
+        assert!(result.is_ok());
+    }
+}
@@ -3,9 +3,46 @@ use std::io::{self, Cursor, Write};
 use std::path::{Path, PathBuf};
 use tar::{Builder, Header};
 use zip::write::FileOptions;
-use zip::{ZipArchive, ZipWriter};
+use zip::ZipWriter;
+pub mod archive;
 pub mod template;
 
+pub use archive::{
+    compress_dir, extract_archive, extract_zip_from_cursor, ArchiveFormat, ExtractError,
+    ExtractLimits,
+};
+
+/// Longest function name accepted; names are used as DNS labels.
+pub const MAX_FUNCTION_NAME_LENGTH: usize = 63;
+
+/// Checks that a function name is safe to use as a DNS label, a Docker image
+/// name, a route segment and a cache key.
+///
+/// Names must be 1 to 63 characters of lowercase letters, digits and `-`,
+/// starting with a letter and not ending with `-`.
+///
+/// # Returns
+///
+/// `Err` with a description of the first rule the name breaks.
+pub fn validate_function_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || name.len() > MAX_FUNCTION_NAME_LENGTH {
+        return Err("must be between 1 and 63 characters");
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    {
+        return Err("may only contain lowercase letters, digits and '-'");
+    }
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err("must start with a lowercase letter");
+    }
+    if name.ends_with('-') {
+        return Err("must not end with '-'");
+    }
+    Ok(())
+}
+
 pub fn to_camel_case_handler(input: &str) -> String {
     let mut result = String::new();
     let mut capitalize_next = false;
@@ -105,29 +142,6 @@ pub fn add_dir_to_tar<W: Write>(
     Ok(())
 }
 
-pub fn extract_zip_from_cursor(cursor: Cursor<Vec<u8>>, dest_dir: &Path) -> io::Result<()> {
-    let mut archive = ZipArchive::new(cursor)?;
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let file_name = file.name().to_string();
-
-        let out_path = dest_dir.join(file_name);
-
-        if file.is_dir() {
-            fs::create_dir_all(&out_path)?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            let mut outfile = File::create(&out_path)?;
-            io::copy(&mut file, &mut outfile)?;
-        }
-    }
-
-    Ok(())
-}
-
 pub fn find_file_in_path(file_name: &str, path: &PathBuf) -> Option<String> {
     let dir = fs::read_dir(path).ok()?;
     for entry in dir {
@@ -129,9 +129,6 @@ COPY --from=builder /app/main .
 # Expose port 8080
 EXPOSE 8080
 
-# Set environment variables (replace with actual environment configurations)
-{{ENV}}
-
 # Command to run the application
 CMD ["./main"]
 "#;